@@ -0,0 +1,272 @@
+use std::time::Duration;
+
+use crate::timed::TimeLimit;
+use crate::{Difficulty, Lang, Msg, HIGH, LOW};
+
+// How long a game given `--timed` on its own has
+const DEFAULT_TIME_LIMIT: u64 = 60;
+
+/// How the game was asked to run, from the command line.
+#[derive(Debug, Clone, PartialEq)]
+pub struct Options {
+    /// The range to pick the secret from, both ends included: `--range
+    /// LOW-HIGH`, or LOW to HIGH
+    pub low: u32,
+    pub high: u32,
+    /// How many guesses a game allows
+    pub difficulty: Difficulty,
+    /// Print the best scores instead of playing
+    pub show_scores: bool,
+    /// Race the clock: `--timed` for the default limit, or
+    /// `--time-limit SECS` for another one
+    pub time_limit: Option<TimeLimit>,
+    /// Let the computer guess a number the player thinks of
+    pub reverse: bool,
+    /// Guess a word instead of a number
+    pub words: bool,
+    /// Play today's challenge, where everyone gets the same number
+    pub daily: bool,
+    /// Host a game for someone else to join, on this port
+    pub host: Option<u16>,
+    /// Join a game hosted at this address, as `host:port`
+    pub join: Option<String>,
+    /// Print without colors, even to a terminal
+    pub no_color: bool,
+    /// The language to talk to the player in
+    pub lang: Lang,
+}
+
+impl Default for Options {
+    fn default() -> Options {
+        Options {
+            low: LOW,
+            high: HIGH,
+            difficulty: Difficulty::default(),
+            show_scores: false,
+            time_limit: None,
+            reverse: false,
+            words: false,
+            daily: false,
+            host: None,
+            join: None,
+            no_color: false,
+            lang: Lang::default(),
+        }
+    }
+}
+
+/// Why the command line couldn't be read. It's said in whichever language
+/// the player asked for, so it keeps the message and not its words.
+#[derive(Debug, Clone, PartialEq)]
+pub struct ArgsError {
+    msg: Msg,
+    // What goes in the message's blank, if it has one
+    arg: String,
+}
+
+impl ArgsError {
+    fn new(msg: Msg) -> ArgsError {
+        ArgsError {
+            msg,
+            arg: String::new(),
+        }
+    }
+
+    fn with_arg(msg: Msg, arg: &str) -> ArgsError {
+        ArgsError {
+            msg,
+            arg: arg.to_string(),
+        }
+    }
+
+    /// What went wrong, in `lang`.
+    pub fn say(&self, lang: Lang) -> String {
+        lang.say(self.msg, &[&self.arg])
+    }
+}
+
+impl Options {
+    /// Reads the options from `args`, without the program name.
+    pub fn parse(args: &[String]) -> Result<Options, ArgsError> {
+        let mut options = Options::default();
+
+        let mut args = args.iter();
+        while let Some(arg) = args.next() {
+            match arg.as_str() {
+                "--range" => {
+                    let (low, high) = args
+                        .next()
+                        .and_then(|range| parse_range(range))
+                        .ok_or(ArgsError::new(Msg::RangeNeeded))?;
+                    options.low = low;
+                    options.high = high;
+                }
+                "--difficulty" => {
+                    let difficulty = args.next().ok_or(ArgsError::new(Msg::DifficultyNeeded))?;
+                    options.difficulty = difficulty
+                        .parse()
+                        .map_err(|_| ArgsError::with_arg(Msg::UnknownDifficulty, difficulty))?;
+                }
+                "--lang" => {
+                    let lang = args.next().ok_or(ArgsError::new(Msg::LangNeeded))?;
+                    options.lang = lang
+                        .parse()
+                        .map_err(|_| ArgsError::with_arg(Msg::UnknownLang, lang))?;
+                }
+                "--scores" => options.show_scores = true,
+                "--reverse" => options.reverse = true,
+                "--words" => options.words = true,
+                "--daily" => options.daily = true,
+                "--no-color" => options.no_color = true,
+                "--timed" => {
+                    let limit = Duration::from_secs(DEFAULT_TIME_LIMIT);
+                    options.time_limit = Some(TimeLimit::new(limit));
+                }
+                "--time-limit" => {
+                    let secs = match args.next().map(|secs| secs.parse()) {
+                        Some(Ok(secs)) if secs > 0 => secs,
+                        _ => return Err(ArgsError::new(Msg::TimeLimitNeeded)),
+                    };
+                    options.time_limit = Some(TimeLimit::new(Duration::from_secs(secs)));
+                }
+                "--host" => match args.next().map(|port| port.parse()) {
+                    Some(Ok(port)) => options.host = Some(port),
+                    _ => return Err(ArgsError::new(Msg::PortNeeded)),
+                },
+                "--join" => match args.next() {
+                    Some(addr) => options.join = Some(addr.clone()),
+                    None => return Err(ArgsError::new(Msg::AddressNeeded)),
+                },
+                _ => return Err(ArgsError::with_arg(Msg::UnknownArgument, arg)),
+            }
+        }
+        if options.host.is_some() && options.join.is_some() {
+            return Err(ArgsError::new(Msg::HostAndJoin));
+        }
+        if options.daily && options.words {
+            return Err(ArgsError::new(Msg::DailyWords));
+        }
+
+        Ok(options)
+    }
+
+    /// The language `args` ask for, or the default if they don't ask for
+    /// one that there is. It's for telling the player what's wrong with
+    /// the rest of them.
+    pub fn requested_lang(args: &[String]) -> Lang {
+        args.iter()
+            .skip_while(|arg| *arg != "--lang")
+            .nth(1)
+            .and_then(|lang| lang.parse().ok())
+            .unwrap_or_default()
+    }
+}
+
+// `low-high`, where both are at least 1 and high is more than low. It
+// can't quite go up to u32::MAX, to leave room for the number after it.
+fn parse_range(range: &str) -> Option<(u32, u32)> {
+    let (low, high) = range.split_once('-')?;
+    let (low, high): (u32, u32) = (low.parse().ok()?, high.parse().ok()?);
+    if low == 0 || high <= low || high == u32::MAX {
+        return None;
+    }
+    Some((low, high))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn args(args: &[&str]) -> Vec<String> {
+        args.iter().map(|arg| arg.to_string()).collect()
+    }
+
+    fn parse(args: &[&str]) -> Result<Options, ArgsError> {
+        Options::parse(&self::args(args))
+    }
+
+    #[test]
+    fn reads_the_options() {
+        assert_eq!(Ok(Options::default()), parse(&[]));
+        assert!(parse(&["--scores"]).unwrap().show_scores);
+        assert!(parse(&["--reverse"]).unwrap().reverse);
+        assert!(parse(&["--words"]).unwrap().words);
+        assert!(parse(&["--daily"]).unwrap().daily);
+        assert!(parse(&["--daily", "--words"]).is_err());
+        assert!(parse(&["--no-color"]).unwrap().no_color);
+        assert_eq!(
+            "unknown argument `--score`",
+            parse(&["--score"]).unwrap_err().say(Lang::English)
+        );
+    }
+
+    #[test]
+    fn reads_the_range_and_difficulty() {
+        let options = parse(&["--range", "10-1000", "--difficulty", "hard"]).unwrap();
+        assert_eq!((10, 1000), (options.low, options.high));
+        assert_eq!(Difficulty::Hard, options.difficulty);
+
+        let options = parse(&[]).unwrap();
+        assert_eq!((LOW, HIGH), (options.low, options.high));
+        assert_eq!(Difficulty::Normal, options.difficulty);
+
+        for range in &["0-10", "10-10", "20-10", "1-", "1-4294967295"] {
+            assert!(parse(&["--range", range]).is_err(), "{}", range);
+        }
+        assert!(parse(&["--difficulty", "medium"]).is_err());
+    }
+
+    #[test]
+    fn reads_the_language() {
+        assert_eq!(Lang::English, parse(&[]).unwrap().lang);
+        assert_eq!(Lang::Spanish, parse(&["--lang", "es"]).unwrap().lang);
+        assert!(parse(&["--lang", "xx"]).is_err());
+        assert!(parse(&["--lang"]).is_err());
+    }
+
+    #[test]
+    fn says_whats_wrong_in_the_language_asked_for() {
+        let args = args(&["--lang", "es", "--difficulty", "medium"]);
+        let lang = Options::requested_lang(&args);
+        assert_eq!(Lang::Spanish, lang);
+        assert_eq!(
+            "dificultad desconocida `medium`, se esperaba `easy`, `normal` o `hard`",
+            Options::parse(&args).unwrap_err().say(lang)
+        );
+
+        assert_eq!(
+            Lang::English,
+            Options::requested_lang(&self::args(&["--lang"]))
+        );
+        assert_eq!(
+            Lang::English,
+            Options::requested_lang(&self::args(&["--lang", "xx"]))
+        );
+    }
+
+    #[test]
+    fn reads_where_to_play() {
+        assert_eq!(Some(7878), parse(&["--host", "7878"]).unwrap().host);
+        assert_eq!(
+            Some(String::from("localhost:7878")),
+            parse(&["--join", "localhost:7878"]).unwrap().join
+        );
+        assert!(parse(&["--host", "port"]).is_err());
+        assert!(parse(&["--join"]).is_err());
+        assert!(parse(&["--host", "1", "--join", "localhost:1"]).is_err());
+    }
+
+    #[test]
+    fn reads_the_time_limit() {
+        let limit = |args| parse(args).map(|options| options.time_limit.map(|l| l.limit()));
+
+        assert_eq!(Ok(None), limit(&[]));
+        assert_eq!(Ok(Some(Duration::from_secs(60))), limit(&["--timed"]));
+        assert_eq!(
+            Ok(Some(Duration::from_secs(30))),
+            limit(&["--time-limit", "30"])
+        );
+        assert!(limit(&["--time-limit", "0"]).is_err());
+        assert!(limit(&["--time-limit"]).is_err());
+    }
+}
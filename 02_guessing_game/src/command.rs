@@ -0,0 +1,101 @@
+/// Something the player typed at the prompt.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum Command {
+    Guess(u32),
+    /// Show the instructions again
+    Help,
+    /// Stop playing
+    Quit,
+}
+
+impl Command {
+    /// Makes sense of one line of input, ignoring the whitespace around it
+    /// and the case of the words. None if it's neither a number nor one of
+    /// the words.
+    pub fn parse(input: &str) -> Option<Command> {
+        let input = input.trim();
+
+        // A number is a guess, anything else has to be a command
+        if let Ok(number) = input.parse() {
+            return Some(Command::Guess(number));
+        }
+        match input.to_lowercase().as_str() {
+            "help" | "h" | "?" => Some(Command::Help),
+            "quit" | "q" | "exit" => Some(Command::Quit),
+            _ => None,
+        }
+    }
+}
+
+/// Why a line of input isn't a guess.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum GuessInputError {
+    /// Nothing but whitespace
+    Empty,
+    /// Something that isn't a whole number
+    NotANumber,
+    /// A number, but one the secret can't be
+    OutOfRange,
+}
+
+/// Reads a guess at a secret from `low` to `high`, ignoring the whitespace
+/// around it. Negative numbers and ones too big for a u32 are out of range
+/// rather than not numbers at all.
+pub fn parse_guess(input: &str, low: u32, high: u32) -> Result<u32, GuessInputError> {
+    let input = input.trim();
+    if input.is_empty() {
+        return Err(GuessInputError::Empty);
+    }
+
+    match input.parse::<i128>() {
+        Ok(number) if number >= low.into() && number <= high.into() => Ok(number as u32),
+        Ok(_) => Err(GuessInputError::OutOfRange),
+        Err(_) => Err(GuessInputError::NotANumber),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn parses_guesses_and_words() {
+        assert_eq!(Some(Command::Guess(42)), Command::parse("42\n"));
+        assert_eq!(Some(Command::Guess(7)), Command::parse("  7 "));
+        assert_eq!(Some(Command::Quit), Command::parse("q"));
+        assert_eq!(Some(Command::Quit), Command::parse("QUIT\r\n"));
+        assert_eq!(Some(Command::Help), Command::parse("help"));
+    }
+
+    #[test]
+    fn rejects_anything_else() {
+        assert_eq!(None, Command::parse("forty-two"));
+        assert_eq!(None, Command::parse("-3"));
+        assert_eq!(None, Command::parse(""));
+    }
+
+    #[test]
+    fn parses_guesses_in_the_range() {
+        assert_eq!(Ok(42), parse_guess(" 42\n", 1, 100));
+        assert_eq!(Ok(1), parse_guess("1", 1, 100));
+        assert_eq!(Ok(100), parse_guess("100", 1, 100));
+    }
+
+    #[test]
+    fn tells_why_a_guess_is_no_good() {
+        assert_eq!(Err(GuessInputError::Empty), parse_guess("", 1, 100));
+        assert_eq!(Err(GuessInputError::Empty), parse_guess(" \r\n", 1, 100));
+        assert_eq!(
+            Err(GuessInputError::NotANumber),
+            parse_guess("forty-two", 1, 100)
+        );
+        assert_eq!(Err(GuessInputError::NotANumber), parse_guess("4.2", 1, 100));
+        assert_eq!(Err(GuessInputError::OutOfRange), parse_guess("0", 1, 100));
+        assert_eq!(Err(GuessInputError::OutOfRange), parse_guess("101", 1, 100));
+        assert_eq!(Err(GuessInputError::OutOfRange), parse_guess("-3", 1, 100));
+        assert_eq!(
+            Err(GuessInputError::OutOfRange),
+            parse_guess("99999999999", 1, 100)
+        );
+    }
+}
@@ -0,0 +1,86 @@
+use std::fmt;
+use std::str::FromStr;
+
+use crate::Msg;
+
+/// How many guesses a game allows, measured against par: the most it
+/// takes to be sure of the number by halving the range each time.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Hash, Default)]
+pub enum Difficulty {
+    /// As many guesses as it takes
+    Easy,
+    /// A few guesses over par
+    #[default]
+    Normal,
+    /// Par and no more, so every guess has to count
+    Hard,
+}
+
+impl Difficulty {
+    /// The most guesses a game with `par` as its par allows, or None if
+    /// there's no limit.
+    pub fn guess_limit(self, par: u32) -> Option<u32> {
+        match self {
+            Difficulty::Easy => None,
+            Difficulty::Normal => Some(par + 3),
+            Difficulty::Hard => Some(par),
+        }
+    }
+
+    /// What the difficulty is called in the game's messages. The names
+    /// from Display are for the command line and the records file.
+    pub fn msg(self) -> Msg {
+        match self {
+            Difficulty::Easy => Msg::Easy,
+            Difficulty::Normal => Msg::Normal,
+            Difficulty::Hard => Msg::Hard,
+        }
+    }
+}
+
+impl FromStr for Difficulty {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Difficulty, String> {
+        match s {
+            "easy" => Ok(Difficulty::Easy),
+            "normal" => Ok(Difficulty::Normal),
+            "hard" => Ok(Difficulty::Hard),
+            _ => Err(format!(
+                "unknown difficulty `{}`, expected `easy`, `normal` or `hard`",
+                s
+            )),
+        }
+    }
+}
+
+impl fmt::Display for Difficulty {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        let name = match self {
+            Difficulty::Easy => "easy",
+            Difficulty::Normal => "normal",
+            Difficulty::Hard => "hard",
+        };
+        write!(f, "{}", name)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn limits_guesses_around_par() {
+        assert_eq!(None, Difficulty::Easy.guess_limit(7));
+        assert_eq!(Some(10), Difficulty::Normal.guess_limit(7));
+        assert_eq!(Some(7), Difficulty::Hard.guess_limit(7));
+    }
+
+    #[test]
+    fn reads_back_its_name() {
+        for difficulty in [Difficulty::Easy, Difficulty::Normal, Difficulty::Hard] {
+            assert_eq!(Ok(difficulty), difficulty.to_string().parse());
+        }
+        assert!("medium".parse::<Difficulty>().is_err());
+    }
+}
@@ -0,0 +1,402 @@
+use std::cmp::Ordering;
+
+use rand::rngs::StdRng;
+use rand::{Rng, SeedableRng};
+
+mod cli;
+mod command;
+mod difficulty;
+mod messages;
+mod mode;
+pub mod net;
+mod records;
+pub mod reverse;
+pub mod scores;
+mod stats;
+pub mod style;
+mod timed;
+pub mod words;
+
+pub use cli::{ArgsError, Options};
+pub use command::{parse_guess, Command, GuessInputError};
+pub use difficulty::Difficulty;
+pub use messages::{Lang, Msg};
+pub use mode::Mode;
+pub use records::Records;
+pub use reverse::Guesser;
+pub use scores::Score;
+pub use stats::SessionStats;
+pub use timed::TimeLimit;
+
+// The range the secret number is picked from, both ends included
+pub const LOW: u32 = 1;
+pub const HIGH: u32 = 100;
+
+/// One round of guess the number: a secret, and the guesses made at it.
+/// The secret is a u32 unless it's something else that can be put in
+/// order, like the words of word mode.
+///
+/// The game doesn't read or print anything itself, so it can be played
+/// from main, or from tests with a secret they already know, or from
+/// anything else that can keep guessing while the state says so:
+///
+/// ```
+/// use guessing_game::{Game, GameState, GuessOutcome};
+///
+/// let mut game = Game::with_secret(42);
+/// assert_eq!(GuessOutcome::TooSmall, game.guess(10));
+/// assert_eq!(GameState::AwaitingGuess, game.state());
+/// assert_eq!(GuessOutcome::Correct, game.guess(42));
+/// assert_eq!(GameState::Won, game.state());
+/// assert_eq!(2, game.guesses());
+/// ```
+#[derive(Debug, Clone, PartialEq)]
+pub struct Game<T = u32> {
+    secret: T,
+    // The range the secret is from, both ends included
+    low: T,
+    high: T,
+    // How many secrets there were to pick from, for working out par
+    choices: u64,
+    // Every guess that counted, in the order they were made
+    history: Vec<T>,
+    // After this many wrong guesses the game is lost, if there's a limit
+    max_guesses: Option<u32>,
+    state: GameState,
+}
+
+/// Where a game is at.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum GameState {
+    /// Still going: the next guess is up
+    AwaitingGuess,
+    /// The number was guessed
+    Won,
+    /// The guesses ran out first
+    Lost,
+}
+
+/// What a guess turned out to be.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum GuessOutcome {
+    TooSmall,
+    TooBig,
+    Correct,
+    /// Wrong, and it was the last guess allowed
+    OutOfGuesses,
+    /// Guessed before, so it doesn't count as another try
+    Repeated,
+}
+
+impl Game<u32> {
+    /// A game with a secret picked by `rng` from LOW to HIGH. Any Rng
+    /// will do: `rand::thread_rng()` for a real game, or a seeded one like
+    /// `StdRng::from_seed` for the same secret every time.
+    pub fn random<R: Rng>(rng: &mut R) -> Game {
+        Game::random_between(rng, LOW, HIGH)
+    }
+
+    /// A game with a secret picked by `rng` from `low` to `high`.
+    ///
+    /// # Panics
+    ///
+    /// Panics if `high` is below `low`, or is u32::MAX.
+    pub fn random_between<R: Rng>(rng: &mut R, low: u32, high: u32) -> Game {
+        // gen_range leaves out the upper bound, hence the + 1
+        Game::with_secret_between(rng.gen_range(low, high + 1), low, high)
+    }
+
+    /// The daily challenge for `day` (days since the Unix epoch), from
+    /// `low` to `high`. The secret comes from an rng seeded with the day,
+    /// so everyone playing the same range that day gets the same number.
+    ///
+    /// # Panics
+    ///
+    /// Panics if `high` is below `low`, or is u32::MAX.
+    pub fn daily(day: u64, low: u32, high: u32) -> Game {
+        let mut seed = [0; 32];
+        seed[..8].copy_from_slice(&day.to_le_bytes());
+        Game::random_between(&mut StdRng::from_seed(seed), low, high)
+    }
+
+    /// A game with `secret` as the number to guess.
+    ///
+    /// # Panics
+    ///
+    /// Panics if `secret` isn't between LOW and HIGH.
+    pub fn with_secret(secret: u32) -> Game {
+        Game::with_secret_between(secret, LOW, HIGH)
+    }
+
+    /// A game with `secret` as the number to guess, from `low` to `high`.
+    ///
+    /// # Panics
+    ///
+    /// Panics if `secret` isn't between `low` and `high`.
+    pub fn with_secret_between(secret: u32, low: u32, high: u32) -> Game {
+        assert!(
+            (low..=high).contains(&secret),
+            "the secret must be between {} and {}, got {}",
+            low,
+            high,
+            secret
+        );
+
+        Game::new(secret, low, high, u64::from(high - low) + 1)
+    }
+}
+
+impl<T: Ord + Clone> Game<T> {
+    /// A game with a secret picked by `rng` from `choices`, which can be
+    /// in any order.
+    ///
+    /// # Panics
+    ///
+    /// Panics if there are no choices.
+    pub fn pick_from<R: Rng>(rng: &mut R, choices: &[T]) -> Game<T> {
+        let secret = rng.choose(choices).expect("there's nothing to pick from");
+        let low = choices.iter().min().unwrap();
+        let high = choices.iter().max().unwrap();
+
+        Game::new(
+            secret.clone(),
+            low.clone(),
+            high.clone(),
+            choices.len() as u64,
+        )
+    }
+
+    fn new(secret: T, low: T, high: T, choices: u64) -> Game<T> {
+        Game {
+            secret,
+            low,
+            high,
+            choices,
+            history: Vec::new(),
+            max_guesses: None,
+            state: GameState::AwaitingGuess,
+        }
+    }
+
+    /// Limits the game to `max_guesses` guesses. Without a limit, it goes
+    /// on until the number is guessed.
+    pub fn max_guesses(mut self, max_guesses: u32) -> Game<T> {
+        self.max_guesses = Some(max_guesses);
+        self
+    }
+
+    /// Checks `guess` against the secret, counting it as one more guess.
+    /// Something that's been guessed already is Repeated instead, and
+    /// isn't counted again.
+    ///
+    /// Once the game is over, guesses don't count any more: they get the
+    /// outcome that ended it, Correct or OutOfGuesses, whatever they are.
+    pub fn guess(&mut self, guess: T) -> GuessOutcome {
+        match self.state {
+            GameState::AwaitingGuess => {}
+            GameState::Won => return GuessOutcome::Correct,
+            GameState::Lost => return GuessOutcome::OutOfGuesses,
+        }
+        if self.history.contains(&guess) {
+            return GuessOutcome::Repeated;
+        }
+
+        // A `match` on an Ordering has to cover Less, Greater and Equal
+        let ordering = guess.cmp(&self.secret);
+        self.history.push(guess);
+        let outcome = match ordering {
+            Ordering::Less => GuessOutcome::TooSmall,
+            Ordering::Greater => GuessOutcome::TooBig,
+            Ordering::Equal => {
+                self.state = GameState::Won;
+                return GuessOutcome::Correct;
+            }
+        };
+
+        match self.max_guesses {
+            Some(max) if self.guesses() >= max => {
+                self.state = GameState::Lost;
+                GuessOutcome::OutOfGuesses
+            }
+            _ => outcome,
+        }
+    }
+
+    pub fn state(&self) -> GameState {
+        self.state
+    }
+
+    /// Whether the game has been won or lost.
+    pub fn is_over(&self) -> bool {
+        self.state != GameState::AwaitingGuess
+    }
+
+    /// How many guesses have been made so far.
+    pub fn guesses(&self) -> u32 {
+        self.history.len() as u32
+    }
+
+    /// The guesses made so far, in the order they were made.
+    pub fn history(&self) -> &[T] {
+        &self.history
+    }
+
+    /// The guesses made so far, smallest first, which shows the gap the
+    /// secret has to be in.
+    pub fn sorted_history(&self) -> Vec<T> {
+        let mut sorted = self.history.clone();
+        sorted.sort_unstable();
+        sorted
+    }
+
+    /// How many guesses the game allows, if there's a limit.
+    pub fn guess_limit(&self) -> Option<u32> {
+        self.max_guesses
+    }
+
+    /// How many guesses it takes to be sure of the secret, halving the
+    /// range each time.
+    pub fn par(&self) -> u32 {
+        // n guesses can tell apart 2^n - 1 choices
+        u64::BITS - self.choices.leading_zeros()
+    }
+
+    /// The range the secret is from, both ends included.
+    pub fn range(&self) -> (T, T) {
+        (self.low.clone(), self.high.clone())
+    }
+
+    pub fn secret(&self) -> T {
+        self.secret.clone()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn compares_guesses_with_the_secret() {
+        let mut game = Game::with_secret(50);
+
+        assert_eq!(GuessOutcome::TooSmall, game.guess(1));
+        assert_eq!(GuessOutcome::TooBig, game.guess(100));
+        assert_eq!(GuessOutcome::TooSmall, game.guess(49));
+        assert_eq!(GuessOutcome::Correct, game.guess(50));
+        assert_eq!(4, game.guesses());
+    }
+
+    #[test]
+    fn is_lost_once_the_guesses_run_out() {
+        let mut game = Game::with_secret(7).max_guesses(3);
+
+        assert_eq!(GuessOutcome::TooBig, game.guess(8));
+        assert_eq!(GuessOutcome::TooSmall, game.guess(6));
+        assert_eq!(GuessOutcome::OutOfGuesses, game.guess(9));
+
+        // The last guess can still win
+        let mut game = Game::with_secret(7).max_guesses(2);
+        game.guess(1);
+        assert_eq!(GuessOutcome::Correct, game.guess(7));
+    }
+
+    #[test]
+    fn plays_with_anything_that_has_an_order() {
+        let words = ["pear", "apple", "plum", "fig"];
+        let mut game = Game::pick_from(&mut StdRng::from_seed([3; 32]), &words);
+
+        assert!(words.contains(&game.secret()));
+        assert_eq!(("apple", "plum"), game.range());
+        assert_eq!(3, game.par());
+        for word in &["apple", "fig", "pear", "plum"] {
+            match game.guess(word) {
+                GuessOutcome::Correct => break,
+                outcome => assert!(*word < game.secret(), "{:?} for {}", outcome, word),
+            }
+        }
+        assert_eq!(GameState::Won, game.state());
+    }
+
+    #[test]
+    fn doesnt_count_a_repeated_guess() {
+        let mut game = Game::with_secret(40).max_guesses(3);
+
+        assert_eq!(GuessOutcome::TooBig, game.guess(80));
+        assert_eq!(GuessOutcome::TooSmall, game.guess(20));
+        assert_eq!(GuessOutcome::Repeated, game.guess(80));
+        assert_eq!(2, game.guesses());
+        assert_eq!(GameState::AwaitingGuess, game.state());
+
+        assert_eq!(GuessOutcome::Correct, game.guess(40));
+        assert_eq!(&[80, 20, 40], game.history());
+        assert_eq!(vec![20, 40, 80], game.sorted_history());
+    }
+
+    #[test]
+    fn moves_from_guessing_to_won_or_lost() {
+        let mut game = Game::with_secret(7).max_guesses(2);
+        assert_eq!(GameState::AwaitingGuess, game.state());
+        game.guess(1);
+        assert_eq!(GameState::AwaitingGuess, game.state());
+        game.guess(2);
+        assert_eq!(GameState::Lost, game.state());
+        assert!(game.is_over());
+
+        let mut game = Game::with_secret(7);
+        game.guess(7);
+        assert_eq!(GameState::Won, game.state());
+    }
+
+    #[test]
+    fn stops_counting_once_its_over() {
+        let mut game = Game::with_secret(7);
+        game.guess(7);
+        assert_eq!(GuessOutcome::Correct, game.guess(3));
+        assert_eq!(1, game.guesses());
+
+        let mut game = Game::with_secret(7).max_guesses(1);
+        game.guess(3);
+        assert_eq!(GuessOutcome::OutOfGuesses, game.guess(7));
+        assert_eq!(1, game.guesses());
+    }
+
+    #[test]
+    fn the_same_seed_picks_the_same_secret() {
+        let secret = |seed| Game::random(&mut StdRng::from_seed([seed; 32])).secret();
+
+        assert_eq!(secret(1), secret(1));
+        for seed in 0..50 {
+            assert!((LOW..=HIGH).contains(&secret(seed)));
+        }
+
+        let mut rng = StdRng::from_seed([7; 32]);
+        for _ in 0..50 {
+            let game = Game::random_between(&mut rng, 10, 12);
+            assert!((10..=12).contains(&game.secret()));
+            assert_eq!((10, 12), game.range());
+        }
+    }
+
+    #[test]
+    fn gives_everyone_the_same_daily_challenge() {
+        let secret = |day| Game::daily(day, LOW, HIGH).secret();
+
+        assert_eq!(secret(20_000), secret(20_000));
+        assert!((0..30).any(|day| secret(day) != secret(20_000)));
+        assert_eq!((10, 20), Game::daily(20_000, 10, 20).range());
+    }
+
+    #[test]
+    fn knows_par_for_the_range() {
+        // 2^7 - 1 = 127 is the first that's enough for 100 numbers
+        assert_eq!(7, Game::with_secret(1).par());
+        assert_eq!(10, Game::with_secret_between(1, 1, 1000).par());
+        assert_eq!(1, Game::with_secret_between(5, 5, 5).par());
+    }
+
+    #[test]
+    #[should_panic]
+    fn refuses_a_secret_out_of_range() {
+        Game::with_secret(101);
+    }
+}
@@ -1,29 +1,283 @@
-use std::io;
+use std::cmp::Ordering;
+use std::env;
+use std::fmt;
+use std::io::{self, IsTerminal};
+use std::net::{TcpListener, TcpStream};
+use std::process;
+use std::time::Instant;
 // By default, Rust brings only a few types into the scope of every program in the prelude.
 // If a type you want to use isn’t in the prelude, you have to bring that type into scope
 // explicitly with a use statement.
 // Using the std::io library provides you with a number of useful features,
 // including the ability to accept user input.
 
-use std::cmp::Ordering; // An enum, like the Result type
+// The game itself lives in lib.rs, so it can be tested without anyone
+// typing at it. The library crate is used by its package name.
+use guessing_game::net::{self, Connection, HostMessage, Hosted, PlayerMessage};
+use guessing_game::style::{self, Color, Style};
+use guessing_game::{
+    parse_guess, reverse, scores, words, Command, Game, GuessInputError, GuessOutcome, Guesser,
+    Lang, Mode, Msg, Options, Records,
+    Score, SessionStats,
+};
 
-use rand::Rng;
-// The Rng trait defines methods that random number generators implement,
-// and this trait must be in scope for us to use those methods.
+// Where finished games are kept, in the directory the game is run from
+const SCORES_FILE: &str = "scores.csv";
+// The fewest guesses for each mode, range and difficulty, rewritten on every
+// record
+const RECORDS_FILE: &str = "records.csv";
 
 fn main() {
-    println!("#####################");
-    println!("# Guess the number! #");
-    println!("#####################");
+    let args: Vec<String> = env::args().skip(1).collect();
+    let options = Options::parse(&args).unwrap_or_else(|err| {
+        // The rest of the arguments may still say what language to use
+        let lang = Options::requested_lang(&args);
+        eprintln!("{}", lang.say(Msg::BadArguments, &[&err.say(lang)]));
+        process::exit(2);
+    });
+
+    // Colors only where they'll show up as colors, and never for anyone
+    // who's asked for none with NO_COLOR
+    let color =
+        !options.no_color && env::var_os("NO_COLOR").is_none() && io::stdout().is_terminal();
+    let style = Style::new(color);
+    let lang = options.lang;
+
+    if options.show_scores {
+        print_scores(lang);
+        return;
+    }
+    if options.reverse {
+        play_reverse(lang, options.low, options.high);
+        return;
+    }
+    if let Some(port) = options.host {
+        host_game(port, &options);
+        return;
+    }
+    if let Some(addr) = &options.join {
+        join_game(addr, style, lang);
+        return;
+    }
+
+    // The box around the title fits it in any language
+    let title = lang.text(Msg::Title);
+    let border = "#".repeat(title.chars().count() + 4);
+    println!("{}", border);
+    println!("# {} #", title);
+    println!("{}", border);
     println!();
+    let words = words::words();
+    if options.words {
+        print_instructions(lang, &words[0], &words[words.len() - 1]);
+    } else {
+        print_instructions(lang, &options.low, &options.high);
+    }
+    if options.daily {
+        say(lang, Msg::DailyChallenge, &[&scores::date(scores::today())]);
+    }
+    let name = ask_name(lang);
+    let mut records = load_records(lang);
+
+    // Every game played this run counts towards the stats printed at the end
+    let mut stats = SessionStats::new();
+    loop {
+        // Word games are kept apart from number games by their mode, and
+        // ranked over the places in the word list
+        let ending = if options.words {
+            let game = word_game(&options, &words);
+            let places = (1, words.len() as u32);
+            play(&name, game, places, &options, style, &mut records)
+        } else {
+            let range = (options.low, options.high);
+            play(&name, new_game(&options), range, &options, style, &mut records)
+        };
+        match ending {
+            Ending::Won(guesses) => stats.record_win(guesses),
+            Ending::Lost => stats.record_loss(),
+            // Giving up counts as a loss, and means no more games either
+            Ending::Quit => {
+                stats.record_loss();
+                break;
+            }
+        }
+        // Today's number is already known, so there's nothing to play again
+        if options.daily {
+            say(lang, Msg::ComeBackTomorrow, &[]);
+            break;
+        }
+        if !play_again(lang) {
+            break;
+        }
+        println!();
+    }
 
-    // Rust defaults to i32 by default
-    // One of the many integer types
-    let secret_number = rand::thread_rng().gen_range(1, 101); // Immutable
+    print_stats(lang, &stats);
+}
+
+// How a game came to an end
+enum Ending {
+    Won(u32),
+    Lost,
+    Quit,
+}
+
+// What can be guessed at: numbers, or words in word mode. Everything else
+// about a game is the same for both.
+trait Secret: Ord + Clone + fmt::Display {
+    // What the game's scores and records are kept under
+    const MODE: Mode;
+    // The instructions, with blanks for the two ends of the range
+    const INSTRUCTIONS: Msg;
+    // What to say when a guess is below or above the secret
+    const TOO_SMALL: Msg;
+    const TOO_BIG: Msg;
+    // What to say to something that can't be a guess at all
+    const NOT_A_GUESS: Msg;
+
+    // A guess at a secret from `low` to `high`, or why `input` isn't one
+    fn read(input: &str, low: &Self, high: &Self) -> Result<Self, GuessInputError>;
+}
+
+impl Secret for u32 {
+    const MODE: Mode = Mode::Numbers;
+    const INSTRUCTIONS: Msg = Msg::Instructions;
+    const TOO_SMALL: Msg = Msg::TooSmall;
+    const TOO_BIG: Msg = Msg::TooBig;
+    const NOT_A_GUESS: Msg = Msg::NotANumber;
+
+    fn read(input: &str, low: &u32, high: &u32) -> Result<u32, GuessInputError> {
+        parse_guess(input, *low, *high)
+    }
+}
+
+impl Secret for String {
+    const MODE: Mode = Mode::Words;
+    const INSTRUCTIONS: Msg = Msg::WordInstructions;
+    const TOO_SMALL: Msg = Msg::ComesAfter;
+    const TOO_BIG: Msg = Msg::ComesBefore;
+    const NOT_A_GUESS: Msg = Msg::NotAWord;
+
+    // A word that isn't one isn't a number either, but NOT_A_GUESS is
+    // what the player sees for it
+    fn read(input: &str, low: &String, high: &String) -> Result<String, GuessInputError> {
+        if input.trim().is_empty() {
+            return Err(GuessInputError::Empty);
+        }
+        match words::parse_word(input) {
+            Some(word) if word < *low || word > *high => Err(GuessInputError::OutOfRange),
+            Some(word) => Ok(word),
+            None => Err(GuessInputError::NotANumber),
+        }
+    }
+}
+
+// Tells the player why what they typed isn't a guess at a secret from
+// `low` to `high`
+fn reject<T: Secret>(lang: Lang, err: GuessInputError, low: &T, high: &T) {
+    match err {
+        GuessInputError::Empty => say(lang, Msg::EmptyGuess, &[]),
+        GuessInputError::NotANumber => say(lang, T::NOT_A_GUESS, &[]),
+        GuessInputError::OutOfRange => say(lang, Msg::OutOfRange, &[low, high]),
+    }
+}
+
+// A new game over the range, with as many guesses as the difficulty allows
+fn new_game(options: &Options) -> Game {
+    let game = if options.daily {
+        Game::daily(scores::today(), options.low, options.high)
+    } else {
+        // thread_rng() is a random number generator local to the current
+        // thread, seeded by the operating system
+        Game::random_between(&mut rand::thread_rng(), options.low, options.high)
+    };
+    limit_guesses(game, options)
+}
+
+fn limit_guesses<T: Secret>(game: Game<T>, options: &Options) -> Game<T> {
+    match options.difficulty.guess_limit(game.par()) {
+        Some(limit) => game.max_guesses(limit),
+        None => game,
+    }
+}
+
+// A new game of guess the word, picked from `words`
+fn word_game(options: &Options, words: &[String]) -> Game<String> {
+    let game = limit_guesses(Game::pick_from(&mut rand::thread_rng(), words), options);
+    say(options.lang, Msg::WordLength, &[&game.secret().chars().count()]);
+    game
+}
+
+// Plays a game, and keeps the score if it's won. The score and the record
+// go under the game's mode and `low` to `high`.
+fn play<T: Secret>(
+    name: &str,
+    mut game: Game<T>, // Mutable, since guesses change it
+    (low, high): (u32, u32),
+    options: &Options,
+    style: Style,
+    records: &mut Records,
+) -> Ending {
+    let (mode, difficulty, lang) = (T::MODE, options.difficulty, options.lang);
+
+    if let Some(best) = records.best(mode, low, high, difficulty) {
+        let called = lang.text(difficulty.msg());
+        match mode {
+            Mode::Numbers => say(lang, Msg::Record, &[&low, &high, &called, &best]),
+            Mode::Words => say(lang, Msg::WordRecord, &[&called, &best]),
+        }
+    }
+
+    let ending = play_game(&mut game, options, style);
+    if let Ending::Won(guesses) = ending {
+        let score = Score {
+            daily: options.daily,
+            mode,
+            ..Score::now(name, guesses, low, high)
+        };
+        // Losing the score is no reason to spoil the win
+        if let Err(e) = scores::record(SCORES_FILE, &score) {
+            eprintln!("{}", lang.say(Msg::ScoreNotSaved, &[&SCORES_FILE, &e]));
+        }
+        if records.beat(mode, low, high, difficulty, guesses) {
+            paint(lang, style, Msg::NewRecord, Color::Green);
+            if let Err(e) = records.save(RECORDS_FILE) {
+                eprintln!("{}", lang.say(Msg::RecordNotSaved, &[&RECORDS_FILE, &e]));
+            }
+        }
+    }
+    ending
+}
+
+// Plays one game through, until it's won, lost or given up on. With a time
+// limit, running out of time loses it too.
+fn play_game<T: Secret>(game: &mut Game<T>, options: &Options, style: Style) -> Ending {
+    let (time_limit, lang) = (options.time_limit, options.lang);
+
+    if let Some(limit) = game.guess_limit() {
+        let called = lang.text(options.difficulty.msg());
+        say(lang, Msg::GuessLimit, &[&limit, &called]);
+    }
+
+    // Instant is a point in time from a clock that only ever goes forward
+    let started = Instant::now();
+    if let Some(limit) = time_limit {
+        say(lang, Msg::TimeLimit, &[&limit.limit().as_secs()]);
+    }
 
     loop { // An infinite loop
 
-        println!("Please input your guess.");
+        // There's no telling how long the player will think, so the
+        // warning comes with each prompt once time is nearly up
+        if let Some(limit) = time_limit {
+            if limit.is_nearly_up(started.elapsed()) {
+                if let Some(left) = limit.remaining(started.elapsed()) {
+                    say(lang, Msg::HurryUp, &[&(left.as_secs() + 1)]);
+                }
+            }
+        }
+
+        say(lang, Msg::InputGuess, &[]);
 
         // Variables in Rust are declared with `let`
 
@@ -37,7 +291,7 @@ fn main() {
         let mut guess = String::new(); // A mutable variable
 
         // We can use `io` instead of `std::io` owing to the `use` declaration
-        io::stdin() // std::io::stdin() returns an instance of std::io::Stdin
+        let read = io::stdin() // std::io::stdin() returns an instance of std::io::Stdin
 
             // read_line() method on the Stdin object
             // Takes input on stdinput and places it into the
@@ -56,27 +310,366 @@ fn main() {
             .expect("Failed to read line");
         // If expect is not called, the program would compile with warnings
 
-        println!("You guessed: {}", guess.trim());
+        // Nothing read at all means the input has ended (Ctrl-D), and there
+        // will never be another guess
+        if read == 0 {
+            println!();
+            quit(lang, game);
+            return Ending::Quit;
+        }
+
+        // A guess that comes in too late doesn't count
+        if let Some(limit) = time_limit {
+            if limit.remaining(started.elapsed()).is_none() {
+                say(lang, Msg::TimeUp, &[&game.secret()]);
+                return Ending::Lost;
+            }
+        }
 
         // Rust allows you to `shadow` the previous value of `guess` with a new one
         // Often used for type conversion
-        // Trim whitespace
-        // Parse to u32 (inferred from type of `guess`
-        // Error handing example in Rust:
-        let guess: u32 = match guess.trim().parse() {
-            Ok(num) => num,
-            Err(_) => continue, // The _ is a catchall value
+        // Command::parse trims the whitespace and picks out the commands,
+        // and anything else had better be a guess in the range
+        let (low, high) = game.range();
+        let guess: T = match Command::parse(&guess) {
+            Some(Command::Help) => {
+                print_instructions(lang, &low, &high);
+                continue;
+            }
+            Some(Command::Quit) => {
+                quit(lang, game);
+                return Ending::Quit;
+            }
+            _ => match T::read(&guess, &low, &high) {
+                Ok(guess) => guess,
+                Err(err) => {
+                    reject(lang, err, &low, &high);
+                    continue;
+                }
+            },
         };
 
+        say(lang, Msg::YouGuessed, &[&guess]);
+
         // A `match` expression is made up of `arms`
         // Sort of like a switch statement
-        match guess.cmp(&secret_number) {
-            Ordering::Less => println!("Too small!"),
-            Ordering::Greater => println!("Too big!"),
-            Ordering::Equal => {
-                println!("You win!");
-                break;
+        match game.guess(guess.clone()) {
+            GuessOutcome::TooSmall => paint(lang, style, T::TOO_SMALL, Color::Blue),
+            GuessOutcome::TooBig => paint(lang, style, T::TOO_BIG, Color::Red),
+            GuessOutcome::Correct => {
+                paint(lang, style, Msg::YouWin, Color::Green);
+                if let Some(limit) = time_limit {
+                    let took = started.elapsed();
+                    let secs = format!("{:.1}", took.as_secs_f64());
+                    say(lang, Msg::TimedScore, &[&secs, &limit.score(game.guesses(), took)]);
+                }
+                return Ending::Won(game.guesses());
+            }
+            // Only when the difficulty limits the guesses
+            GuessOutcome::OutOfGuesses => {
+                say(lang, Msg::OutOfGuesses, &[&game.secret()]);
+                return Ending::Lost;
+            }
+            GuessOutcome::Repeated => {
+                say(lang, Msg::Repeated, &[&guess]);
+                continue;
+            }
+        }
+
+        // Guesses used so far, out of the limit, or out of the most it
+        // should take if there isn't one
+        let total = game.guess_limit().unwrap_or_else(|| game.par());
+        println!("{}", style::progress_bar(game.guesses(), total, total as usize));
+        say(lang, Msg::History, &[&history_line(&game.sorted_history())]);
+    }
+}
+
+// The guesses, separated by spaces
+fn history_line<T: fmt::Display>(guesses: &[T]) -> String {
+    let guesses: Vec<String> = guesses.iter().map(|guess| guess.to_string()).collect();
+    guesses.join(" ")
+}
+
+// The computer guesses, and the player says how close it got
+fn play_reverse(lang: Lang, low: u32, high: u32) {
+    say(lang, Msg::ThinkOfANumber, &[&low, &high]);
+    say(lang, Msg::HowToAnswer, &[]);
+    println!();
+
+    let mut guesser = Guesser::between(low, high);
+    loop {
+        let guess = match guesser.next_guess() {
+            Some(guess) => guess,
+            None => {
+                say(lang, Msg::Cheated, &[]);
+                return;
+            }
+        };
+
+        // Ask until the answer makes sense
+        let answer = loop {
+            say(lang, Msg::IsIt, &[&guess]);
+
+            let mut answer = String::new();
+            let read = io::stdin()
+                .read_line(&mut answer)
+                .expect("Failed to read line");
+            if read == 0 || Command::parse(&answer) == Some(Command::Quit) {
+                say(lang, Msg::KeepYourSecret, &[]);
+                return;
+            }
+            match reverse::parse_answer(&answer) {
+                Some(answer) => break answer,
+                None => say(lang, Msg::AnswerAgain, &[]),
             }
+        };
+
+        if answer == Ordering::Equal {
+            say(lang, Msg::GotIt, &[&guesser.guesses()]);
+            return;
+        }
+        guesser.answer(answer);
+    }
+}
+
+// Picks the secret and waits for someone to come and guess it
+fn host_game(port: u16, options: &Options) {
+    let lang = options.lang;
+
+    // 0.0.0.0 listens on every network the machine is on, not just this one
+    let listener = TcpListener::bind(("0.0.0.0", port)).unwrap_or_else(|e| {
+        eprintln!("{}", lang.say(Msg::CantListen, &[&port, &e]));
+        process::exit(1);
+    });
+    say(lang, Msg::WaitingForPlayer, &[&port]);
+
+    let (stream, addr) = listener.accept().unwrap_or_else(|e| {
+        eprintln!("{}", lang.say(Msg::CantAccept, &[&e]));
+        process::exit(1);
+    });
+    let mut game = new_game(options);
+    say(lang, Msg::PlayerJoined, &[&addr, &game.secret()]);
+
+    let mut connection = Connection::new(stream).expect("Failed to set up the connection");
+    let watch = |guess, outcome| {
+        let how = match outcome {
+            GuessOutcome::TooSmall => Msg::TheirTooSmall,
+            GuessOutcome::TooBig => Msg::TheirTooBig,
+            GuessOutcome::Correct => Msg::TheirCorrect,
+            GuessOutcome::OutOfGuesses => Msg::TheirLastTry,
+            GuessOutcome::Repeated => Msg::TheirRepeat,
+        };
+        say(lang, Msg::TheyGuessed, &[&guess, &lang.text(how)]);
+    };
+    match net::host(&mut connection, &mut game, watch) {
+        Ok(Hosted::Won) => say(lang, Msg::TheyWon, &[&game.guesses()]),
+        Ok(Hosted::Lost) => say(lang, Msg::TheyLost, &[]),
+        Ok(Hosted::Left) => say(lang, Msg::TheyLeft, &[]),
+        Err(e) => eprintln!("{}", lang.say(Msg::ConnectionLost, &[&e])),
+    }
+}
+
+// Plays a game someone else is hosting, sending them each guess
+fn join_game(addr: &str, style: Style, lang: Lang) {
+    let stream = TcpStream::connect(addr).unwrap_or_else(|e| {
+        eprintln!("{}", lang.say(Msg::CantConnect, &[&addr, &e]));
+        process::exit(1);
+    });
+    let mut connection = Connection::new(stream).expect("Failed to set up the connection");
+
+    let (low, high) = match receive(lang, &mut connection) {
+        Some(HostMessage::Welcome { low, high }) => (low, high),
+        _ => {
+            eprintln!("{}", lang.say(Msg::NotHosting, &[&addr]));
+            process::exit(1);
+        }
+    };
+    say(lang, Msg::Joined, &[&low, &high]);
+
+    loop {
+        say(lang, Msg::InputGuess, &[]);
+
+        let mut input = String::new();
+        let read = io::stdin()
+            .read_line(&mut input)
+            .expect("Failed to read line");
+        // The end of the input is as good as giving up
+        let command = if read == 0 {
+            Some(Command::Quit)
+        } else {
+            Command::parse(&input)
+        };
+        let message = match command {
+            Some(Command::Help) => {
+                say(lang, Msg::JoinedHelp, &[&low, &high]);
+                continue;
+            }
+            Some(Command::Quit) => PlayerMessage::Quit,
+            _ => match parse_guess(&input, low, high) {
+                Ok(num) => PlayerMessage::Guess(num),
+                Err(err) => {
+                    reject(lang, err, &low, &high);
+                    continue;
+                }
+            },
+        };
+        if let Err(e) = connection.send(message) {
+            say(lang, Msg::HostLost, &[&e]);
+            return;
+        }
+
+        match receive(lang, &mut connection) {
+            Some(HostMessage::TooSmall) => paint(lang, style, Msg::TooSmall, Color::Blue),
+            Some(HostMessage::TooBig) => paint(lang, style, Msg::TooBig, Color::Red),
+            Some(HostMessage::Win(guesses)) => {
+                let win = lang.say(Msg::YouWinIn, &[&guesses]);
+                println!("{}", style.paint(&win, Color::Green));
+                return;
+            }
+            Some(HostMessage::Lose(secret)) => {
+                say(lang, Msg::GameOver, &[&secret]);
+                return;
+            }
+            Some(HostMessage::Repeat(guess)) => say(lang, Msg::Repeated, &[&guess]),
+            Some(HostMessage::Error(reason)) => say(lang, Msg::HostSaid, &[&reason]),
+            Some(HostMessage::Welcome { .. }) | None => {
+                say(lang, Msg::HostGone, &[]);
+                return;
+            }
+        }
+    }
+}
+
+// The next message from the host. None if they've gone, or sent something
+// that isn't a message.
+fn receive(lang: Lang, connection: &mut Connection) -> Option<HostMessage> {
+    match connection.receive() {
+        Ok(Some(line)) => HostMessage::parse(&line),
+        Ok(None) => None,
+        Err(e) => {
+            eprintln!("{}", lang.say(Msg::HostLost, &[&e]));
+            None
+        }
+    }
+}
+
+// Prints what the game says for `msg` in `lang`, filled in with `args`
+fn say(lang: Lang, msg: Msg, args: &[&dyn fmt::Display]) {
+    println!("{}", lang.say(msg, args));
+}
+
+// Prints `msg` in `color`, if colors are on
+fn paint(lang: Lang, style: Style, msg: Msg, color: Color) {
+    println!("{}", style.paint(lang.text(msg), color));
+}
+
+fn print_instructions<T: Secret>(lang: Lang, low: &T, high: &T) {
+    say(lang, T::INSTRUCTIONS, &[low, high]);
+    say(lang, Msg::HelpAndQuit, &[]);
+    println!();
+}
+
+fn quit<T: Secret>(lang: Lang, game: &Game<T>) {
+    say(lang, Msg::GiveUp, &[&game.secret()]);
+}
+
+// The player's name for the scoreboard
+fn ask_name(lang: Lang) -> String {
+    say(lang, Msg::AskName, &[]);
+
+    let mut name = String::new();
+    io::stdin().read_line(&mut name).expect("Failed to read line");
+    match name.trim() {
+        "" => String::from("anonymous"),
+        name => name.to_string(),
+    }
+}
+
+// Anything but a yes, or no answer at all, means no
+fn play_again(lang: Lang) -> bool {
+    say(lang, Msg::PlayAgain, &[]);
+
+    let mut answer = String::new();
+    io::stdin().read_line(&mut answer).expect("Failed to read line");
+    matches!(answer.trim().to_lowercase().as_str(), "y" | "yes")
+}
+
+fn print_stats(lang: Lang, stats: &SessionStats) {
+    println!();
+    say(lang, Msg::GamesPlayed, &[&stats.played()]);
+    if let Some(rate) = stats.win_rate() {
+        let percent = format!("{:.0}", rate * 100.0);
+        say(lang, Msg::WinRate, &[&percent, &stats.won()]);
+    }
+    if let Some(average) = stats.average_guesses() {
+        say(lang, Msg::AverageGuesses, &[&format!("{:.1}", average)]);
+    }
+}
+
+// The records so far. A file that's been mangled is no use to anyone, so
+// it's started over rather than stopping anyone from playing.
+fn load_records(lang: Lang) -> Records {
+    match Records::load(RECORDS_FILE) {
+        Ok(records) => records,
+        Err(e) if e.kind() == io::ErrorKind::InvalidData => {
+            eprintln!("{}", lang.say(Msg::RecordsCorrupted, &[&RECORDS_FILE, &e]));
+            let records = Records::new();
+            if let Err(e) = records.save(RECORDS_FILE) {
+                eprintln!("{}", lang.say(Msg::RecordsNotRecreated, &[&RECORDS_FILE, &e]));
+            }
+            records
+        }
+        Err(e) => {
+            eprintln!("{}", lang.say(Msg::RecordsNotRead, &[&RECORDS_FILE, &e]));
+            Records::new()
+        }
+    }
+}
+
+fn print_scores(lang: Lang) {
+    let scores = scores::load(SCORES_FILE).unwrap_or_else(|e| {
+        eprintln!("{}", lang.say(Msg::ScoresNotRead, &[&SCORES_FILE, &e]));
+        process::exit(1);
+    });
+    // Daily challenges are ranked on their own, and only today's count
+    let today = scores::today();
+    let (daily, scores): (Vec<Score>, Vec<Score>) =
+        scores.into_iter().partition(|score| score.daily);
+    let daily: Vec<Score> = daily.into_iter().filter(|score| score.day() == today).collect();
+    if scores.is_empty() && daily.is_empty() {
+        say(lang, Msg::NoScores, &[]);
+        return;
+    }
+
+    if !scores.is_empty() {
+        say(lang, Msg::BestScores, &[]);
+        print_score_lines(lang, &scores);
+    }
+    if !daily.is_empty() {
+        say(lang, Msg::DailyScores, &[&scores::date(today)]);
+        print_score_lines(lang, &daily);
+    }
+}
+
+// The ten best of `scores`, ranked
+fn print_score_lines(lang: Lang, scores: &[Score]) {
+    for (rank, score) in scores::best(scores, 10).iter().enumerate() {
+        // The catalog only fills in blanks, so the columns are lined up
+        // before they go in
+        let rank = format!("{:>2}", rank + 1);
+        let name = format!("{:<20}", score.name);
+        let guesses = format!("{:>3}", score.guesses);
+        match score.mode {
+            Mode::Numbers => say(
+                lang,
+                Msg::ScoreLine,
+                &[&rank, &name, &guesses, &score.low, &score.high, &score.date()],
+            ),
+            Mode::Words => say(
+                lang,
+                Msg::WordScoreLine,
+                &[&rank, &name, &guesses, &score.date()],
+            ),
         }
     }
 }
@@ -0,0 +1,482 @@
+use std::fmt;
+use std::str::FromStr;
+
+/// A language the game can talk in.
+#[derive(Debug, Clone, Copy, PartialEq, Default)]
+pub enum Lang {
+    #[default]
+    English,
+    Spanish,
+}
+
+impl Lang {
+    /// What the game says for `msg`, with each `{}` in it replaced by the
+    /// next of `args`.
+    pub fn say(self, msg: Msg, args: &[&dyn fmt::Display]) -> String {
+        fill(self.text(msg), args)
+    }
+}
+
+impl FromStr for Lang {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Lang, String> {
+        match s {
+            "en" => Ok(Lang::English),
+            "es" => Ok(Lang::Spanish),
+            _ => Err(format!("unknown language `{}`, expected `en` or `es`", s)),
+        }
+    }
+}
+
+// Writes out the Msg enum and Lang::text from each message's text in each
+// language, so a message can't be added without all of its translations
+macro_rules! catalog {
+    ($($msg:ident { en: $en:expr, es: $es:expr $(,)? })*) => {
+        /// Everything the game says to the player, by what it's for. The
+        /// words come from `Lang::text`.
+        #[derive(Debug, Clone, Copy, PartialEq)]
+        pub enum Msg {
+            $($msg,)*
+        }
+
+        #[cfg(test)]
+        const ALL: &[Msg] = &[$(Msg::$msg,)*];
+
+        impl Lang {
+            /// The text for `msg`, with a `{}` wherever something goes.
+            pub fn text(self, msg: Msg) -> &'static str {
+                match self {
+                    Lang::English => match msg {
+                        $(Msg::$msg => $en,)*
+                    },
+                    Lang::Spanish => match msg {
+                        $(Msg::$msg => $es,)*
+                    },
+                }
+            }
+        }
+    };
+}
+
+catalog! {
+    BadArguments {
+        en: "Problem parsing arguments: {}",
+        es: "Problema al leer los argumentos: {}",
+    }
+    RangeNeeded {
+        en: "`--range` needs two numbers like `1-100`, from 1 up to 4294967294",
+        es: "`--range` necesita dos números como `1-100`, de 1 a 4294967294",
+    }
+    DifficultyNeeded {
+        en: "`--difficulty` needs a difficulty",
+        es: "`--difficulty` necesita una dificultad",
+    }
+    UnknownDifficulty {
+        en: "unknown difficulty `{}`, expected `easy`, `normal` or `hard`",
+        es: "dificultad desconocida `{}`, se esperaba `easy`, `normal` o `hard`",
+    }
+    LangNeeded {
+        en: "`--lang` needs a language",
+        es: "`--lang` necesita un idioma",
+    }
+    UnknownLang {
+        en: "unknown language `{}`, expected `en` or `es`",
+        es: "idioma desconocido `{}`, se esperaba `en` o `es`",
+    }
+    TimeLimitNeeded {
+        en: "`--time-limit` needs a number of seconds",
+        es: "`--time-limit` necesita un número de segundos",
+    }
+    PortNeeded {
+        en: "`--host` needs a port to listen on",
+        es: "`--host` necesita un puerto en el que escuchar",
+    }
+    AddressNeeded {
+        en: "`--join` needs an address to connect to",
+        es: "`--join` necesita una dirección a la que conectarse",
+    }
+    UnknownArgument {
+        en: "unknown argument `{}`",
+        es: "argumento desconocido `{}`",
+    }
+    HostAndJoin {
+        en: "can't both host and join a game",
+        es: "no se puede organizar una partida y unirse a otra a la vez",
+    }
+    DailyWords {
+        en: "the daily challenge is a number, not a word",
+        es: "el reto diario es un número, no una palabra",
+    }
+    Title {
+        en: "Guess the number!",
+        es: "¡Adivina el número!",
+    }
+    Instructions {
+        en: "I'm thinking of a number between {} and {}. Try to guess it!",
+        es: "Estoy pensando en un número entre {} y {}. ¡Intenta adivinarlo!",
+    }
+    HelpAndQuit {
+        en: "Type `help` to see this again, or `quit` (or `q`) to give up.",
+        es: "Escribe `help` para volver a ver esto, o `quit` (o `q`) para rendirte.",
+    }
+    AskName {
+        en: "What's your name?",
+        es: "¿Cómo te llamas?",
+    }
+    Easy {
+        en: "easy",
+        es: "fácil",
+    }
+    Normal {
+        en: "normal",
+        es: "normal",
+    }
+    Hard {
+        en: "hard",
+        es: "difícil",
+    }
+    GuessLimit {
+        en: "You have {} guesses on {}.",
+        es: "Tienes {} intentos en modo {}.",
+    }
+    Record {
+        en: "The record for {}-{} on {} is {} guesses.",
+        es: "El récord para {}-{} en modo {} es de {} intentos.",
+    }
+    WordRecord {
+        en: "The record for guessing a word on {} is {} guesses.",
+        es: "El récord para adivinar una palabra en modo {} es de {} intentos.",
+    }
+    TimeLimit {
+        en: "You have {} seconds. Go!",
+        es: "Tienes {} segundos. ¡Adelante!",
+    }
+    HurryUp {
+        en: "Hurry up! {} seconds left.",
+        es: "¡Date prisa! Quedan {} segundos.",
+    }
+    InputGuess {
+        en: "Please input your guess.",
+        es: "Escribe tu número, por favor.",
+    }
+    TimeUp {
+        en: "Time's up! The answer was {}.",
+        es: "¡Se acabó el tiempo! La respuesta era {}.",
+    }
+    NotANumber {
+        en: "That's not a number. Type `help` to see what you can do.",
+        es: "Eso no es un número. Escribe `help` para ver lo que puedes hacer.",
+    }
+    EmptyGuess {
+        en: "Type a guess, or `help` to see what you can do.",
+        es: "Escribe un intento, o `help` para ver lo que puedes hacer.",
+    }
+    OutOfRange {
+        en: "That's not between {} and {}, so it doesn't count.",
+        es: "Eso no está entre {} y {}, así que no cuenta.",
+    }
+    YouGuessed {
+        en: "You guessed: {}",
+        es: "Has dicho: {}",
+    }
+    TooSmall {
+        en: "Too small!",
+        es: "¡Demasiado pequeño!",
+    }
+    TooBig {
+        en: "Too big!",
+        es: "¡Demasiado grande!",
+    }
+    DailyChallenge {
+        en: "Daily challenge for {}: everyone gets the same number today.",
+        es: "Reto diario del {}: hoy todos tienen el mismo número.",
+    }
+    ComeBackTomorrow {
+        en: "Come back tomorrow for a new challenge!",
+        es: "¡Vuelve mañana para un nuevo reto!",
+    }
+    WordInstructions {
+        en: "I'm thinking of a word between `{}` and `{}` in the dictionary. Try to guess it!",
+        es: "Pienso en una palabra entre `{}` y `{}` del diccionario. ¡Intenta adivinarla!",
+    }
+    WordLength {
+        en: "My word has {} letters.",
+        es: "Mi palabra tiene {} letras.",
+    }
+    NotAWord {
+        en: "That's not a word. Type `help` to see what you can do.",
+        es: "Eso no es una palabra. Escribe `help` para ver lo que puedes hacer.",
+    }
+    ComesAfter {
+        en: "My word comes after that one!",
+        es: "¡Mi palabra va después de esa!",
+    }
+    ComesBefore {
+        en: "My word comes before that one!",
+        es: "¡Mi palabra va antes de esa!",
+    }
+    YouWin {
+        en: "You win!",
+        es: "¡Has ganado!",
+    }
+    TimedScore {
+        en: "That took {} seconds. Your score: {}",
+        es: "Has tardado {} segundos. Tu puntuación: {}",
+    }
+    ScoreNotSaved {
+        en: "Couldn't save your score to {}: {}",
+        es: "No se pudo guardar tu puntuación en {}: {}",
+    }
+    NewRecord {
+        en: "New record!",
+        es: "¡Nuevo récord!",
+    }
+    RecordNotSaved {
+        en: "Couldn't save the record to {}: {}",
+        es: "No se pudo guardar el récord en {}: {}",
+    }
+    OutOfGuesses {
+        en: "Out of guesses! The answer was {}.",
+        es: "¡Te has quedado sin intentos! La respuesta era {}.",
+    }
+    Repeated {
+        en: "You already guessed {}! That one doesn't count.",
+        es: "¡Ya habías dicho {}! Ese no cuenta.",
+    }
+    History {
+        en: "Your guesses so far: {}",
+        es: "Tus intentos hasta ahora: {}",
+    }
+    GiveUp {
+        en: "Giving up? The answer was {}.",
+        es: "¿Te rindes? La respuesta era {}.",
+    }
+    PlayAgain {
+        en: "Play again? (y/n)",
+        es: "¿Jugar otra vez? (y/n)",
+    }
+    GamesPlayed {
+        en: "Games played: {}",
+        es: "Partidas jugadas: {}",
+    }
+    WinRate {
+        en: "Win rate: {}% ({} won)",
+        es: "Victorias: {}% ({} ganadas)",
+    }
+    AverageGuesses {
+        en: "Average guesses per win: {}",
+        es: "Media de intentos por victoria: {}",
+    }
+    ThinkOfANumber {
+        en: "Think of a number between {} and {}, and I'll guess it.",
+        es: "Piensa en un número entre {} y {}, y yo lo adivinaré.",
+    }
+    HowToAnswer {
+        en: "Answer `higher`, `lower` or `correct` (or h, l, c), or `quit` to stop.",
+        es: "Responde `higher`, `lower` o `correct` (o h, l, c), o `quit` para parar.",
+    }
+    IsIt {
+        en: "Is it {}?",
+        es: "¿Es el {}?",
+    }
+    AnswerAgain {
+        en: "Please answer `higher`, `lower` or `correct`.",
+        es: "Responde `higher`, `lower` o `correct`, por favor.",
+    }
+    Cheated {
+        en: "That can't be right: no number fits all your answers!",
+        es: "Eso no puede ser: ¡ningún número encaja con todas tus respuestas!",
+    }
+    KeepYourSecret {
+        en: "Fine, keep your secret.",
+        es: "Vale, guárdate tu secreto.",
+    }
+    GotIt {
+        en: "Got it in {} guesses!",
+        es: "¡Lo adiviné en {} intentos!",
+    }
+    CantListen {
+        en: "Couldn't listen on port {}: {}",
+        es: "No se pudo escuchar en el puerto {}: {}",
+    }
+    WaitingForPlayer {
+        en: "Waiting for a player on port {}...",
+        es: "Esperando a un jugador en el puerto {}...",
+    }
+    CantAccept {
+        en: "Couldn't take the connection: {}",
+        es: "No se pudo aceptar la conexión: {}",
+    }
+    PlayerJoined {
+        en: "{} joined. The number is {}.",
+        es: "{} se ha unido. El número es {}.",
+    }
+    TheyGuessed {
+        en: "They guessed {}: {}.",
+        es: "Ha dicho {}: {}.",
+    }
+    TheirTooSmall {
+        en: "too small",
+        es: "demasiado pequeño",
+    }
+    TheirTooBig {
+        en: "too big",
+        es: "demasiado grande",
+    }
+    TheirCorrect {
+        en: "right",
+        es: "correcto",
+    }
+    TheirLastTry {
+        en: "wrong, and the last try",
+        es: "incorrecto, y era el último intento",
+    }
+    TheirRepeat {
+        en: "again, so it doesn't count",
+        es: "otra vez, así que no cuenta",
+    }
+    TheyWon {
+        en: "They got it in {} guesses.",
+        es: "Lo ha adivinado en {} intentos.",
+    }
+    TheyLost {
+        en: "They ran out of guesses.",
+        es: "Se ha quedado sin intentos.",
+    }
+    TheyLeft {
+        en: "They left the game.",
+        es: "Ha abandonado la partida.",
+    }
+    ConnectionLost {
+        en: "Lost the connection: {}",
+        es: "Se perdió la conexión: {}",
+    }
+    CantConnect {
+        en: "Couldn't connect to {}: {}",
+        es: "No se pudo conectar a {}: {}",
+    }
+    NotHosting {
+        en: "{} isn't hosting a game.",
+        es: "{} no está organizando ninguna partida.",
+    }
+    Joined {
+        en: "Joined! The number is between {} and {}.",
+        es: "¡Dentro! El número está entre {} y {}.",
+    }
+    JoinedHelp {
+        en: "Guess a number between {} and {}, or `quit` to give up.",
+        es: "Adivina un número entre {} y {}, o escribe `quit` para rendirte.",
+    }
+    YouWinIn {
+        en: "You win, in {} guesses!",
+        es: "¡Has ganado en {} intentos!",
+    }
+    GameOver {
+        en: "Game over. The number was {}.",
+        es: "Fin de la partida. El número era {}.",
+    }
+    HostSaid {
+        en: "The host said: {}",
+        es: "El anfitrión dice: {}",
+    }
+    HostLost {
+        en: "Lost the connection to the host: {}",
+        es: "Se perdió la conexión con el anfitrión: {}",
+    }
+    HostGone {
+        en: "The host went away.",
+        es: "El anfitrión se ha ido.",
+    }
+    RecordsCorrupted {
+        en: "{} is corrupted ({}), starting it over.",
+        es: "{} está dañado ({}), se empieza de nuevo.",
+    }
+    RecordsNotRecreated {
+        en: "Couldn't recreate {}: {}",
+        es: "No se pudo volver a crear {}: {}",
+    }
+    RecordsNotRead {
+        en: "Couldn't read the records from {}: {}",
+        es: "No se pudieron leer los récords de {}: {}",
+    }
+    ScoresNotRead {
+        en: "Couldn't read the scores from {}: {}",
+        es: "No se pudieron leer las puntuaciones de {}: {}",
+    }
+    NoScores {
+        en: "No scores yet. Win a game to get on the board!",
+        es: "Todavía no hay puntuaciones. ¡Gana una partida para aparecer en la tabla!",
+    }
+    BestScores {
+        en: "Best scores:",
+        es: "Mejores puntuaciones:",
+    }
+    DailyScores {
+        en: "Today's challenge ({}):",
+        es: "Reto de hoy ({}):",
+    }
+    ScoreLine {
+        en: "{}. {} {} guesses  ({}-{}, {})",
+        es: "{}. {} {} intentos  ({}-{}, {})",
+    }
+    WordScoreLine {
+        en: "{}. {} {} guesses  (a word, {})",
+        es: "{}. {} {} intentos  (una palabra, {})",
+    }
+}
+
+// `template` with each `{}` replaced by the next of `args`. Any `{}` left
+// over once the arguments run out is left as it is.
+fn fill(template: &str, args: &[&dyn fmt::Display]) -> String {
+    let mut filled = String::new();
+    let mut args = args.iter();
+    let mut pieces = template.split("{}");
+
+    if let Some(first) = pieces.next() {
+        filled += first;
+    }
+    for piece in pieces {
+        match args.next() {
+            Some(arg) => filled += &arg.to_string(),
+            None => filled += "{}",
+        }
+        filled += piece;
+    }
+    filled
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn fills_in_the_blanks() {
+        assert_eq!("Too small!", Lang::English.say(Msg::TooSmall, &[]));
+        assert_eq!(
+            "Estoy pensando en un número entre 1 y 100. ¡Intenta adivinarlo!",
+            Lang::Spanish.say(Msg::Instructions, &[&1, &100])
+        );
+        assert_eq!("a 1 b {}", fill("a {} b {}", &[&1]));
+    }
+
+    #[test]
+    fn every_translation_has_the_same_blanks() {
+        for &msg in ALL {
+            let blanks = |lang: Lang| lang.text(msg).matches("{}").count();
+            assert_eq!(
+                blanks(Lang::English),
+                blanks(Lang::Spanish),
+                "{:?} has a different number of blanks",
+                msg
+            );
+        }
+    }
+
+    #[test]
+    fn picks_the_language() {
+        assert_eq!(Ok(Lang::English), "en".parse());
+        assert_eq!(Ok(Lang::Spanish), "es".parse());
+        assert!("fr".parse::<Lang>().is_err());
+    }
+}
@@ -0,0 +1,49 @@
+use std::fmt;
+use std::str::FromStr;
+
+/// What the secret is, which scores and records are kept apart by: a
+/// number, or a word from the word list.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Hash, Default)]
+pub enum Mode {
+    #[default]
+    Numbers,
+    Words,
+}
+
+impl FromStr for Mode {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Mode, String> {
+        match s {
+            "numbers" => Ok(Mode::Numbers),
+            "words" => Ok(Mode::Words),
+            _ => Err(format!(
+                "unknown mode `{}`, expected `numbers` or `words`",
+                s
+            )),
+        }
+    }
+}
+
+impl fmt::Display for Mode {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        let name = match self {
+            Mode::Numbers => "numbers",
+            Mode::Words => "words",
+        };
+        write!(f, "{}", name)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn reads_back_its_name() {
+        for mode in [Mode::Numbers, Mode::Words] {
+            assert_eq!(Ok(mode), mode.to_string().parse());
+        }
+        assert!("letters".parse::<Mode>().is_err());
+    }
+}
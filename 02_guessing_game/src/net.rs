@@ -0,0 +1,313 @@
+use std::fmt;
+use std::io::{self, BufRead, BufReader, ErrorKind, Write};
+use std::net::TcpStream;
+
+use crate::{Game, GuessOutcome};
+
+// Guess the number over TCP: the host picks the secret, and the player
+// who joins sends the guesses.
+//
+// The protocol is one message per line. When a player connects, the host
+// sends `WELCOME <low> <high>`. After that the player sends `GUESS <n>` or
+// `QUIT`, and the host answers each guess with `SMALL`, `BIG`,
+// `WIN <guesses>`, `LOSE <secret>` or `REPEAT <n>` for a number that's been
+// guessed already, or `ERROR <reason>` for a line it couldn't read. Either
+// side hanging up ends the game.
+
+/// What the player sends to the host.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum PlayerMessage {
+    Guess(u32),
+    Quit,
+}
+
+/// What the host sends to the player.
+#[derive(Debug, Clone, PartialEq)]
+pub enum HostMessage {
+    /// The game is on, with the secret somewhere from low to high
+    Welcome {
+        low: u32,
+        high: u32,
+    },
+    TooSmall,
+    TooBig,
+    /// Guessed it, in this many guesses
+    Win(u32),
+    /// Out of guesses, or given up; this was the secret
+    Lose(u32),
+    /// This number has been guessed already, and didn't count
+    Repeat(u32),
+    Error(String),
+}
+
+impl PlayerMessage {
+    pub fn parse(line: &str) -> Option<PlayerMessage> {
+        let mut words = line.split_whitespace();
+        let message = match (words.next()?, words.next()) {
+            ("GUESS", Some(number)) => PlayerMessage::Guess(number.parse().ok()?),
+            ("QUIT", None) => PlayerMessage::Quit,
+            _ => return None,
+        };
+        match words.next() {
+            Some(_) => None,
+            None => Some(message),
+        }
+    }
+}
+
+impl fmt::Display for PlayerMessage {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            PlayerMessage::Guess(number) => write!(f, "GUESS {}", number),
+            PlayerMessage::Quit => write!(f, "QUIT"),
+        }
+    }
+}
+
+impl HostMessage {
+    pub fn parse(line: &str) -> Option<HostMessage> {
+        let line = line.trim_end();
+        let (word, rest) = match line.split_once(' ') {
+            Some((word, rest)) => (word, Some(rest)),
+            None => (line, None),
+        };
+
+        let number = |text: Option<&str>| text?.parse().ok();
+        match (word, rest) {
+            ("WELCOME", Some(range)) => {
+                let (low, high) = range.split_once(' ')?;
+                Some(HostMessage::Welcome {
+                    low: low.parse().ok()?,
+                    high: high.parse().ok()?,
+                })
+            }
+            ("SMALL", None) => Some(HostMessage::TooSmall),
+            ("BIG", None) => Some(HostMessage::TooBig),
+            ("WIN", guesses) => Some(HostMessage::Win(number(guesses)?)),
+            ("LOSE", secret) => Some(HostMessage::Lose(number(secret)?)),
+            ("REPEAT", guess) => Some(HostMessage::Repeat(number(guess)?)),
+            ("ERROR", reason) => Some(HostMessage::Error(reason.unwrap_or("").to_string())),
+            _ => None,
+        }
+    }
+}
+
+impl fmt::Display for HostMessage {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            HostMessage::Welcome { low, high } => write!(f, "WELCOME {} {}", low, high),
+            HostMessage::TooSmall => write!(f, "SMALL"),
+            HostMessage::TooBig => write!(f, "BIG"),
+            HostMessage::Win(guesses) => write!(f, "WIN {}", guesses),
+            HostMessage::Lose(secret) => write!(f, "LOSE {}", secret),
+            HostMessage::Repeat(guess) => write!(f, "REPEAT {}", guess),
+            HostMessage::Error(reason) => write!(f, "ERROR {}", reason),
+        }
+    }
+}
+
+/// One end of a game's connection, sending and receiving whole lines.
+pub struct Connection {
+    reader: BufReader<TcpStream>,
+    writer: TcpStream,
+}
+
+impl Connection {
+    pub fn new(stream: TcpStream) -> io::Result<Connection> {
+        Ok(Connection {
+            reader: BufReader::new(stream.try_clone()?),
+            writer: stream,
+        })
+    }
+
+    pub fn send(&mut self, message: impl fmt::Display) -> io::Result<()> {
+        writeln!(self.writer, "{}", message)
+    }
+
+    /// The next line, without its line break. None once the other side
+    /// has hung up.
+    pub fn receive(&mut self) -> io::Result<Option<String>> {
+        let mut line = String::new();
+        match self.reader.read_line(&mut line) {
+            Ok(0) => Ok(None),
+            Ok(_) => Ok(Some(line.trim_end_matches(&['\r', '\n'][..]).to_string())),
+            // Hanging up without a goodbye is still hanging up
+            Err(e) if hung_up(&e) => Ok(None),
+            Err(e) => Err(e),
+        }
+    }
+}
+
+/// How a hosted game ended.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum Hosted {
+    /// The player guessed the number
+    Won,
+    /// The player ran out of guesses
+    Lost,
+    /// The player quit, or hung up
+    Left,
+}
+
+/// Plays `game` with whoever is on the other end of `connection`, calling
+/// `watch` with each guess they make and how it went.
+pub fn host(
+    connection: &mut Connection,
+    game: &mut Game,
+    watch: impl FnMut(u32, GuessOutcome),
+) -> io::Result<Hosted> {
+    match play_host(connection, game, watch) {
+        Err(e) if hung_up(&e) => Ok(Hosted::Left),
+        result => result,
+    }
+}
+
+fn play_host(
+    connection: &mut Connection,
+    game: &mut Game,
+    mut watch: impl FnMut(u32, GuessOutcome),
+) -> io::Result<Hosted> {
+    let (low, high) = game.range();
+    connection.send(HostMessage::Welcome { low, high })?;
+
+    loop {
+        let line = match connection.receive()? {
+            Some(line) => line,
+            None => return Ok(Hosted::Left),
+        };
+
+        let guess = match PlayerMessage::parse(&line) {
+            Some(PlayerMessage::Guess(guess)) => guess,
+            Some(PlayerMessage::Quit) => {
+                connection.send(HostMessage::Lose(game.secret()))?;
+                return Ok(Hosted::Left);
+            }
+            None => {
+                connection.send(HostMessage::Error(format!("can't read `{}`", line)))?;
+                continue;
+            }
+        };
+
+        let outcome = game.guess(guess);
+        watch(guess, outcome);
+        match outcome {
+            GuessOutcome::TooSmall => connection.send(HostMessage::TooSmall)?,
+            GuessOutcome::TooBig => connection.send(HostMessage::TooBig)?,
+            GuessOutcome::Repeated => connection.send(HostMessage::Repeat(guess))?,
+            GuessOutcome::Correct => {
+                connection.send(HostMessage::Win(game.guesses()))?;
+                return Ok(Hosted::Won);
+            }
+            GuessOutcome::OutOfGuesses => {
+                connection.send(HostMessage::Lose(game.secret()))?;
+                return Ok(Hosted::Lost);
+            }
+        }
+    }
+}
+
+// Whether `e` is the other side going away, rather than something wrong
+fn hung_up(e: &io::Error) -> bool {
+    matches!(
+        e.kind(),
+        ErrorKind::ConnectionReset | ErrorKind::ConnectionAborted | ErrorKind::BrokenPipe
+    )
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::net::TcpListener;
+    use std::thread;
+
+    // Hosts `game` for one player on a free port, and connects to it
+    fn hosted(game: Game) -> (Connection, thread::JoinHandle<io::Result<Hosted>>) {
+        let listener = TcpListener::bind("127.0.0.1:0").unwrap();
+        let addr = listener.local_addr().unwrap();
+        let host = thread::spawn(move || {
+            let (stream, _) = listener.accept()?;
+            let mut game = game;
+            host(&mut Connection::new(stream)?, &mut game, |_, _| {})
+        });
+
+        let player = Connection::new(TcpStream::connect(addr).unwrap()).unwrap();
+        (player, host)
+    }
+
+    fn reply(player: &mut Connection, message: PlayerMessage) -> Option<HostMessage> {
+        player.send(message).unwrap();
+        HostMessage::parse(&player.receive().unwrap()?)
+    }
+
+    #[test]
+    fn reads_back_what_it_writes() {
+        let messages = vec![
+            HostMessage::Welcome { low: 1, high: 100 },
+            HostMessage::TooSmall,
+            HostMessage::TooBig,
+            HostMessage::Win(4),
+            HostMessage::Lose(42),
+            HostMessage::Repeat(7),
+            HostMessage::Error(String::from("can't read `x`")),
+        ];
+        for message in messages {
+            assert_eq!(
+                Some(message.clone()),
+                HostMessage::parse(&message.to_string())
+            );
+        }
+
+        assert_eq!(
+            Some(PlayerMessage::Guess(7)),
+            PlayerMessage::parse("GUESS 7\r\n")
+        );
+        assert_eq!(Some(PlayerMessage::Quit), PlayerMessage::parse("QUIT"));
+        assert_eq!(None, PlayerMessage::parse("GUESS seven"));
+        assert_eq!(None, PlayerMessage::parse("GUESS 7 8"));
+    }
+
+    #[test]
+    fn plays_a_game_over_tcp() {
+        let (mut player, host) = hosted(Game::with_secret(30));
+
+        let welcome = HostMessage::parse(&player.receive().unwrap().unwrap());
+        assert_eq!(Some(HostMessage::Welcome { low: 1, high: 100 }), welcome);
+        assert_eq!(
+            Some(HostMessage::TooBig),
+            reply(&mut player, PlayerMessage::Guess(50))
+        );
+        player.send("GUESS").unwrap();
+        assert!(matches!(
+            HostMessage::parse(&player.receive().unwrap().unwrap()),
+            Some(HostMessage::Error(_))
+        ));
+        assert_eq!(
+            Some(HostMessage::TooSmall),
+            reply(&mut player, PlayerMessage::Guess(25))
+        );
+        assert_eq!(
+            Some(HostMessage::Repeat(50)),
+            reply(&mut player, PlayerMessage::Guess(50))
+        );
+        assert_eq!(
+            Some(HostMessage::Win(3)),
+            reply(&mut player, PlayerMessage::Guess(30))
+        );
+        assert_eq!(Hosted::Won, host.join().unwrap().unwrap());
+    }
+
+    #[test]
+    fn notices_the_player_leaving() {
+        let (mut player, host) = hosted(Game::with_secret(30));
+        player.receive().unwrap();
+        assert_eq!(
+            Some(HostMessage::Lose(30)),
+            reply(&mut player, PlayerMessage::Quit)
+        );
+        assert_eq!(Hosted::Left, host.join().unwrap().unwrap());
+
+        let (player, host) = hosted(Game::with_secret(30));
+        drop(player);
+        assert_eq!(Hosted::Left, host.join().unwrap().unwrap());
+    }
+}
@@ -0,0 +1,177 @@
+use std::collections::BTreeMap;
+use std::fs;
+use std::io::{self, ErrorKind};
+use std::path::Path;
+
+use crate::{Difficulty, Mode};
+
+// Where a record was set. In word mode, the range is the places in the
+// word list, like for scores.
+type Key = (Mode, u32, u32, Difficulty);
+
+/// The fewest guesses any game has been won in, for each mode, range and
+/// difficulty it's been played at.
+#[derive(Debug, Clone, Default, PartialEq)]
+pub struct Records {
+    // (mode, low, high, difficulty) to guesses. A BTreeMap keeps the saved
+    // file in the same order every time.
+    best: BTreeMap<Key, u32>,
+}
+
+impl Records {
+    pub fn new() -> Records {
+        Records::default()
+    }
+
+    /// The records saved at `path`. A missing file has none yet. A file
+    /// that doesn't make sense is an `InvalidData` error, naming the first
+    /// line that doesn't.
+    pub fn load(path: impl AsRef<Path>) -> io::Result<Records> {
+        let text = match fs::read_to_string(path) {
+            Ok(text) => text,
+            Err(e) if e.kind() == ErrorKind::NotFound => return Ok(Records::new()),
+            Err(e) => return Err(e),
+        };
+
+        let mut records = Records::new();
+        for (number, line) in text.lines().enumerate() {
+            let (key, guesses) = parse_line(line).ok_or_else(|| {
+                io::Error::new(
+                    ErrorKind::InvalidData,
+                    format!("line {} isn't a record: `{}`", number + 1, line),
+                )
+            })?;
+            records.best.insert(key, guesses);
+        }
+        Ok(records)
+    }
+
+    /// Writes every record to `path`, one per line as
+    /// `low-high,difficulty,guesses`, with `,words` after that for word
+    /// mode. The file is written next to the old
+    /// one first and then moved over it, so a crash halfway through can't
+    /// leave half a file behind.
+    pub fn save(&self, path: impl AsRef<Path>) -> io::Result<()> {
+        let path = path.as_ref();
+        let mut text = String::new();
+        for (&(mode, low, high, difficulty), guesses) in &self.best {
+            text += &format!("{}-{},{},{}", low, high, difficulty, guesses);
+            if mode != Mode::Numbers {
+                text += &format!(",{}", mode);
+            }
+            text += "\n";
+        }
+
+        let partial = path.with_extension("partial");
+        fs::write(&partial, text)?;
+        fs::rename(partial, path)
+    }
+
+    /// The record in `mode` for the range `low` to `high` at `difficulty`,
+    /// if a game has been won there.
+    pub fn best(&self, mode: Mode, low: u32, high: u32, difficulty: Difficulty) -> Option<u32> {
+        self.best.get(&(mode, low, high, difficulty)).copied()
+    }
+
+    /// Counts a win in `guesses` guesses. True if that's a new record:
+    /// the first win there, or fewer guesses than the old record.
+    pub fn beat(
+        &mut self,
+        mode: Mode,
+        low: u32,
+        high: u32,
+        difficulty: Difficulty,
+        guesses: u32,
+    ) -> bool {
+        let best = self
+            .best
+            .entry((mode, low, high, difficulty))
+            .or_insert(u32::MAX);
+        if guesses < *best {
+            *best = guesses;
+            true
+        } else {
+            false
+        }
+    }
+}
+
+fn parse_line(line: &str) -> Option<(Key, u32)> {
+    let mut fields = line.split(',');
+    let (low, high) = fields.next()?.split_once('-')?;
+    let difficulty = fields.next()?.parse().ok()?;
+    let guesses = fields.next()?.parse().ok()?;
+    let mode = match fields.next() {
+        None => Mode::Numbers,
+        Some(mode) => mode.parse().ok()?,
+    };
+    if fields.next().is_some() {
+        return None;
+    }
+
+    Some((
+        (mode, low.parse().ok()?, high.parse().ok()?, difficulty),
+        guesses,
+    ))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::env;
+
+    fn temp_file(name: &str) -> std::path::PathBuf {
+        env::temp_dir().join(format!("guessing-{}-{}.csv", name, std::process::id()))
+    }
+
+    #[test]
+    fn keeps_the_fewest_guesses_for_each_mode_range_and_difficulty() {
+        let mut records = Records::new();
+
+        let numbers = Mode::Numbers;
+
+        assert!(records.beat(numbers, 1, 100, Difficulty::Normal, 6));
+        assert!(!records.beat(numbers, 1, 100, Difficulty::Normal, 6));
+        assert!(!records.beat(numbers, 1, 100, Difficulty::Normal, 8));
+        assert!(records.beat(numbers, 1, 100, Difficulty::Normal, 4));
+        assert!(records.beat(numbers, 1, 100, Difficulty::Hard, 7));
+        assert!(records.beat(numbers, 1, 1000, Difficulty::Normal, 9));
+        assert!(records.beat(Mode::Words, 1, 100, Difficulty::Normal, 5));
+
+        assert_eq!(Some(4), records.best(numbers, 1, 100, Difficulty::Normal));
+        assert_eq!(Some(7), records.best(numbers, 1, 100, Difficulty::Hard));
+        assert_eq!(None, records.best(numbers, 1, 100, Difficulty::Easy));
+        assert_eq!(
+            Some(5),
+            records.best(Mode::Words, 1, 100, Difficulty::Normal)
+        );
+        assert_eq!(None, records.best(Mode::Words, 1, 100, Difficulty::Hard));
+    }
+
+    #[test]
+    fn saves_and_loads_records() {
+        let path = temp_file("records");
+        let _ = fs::remove_file(&path);
+        assert_eq!(Records::new(), Records::load(&path).unwrap());
+
+        let mut records = Records::new();
+        records.beat(Mode::Numbers, 1, 100, Difficulty::Easy, 5);
+        records.beat(Mode::Numbers, 10, 20, Difficulty::Hard, 3);
+        records.beat(Mode::Words, 1, 100, Difficulty::Easy, 4);
+        records.save(&path).unwrap();
+
+        assert_eq!(records, Records::load(&path).unwrap());
+        fs::remove_file(path).unwrap();
+    }
+
+    #[test]
+    fn refuses_a_corrupted_file() {
+        let path = temp_file("corrupt");
+        fs::write(&path, "1-100,normal,5,words\n1-100,nightmare,2\n").unwrap();
+
+        let err = Records::load(&path).unwrap_err();
+        assert_eq!(ErrorKind::InvalidData, err.kind());
+        assert!(err.to_string().contains("line 2"));
+        fs::remove_file(path).unwrap();
+    }
+}
@@ -0,0 +1,163 @@
+use std::cmp::Ordering;
+
+use crate::{HIGH, LOW};
+
+/// The other way round: the player thinks of a number from LOW to HIGH,
+/// and the guesser finds it by halving the range it could be in.
+///
+/// ```
+/// use std::cmp::Ordering;
+/// use guessing_game::Guesser;
+///
+/// let secret = 70;
+/// let mut guesser = Guesser::new();
+/// loop {
+///     let guess = guesser.next_guess().unwrap();
+///     // The player's answer is the same comparison the game makes
+///     let answer = guess.cmp(&secret);
+///     if answer == Ordering::Equal {
+///         break;
+///     }
+///     guesser.answer(answer);
+/// }
+/// assert!(guesser.guesses() <= 7);
+/// ```
+#[derive(Debug, Clone, PartialEq)]
+pub struct Guesser {
+    // What the number could still be, both ends included. `low > high`
+    // means nothing is left.
+    low: u32,
+    high: u32,
+    last: Option<u32>,
+    guesses: u32,
+}
+
+impl Guesser {
+    /// A guesser for a number from LOW to HIGH.
+    pub fn new() -> Guesser {
+        Guesser::between(LOW, HIGH)
+    }
+
+    /// A guesser for a number from `low` to `high`.
+    ///
+    /// # Panics
+    ///
+    /// Panics if `low` is 0.
+    pub fn between(low: u32, high: u32) -> Guesser {
+        assert!(low > 0, "the range has to start at 1 or more");
+
+        Guesser {
+            low,
+            high,
+            last: None,
+            guesses: 0,
+        }
+    }
+
+    /// The next number to try: the middle of what's left. None when the
+    /// answers so far rule out every number, which means one was wrong.
+    pub fn next_guess(&mut self) -> Option<u32> {
+        if self.low > self.high {
+            return None;
+        }
+
+        let guess = self.low + (self.high - self.low) / 2;
+        self.last = Some(guess);
+        self.guesses += 1;
+        Some(guess)
+    }
+
+    /// Narrows the range down with how the last guess compares with the
+    /// player's number: Less if the number is higher, Greater if it's
+    /// lower.
+    pub fn answer(&mut self, answer: Ordering) {
+        let last = match self.last {
+            Some(last) => last,
+            None => return,
+        };
+
+        match answer {
+            Ordering::Less => self.low = last + 1,
+            // The range starts at 1 or more, so there's always room below
+            // a guess
+            Ordering::Greater => self.high = last - 1,
+            Ordering::Equal => {
+                self.low = last;
+                self.high = last;
+            }
+        }
+    }
+
+    /// How many guesses have been made so far.
+    pub fn guesses(&self) -> u32 {
+        self.guesses
+    }
+}
+
+impl Default for Guesser {
+    fn default() -> Guesser {
+        Guesser::new()
+    }
+}
+
+/// Reads the player's answer to a guess, as how the guess compares with
+/// their number: `higher` is Less, `lower` is Greater and `correct` is
+/// Equal. None if it's none of those.
+pub fn parse_answer(input: &str) -> Option<Ordering> {
+    match input.trim().to_lowercase().as_str() {
+        "higher" | "h" | "+" => Some(Ordering::Less),
+        "lower" | "l" | "-" => Some(Ordering::Greater),
+        "correct" | "c" | "yes" | "y" | "=" => Some(Ordering::Equal),
+        _ => None,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    // Plays a guesser against `secret`, answering truthfully
+    fn guesses_for(secret: u32) -> u32 {
+        let mut guesser = Guesser::new();
+        loop {
+            let guess = guesser.next_guess().expect("ran out of numbers");
+            match guess.cmp(&secret) {
+                Ordering::Equal => return guesser.guesses(),
+                answer => guesser.answer(answer),
+            }
+        }
+    }
+
+    #[test]
+    fn finds_every_number_in_seven_guesses() {
+        for secret in LOW..=HIGH {
+            assert!(guesses_for(secret) <= 7, "took too long for {}", secret);
+        }
+        assert_eq!(1, guesses_for(50));
+
+        let mut guesser = Guesser::between(1, 1000);
+        assert_eq!(Some(500), guesser.next_guess());
+    }
+
+    #[test]
+    fn notices_answers_that_cant_all_be_true() {
+        let mut guesser = Guesser::new();
+        assert_eq!(Some(50), guesser.next_guess());
+        guesser.answer(Ordering::Less);
+        assert_eq!(Some(75), guesser.next_guess());
+        guesser.answer(Ordering::Greater);
+        while let Some(guess) = guesser.next_guess() {
+            assert!(guess > 50 && guess < 75);
+            // Always "lower", even below 51
+            guesser.answer(Ordering::Greater);
+        }
+    }
+
+    #[test]
+    fn reads_answers() {
+        assert_eq!(Some(Ordering::Less), parse_answer("Higher\n"));
+        assert_eq!(Some(Ordering::Greater), parse_answer(" l "));
+        assert_eq!(Some(Ordering::Equal), parse_answer("correct"));
+        assert_eq!(None, parse_answer("maybe"));
+    }
+}
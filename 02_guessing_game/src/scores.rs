@@ -0,0 +1,238 @@
+use std::fs::{self, OpenOptions};
+use std::io::{self, Write};
+use std::path::Path;
+use std::time::{SystemTime, UNIX_EPOCH};
+
+use crate::Mode;
+
+/// A won game, as kept on the scoreboard.
+#[derive(Debug, Clone, PartialEq)]
+pub struct Score {
+    pub name: String,
+    /// How many guesses it took
+    pub guesses: u32,
+    /// The range the secret was picked from, both ends included. In word
+    /// mode, it's the places in the word list, from 1.
+    pub low: u32,
+    pub high: u32,
+    /// When the game was won, in seconds since the Unix epoch
+    pub time: u64,
+    /// Whether it was a daily challenge, which is ranked on its own
+    pub daily: bool,
+    /// Whether the secret was a number or a word
+    pub mode: Mode,
+}
+
+impl Score {
+    /// A score for a game won just now.
+    pub fn now(name: &str, guesses: u32, low: u32, high: u32) -> Score {
+        let time = SystemTime::now()
+            .duration_since(UNIX_EPOCH)
+            .map_or(0, |since| since.as_secs());
+
+        Score {
+            name: name.to_string(),
+            guesses,
+            low,
+            high,
+            time,
+            daily: false,
+            mode: Mode::Numbers,
+        }
+    }
+
+    /// The day the game was won on, in days since the Unix epoch.
+    pub fn day(&self) -> u64 {
+        self.time / 86_400
+    }
+
+    /// The day the game was won on, as `YYYY-MM-DD` in UTC.
+    pub fn date(&self) -> String {
+        date(self.day())
+    }
+
+    // One line of the scores file: `name,guesses,low-high,time`, then
+    // `,daily` after that for a daily challenge and `,words` for a word
+    // game. Commas and line breaks in the name would break the line up, so
+    // they're turned into spaces.
+    fn to_line(&self) -> String {
+        let name: String = self
+            .name
+            .chars()
+            .map(|c| if c == ',' || c.is_control() { ' ' } else { c })
+            .collect();
+
+        let mut line = format!(
+            "{},{},{}-{},{}",
+            name.trim(),
+            self.guesses,
+            self.low,
+            self.high,
+            self.time
+        );
+        if self.daily {
+            line += ",daily";
+        }
+        if self.mode != Mode::Numbers {
+            line += &format!(",{}", self.mode);
+        }
+        line
+    }
+
+    fn from_line(line: &str) -> Option<Score> {
+        let mut fields = line.split(',');
+        let name = fields.next()?.to_string();
+        let guesses = fields.next()?.parse().ok()?;
+        let (low, high) = fields.next()?.split_once('-')?;
+        let time = fields.next()?.parse().ok()?;
+        let mut daily = false;
+        let mut mode = Mode::Numbers;
+        for flag in fields {
+            match flag {
+                "daily" if !daily => daily = true,
+                _ if mode == Mode::Numbers => mode = flag.parse().ok()?,
+                _ => return None,
+            }
+        }
+
+        Some(Score {
+            name,
+            guesses,
+            low: low.parse().ok()?,
+            high: high.parse().ok()?,
+            time,
+            daily,
+            mode,
+        })
+    }
+}
+
+/// Adds `score` to the end of the scores file at `path`, creating the file
+/// if there isn't one yet.
+pub fn record(path: impl AsRef<Path>, score: &Score) -> io::Result<()> {
+    let mut file = OpenOptions::new().create(true).append(true).open(path)?;
+    writeln!(file, "{}", score.to_line())
+}
+
+/// Every score in the scores file at `path`, oldest first. A missing file
+/// has no scores, and lines that don't make sense are skipped.
+pub fn load(path: impl AsRef<Path>) -> io::Result<Vec<Score>> {
+    let text = match fs::read_to_string(path) {
+        Ok(text) => text,
+        Err(e) if e.kind() == io::ErrorKind::NotFound => return Ok(Vec::new()),
+        Err(e) => return Err(e),
+    };
+
+    Ok(text.lines().filter_map(Score::from_line).collect())
+}
+
+/// The `n` best of `scores`: the fewest guesses first, and of those, the
+/// ones that got there first.
+pub fn best(scores: &[Score], n: usize) -> Vec<&Score> {
+    let mut best: Vec<&Score> = scores.iter().collect();
+    best.sort_by_key(|score| (score.guesses, score.time));
+    best.truncate(n);
+    best
+}
+
+/// Today, in days since the Unix epoch.
+pub fn today() -> u64 {
+    SystemTime::now()
+        .duration_since(UNIX_EPOCH)
+        .map_or(0, |since| since.as_secs() / 86_400)
+}
+
+/// The day `days` days after 1970-01-01, as `YYYY-MM-DD`.
+pub fn date(days: u64) -> String {
+    let (year, month, day) = civil_date(days);
+    format!("{:04}-{:02}-{:02}", year, month, day)
+}
+
+// The (year, month, day) `days` days after 1970-01-01, from Howard
+// Hinnant's days_from_civil algorithm run backwards
+fn civil_date(days: u64) -> (u64, u64, u64) {
+    let z = days + 719_468;
+    let era = z / 146_097;
+    let day_of_era = z - era * 146_097;
+    let year_of_era =
+        (day_of_era - day_of_era / 1460 + day_of_era / 36_524 - day_of_era / 146_096) / 365;
+    let day_of_year = day_of_era - (365 * year_of_era + year_of_era / 4 - year_of_era / 100);
+    let mp = (5 * day_of_year + 2) / 153;
+    let day = day_of_year - (153 * mp + 2) / 5 + 1;
+    let month = if mp < 10 { mp + 3 } else { mp - 9 };
+    let year = year_of_era + era * 400 + if month <= 2 { 1 } else { 0 };
+
+    (year, month, day)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::env;
+
+    fn score(name: &str, guesses: u32, time: u64) -> Score {
+        Score {
+            name: name.to_string(),
+            guesses,
+            low: 1,
+            high: 100,
+            time,
+            daily: false,
+            mode: Mode::Numbers,
+        }
+    }
+
+    #[test]
+    fn keeps_scores_in_a_file() {
+        let path = env::temp_dir().join(format!("guessing-scores-{}.csv", std::process::id()));
+        let _ = fs::remove_file(&path);
+        assert_eq!(Vec::<Score>::new(), load(&path).unwrap());
+
+        record(&path, &score("Ann", 5, 100)).unwrap();
+        record(&path, &score("Bo, the great\n", 7, 200)).unwrap();
+        let daily = Score {
+            daily: true,
+            ..score("Cy", 3, 300)
+        };
+        record(&path, &daily).unwrap();
+        let words = Score {
+            mode: Mode::Words,
+            ..daily.clone()
+        };
+        record(&path, &words).unwrap();
+        let bad = "not a score\nDi,2,1-9,400,words,words\n";
+        fs::write(&path, fs::read_to_string(&path).unwrap() + bad).unwrap();
+
+        let scores = load(&path).unwrap();
+        assert_eq!(
+            vec![
+                score("Ann", 5, 100),
+                score("Bo  the great", 7, 200),
+                daily,
+                words
+            ],
+            scores
+        );
+        fs::remove_file(path).unwrap();
+    }
+
+    #[test]
+    fn ranks_fewest_guesses_first() {
+        let scores = vec![
+            score("late", 4, 300),
+            score("slow", 9, 100),
+            score("early", 4, 200),
+            score("fast", 2, 400),
+        ];
+
+        let names: Vec<&str> = best(&scores, 3).iter().map(|s| s.name.as_str()).collect();
+        assert_eq!(vec!["fast", "early", "late"], names);
+    }
+
+    #[test]
+    fn dates_scores() {
+        assert_eq!("1970-01-01", score("a", 1, 0).date());
+        assert_eq!("2000-02-29", score("a", 1, 951_782_400).date());
+        assert_eq!("2024-12-31", score("a", 1, 1_735_689_599).date());
+    }
+}
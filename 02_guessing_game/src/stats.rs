@@ -0,0 +1,89 @@
+/// How the games played since the program started have gone.
+#[derive(Debug, Clone, Default, PartialEq)]
+pub struct SessionStats {
+    played: u32,
+    won: u32,
+    // Guesses added up over the won games only, for the average
+    winning_guesses: u32,
+}
+
+impl SessionStats {
+    pub fn new() -> SessionStats {
+        SessionStats::default()
+    }
+
+    /// Counts a game won in `guesses` guesses.
+    pub fn record_win(&mut self, guesses: u32) {
+        self.played += 1;
+        self.won += 1;
+        self.winning_guesses += guesses;
+    }
+
+    /// Counts a game that was lost or given up on.
+    pub fn record_loss(&mut self) {
+        self.played += 1;
+    }
+
+    pub fn played(&self) -> u32 {
+        self.played
+    }
+
+    pub fn won(&self) -> u32 {
+        self.won
+    }
+
+    /// The share of games won, from 0.0 to 1.0. None before any game has
+    /// been played.
+    pub fn win_rate(&self) -> Option<f64> {
+        if self.played == 0 {
+            return None;
+        }
+        Some(f64::from(self.won) / f64::from(self.played))
+    }
+
+    /// How many guesses a win took on average. None until something has
+    /// been won.
+    pub fn average_guesses(&self) -> Option<f64> {
+        if self.won == 0 {
+            return None;
+        }
+        Some(f64::from(self.winning_guesses) / f64::from(self.won))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn starts_with_nothing_to_average() {
+        let stats = SessionStats::new();
+
+        assert_eq!(0, stats.played());
+        assert_eq!(None, stats.win_rate());
+        assert_eq!(None, stats.average_guesses());
+    }
+
+    #[test]
+    fn averages_the_guesses_over_wins_only() {
+        let mut stats = SessionStats::new();
+        stats.record_win(4);
+        stats.record_loss();
+        stats.record_win(7);
+        stats.record_loss();
+
+        assert_eq!(4, stats.played());
+        assert_eq!(2, stats.won());
+        assert_eq!(Some(0.5), stats.win_rate());
+        assert_eq!(Some(5.5), stats.average_guesses());
+    }
+
+    #[test]
+    fn has_no_average_without_a_win() {
+        let mut stats = SessionStats::new();
+        stats.record_loss();
+
+        assert_eq!(Some(0.0), stats.win_rate());
+        assert_eq!(None, stats.average_guesses());
+    }
+}
@@ -0,0 +1,87 @@
+/// A color to print text in.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum Color {
+    Red,
+    Green,
+    Blue,
+}
+
+impl Color {
+    // The ANSI escape sequence that switches to the color
+    fn code(self) -> &'static str {
+        match self {
+            Color::Red => "\x1b[31m",
+            Color::Green => "\x1b[32m",
+            Color::Blue => "\x1b[34m",
+        }
+    }
+}
+
+// Back to the terminal's own color
+const RESET: &str = "\x1b[0m";
+
+/// Whether to color what's printed. Terminals show ANSI escape sequences
+/// as colors, but anywhere else they're just noise.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct Style {
+    color: bool,
+}
+
+impl Style {
+    pub fn new(color: bool) -> Style {
+        Style { color }
+    }
+
+    /// `text` in `color`, or as it is if color is off.
+    pub fn paint(&self, text: &str, color: Color) -> String {
+        if self.color {
+            format!("{}{}{}", color.code(), text, RESET)
+        } else {
+            text.to_string()
+        }
+    }
+}
+
+/// A bar `width` characters wide, filled in for `used` out of `total`,
+/// followed by the count: `[###-------] 3/10`. Going over the total just
+/// fills the bar.
+pub fn progress_bar(used: u32, total: u32, width: usize) -> String {
+    let filled = if total == 0 {
+        width
+    } else {
+        (used as usize * width / total as usize).min(width)
+    };
+
+    format!(
+        "[{}{}] {}/{}",
+        "#".repeat(filled),
+        "-".repeat(width - filled),
+        used,
+        total
+    )
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn paints_only_with_color_on() {
+        assert_eq!(
+            "\x1b[32mYou win!\x1b[0m",
+            Style::new(true).paint("You win!", Color::Green)
+        );
+        assert_eq!(
+            "You win!",
+            Style::new(false).paint("You win!", Color::Green)
+        );
+    }
+
+    #[test]
+    fn fills_the_bar_with_the_attempts_used() {
+        assert_eq!("[----------] 0/10", progress_bar(0, 10, 10));
+        assert_eq!("[###-------] 3/10", progress_bar(3, 10, 10));
+        assert_eq!("[###----] 3/7", progress_bar(3, 7, 7));
+        assert_eq!("[#######] 9/7", progress_bar(9, 7, 7));
+    }
+}
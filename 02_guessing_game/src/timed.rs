@@ -0,0 +1,94 @@
+use std::time::Duration;
+
+// Each half of a score is worth up to this many points
+const HALF_SCORE: u64 = 500;
+
+/// How long a timed game may take, and what a win in time is worth.
+///
+/// It doesn't keep the time itself: the game's clock is up to whoever
+/// plays it, and `elapsed` is how long has gone by on it.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct TimeLimit {
+    limit: Duration,
+}
+
+impl TimeLimit {
+    /// # Panics
+    ///
+    /// Panics if `limit` is zero.
+    pub fn new(limit: Duration) -> TimeLimit {
+        assert!(limit > Duration::from_secs(0), "a time limit can't be zero");
+        TimeLimit { limit }
+    }
+
+    pub fn limit(&self) -> Duration {
+        self.limit
+    }
+
+    /// The time left, or None once it has run out.
+    pub fn remaining(&self, elapsed: Duration) -> Option<Duration> {
+        self.limit
+            .checked_sub(elapsed)
+            .filter(|left| *left > Duration::from_secs(0))
+    }
+
+    /// Whether time is nearly up: the last quarter of the limit has been
+    /// reached, but it hasn't run out yet.
+    pub fn is_nearly_up(&self, elapsed: Duration) -> bool {
+        match self.remaining(elapsed) {
+            Some(left) => left <= self.limit / 4,
+            None => false,
+        }
+    }
+
+    /// The score for a win in `guesses` guesses after `elapsed`, out of
+    /// 1000. Half of it is for guessing in as few tries as possible, the
+    /// other half for the time left over.
+    pub fn score(&self, guesses: u32, elapsed: Duration) -> u32 {
+        let for_guesses = HALF_SCORE / u64::from(guesses.max(1));
+        let left = self.remaining(elapsed).unwrap_or_default();
+        let for_time = HALF_SCORE * left.as_millis() as u64 / self.limit.as_millis().max(1) as u64;
+
+        (for_guesses + for_time) as u32
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn secs(secs: u64) -> Duration {
+        Duration::from_secs(secs)
+    }
+
+    #[test]
+    fn counts_down_to_the_limit() {
+        let limit = TimeLimit::new(secs(60));
+
+        assert_eq!(Some(secs(60)), limit.remaining(secs(0)));
+        assert_eq!(Some(secs(15)), limit.remaining(secs(45)));
+        assert_eq!(None, limit.remaining(secs(60)));
+        assert_eq!(None, limit.remaining(secs(90)));
+    }
+
+    #[test]
+    fn is_nearly_up_in_the_last_quarter() {
+        let limit = TimeLimit::new(secs(60));
+
+        assert!(!limit.is_nearly_up(secs(44)));
+        assert!(limit.is_nearly_up(secs(45)));
+        assert!(limit.is_nearly_up(secs(59)));
+        assert!(!limit.is_nearly_up(secs(60)));
+    }
+
+    #[test]
+    fn scores_fewer_guesses_and_less_time_higher() {
+        let limit = TimeLimit::new(secs(60));
+
+        assert_eq!(1000, limit.score(1, secs(0)));
+        assert_eq!(250 + 250, limit.score(2, secs(30)));
+        assert_eq!(100, limit.score(5, secs(60)));
+        assert!(limit.score(4, secs(10)) > limit.score(4, secs(20)));
+        assert!(limit.score(3, secs(10)) > limit.score(6, secs(10)));
+    }
+}
@@ -0,0 +1,48 @@
+// The words word mode picks from, one to a line. include_str! puts the
+// file into the program when it's compiled, so there's nothing to find
+// at runtime.
+const WORDS: &str = include_str!("words.txt");
+
+/// Every word the secret can be in word mode, in alphabetical order.
+pub fn words() -> Vec<String> {
+    WORDS.lines().map(|word| word.to_string()).collect()
+}
+
+/// Reads a guessed word, ignoring the whitespace around it and its case.
+/// None if it isn't a word: nothing at all, or anything besides letters.
+pub fn parse_word(input: &str) -> Option<String> {
+    let word = input.trim().to_lowercase();
+    if word.is_empty() || !word.chars().all(|c| c.is_alphabetic()) {
+        return None;
+    }
+    Some(word)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::Command;
+
+    #[test]
+    fn has_sorted_words_that_arent_commands() {
+        let words = words();
+        assert!(words.len() > 50);
+
+        let mut sorted = words.clone();
+        sorted.sort();
+        sorted.dedup();
+        assert_eq!(sorted, words);
+        for word in &words {
+            assert_eq!(Some(word.clone()), parse_word(word));
+            assert_eq!(None, Command::parse(word), "{} is a command", word);
+        }
+    }
+
+    #[test]
+    fn reads_words() {
+        assert_eq!(Some(String::from("castle")), parse_word(" Castle\n"));
+        assert_eq!(None, parse_word("42"));
+        assert_eq!(None, parse_word("two words"));
+        assert_eq!(None, parse_word(""));
+    }
+}
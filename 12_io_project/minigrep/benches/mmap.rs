@@ -0,0 +1,48 @@
+// Compares the buffered and memory-mapped search paths on a generated file.
+// Run with `cargo bench`; each mode is timed over a few runs and the best
+// time is reported, so a warm page cache favours neither of them.
+
+use std::env;
+use std::fs;
+use std::io;
+use std::path::Path;
+use std::time::{Duration, Instant};
+
+use minigrep::Config;
+
+const LINES: usize = 2_000_000;
+const RUNS: usize = 5;
+
+fn best_of(path: &Path, mmap: bool) -> Duration {
+  let config = Config::builder("needle").count(true).mmap(mmap).build();
+
+  (0..RUNS)
+    .map(|_| {
+      let start = Instant::now();
+      minigrep::search_path(&config, path, false, &mut io::sink()).unwrap();
+      start.elapsed()
+    })
+    .min()
+    .unwrap()
+}
+
+fn main() {
+  let path = env::temp_dir().join(format!("minigrep-bench-{}.txt", std::process::id()));
+  let mut contents = String::with_capacity(LINES * 40);
+  for i in 0..LINES {
+    if i % 100 == 0 {
+      contents.push_str("a line with the needle somewhere in it\n");
+    } else {
+      contents.push_str("a line made of nothing but plain hay\n");
+    }
+  }
+  fs::write(&path, &contents).unwrap();
+
+  let buffered = best_of(&path, false);
+  let mapped = best_of(&path, true);
+  fs::remove_file(&path).unwrap();
+
+  println!("{} MiB, best of {} runs", contents.len() >> 20, RUNS);
+  println!("buffered: {:>8.1?}", buffered);
+  println!("mmap:     {:>8.1?}", mapped);
+}
@@ -0,0 +1,72 @@
+// Compares the byte-scanning search with the line-by-line search it
+// replaced, on inputs where matches are rare and where they are common. Run
+// with `cargo bench`; the best of a few runs is reported for each.
+
+use std::time::{Duration, Instant};
+
+use minigrep::{count_pattern_matches, find_matches, Pattern};
+
+const LINES: usize = 2_000_000;
+const RUNS: usize = 5;
+
+fn best_of<F: FnMut() -> usize>(mut search: F) -> (Duration, usize) {
+  let mut found = 0;
+  let best = (0..RUNS)
+    .map(|_| {
+      let start = Instant::now();
+      found = search();
+      start.elapsed()
+    })
+    .min()
+    .unwrap();
+
+  (best, found)
+}
+
+// `every`th line holds the needle
+fn corpus(every: usize) -> String {
+  let mut contents = String::with_capacity(LINES * 40);
+  for i in 0..LINES {
+    if i % every == 0 {
+      contents.push_str("a line with the needle somewhere in it\n");
+    } else {
+      contents.push_str("a line made of nothing but plain hay\n");
+    }
+  }
+  contents
+}
+
+fn compare(name: &str, contents: &str) {
+  let pattern = Pattern::new("needle", true);
+
+  // What find_matches and count_pattern_matches used to do: split out every
+  // line, then test it
+  let (old_find, found) = best_of(|| {
+    contents
+      .lines()
+      .filter_map(|line| pattern.find_all(line))
+      .count()
+  });
+  let (old_count, _) = best_of(|| {
+    contents
+      .lines()
+      .filter(|line| pattern.is_match(line))
+      .count()
+  });
+
+  let (new_find, new_found) = best_of(|| find_matches(&pattern, contents, false).count());
+  let (new_count, new_counted) = best_of(|| count_pattern_matches(&pattern, contents, false));
+  assert_eq!((found, found), (new_found, new_counted));
+
+  println!("{} ({} matching lines)", name, found);
+  println!("  find,  line by line: {:>8.1?}", old_find);
+  println!("  find,  scanning:     {:>8.1?}", new_find);
+  println!("  count, line by line: {:>8.1?}", old_count);
+  println!("  count, scanning:     {:>8.1?}", new_count);
+}
+
+fn main() {
+  compare("rare matches", &corpus(10_000));
+  compare("one line in a hundred", &corpus(100));
+  compare("every line", &corpus(1));
+}
@@ -0,0 +1,884 @@
+// Command line parsing for minigrep.
+// A PATH of `-` reads standard input.
+//
+// Every flag is described once in FLAGS; both the parser and the --help
+// text are driven by that table, so they can't drift apart. Short flags can
+// be grouped (`-in`), values can be given as `--format json`,
+// `--format=json` or `-m2`, and `--` ends flag parsing so a query can start
+// with a dash.
+
+use std::env;
+use std::fmt;
+use std::path::PathBuf;
+
+use crate::config::{ColorChoice, Config, ConfigBuilder, ListFiles};
+use crate::encoding::Encoding;
+use crate::output::OutputFormat;
+use crate::rc::{self, Setting, Value};
+
+struct Flag {
+  short: Option<char>,
+  long: &'static str,
+  // Name of the value the flag expects, if it takes one
+  value: Option<&'static str>,
+  help: &'static str,
+}
+
+const FLAGS: &[Flag] = &[
+  Flag {
+    short: Some('e'),
+    long: "pattern",
+    value: Some("QUERY"),
+    help: "Search for QUERY; repeat to match lines containing any of them",
+  },
+  Flag {
+    short: Some('i'),
+    long: "ignore-case",
+    value: None,
+    help: "Search case insensitively (same as setting CASE_INSENSITIVE)",
+  },
+  Flag {
+    short: None,
+    long: "case-sensitive",
+    value: None,
+    help: "Search case sensitively, even if CASE_INSENSITIVE is set",
+  },
+  Flag {
+    short: None,
+    long: "smart-case",
+    value: None,
+    help: "Ignore case unless the query has an uppercase letter in it",
+  },
+  Flag {
+    short: Some('v'),
+    long: "invert-match",
+    value: None,
+    help: "Print the lines that do not match",
+  },
+  Flag {
+    short: Some('w'),
+    long: "word-regexp",
+    value: None,
+    help: "Only match the query as a whole word",
+  },
+  Flag {
+    short: Some('n'),
+    long: "line-number",
+    value: None,
+    help: "Prefix each line with its line number",
+  },
+  Flag {
+    short: Some('b'),
+    long: "byte-offset",
+    value: None,
+    help: "Prefix each line with the byte offset of its first match",
+  },
+  Flag {
+    short: Some('c'),
+    long: "count",
+    value: None,
+    help: "Print only the number of matching lines per file",
+  },
+  Flag {
+    short: Some('l'),
+    long: "files-with-matches",
+    value: None,
+    help: "Print only the names of files with a matching line",
+  },
+  Flag {
+    short: Some('L'),
+    long: "files-without-match",
+    value: None,
+    help: "Print only the names of files without a matching line",
+  },
+  Flag {
+    short: Some('0'),
+    long: "null",
+    value: None,
+    help: "End file names and lines with a NUL byte, for use with `xargs -0`",
+  },
+  Flag {
+    short: Some('q'),
+    long: "quiet",
+    value: None,
+    help: "Print nothing; exit with 0 if any line matched, 1 otherwise",
+  },
+  Flag {
+    short: Some('m'),
+    long: "max-count",
+    value: Some("NUM"),
+    help: "Stop searching a file after NUM matching lines",
+  },
+  Flag {
+    short: Some('A'),
+    long: "after-context",
+    value: Some("NUM"),
+    help: "Print NUM lines after each matching line",
+  },
+  Flag {
+    short: Some('B'),
+    long: "before-context",
+    value: Some("NUM"),
+    help: "Print NUM lines before each matching line",
+  },
+  Flag {
+    short: Some('C'),
+    long: "context",
+    value: Some("NUM"),
+    help: "Print NUM lines before and after each matching line",
+  },
+  Flag {
+    short: None,
+    long: "group-separator",
+    value: Some("SEP"),
+    help: "Print SEP between groups of context lines [default: --]",
+  },
+  Flag {
+    short: None,
+    long: "no-group-separator",
+    value: None,
+    help: "Print nothing between groups of context lines",
+  },
+  Flag {
+    short: None,
+    long: "stats",
+    value: None,
+    help: "Print how many files and lines were searched, and how long it took",
+  },
+  Flag {
+    short: None,
+    long: "color",
+    value: Some("WHEN"),
+    help: "Highlight matches: auto, always or never [default: auto]",
+  },
+  Flag {
+    short: None,
+    long: "format",
+    value: Some("FORMAT"),
+    help: "Output format: text or json [default: text]",
+  },
+  Flag {
+    short: None,
+    long: "glob",
+    value: Some("GLOB"),
+    help: "Only search files matching GLOB, e.g. '*.rs' (repeatable)",
+  },
+  Flag {
+    short: None,
+    long: "no-ignore",
+    value: None,
+    help: "Don't skip files listed in .gitignore",
+  },
+  Flag {
+    short: None,
+    long: "follow",
+    value: None,
+    help: "Follow symlinks while walking directories",
+  },
+  Flag {
+    short: None,
+    long: "encoding",
+    value: Some("ENC"),
+    help: "Read files as auto, utf-8, utf-16le, utf-16be or latin1 [default: auto]",
+  },
+  Flag {
+    short: None,
+    long: "mmap",
+    value: None,
+    help: "Memory-map large files instead of reading them (faster on big files)",
+  },
+  Flag {
+    short: None,
+    long: "no-config",
+    value: None,
+    help: "Ignore ~/.minigreprc and ./minigrep.toml",
+  },
+  Flag {
+    short: Some('h'),
+    long: "help",
+    value: None,
+    help: "Print this help and exit",
+  },
+];
+
+// Flags that only make sense on the command line
+const NOT_IN_CONFIG: &[&str] = &["pattern", "no-config", "help"];
+
+#[derive(Debug, PartialEq)]
+pub enum ArgsError {
+  // --help was given; not really an error, but parsing stops there
+  HelpRequested,
+  Invalid(String),
+}
+
+impl fmt::Display for ArgsError {
+  fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+    match self {
+      ArgsError::HelpRequested => write!(f, "help requested"),
+      ArgsError::Invalid(message) => write!(f, "{}", message),
+    }
+  }
+}
+
+impl std::error::Error for ArgsError {}
+
+fn invalid(message: String) -> ArgsError {
+  ArgsError::Invalid(message)
+}
+
+pub fn usage() -> String {
+  let mut text = String::from(
+    "Usage: minigrep [OPTIONS] <QUERY> <PATH>...\n\
+     \x20      minigrep [OPTIONS] -e <QUERY>... <PATH>...\n\n\
+     Search for QUERY in each PATH. Directories are searched recursively,\n\
+     and `-` reads standard input.\n\n\
+     Exits with 0 if any line was selected and 1 if none was. Errors exit\n\
+     with 2 for bad arguments, 3 for a bad query and 4 for I/O failures.\n\n\
+     Options:\n",
+  );
+
+  let names: Vec<String> = FLAGS
+    .iter()
+    .map(|flag| {
+      let mut name = match flag.short {
+        Some(short) => format!("-{}, --{}", short, flag.long),
+        None => format!("    --{}", flag.long),
+      };
+      if let Some(value) = flag.value {
+        name.push_str(&format!(" <{}>", value));
+      }
+      name
+    })
+    .collect();
+  let width = names.iter().map(|name| name.len()).max().unwrap_or(0);
+
+  for (flag, name) in FLAGS.iter().zip(&names) {
+    text.push_str(&format!(
+      "  {:width$}  {}\n",
+      name,
+      flag.help,
+      width = width
+    ));
+  }
+
+  text
+}
+
+pub fn parse(args: &[String]) -> Result<Config, ArgsError> {
+  parse_in(
+    args,
+    &rc::config_files(),
+    env::var_os("CASE_INSENSITIVE").is_some(),
+  )
+}
+
+// Reads the config `files` and then parses `args` on top of them, so tests
+// can give their own files instead of the real ones
+fn parse_in(
+  args: &[String],
+  files: &[PathBuf],
+  case_insensitive_env: bool,
+) -> Result<Config, ArgsError> {
+  // The config files are skipped for --no-config, which has to be spotted
+  // before the real parsing starts
+  let no_config = args
+    .iter()
+    .skip(1)
+    .take_while(|arg| *arg != "--")
+    .any(|arg| arg == "--no-config");
+  let defaults = if no_config {
+    Vec::new()
+  } else {
+    rc::load(files).map_err(invalid)?
+  };
+
+  parse_with(args, &defaults, case_insensitive_env)
+}
+
+// The parser proper, with the config file settings and the CASE_INSENSITIVE
+// environment variable passed in so tests don't depend on the real files
+// and environment. Settings from config files count as flags given before
+// the command line ones, and -i and --case-sensitive always beat the
+// variable; wherever two flags disagree, the last one given wins.
+fn parse_with(
+  args: &[String],
+  defaults: &[Setting],
+  case_insensitive_env: bool,
+) -> Result<Config, ArgsError> {
+  // Unlike the library default, the command line only colors output when
+  // it goes to a terminal
+  let mut builder = Config::builder("").color(ColorChoice::Auto);
+  let mut state = State {
+    case_sensitive: !case_insensitive_env,
+    smart_case: false,
+    patterns: Vec::new(),
+  };
+  let mut positional = Vec::new();
+
+  for setting in defaults {
+    builder = apply_setting(builder, setting, &mut state)
+      .map_err(|e| invalid(format!("{}: {}", setting.source, e)))?;
+  }
+
+  // Skip the name of the program
+  let mut args = args.iter().skip(1);
+
+  while let Some(arg) = args.next() {
+    if arg == "--" {
+      positional.extend(args.by_ref());
+    } else if let Some(long) = arg.strip_prefix("--") {
+      let (name, inline) = match long.find('=') {
+        Some(i) => (&long[..i], Some(&long[i + 1..])),
+        None => (long, None),
+      };
+      let flag = FLAGS
+        .iter()
+        .find(|flag| flag.long == name)
+        .ok_or_else(|| invalid(format!("unknown option `--{}`", name)))?;
+
+      let value = match (flag.value, inline) {
+        (Some(_), Some(value)) => Some(value.to_string()),
+        (Some(_), None) => Some(take_value(flag, args.next())?),
+        (None, Some(_)) => {
+          return Err(invalid(format!("`--{}` doesn't take a value", flag.long)));
+        }
+        (None, None) => None,
+      };
+
+      builder = apply(builder, flag, value, &mut state)?;
+    } else if arg.len() > 1 && arg.starts_with('-') {
+      // A group of short flags like `-in`; a flag that takes a value uses
+      // the rest of the group, or the next argument
+      let shorts = &arg[1..];
+
+      for (i, c) in shorts.char_indices() {
+        let flag = FLAGS
+          .iter()
+          .find(|flag| flag.short == Some(c))
+          .ok_or_else(|| invalid(format!("unknown option `-{}`", c)))?;
+
+        if flag.value.is_some() {
+          let rest = &shorts[i + c.len_utf8()..];
+          let value = if rest.is_empty() {
+            take_value(flag, args.next())?
+          } else {
+            rest.to_string()
+          };
+
+          builder = apply(builder, flag, Some(value), &mut state)?;
+          break;
+        }
+
+        builder = apply(builder, flag, None, &mut state)?;
+      }
+    } else {
+      positional.push(arg);
+    }
+  }
+
+  // Without -e the first positional argument is the query
+  let mut positional = positional.into_iter();
+  if state.patterns.is_empty() {
+    let query = positional
+      .next()
+      .ok_or_else(|| invalid(String::from("missing the QUERY to search for")))?;
+    state.patterns.push(query.clone());
+  }
+  let filenames: Vec<&String> = positional.collect();
+  if filenames.is_empty() {
+    return Err(invalid(String::from("missing a PATH to search in")));
+  }
+
+  let mut config = builder
+    .case_sensitive(state.case_sensitive)
+    .smart_case(state.smart_case)
+    .build();
+  config.patterns = state.patterns;
+  config.filenames = filenames.into_iter().cloned().collect();
+
+  Ok(config)
+}
+
+// Applies one config file setting the way the flag of the same name would
+// be applied
+fn apply_setting(
+  builder: ConfigBuilder,
+  setting: &Setting,
+  state: &mut State,
+) -> Result<ConfigBuilder, ArgsError> {
+  let flag = FLAGS
+    .iter()
+    .find(|flag| flag.long == setting.name && !NOT_IN_CONFIG.contains(&flag.long))
+    .ok_or_else(|| invalid(format!("unknown option `{}`", setting.name)))?;
+
+  match (flag.value, &setting.value) {
+    (Some(_), Value::Text(value)) => apply(builder, flag, Some(value.clone()), state),
+    (Some(_), Value::Switch(_)) => Err(invalid(format!("`{}` expects a value", flag.long))),
+    (None, Value::Switch(true)) => apply(builder, flag, None, state),
+    // There's nothing to turn off, as every switch starts out off
+    (None, Value::Switch(false)) => Ok(builder),
+    (None, Value::Text(_)) => Err(invalid(format!(
+      "`{}` is a switch, set it to true or false",
+      flag.long
+    ))),
+  }
+}
+
+fn take_value(flag: &Flag, value: Option<&String>) -> Result<String, ArgsError> {
+  value
+    .cloned()
+    .ok_or_else(|| invalid(format!("`--{}` expects a value", flag.long)))
+}
+
+// What parsing has to keep track of on the side, because it isn't settled
+// until every argument has been seen
+struct State {
+  case_sensitive: bool,
+  smart_case: bool,
+  // Queries given with -e; when there are none the query is positional
+  patterns: Vec<String>,
+}
+
+fn apply(
+  builder: ConfigBuilder,
+  flag: &Flag,
+  value: Option<String>,
+  state: &mut State,
+) -> Result<ConfigBuilder, ArgsError> {
+  let value = value.unwrap_or_default();
+  let bad_value = |expected: &str| {
+    invalid(format!(
+      "invalid value `{}` for `--{}`: expected {}",
+      value, flag.long, expected
+    ))
+  };
+
+  let builder = match flag.long {
+    "pattern" => {
+      state.patterns.push(value);
+      builder
+    }
+    "ignore-case" => {
+      state.case_sensitive = false;
+      state.smart_case = false;
+      builder
+    }
+    "case-sensitive" => {
+      state.case_sensitive = true;
+      state.smart_case = false;
+      builder
+    }
+    "smart-case" => {
+      state.smart_case = true;
+      builder
+    }
+    "invert-match" => builder.invert_match(true),
+    "word-regexp" => builder.whole_word(true),
+    "line-number" => builder.line_number(true),
+    "byte-offset" => builder.byte_offset(true),
+    "count" => builder.count(true),
+    "files-with-matches" => builder.list_files(ListFiles::WithMatches),
+    "files-without-match" => builder.list_files(ListFiles::WithoutMatch),
+    "null" => builder.null(true),
+    "quiet" => builder.quiet(true),
+    "max-count" => builder.max_count(value.parse().map_err(|_| bad_value("a number"))?),
+    "after-context" => builder.after_context(value.parse().map_err(|_| bad_value("a number"))?),
+    "before-context" => builder.before_context(value.parse().map_err(|_| bad_value("a number"))?),
+    "context" => {
+      let lines = value.parse().map_err(|_| bad_value("a number"))?;
+      builder.before_context(lines).after_context(lines)
+    }
+    "group-separator" => builder.group_separator(Some(&value)),
+    "no-group-separator" => builder.group_separator(None),
+    "color" => {
+      builder.color(ColorChoice::parse(&value).ok_or_else(|| bad_value("auto, always or never"))?)
+    }
+    "format" => builder.format(OutputFormat::parse(&value).map_err(|_| bad_value("text or json"))?),
+    "stats" => builder.stats(true),
+    "glob" => builder.glob(&value),
+    "no-ignore" => builder.no_ignore(true),
+    "follow" => builder.follow(true),
+    "encoding" => builder.encoding(
+      Encoding::parse(&value)
+        .ok_or_else(|| bad_value("auto, utf-8, utf-16le, utf-16be or latin1"))?,
+    ),
+    "mmap" => builder.mmap(true),
+    // Handled before parsing starts
+    "no-config" => builder,
+    "help" => return Err(ArgsError::HelpRequested),
+    _ => unreachable!("every flag in FLAGS is handled"),
+  };
+
+  Ok(builder)
+}
+
+#[cfg(test)]
+mod tests {
+  use super::*;
+  use std::fs;
+
+  fn args(list: &[&str]) -> Vec<String> {
+    std::iter::once("minigrep")
+      .chain(list.iter().copied())
+      .map(String::from)
+      .collect()
+  }
+
+  // Shadows the real parse, so these tests never see the config files or
+  // CASE_INSENSITIVE of the machine running them
+  fn parse(args: &[String]) -> Result<Config, ArgsError> {
+    parse_with(args, &[], false)
+  }
+
+  // A home and a project config file with the given contents, in a fresh
+  // directory named after `name`
+  fn config_files(name: &str, home: &str, project: &str) -> (PathBuf, Vec<PathBuf>) {
+    let dir = env::temp_dir().join(format!("minigrep-{}-{}", name, std::process::id()));
+    fs::create_dir_all(&dir).unwrap();
+    let files = vec![dir.join(".minigreprc"), dir.join("minigrep.toml")];
+    fs::write(&files[0], home).unwrap();
+    fs::write(&files[1], project).unwrap();
+    (dir, files)
+  }
+
+  #[test]
+  fn reads_query_and_paths() {
+    let config = parse(&args(&["to", "a.txt", "b.txt"])).unwrap();
+
+    assert_eq!(vec!["to"], config.patterns);
+    assert_eq!(vec!["a.txt", "b.txt"], config.filenames);
+  }
+
+  #[test]
+  fn flags_can_appear_anywhere() {
+    let config = parse(&args(&["-v", "to", "poem.txt", "-n"])).unwrap();
+
+    assert!(config.invert_match);
+    assert!(config.line_number);
+    assert_eq!(vec!["to"], config.patterns);
+    assert_eq!(vec!["poem.txt"], config.filenames);
+  }
+
+  #[test]
+  fn repeated_patterns_leave_every_positional_a_path() {
+    let config = parse(&args(&[
+      "-e",
+      "to",
+      "--pattern=go",
+      "-e-v",
+      "a.txt",
+      "b.txt",
+    ]))
+    .unwrap();
+
+    assert_eq!(vec!["to", "go", "-v"], config.patterns);
+    assert_eq!(vec!["a.txt", "b.txt"], config.filenames);
+    assert_eq!(
+      Err(invalid(String::from("missing a PATH to search in"))),
+      parse(&args(&["-e", "to"])).map(|_| ())
+    );
+  }
+
+  #[test]
+  fn short_flags_can_be_grouped() {
+    let config = parse(&args(&["-vwn", "to", "poem.txt"])).unwrap();
+
+    assert!(config.invert_match && config.whole_word && config.line_number);
+  }
+
+  #[test]
+  fn ignore_case_flag() {
+    assert!(
+      !parse(&args(&["-i", "to", "poem.txt"]))
+        .unwrap()
+        .case_sensitive
+    );
+  }
+
+  #[test]
+  fn case_flags_beat_the_environment() {
+    let case_sensitive = |flags: &[&str], env: bool| {
+      let mut list = flags.to_vec();
+      list.extend(["to", "poem.txt"]);
+      parse_with(&args(&list), &[], env).unwrap().case_sensitive
+    };
+
+    // (flags, CASE_INSENSITIVE set, expected case sensitivity)
+    let cases: &[(&[&str], bool, bool)] = &[
+      (&[], false, true),
+      (&[], true, false),
+      (&["-i"], false, false),
+      (&["-i"], true, false),
+      (&["--case-sensitive"], false, true),
+      (&["--case-sensitive"], true, true),
+      (&["-i", "--case-sensitive"], false, true),
+      (&["-i", "--case-sensitive"], true, true),
+      (&["--case-sensitive", "-i"], false, false),
+      (&["--case-sensitive", "-i"], true, false),
+    ];
+
+    for &(flags, env, expected) in cases {
+      assert_eq!(
+        expected,
+        case_sensitive(flags, env),
+        "{:?}, env set: {}",
+        flags,
+        env
+      );
+    }
+  }
+
+  #[test]
+  fn the_last_case_flag_wins_over_smart_case() {
+    let parse = |flags: &[&str], query: &str| {
+      let mut list = flags.to_vec();
+      list.extend([query, "poem.txt"]);
+      parse_with(&args(&list), &[], true)
+        .unwrap()
+        .is_case_sensitive()
+    };
+
+    assert!(!parse(&["--smart-case"], "rust"));
+    assert!(parse(&["--smart-case"], "Rust"));
+    assert!(!parse(&["--smart-case", "-i"], "Rust"));
+    assert!(parse(&["-i", "--smart-case"], "Rust"));
+    assert!(parse(&["--smart-case", "--case-sensitive"], "rust"));
+  }
+
+  #[test]
+  fn command_line_beats_config_files() {
+    let (dir, files) = config_files(
+      "rc-beaten",
+      "color = \"always\"\nignore-case = true\nglob = [\"*.rs\"]\nmax-count = 5\n",
+      "",
+    );
+    let parse = |list: &[&str]| parse_in(&args(list), &files, false).unwrap();
+
+    let config = parse(&["to", "f"]);
+    assert_eq!(ColorChoice::Always, config.color);
+    assert!(!config.case_sensitive);
+    assert_eq!(vec!["*.rs"], config.globs);
+    assert_eq!(Some(5), config.max_count);
+
+    let config = parse(&[
+      "--color=never",
+      "--case-sensitive",
+      "--glob=*.md",
+      "-m1",
+      "to",
+      "f",
+    ]);
+    assert_eq!(ColorChoice::Never, config.color);
+    assert!(config.case_sensitive);
+    assert_eq!(vec!["*.rs", "*.md"], config.globs);
+    assert_eq!(Some(1), config.max_count);
+
+    let config = parse(&["--no-config", "to", "f"]);
+    assert_eq!(ColorChoice::Auto, config.color);
+    assert!(config.case_sensitive);
+    assert!(config.globs.is_empty());
+
+    fs::remove_dir_all(&dir).unwrap();
+  }
+
+  #[test]
+  fn later_config_files_beat_earlier_ones() {
+    let (dir, files) = config_files(
+      "rc-order",
+      "format = \"json\"\ncolor = \"never\"",
+      "color = 'always'",
+    );
+    let config = parse_in(&args(&["to", "f"]), &files, false).unwrap();
+    fs::remove_dir_all(&dir).unwrap();
+
+    assert_eq!(ColorChoice::Always, config.color);
+    assert_eq!(OutputFormat::Json, config.format);
+  }
+
+  #[test]
+  fn the_environment_still_counts_with_config_files() {
+    let (dir, files) = config_files("rc-env", "count = true", "");
+    let config = parse_in(&args(&["to", "f"]), &files, true).unwrap();
+    fs::remove_dir_all(&dir).unwrap();
+
+    assert!(!config.case_sensitive);
+    assert!(config.count);
+  }
+
+  #[test]
+  fn bad_config_settings_name_their_source() {
+    let message = |contents: &str| {
+      let defaults = rc::parse(contents, "minigrep.toml").unwrap();
+      parse_with(&args(&["to", "f"]), &defaults, false)
+        .unwrap_err()
+        .to_string()
+    };
+
+    assert_eq!(
+      "minigrep.toml:1: unknown option `threads`",
+      message("threads = 3")
+    );
+    assert_eq!(
+      "minigrep.toml:2: unknown option `help`",
+      message("\nhelp = true")
+    );
+    assert_eq!(
+      "minigrep.toml:1: `count` is a switch, set it to true or false",
+      message("count = \"yes\"")
+    );
+    assert_eq!(
+      "minigrep.toml:1: `color` expects a value",
+      message("color = true")
+    );
+    assert_eq!(
+      "minigrep.toml:1: invalid value `red` for `--color`: expected auto, always or never",
+      message("color = \"red\"")
+    );
+  }
+
+  #[test]
+  fn values_in_every_spelling() {
+    for list in [
+      &["--max-count", "2", "to", "f"][..],
+      &["--max-count=2", "to", "f"],
+      &["-m", "2", "to", "f"],
+      &["-m2", "to", "f"],
+      &["-cm2", "to", "f"],
+    ] {
+      assert_eq!(Some(2), parse(&args(list)).unwrap().max_count, "{:?}", list);
+    }
+  }
+
+  #[test]
+  fn reads_color_format_and_globs() {
+    let config = parse(&args(&[
+      "--color=always",
+      "--format",
+      "json",
+      "--glob",
+      "*.rs",
+      "--glob=*.md",
+      "--no-ignore",
+      "to",
+      ".",
+    ]))
+    .unwrap();
+
+    assert_eq!(ColorChoice::Always, config.color);
+    assert_eq!(OutputFormat::Json, config.format);
+    assert_eq!(vec!["*.rs", "*.md"], config.globs);
+    assert!(config.no_ignore);
+  }
+
+  #[test]
+  fn the_last_listing_flag_wins() {
+    assert_eq!(
+      Some(ListFiles::WithMatches),
+      parse(&args(&["-L", "-l", "to", "f"])).unwrap().list_files
+    );
+    assert_eq!(
+      Some(ListFiles::WithoutMatch),
+      parse(&args(&["-l", "--files-without-match", "to", "f"]))
+        .unwrap()
+        .list_files
+    );
+  }
+
+  #[test]
+  fn null_has_a_short_and_a_long_form() {
+    assert!(parse(&args(&["-l0", "to", "f"])).unwrap().null);
+    assert!(parse(&args(&["--null", "to", "f"])).unwrap().null);
+    assert!(!parse(&args(&["to", "f"])).unwrap().null);
+  }
+
+  #[test]
+  fn reads_context_and_group_separators() {
+    let config = parse(&args(&["-A1", "-B", "2", "to", "f"])).unwrap();
+    assert_eq!((2, 1), (config.before_context, config.after_context));
+    assert_eq!(Some(String::from("--")), config.group_separator);
+
+    let config = parse(&args(&["-C3", "-A0", "to", "f"])).unwrap();
+    assert_eq!((3, 0), (config.before_context, config.after_context));
+
+    let config = parse(&args(&[
+      "--context=1",
+      "--group-separator",
+      "==",
+      "to",
+      "f",
+    ]))
+    .unwrap();
+    assert_eq!((1, 1), (config.before_context, config.after_context));
+    assert_eq!(Some(String::from("==")), config.group_separator);
+
+    let config = parse(&args(&["--group-separator=", "to", "f"])).unwrap();
+    assert_eq!(Some(String::new()), config.group_separator);
+    let config = parse(&args(&["--no-group-separator", "to", "f"])).unwrap();
+    assert_eq!(None, config.group_separator);
+  }
+
+  #[test]
+  fn color_defaults_to_auto() {
+    assert_eq!(ColorChoice::Auto, parse(&args(&["to", "f"])).unwrap().color);
+  }
+
+  #[test]
+  fn double_dash_ends_flags() {
+    let config = parse(&args(&["--", "-v", "poem.txt"])).unwrap();
+
+    assert_eq!(vec!["-v"], config.patterns);
+    assert!(!config.invert_match);
+  }
+
+  #[test]
+  fn help_flag_stops_parsing() {
+    assert_eq!(
+      Err(ArgsError::HelpRequested),
+      parse(&args(&["--help"])).map(|_| ())
+    );
+    assert_eq!(
+      Err(ArgsError::HelpRequested),
+      parse(&args(&["to", "-h"])).map(|_| ())
+    );
+  }
+
+  #[test]
+  fn explains_what_went_wrong() {
+    let message = |list: &[&str]| parse(&args(list)).unwrap_err().to_string();
+
+    assert_eq!("missing the QUERY to search for", message(&[]));
+    assert_eq!("missing a PATH to search in", message(&["to"]));
+    assert_eq!(
+      "unknown option `--colour`",
+      message(&["--colour", "to", "f"])
+    );
+    assert_eq!("unknown option `-x`", message(&["-x", "to", "f"]));
+    assert_eq!(
+      "`--format` expects a value",
+      message(&["to", "f", "--format"])
+    );
+    assert_eq!(
+      "`--count` doesn't take a value",
+      message(&["--count=3", "to", "f"])
+    );
+    assert_eq!(
+      "invalid value `two` for `--max-count`: expected a number",
+      message(&["-m", "two", "to", "f"])
+    );
+    assert_eq!(
+      "invalid value `xml` for `--format`: expected text or json",
+      message(&["--format=xml", "to", "f"])
+    );
+  }
+
+  #[test]
+  fn help_lists_every_flag() {
+    let text = usage();
+
+    assert!(text.starts_with("Usage: minigrep [OPTIONS] <QUERY> <PATH>..."));
+    for flag in FLAGS {
+      assert!(text.contains(&format!("--{}", flag.long)), "{}", flag.long);
+    }
+    assert!(text.contains("  -m, --max-count <NUM>  "));
+  }
+}
@@ -0,0 +1,407 @@
+// Everything that controls a minigrep run. A Config usually comes from the
+// command line (see Config::new and the cli module), but library users can
+// build one directly:
+//
+//     let config = Config::builder("nobody")
+//       .file("poem.txt")
+//       .case_sensitive(false)
+//       .line_number(true)
+//       .build();
+
+use std::io::{self, IsTerminal};
+
+use crate::cli::{self, ArgsError};
+use crate::encoding::Encoding;
+use crate::output::OutputFormat;
+use crate::search::{self, Pattern};
+
+// When to highlight matches with terminal colors
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum ColorChoice {
+  // Only when stdout is a terminal
+  Auto,
+  Always,
+  Never,
+}
+
+impl ColorChoice {
+  pub fn parse(name: &str) -> Option<ColorChoice> {
+    match name {
+      "auto" => Some(ColorChoice::Auto),
+      "always" => Some(ColorChoice::Always),
+      "never" => Some(ColorChoice::Never),
+      _ => None,
+    }
+  }
+}
+
+// Which files -l and -L list instead of printing matching lines
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum ListFiles {
+  // Files with at least one matching line (-l)
+  WithMatches,
+  // Files without any (-L)
+  WithoutMatch,
+}
+
+#[derive(Debug, Clone)]
+pub struct Config {
+  // The queries to look for; a line matches if it contains any of them
+  pub patterns: Vec<String>,
+  // Files or directories to search; directories are searched recursively
+  pub filenames: Vec<String>,
+  pub case_sensitive: bool,
+  // When set, case_sensitive is ignored and the queries decide instead:
+  // any uppercase letter in them makes the search case sensitive
+  pub smart_case: bool,
+  // When set, print the lines that do NOT contain the query (like grep -v)
+  pub invert_match: bool,
+  // When set, the query only matches whole words (like grep -w)
+  pub whole_word: bool,
+  // When set, print only how many lines matched instead of the lines
+  pub count: bool,
+  // Stop searching a file after this many matching lines (like grep -m)
+  pub max_count: Option<usize>,
+  // When set, put the line number in front of every printed line
+  pub line_number: bool,
+  // When set, also print the byte offset of each match within its file.
+  // Offsets count bytes after decoding (see --encoding), so they only match
+  // the file on disk for UTF-8 files.
+  pub byte_offset: bool,
+  pub color: ColorChoice,
+  // How each matching line gets printed
+  pub format: OutputFormat,
+  // When set, search files even if a .gitignore says to skip them
+  pub no_ignore: bool,
+  // When set, follow symlinks found while walking directories
+  pub follow: bool,
+  // Only search files (found while walking directories) that match one of
+  // these wildcard patterns, e.g. `*.rs`
+  pub globs: Vec<String>,
+  // Memory-map large files instead of reading them through a buffer
+  pub mmap: bool,
+  // Encoding of the files being searched
+  pub encoding: Encoding,
+  // When set, print nothing; only the exit status tells if a line matched
+  pub quiet: bool,
+  // When set, print only the names of files (like grep -l and -L)
+  pub list_files: Option<ListFiles>,
+  // When set, end printed file names and lines with a NUL byte instead of
+  // `:` or a newline, so the output can be fed to `xargs -0`
+  pub null: bool,
+  // How many lines to print before and after each matching line (like grep
+  // -B and -A). Context is only printed with the text format.
+  pub before_context: usize,
+  pub after_context: usize,
+  // Printed between groups of context lines that aren't next to each
+  // other; None prints nothing there
+  pub group_separator: Option<String>,
+  // When set, print a summary of the search at the end
+  pub stats: bool,
+}
+
+impl Config {
+  // Parses the command line arguments, including the program name in
+  // args[0], the way std::env::args() hands them over
+  pub fn new(args: &[String]) -> Result<Config, ArgsError> {
+    cli::parse(args)
+  }
+
+  pub fn builder(query: &str) -> ConfigBuilder {
+    ConfigBuilder {
+      config: Config {
+        patterns: vec![query.to_string()],
+        filenames: Vec::new(),
+        case_sensitive: true,
+        smart_case: false,
+        invert_match: false,
+        whole_word: false,
+        count: false,
+        max_count: None,
+        line_number: false,
+        byte_offset: false,
+        color: ColorChoice::Never,
+        format: OutputFormat::Text,
+        no_ignore: false,
+        follow: false,
+        globs: Vec::new(),
+        mmap: false,
+        encoding: Encoding::Auto,
+        quiet: false,
+        list_files: None,
+        null: false,
+        before_context: 0,
+        after_context: 0,
+        group_separator: Some(String::from("--")),
+        stats: false,
+      },
+    }
+  }
+
+  // The most matching lines to report per file
+  pub fn limit(&self) -> usize {
+    self.max_count.unwrap_or(usize::MAX)
+  }
+
+  // The query and matching options, ready to test lines with
+  pub fn pattern(&self) -> Pattern {
+    Pattern::any(&self.patterns, self.is_case_sensitive()).whole_word(self.whole_word)
+  }
+
+  // Whether the search really is case sensitive, once smart case is taken
+  // into account
+  pub fn is_case_sensitive(&self) -> bool {
+    if self.smart_case {
+      self
+        .patterns
+        .iter()
+        .any(|query| search::is_smart_case_sensitive(query))
+    } else {
+      self.case_sensitive
+    }
+  }
+
+  // Whether lines around the matches get printed too, which only happens
+  // when the matching lines themselves are printed as text
+  pub fn shows_context(&self) -> bool {
+    (self.before_context > 0 || self.after_context > 0)
+      && self.format == OutputFormat::Text
+      && !self.count
+      && !self.quiet
+      && self.list_files.is_none()
+  }
+
+  // What ends each printed line of text
+  pub fn line_end(&self) -> &'static str {
+    if self.null {
+      "\0"
+    } else {
+      "\n"
+    }
+  }
+
+  pub fn use_color(&self) -> bool {
+    match self.color {
+      ColorChoice::Always => true,
+      ColorChoice::Never => false,
+      ColorChoice::Auto => io::stdout().is_terminal(),
+    }
+  }
+}
+
+pub struct ConfigBuilder {
+  config: Config,
+}
+
+impl ConfigBuilder {
+  // Adds another query; lines containing any of the queries match
+  pub fn pattern(mut self, query: &str) -> ConfigBuilder {
+    self.config.patterns.push(query.to_string());
+    self
+  }
+
+  // Adds a file or directory to search; call it once per path
+  pub fn file(mut self, filename: &str) -> ConfigBuilder {
+    self.config.filenames.push(filename.to_string());
+    self
+  }
+
+  pub fn case_sensitive(mut self, case_sensitive: bool) -> ConfigBuilder {
+    self.config.case_sensitive = case_sensitive;
+    self
+  }
+
+  pub fn invert_match(mut self, invert_match: bool) -> ConfigBuilder {
+    self.config.invert_match = invert_match;
+    self
+  }
+
+  pub fn whole_word(mut self, whole_word: bool) -> ConfigBuilder {
+    self.config.whole_word = whole_word;
+    self
+  }
+
+  pub fn count(mut self, count: bool) -> ConfigBuilder {
+    self.config.count = count;
+    self
+  }
+
+  pub fn max_count(mut self, max_count: usize) -> ConfigBuilder {
+    self.config.max_count = Some(max_count);
+    self
+  }
+
+  pub fn line_number(mut self, line_number: bool) -> ConfigBuilder {
+    self.config.line_number = line_number;
+    self
+  }
+
+  pub fn color(mut self, color: ColorChoice) -> ConfigBuilder {
+    self.config.color = color;
+    self
+  }
+
+  pub fn format(mut self, format: OutputFormat) -> ConfigBuilder {
+    self.config.format = format;
+    self
+  }
+
+  pub fn no_ignore(mut self, no_ignore: bool) -> ConfigBuilder {
+    self.config.no_ignore = no_ignore;
+    self
+  }
+
+  pub fn glob(mut self, glob: &str) -> ConfigBuilder {
+    self.config.globs.push(glob.to_string());
+    self
+  }
+
+  pub fn mmap(mut self, mmap: bool) -> ConfigBuilder {
+    self.config.mmap = mmap;
+    self
+  }
+
+  pub fn encoding(mut self, encoding: Encoding) -> ConfigBuilder {
+    self.config.encoding = encoding;
+    self
+  }
+
+  pub fn quiet(mut self, quiet: bool) -> ConfigBuilder {
+    self.config.quiet = quiet;
+    self
+  }
+
+  pub fn list_files(mut self, list_files: ListFiles) -> ConfigBuilder {
+    self.config.list_files = Some(list_files);
+    self
+  }
+
+  pub fn null(mut self, null: bool) -> ConfigBuilder {
+    self.config.null = null;
+    self
+  }
+
+  pub fn before_context(mut self, lines: usize) -> ConfigBuilder {
+    self.config.before_context = lines;
+    self
+  }
+
+  pub fn after_context(mut self, lines: usize) -> ConfigBuilder {
+    self.config.after_context = lines;
+    self
+  }
+
+  pub fn group_separator(mut self, separator: Option<&str>) -> ConfigBuilder {
+    self.config.group_separator = separator.map(String::from);
+    self
+  }
+
+  pub fn byte_offset(mut self, byte_offset: bool) -> ConfigBuilder {
+    self.config.byte_offset = byte_offset;
+    self
+  }
+
+  pub fn follow(mut self, follow: bool) -> ConfigBuilder {
+    self.config.follow = follow;
+    self
+  }
+
+  pub fn smart_case(mut self, smart_case: bool) -> ConfigBuilder {
+    self.config.smart_case = smart_case;
+    self
+  }
+
+  pub fn stats(mut self, stats: bool) -> ConfigBuilder {
+    self.config.stats = stats;
+    self
+  }
+
+  pub fn build(self) -> Config {
+    self.config
+  }
+}
+
+#[cfg(test)]
+mod tests {
+  use super::*;
+
+  #[test]
+  fn smart_case_overrides_case_sensitive() {
+    let config = |queries: &[&str]| {
+      let mut builder = Config::builder(queries[0])
+        .case_sensitive(false)
+        .smart_case(true);
+      for query in &queries[1..] {
+        builder = builder.pattern(query);
+      }
+      builder.build()
+    };
+
+    assert!(!config(&["rust"]).is_case_sensitive());
+    assert!(config(&["Rust"]).is_case_sensitive());
+    assert!(config(&["rust", "Trust"]).is_case_sensitive());
+    assert!(!Config::builder("Rust")
+      .case_sensitive(false)
+      .build()
+      .is_case_sensitive());
+  }
+
+  #[test]
+  fn builder_defaults_match_plain_minigrep() {
+    let config = Config::builder("to").file("poem.txt").build();
+
+    assert_eq!(vec!["to"], config.patterns);
+    assert_eq!(vec!["poem.txt"], config.filenames);
+    assert!(config.case_sensitive);
+    assert!(!config.invert_match && !config.count && !config.line_number);
+    assert_eq!(usize::MAX, config.limit());
+    assert!(!config.use_color());
+  }
+
+  #[test]
+  fn builder_sets_every_option() {
+    let config = Config::builder("to")
+      .pattern("go")
+      .file("a.txt")
+      .file("b.txt")
+      .case_sensitive(false)
+      .invert_match(true)
+      .whole_word(true)
+      .count(true)
+      .max_count(3)
+      .line_number(true)
+      .byte_offset(true)
+      .color(ColorChoice::Always)
+      .format(OutputFormat::Json)
+      .no_ignore(true)
+      .follow(true)
+      .glob("*.rs")
+      .mmap(true)
+      .encoding(Encoding::Latin1)
+      .quiet(true)
+      .list_files(ListFiles::WithoutMatch)
+      .null(true)
+      .before_context(1)
+      .after_context(2)
+      .group_separator(None)
+      .stats(true)
+      .build();
+
+    assert_eq!(vec!["to", "go"], config.patterns);
+    assert_eq!(vec!["a.txt", "b.txt"], config.filenames);
+    assert!(!config.case_sensitive);
+    assert!(config.invert_match && config.whole_word && config.count);
+    assert_eq!(3, config.limit());
+    assert!(config.line_number && config.byte_offset && config.use_color());
+    assert_eq!(OutputFormat::Json, config.format);
+    assert!(config.no_ignore);
+    assert_eq!(vec!["*.rs"], config.globs);
+    assert!(config.follow && config.mmap);
+    assert_eq!(Encoding::Latin1, config.encoding);
+    assert!(config.quiet);
+    assert_eq!(Some(ListFiles::WithoutMatch), config.list_files);
+    assert!(config.null && config.stats);
+    assert_eq!((1, 2), (config.before_context, config.after_context));
+    assert_eq!(None, config.group_separator);
+  }
+}
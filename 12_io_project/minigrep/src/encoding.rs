@@ -0,0 +1,287 @@
+// Decoding of non-UTF-8 input. Everything else in minigrep works on UTF-8,
+// so files in other encodings are transcoded while they're read, a chunk at
+// a time, instead of being loaded into memory first.
+//
+// By default the encoding is sniffed from a byte order mark: files starting
+// with a UTF-16 BOM are decoded as UTF-16, everything else is read as UTF-8.
+// Latin-1 has no BOM, so it has to be asked for with --encoding.
+
+use std::char;
+use std::io::{self, BufRead, Read};
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum Encoding {
+  Auto,
+  Utf8,
+  Utf16Le,
+  Utf16Be,
+  Latin1,
+}
+
+impl Encoding {
+  pub fn parse(name: &str) -> Option<Encoding> {
+    match name.to_lowercase().as_str() {
+      "auto" => Some(Encoding::Auto),
+      "utf-8" | "utf8" => Some(Encoding::Utf8),
+      "utf-16le" | "utf16le" => Some(Encoding::Utf16Le),
+      "utf-16be" | "utf16be" => Some(Encoding::Utf16Be),
+      "latin1" | "latin-1" | "iso-8859-1" => Some(Encoding::Latin1),
+      _ => None,
+    }
+  }
+}
+
+// Looks for a byte order mark at the start of `bytes`, returning the
+// encoding it stands for and its length
+pub fn sniff(bytes: &[u8]) -> Option<(Encoding, usize)> {
+  if bytes.starts_with(&[0xef, 0xbb, 0xbf]) {
+    Some((Encoding::Utf8, 3))
+  } else if bytes.starts_with(&[0xff, 0xfe]) {
+    Some((Encoding::Utf16Le, 2))
+  } else if bytes.starts_with(&[0xfe, 0xff]) {
+    Some((Encoding::Utf16Be, 2))
+  } else {
+    None
+  }
+}
+
+// Works out the encoding to read with, and how many BOM bytes to skip. A
+// BOM is only skipped when it agrees with the encoding being used.
+pub fn resolve(requested: Encoding, start: &[u8]) -> (Encoding, usize) {
+  match (requested, sniff(start)) {
+    (Encoding::Auto, Some((found, bom))) => (found, bom),
+    (Encoding::Auto, None) => (Encoding::Utf8, 0),
+    (requested, Some((found, bom))) if found == requested => (requested, bom),
+    (requested, _) => (requested, 0),
+  }
+}
+
+// A reader that yields UTF-8 whatever the encoding of the reader it wraps.
+// UTF-8 input is passed through untouched.
+pub struct Decoder<R> {
+  inner: R,
+  encoding: Encoding,
+  // Decoded bytes waiting to be read, and how far into them we are
+  decoded: Vec<u8>,
+  pos: usize,
+  // Bytes of a UTF-16 code unit or surrogate pair cut in half by a read
+  pending: Vec<u8>,
+}
+
+impl<R: BufRead> Decoder<R> {
+  pub fn new(mut inner: R, encoding: Encoding) -> io::Result<Decoder<R>> {
+    // A BOM is at most three bytes, but a reader may hand them out one at a
+    // time, so collect them before sniffing
+    let mut head = Vec::new();
+    while head.len() < 3 {
+      let available = inner.fill_buf()?;
+      if available.is_empty() {
+        break;
+      }
+      let n = available.len().min(3 - head.len());
+      head.extend_from_slice(&available[..n]);
+      inner.consume(n);
+    }
+
+    let (encoding, bom) = resolve(encoding, &head);
+    let mut decoder = Decoder {
+      inner,
+      encoding,
+      decoded: Vec::new(),
+      pos: 0,
+      pending: Vec::new(),
+    };
+    decode(
+      encoding,
+      &head[bom..],
+      &mut decoder.pending,
+      &mut decoder.decoded,
+    );
+
+    Ok(decoder)
+  }
+}
+
+impl<R: BufRead> Read for Decoder<R> {
+  fn read(&mut self, buf: &mut [u8]) -> io::Result<usize> {
+    let available = self.fill_buf()?;
+    let n = available.len().min(buf.len());
+    buf[..n].copy_from_slice(&available[..n]);
+    self.consume(n);
+    Ok(n)
+  }
+}
+
+impl<R: BufRead> BufRead for Decoder<R> {
+  fn fill_buf(&mut self) -> io::Result<&[u8]> {
+    if self.pos < self.decoded.len() {
+      return Ok(&self.decoded[self.pos..]);
+    }
+    if self.encoding == Encoding::Utf8 {
+      return self.inner.fill_buf();
+    }
+
+    // A chunk can decode to nothing when it only holds half a code unit,
+    // so keep reading until there's something to hand out or the input ends
+    while self.pos == self.decoded.len() {
+      self.decoded.clear();
+      self.pos = 0;
+
+      let input = self.inner.fill_buf()?;
+      if input.is_empty() {
+        if !self.pending.is_empty() {
+          // The input ended in the middle of a character
+          self.pending.clear();
+          push_char(&mut self.decoded, char::REPLACEMENT_CHARACTER);
+        }
+        break;
+      }
+
+      let read = input.len();
+      decode(self.encoding, input, &mut self.pending, &mut self.decoded);
+      self.inner.consume(read);
+    }
+
+    Ok(&self.decoded[self.pos..])
+  }
+
+  fn consume(&mut self, amount: usize) {
+    if self.pos < self.decoded.len() {
+      self.pos += amount;
+    } else {
+      self.inner.consume(amount);
+    }
+  }
+}
+
+// Appends `input` to `out` as UTF-8. UTF-8 input is copied as it is; it's
+// validated later, when it's read as lines.
+fn decode(encoding: Encoding, input: &[u8], pending: &mut Vec<u8>, out: &mut Vec<u8>) {
+  match encoding {
+    Encoding::Auto | Encoding::Utf8 => out.extend_from_slice(input),
+    Encoding::Latin1 => {
+      for &byte in input {
+        push_char(out, char::from(byte));
+      }
+    }
+    Encoding::Utf16Le | Encoding::Utf16Be => {
+      pending.extend_from_slice(input);
+      decode_utf16(pending, encoding == Encoding::Utf16Be, out);
+    }
+  }
+}
+
+fn push_char(out: &mut Vec<u8>, c: char) {
+  let mut buf = [0; 4];
+  out.extend_from_slice(c.encode_utf8(&mut buf).as_bytes());
+}
+
+// Decodes as many whole characters from `bytes` as possible, leaving behind
+// a trailing odd byte or a high surrogate still waiting for its other half.
+// Unpaired surrogates decode to U+FFFD.
+fn decode_utf16(bytes: &mut Vec<u8>, big_endian: bool, out: &mut Vec<u8>) {
+  let mut units = bytes
+    .chunks_exact(2)
+    .map(|pair| {
+      if big_endian {
+        u16::from_be_bytes([pair[0], pair[1]])
+      } else {
+        u16::from_le_bytes([pair[0], pair[1]])
+      }
+    })
+    .collect::<Vec<_>>();
+
+  let mut keep = bytes.len() % 2;
+  if units
+    .last()
+    .is_some_and(|unit| (0xd800..0xdc00).contains(unit))
+  {
+    units.pop();
+    keep += 2;
+  }
+
+  for c in char::decode_utf16(units) {
+    push_char(out, c.unwrap_or(char::REPLACEMENT_CHARACTER));
+  }
+
+  bytes.drain(..bytes.len() - keep);
+}
+
+#[cfg(test)]
+mod tests {
+  use super::*;
+
+  fn decode_all(bytes: &[u8], encoding: Encoding) -> String {
+    // A one byte buffer splits every code unit, the worst case for the
+    // chunked decoding
+    let reader = io::BufReader::with_capacity(1, bytes);
+    let mut out = String::new();
+    Decoder::new(reader, encoding)
+      .unwrap()
+      .read_to_string(&mut out)
+      .unwrap();
+    out
+  }
+
+  fn utf16(text: &str, big_endian: bool) -> Vec<u8> {
+    text
+      .encode_utf16()
+      .flat_map(|unit| {
+        if big_endian {
+          unit.to_be_bytes()
+        } else {
+          unit.to_le_bytes()
+        }
+      })
+      .collect()
+  }
+
+  #[test]
+  fn parses_encoding_names() {
+    assert_eq!(Some(Encoding::Utf16Le), Encoding::parse("UTF-16LE"));
+    assert_eq!(Some(Encoding::Latin1), Encoding::parse("iso-8859-1"));
+    assert_eq!(None, Encoding::parse("ebcdic"));
+  }
+
+  #[test]
+  fn sniffs_utf16_from_the_bom() {
+    let text = "Rust:\nsafe, fast, 🦀\n";
+    let mut le = vec![0xff, 0xfe];
+    le.extend(utf16(text, false));
+    let mut be = vec![0xfe, 0xff];
+    be.extend(utf16(text, true));
+
+    assert_eq!(text, decode_all(&le, Encoding::Auto));
+    assert_eq!(text, decode_all(&be, Encoding::Auto));
+  }
+
+  #[test]
+  fn decodes_utf16_without_a_bom_when_asked() {
+    let text = "naïve café";
+
+    assert_eq!(text, decode_all(&utf16(text, false), Encoding::Utf16Le));
+    assert_eq!(text, decode_all(&utf16(text, true), Encoding::Utf16Be));
+  }
+
+  #[test]
+  fn decodes_latin1() {
+    assert_eq!("café £5", decode_all(b"caf\xe9 \xa35", Encoding::Latin1));
+  }
+
+  #[test]
+  fn strips_a_utf8_bom_and_passes_utf8_through() {
+    assert_eq!(
+      "duct tape",
+      decode_all(b"\xef\xbb\xbfduct tape", Encoding::Auto)
+    );
+    assert_eq!("duct tape", decode_all(b"duct tape", Encoding::Auto));
+  }
+
+  #[test]
+  fn replaces_broken_utf16() {
+    // A lone low surrogate, then a high surrogate cut off by the end
+    let bytes = [0x00, 0xdc, b'a', 0x00, 0x3d, 0xd8];
+
+    assert_eq!("\u{fffd}a\u{fffd}", decode_all(&bytes, Encoding::Utf16Le));
+  }
+}
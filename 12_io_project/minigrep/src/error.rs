@@ -0,0 +1,126 @@
+// Everything that can make a minigrep run fail. main.rs matches on the kind
+// of error to pick the message and the exit status.
+
+use std::error::Error;
+use std::fmt;
+use std::io;
+use std::path::PathBuf;
+
+use crate::cli::ArgsError;
+
+#[derive(Debug)]
+pub enum MinigrepError {
+  // The command line or a config file couldn't be understood
+  BadArgs(String),
+  // A query that can never match anything
+  InvalidPattern {
+    pattern: String,
+    reason: &'static str,
+  },
+  // Reading or writing failed. `path` is the file being read, when the
+  // error came from reading one.
+  Io {
+    path: Option<PathBuf>,
+    source: io::Error,
+  },
+  // Searching several files, this many of them couldn't be read. Each one
+  // has already been reported on its own as it happened.
+  FilesFailed(usize),
+}
+
+impl MinigrepError {
+  // Wraps an error that came from reading `path`
+  pub fn io(path: impl Into<PathBuf>, source: io::Error) -> MinigrepError {
+    MinigrepError::Io {
+      path: Some(path.into()),
+      source,
+    }
+  }
+
+  // The process exit status for this error. Like grep, anything from 2 up
+  // means trouble (0 and 1 say whether a line matched); the exact number
+  // tells scripts what kind of trouble.
+  pub fn exit_code(&self) -> i32 {
+    match self {
+      MinigrepError::BadArgs(_) => 2,
+      MinigrepError::InvalidPattern { .. } => 3,
+      MinigrepError::Io { .. } | MinigrepError::FilesFailed(_) => 4,
+    }
+  }
+}
+
+impl fmt::Display for MinigrepError {
+  fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+    match self {
+      MinigrepError::BadArgs(message) => write!(f, "{}", message),
+      MinigrepError::InvalidPattern { pattern, reason } => {
+        write!(f, "invalid query {:?}: {}", pattern, reason)
+      }
+      MinigrepError::Io {
+        path: Some(path),
+        source,
+      } => write!(f, "{}: {}", path.display(), source),
+      MinigrepError::Io { path: None, source } => write!(f, "{}", source),
+      MinigrepError::FilesFailed(1) => write!(f, "1 file could not be searched"),
+      MinigrepError::FilesFailed(n) => write!(f, "{} files could not be searched", n),
+    }
+  }
+}
+
+impl Error for MinigrepError {
+  fn source(&self) -> Option<&(dyn Error + 'static)> {
+    match self {
+      MinigrepError::Io { source, .. } => Some(source),
+      _ => None,
+    }
+  }
+}
+
+impl From<io::Error> for MinigrepError {
+  fn from(source: io::Error) -> MinigrepError {
+    MinigrepError::Io { path: None, source }
+  }
+}
+
+impl From<ArgsError> for MinigrepError {
+  fn from(err: ArgsError) -> MinigrepError {
+    MinigrepError::BadArgs(err.to_string())
+  }
+}
+
+#[cfg(test)]
+mod tests {
+  use super::*;
+
+  #[test]
+  fn messages_and_exit_codes() {
+    let not_found = || io::Error::new(io::ErrorKind::NotFound, "not found");
+
+    let err = MinigrepError::io("poem.txt", not_found());
+    assert_eq!("poem.txt: not found", err.to_string());
+    assert!(err.source().is_some());
+    assert_eq!(4, err.exit_code());
+
+    let err = MinigrepError::from(not_found());
+    assert_eq!("not found", err.to_string());
+
+    let err = MinigrepError::from(ArgsError::Invalid(String::from("missing a PATH")));
+    assert_eq!("missing a PATH", err.to_string());
+    assert_eq!(2, err.exit_code());
+
+    let err = MinigrepError::InvalidPattern {
+      pattern: String::from("a\nb"),
+      reason: "lines are searched one at a time",
+    };
+    assert_eq!(
+      "invalid query \"a\\nb\": lines are searched one at a time",
+      err.to_string()
+    );
+    assert_eq!(3, err.exit_code());
+
+    assert_eq!(
+      "3 files could not be searched",
+      MinigrepError::FilesFailed(3).to_string()
+    );
+  }
+}
@@ -0,0 +1,143 @@
+// Shell-style wildcard matching for paths, shared by the --glob filter and
+// the .gitignore rules:
+//
+// - `*` matches any run of characters except `/`
+// - `?` matches exactly one character except `/`
+// - `**` matches anything, including `/`; `**/` may also match nothing, so
+//   `**/*.rs` matches `main.rs` as well as `src/bin/main.rs`
+// - `\` makes the next character literal
+
+// A --glob filter. Like in .gitignore, a glob without a `/` is matched
+// against the file name alone, and one with a `/` against the path relative
+// to the directory being searched.
+pub struct Glob {
+  pattern: String,
+  anchored: bool,
+}
+
+impl Glob {
+  pub fn new(pattern: &str) -> Glob {
+    Glob {
+      anchored: pattern.contains('/'),
+      pattern: pattern.trim_start_matches('/').to_string(),
+    }
+  }
+
+  // `relative` uses `/` between components whatever the platform
+  pub fn is_match(&self, relative: &str) -> bool {
+    if self.anchored {
+      glob_match(&self.pattern, relative)
+    } else {
+      let name = relative.rsplit('/').next().unwrap_or("");
+      glob_match(&self.pattern, name)
+    }
+  }
+}
+
+pub fn glob_match(pattern: &str, text: &str) -> bool {
+  let pattern: Vec<char> = pattern.chars().collect();
+  let text: Vec<char> = text.chars().collect();
+
+  matches_from(&pattern, &text)
+}
+
+fn matches_from(pattern: &[char], text: &[char]) -> bool {
+  match pattern.first() {
+    None => text.is_empty(),
+    Some('*') if pattern.get(1) == Some(&'*') => {
+      let rest = &pattern[2..];
+
+      // `**/` also matches zero directories
+      if rest.first() == Some(&'/') {
+        let rest = &rest[1..];
+        if matches_from(rest, text) {
+          return true;
+        }
+
+        return (0..text.len())
+          .filter(|&i| text[i] == '/')
+          .any(|i| matches_from(rest, &text[i + 1..]));
+      }
+
+      (0..=text.len()).any(|i| matches_from(rest, &text[i..]))
+    }
+    Some('*') => {
+      let rest = &pattern[1..];
+
+      for i in 0..=text.len() {
+        if matches_from(rest, &text[i..]) {
+          return true;
+        }
+        if i < text.len() && text[i] == '/' {
+          break;
+        }
+      }
+
+      false
+    }
+    Some('?') => match text.first() {
+      Some(&c) if c != '/' => matches_from(&pattern[1..], &text[1..]),
+      _ => false,
+    },
+    Some('\\') if pattern.len() > 1 => {
+      text.first() == Some(&pattern[1]) && matches_from(&pattern[2..], &text[1..])
+    }
+    Some(&c) => text.first() == Some(&c) && matches_from(&pattern[1..], &text[1..]),
+  }
+}
+
+#[cfg(test)]
+mod tests {
+  use super::*;
+
+  #[test]
+  fn star_matches_within_one_component() {
+    assert!(glob_match("*.rs", "main.rs"));
+    assert!(glob_match("*.rs", ".rs"));
+    assert!(glob_match("src/*", "src/lib.rs"));
+    assert!(!glob_match("*.rs", "main.rs.bak"));
+    assert!(!glob_match("*.rs", "src/main.rs"));
+  }
+
+  #[test]
+  fn question_mark_matches_one_character() {
+    assert!(glob_match("?.txt", "a.txt"));
+    assert!(glob_match("?.txt", "é.txt"));
+    assert!(!glob_match("?.txt", "ab.txt"));
+    assert!(!glob_match("a?b", "a/b"));
+  }
+
+  #[test]
+  fn double_star_matches_across_directories() {
+    assert!(glob_match("**/*.rs", "main.rs"));
+    assert!(glob_match("**/*.rs", "src/bin/main.rs"));
+    assert!(glob_match("src/**", "src/a/b.txt"));
+    assert!(glob_match("a/**/z", "a/z"));
+    assert!(glob_match("a/**/z", "a/b/c/z"));
+    assert!(!glob_match("a/**/z", "b/z"));
+  }
+
+  #[test]
+  fn backslash_escapes_wildcards() {
+    assert!(glob_match("\\*.txt", "*.txt"));
+    assert!(!glob_match("\\*.txt", "a.txt"));
+  }
+
+  #[test]
+  fn globs_without_a_slash_match_the_file_name() {
+    let glob = Glob::new("*.rs");
+
+    assert!(glob.is_match("main.rs"));
+    assert!(glob.is_match("src/bin/main.rs"));
+    assert!(!glob.is_match("src/notes.md"));
+  }
+
+  #[test]
+  fn globs_with_a_slash_match_the_relative_path() {
+    let glob = Glob::new("src/*.rs");
+
+    assert!(glob.is_match("src/main.rs"));
+    assert!(!glob.is_match("tests/src/main.rs"));
+    assert!(!glob.is_match("src/bin/main.rs"));
+  }
+}
@@ -0,0 +1,175 @@
+// Just enough of the .gitignore format to skip the files a project has
+// asked git to ignore while walking directories:
+//
+// - blank lines and lines starting with `#` are skipped
+// - a leading `!` re-includes a path an earlier pattern excluded
+// - a trailing `/` only matches directories
+// - a pattern containing a `/` is matched against the path relative to the
+//   .gitignore; any other pattern is matched against the file name alone
+// - `*` and `?` don't cross `/`, while `**` matches any number of directories
+//
+// Character classes like `[abc]` are not supported.
+
+use std::fs;
+use std::io;
+use std::path::{Path, PathBuf};
+
+use crate::glob::glob_match;
+
+struct Rule {
+  pattern: String,
+  negated: bool,
+  dir_only: bool,
+  anchored: bool,
+}
+
+pub struct Gitignore {
+  base: PathBuf,
+  rules: Vec<Rule>,
+}
+
+impl Gitignore {
+  // Loads `dir/.gitignore`, returning None if the directory doesn't have one
+  pub fn load(dir: &Path) -> io::Result<Option<Gitignore>> {
+    let path = dir.join(".gitignore");
+
+    if !path.is_file() {
+      return Ok(None);
+    }
+
+    let contents = fs::read_to_string(path)?;
+    Ok(Some(Gitignore::parse(dir, &contents)))
+  }
+
+  pub fn parse(base: &Path, contents: &str) -> Gitignore {
+    let mut rules = Vec::new();
+
+    for line in contents.lines() {
+      let line = line.trim_end();
+      if line.is_empty() || line.starts_with('#') {
+        continue;
+      }
+
+      let (negated, line) = match line.strip_prefix('!') {
+        Some(rest) => (true, rest),
+        None => (false, line),
+      };
+      let (dir_only, line) = match line.strip_suffix('/') {
+        Some(rest) => (true, rest),
+        None => (false, line),
+      };
+      let anchored = line.contains('/');
+      let pattern = line.trim_start_matches('/').to_string();
+
+      rules.push(Rule {
+        pattern,
+        negated,
+        dir_only,
+        anchored,
+      });
+    }
+
+    Gitignore {
+      base: base.to_path_buf(),
+      rules,
+    }
+  }
+
+  // Some(true) if the path is ignored, Some(false) if a negated pattern
+  // re-included it, and None if no pattern in this file mentions it.
+  // Like git, the last matching pattern wins.
+  pub fn matched(&self, path: &Path, is_dir: bool) -> Option<bool> {
+    let relative = path.strip_prefix(&self.base).ok()?;
+    let relative = relative
+      .components()
+      .map(|c| c.as_os_str().to_string_lossy())
+      .collect::<Vec<_>>()
+      .join("/");
+    let name = relative.rsplit('/').next().unwrap_or("");
+
+    self
+      .rules
+      .iter()
+      .rev()
+      .find(|rule| {
+        if rule.dir_only && !is_dir {
+          return false;
+        }
+
+        let target = if rule.anchored { &relative[..] } else { name };
+        glob_match(&rule.pattern, target)
+      })
+      .map(|rule| !rule.negated)
+  }
+}
+
+// Checks a stack of .gitignore files, innermost last. Rules in a deeper
+// directory take precedence over the ones above it.
+pub fn is_ignored(stack: &[Gitignore], path: &Path, is_dir: bool) -> bool {
+  stack
+    .iter()
+    .rev()
+    .find_map(|ignore| ignore.matched(path, is_dir))
+    .unwrap_or(false)
+}
+
+#[cfg(test)]
+mod tests {
+  use super::*;
+
+  fn ignored(contents: &str, path: &str, is_dir: bool) -> bool {
+    let ignore = Gitignore::parse(Path::new("/repo"), contents);
+    is_ignored(&[ignore], &Path::new("/repo").join(path), is_dir)
+  }
+
+  #[test]
+  fn matches_file_names_at_any_depth() {
+    assert!(ignored("*.log", "debug.log", false));
+    assert!(ignored("*.log", "a/b/debug.log", false));
+    assert!(!ignored("*.log", "debug.txt", false));
+  }
+
+  #[test]
+  fn skips_comments_and_blank_lines() {
+    assert!(!ignored("# *.txt\n\n", "notes.txt", false));
+  }
+
+  #[test]
+  fn anchored_patterns_use_the_relative_path() {
+    assert!(ignored("/build", "build", true));
+    assert!(!ignored("/build", "src/build", true));
+    assert!(ignored("docs/*.md", "docs/a.md", false));
+    assert!(!ignored("docs/*.md", "docs/sub/a.md", false));
+  }
+
+  #[test]
+  fn trailing_slash_only_matches_directories() {
+    assert!(ignored("target/", "target", true));
+    assert!(!ignored("target/", "target", false));
+  }
+
+  #[test]
+  fn negation_reincludes_a_path() {
+    assert!(!ignored("*.log\n!keep.log", "keep.log", false));
+    assert!(ignored("*.log\n!keep.log", "other.log", false));
+  }
+
+  #[test]
+  fn double_star_crosses_directories() {
+    assert!(ignored("**/cache", "cache", true));
+    assert!(ignored("**/cache", "a/b/cache", true));
+    assert!(ignored("logs/**", "logs/a/b.txt", false));
+    assert!(ignored("a/**/z", "a/z", false));
+    assert!(ignored("a/**/z", "a/b/c/z", false));
+  }
+
+  #[test]
+  fn deeper_files_take_precedence() {
+    let root = Gitignore::parse(Path::new("/repo"), "*.txt");
+    let inner = Gitignore::parse(Path::new("/repo/keep"), "!*.txt");
+    let stack = [root, inner];
+
+    assert!(is_ignored(&stack, Path::new("/repo/a.txt"), false));
+    assert!(!is_ignored(&stack, Path::new("/repo/keep/a.txt"), false));
+  }
+}
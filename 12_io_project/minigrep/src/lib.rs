@@ -1,101 +1,662 @@
-use std::env;
-use std::error::Error;
-use std::fs;
+use std::fs::File;
+use std::io::{self, BufRead, BufReader, BufWriter, Write};
+use std::path::Path;
+use std::str;
+use std::sync::{mpsc, Arc};
+use std::thread;
+use std::time::Instant;
+
+use memmap2::Mmap;
+
+use encoding::Decoder;
+use printer::Printer;
+
+mod cli;
+mod config;
+mod encoding;
+mod error;
+mod glob;
+mod ignore;
+mod matcher;
+mod output;
+mod pool;
+mod printer;
+mod rc;
+mod scan;
+mod search;
+mod stats;
+mod walk;
+
+pub use cli::{usage, ArgsError};
+pub use config::{ColorChoice, Config, ConfigBuilder, ListFiles};
+pub use encoding::Encoding;
+pub use error::MinigrepError;
+pub use output::OutputFormat;
+pub use search::{
+  count_matches, count_pattern_matches, find_matches, is_smart_case_sensitive, match_offsets,
+  search, search_case_insensitive, search_inverted, search_reader, search_smart_case,
+  search_whole_word, Hit, Match, Pattern,
+};
+pub use stats::Stats;
+
+// Runs a whole search as described by `config`, printing the results to
+// stdout. Returns whether the search succeeded in grep's sense, which main
+// turns into the exit status: some line was selected or, with -L, some file
+// was listed.
+pub fn run(config: Config) -> Result<bool, MinigrepError> {
+  let started = Instant::now();
+  // Input is searched line by line, so a query spanning lines can't match
+  for pattern in &config.patterns {
+    if pattern.contains('\n') {
+      return Err(MinigrepError::InvalidPattern {
+        pattern: pattern.clone(),
+        reason: "queries can't span more than one line",
+      });
+    }
+  }
+
+  let globs: Vec<_> = config.globs.iter().map(|g| glob::Glob::new(g)).collect();
+  let walk = walk::collect_files(&config.filenames, !config.no_ignore, config.follow, &globs);
+  for warning in &walk.warnings {
+    eprintln!("minigrep: warning: {}", warning);
+  }
+  // Paths the walk couldn't read count as failed files, the same as files
+  // that can't be searched
+  for e in &walk.errors {
+    eprintln!("minigrep: {}", e);
+  }
+  let files = walk.files;
+
+  let stdout = io::stdout();
+  let mut out = BufWriter::new(stdout.lock());
+  let mut stats = Stats::default();
+  let mut failed = walk.errors.len();
+
+  // A single file keeps the classic output: no file name prefix, and a read
+  // error is fatal rather than just reported. Its results are streamed
+  // straight to stdout. That's decided from the paths given, since the walk
+  // leaves out the ones it couldn't read.
+  if config.filenames.len() == 1 && !Path::new(&config.filenames[0]).is_dir() {
+    stats += search_path(&config, &files[0], false, &mut out)?;
+  } else {
+    let mut printed_any = false;
+    for (_, result) in search_files(Arc::new(config.clone()), files) {
+      match result {
+        Ok((output, file_stats)) => {
+          // Like grep, context from different files is kept apart by the
+          // group separator too
+          if let Some(separator) = &config.group_separator {
+            if config.shows_context() && printed_any && !output.is_empty() {
+              write!(out, "{}{}", separator, config.line_end())?;
+            }
+          }
+          printed_any |= !output.is_empty();
+          out.write_all(output.as_bytes())?;
+          stats += file_stats;
+        }
+        Err(e) => {
+          eprintln!("minigrep: {}", e);
+          failed += 1;
+        }
+      }
+    }
+  }
+
+  if config.stats {
+    writeln!(out)?;
+    write!(out, "{}", stats.summary(started.elapsed()))?;
+  }
+  out.flush()?;
+
+  let success = match config.list_files {
+    Some(ListFiles::WithoutMatch) => stats.files_searched > stats.files_with_matches,
+    _ => stats.matching_lines > 0,
+  };
+
+  // Like grep, a match found in quiet mode wins over errors in other files
+  if failed > 0 && !(config.quiet && success) {
+    return Err(MinigrepError::FilesFailed(failed));
+  }
+
+  Ok(success)
+}
+
+// What searching one file produced: the output to print and the file's
+// stats, or the error that stopped it
+type FileResult = Result<(String, Stats), MinigrepError>;
+
+// Searches every file on a pool of worker threads. Each job sends its
+// rendered output and its stats back over a channel, together with the
+// file's position in the list, so the results can be returned in the same
+// order as `files` however the threads happen to finish.
+fn search_files(
+  config: Arc<Config>,
+  files: Vec<std::path::PathBuf>,
+) -> Vec<(String, FileResult)> {
+  let workers = thread::available_parallelism().map_or(4, |n| n.get());
+  let pool = pool::ThreadPool::new(workers.min(files.len()).max(1));
+  let (tx, rx) = mpsc::channel();
+  let names: Vec<String> = files.iter().map(|path| display_name(path)).collect();
+
+  for (index, path) in files.into_iter().enumerate() {
+    let config = Arc::clone(&config);
+    let tx = tx.clone();
+
+    pool.execute(move || {
+      let mut output = Vec::new();
+      let result = search_path(&config, &path, true, &mut output).map(|stats| {
+        let output = String::from_utf8(output).expect("output is built from UTF-8 lines");
+        (output, stats)
+      });
+
+      tx.send((index, result)).unwrap();
+    });
+  }
+  drop(tx);
+
+  let mut results: Vec<Option<FileResult>> = names.iter().map(|_| None).collect();
+  for (index, result) in rx {
+    results[index] = Some(result);
+  }
+
+  names
+    .into_iter()
+    .zip(results)
+    .map(|(name, result)| {
+      // A job that panicked never sent anything back. The file still
+      // counts as one that couldn't be searched, rather than vanishing.
+      let result = result.unwrap_or_else(|| {
+        let failed = io::Error::other("the search stopped unexpectedly");
+        Err(MinigrepError::io(name.as_str(), failed))
+      });
+      (name, result)
+    })
+    .collect()
+}
+
+// Searches the contents of one file with every option in `config`,
+// stopping early once --max-count lines have been found
+pub fn search_file<'a>(config: &Config, filename: &'a str, contents: &'a str) -> Vec<Match<'a>> {
+  let pattern = config.pattern();
+
+  find_matches(&pattern, contents, config.invert_match)
+    .take(config.limit())
+    .map(|m| Match {
+      file: Some(filename),
+      ..m
+    })
+    .collect()
+}
+
+// Files smaller than this are always read through a buffer: setting up a
+// memory map costs more than it saves on small inputs
+const MMAP_THRESHOLD: u64 = 64 * 1024;
+
+// `-` stands for standard input, like in most command line tools
+fn display_name(path: &Path) -> String {
+  if path == Path::new("-") {
+    String::from("(standard input)")
+  } else {
+    path.display().to_string()
+  }
+}
+
+// Searches one file (or stdin, for `-`) and writes everything minigrep
+// prints for it to `out`, returning the file's stats. Large files are
+// memory-mapped when --mmap is set; everything else is streamed through a
+// buffered reader.
+pub fn search_path<W: Write>(
+  config: &Config,
+  path: &Path,
+  with_filename: bool,
+  out: &mut W,
+) -> Result<Stats, MinigrepError> {
+  let name = display_name(path);
+
+  if path == Path::new("-") {
+    return write_file(config, &name, io::stdin().lock(), with_filename, out);
+  }
+
+  let read_error = |e| MinigrepError::io(path, e);
+  let file = File::open(path).map_err(read_error)?;
+  if config.mmap && file.metadata().map_err(read_error)?.len() >= MMAP_THRESHOLD {
+    // SAFETY: the map is only read, and only while `file` is open. If
+    // another process truncates the file in the meantime we can read
+    // garbage or crash, the same trade-off grep and ripgrep make for their
+    // mmap modes; that's why the mode is opt-in.
+    let map = unsafe { Mmap::map(&file).map_err(read_error)? };
+
+    // Only UTF-8 can be searched in place; anything else is decoded by the
+    // buffered path below. Mapping doesn't move the file's read position.
+    if let (Encoding::Utf8, bom) = encoding::resolve(config.encoding, &map) {
+      let contents = str::from_utf8(&map[bom..])
+        .map_err(|e| read_error(io::Error::new(io::ErrorKind::InvalidData, e)))?;
+
+      return write_contents(config, &name, contents, with_filename, out);
+    }
+  }
+
+  write_file(config, &name, BufReader::new(file), with_filename, out)
+}
+
+// Streams one input through the search, decoding it to UTF-8 on the way,
+// and writes what minigrep prints for it to `out`. Read errors are tagged
+// with `filename`; write errors aren't, as they're about `out`.
+fn write_file<R: BufRead, W: Write>(
+  config: &Config,
+  filename: &str,
+  reader: R,
+  with_filename: bool,
+  out: &mut W,
+) -> Result<Stats, MinigrepError> {
+  let pattern = config.pattern();
+  let mut printer = Printer::new(config, filename, with_filename, out);
+
+  let read_error = |e| MinigrepError::io(filename, e);
+  let reader = Decoder::new(reader, config.encoding).map_err(read_error)?;
+  let lines = search::search_lines(&pattern, reader, config.invert_match, |line| {
+    printer.on_line(line)
+  })
+  .map_err(read_error)?;
+
+  Ok(Stats::for_file(lines, printer.finish()?))
+}
+
+// Like write_file, for input that is already in memory. The lines are
+// searched in place, without copying them anywhere first.
+fn write_contents<W: Write>(
+  config: &Config,
+  filename: &str,
+  contents: &str,
+  with_filename: bool,
+  out: &mut W,
+) -> Result<Stats, MinigrepError> {
+  let pattern = config.pattern();
+  let mut printer = Printer::new(config, filename, with_filename, out);
+
+  // Context needs the lines between the matches too, which find_matches
+  // skips over, so they're read one by one the way write_file does
+  if config.shows_context() {
+    let lines = search::search_lines(&pattern, contents.as_bytes(), config.invert_match, |line| {
+      printer.on_line(line)
+    })
+    .map_err(|e| MinigrepError::io(filename, e))?;
+    return Ok(Stats::for_file(lines, printer.finish()?));
+  }
+
+  let mut stopped_at = None;
+  for m in find_matches(&pattern, contents, config.invert_match) {
+    if !printer.on_match(&m) {
+      stopped_at = Some(m.line_number);
+      break;
+    }
+  }
+
+  // Only count the lines again when the search went through all of them
+  let lines = stopped_at.unwrap_or_else(|| scan::count_lines(contents));
+  Ok(Stats::for_file(lines, printer.finish()?))
+}
 
 #[cfg(test)]
 mod tests {
   use super::*;
+  use std::env;
+  use std::fs;
+
+  fn render_file(config: &Config, filename: &str, contents: &str, with_filename: bool) -> String {
+    let mut out = Vec::new();
+    write_file(
+      config,
+      filename,
+      contents.as_bytes(),
+      with_filename,
+      &mut out,
+    )
+    .unwrap();
+    String::from_utf8(out).unwrap()
+  }
 
   #[test]
-  fn case_sensitive() {
-    let query = "duct";
-    let contents = "\
-Rust:
-safe, fast, productive.
-Pick three.
-Duct tape.";
+  fn parallel_results_keep_file_order() {
+    let dir = env::temp_dir().join(format!("minigrep-parallel-{}", std::process::id()));
+    fs::create_dir_all(&dir).unwrap();
+
+    let mut files = Vec::new();
+    for i in 0..20 {
+      let path = dir.join(format!("{:02}.txt", i));
+      fs::write(&path, format!("line {}\nmatch {}\n", i, i)).unwrap();
+      files.push(path);
+    }
+    files.push(dir.join("missing.txt"));
+
+    let config = Config::builder("match").build();
+    let results = search_files(Arc::new(config), files.clone());
+
+    assert_eq!(21, results.len());
+    for (i, (name, result)) in results.iter().take(20).enumerate() {
+      assert_eq!(&files[i].display().to_string(), name);
+      assert_eq!(
+        &(format!("{}:match {}\n", name, i), Stats::for_file(2, 1)),
+        result.as_ref().unwrap()
+      );
+    }
+    assert!(results[20].1.is_err());
 
-    assert_eq!(vec!["safe, fast, productive."], search(query, contents));
+    fs::remove_dir_all(&dir).unwrap();
   }
 
   #[test]
-  fn case_insensitive() {
-    let query = "rUsT";
-    let contents = "\
-Rust:
-safe, fast, productive.
-Pick three.
-Trust me.";
+  fn whole_word_config_builds_a_whole_word_pattern() {
+    let config = Config::builder("to").whole_word(true).build();
+
+    assert!(!config.pattern().is_match("tomato"));
+    assert!(config.pattern().is_match("to be"));
+  }
+
+  #[test]
+  fn max_count_stops_after_n_lines() {
+    let config = Config::builder("x").max_count(2).build();
+
+    assert_eq!("x1\nx2\n", render_file(&config, "f", "x1\nx2\nx3\n", false));
+  }
+
+  #[test]
+  fn line_numbers_follow_the_file_name() {
+    let config = Config::builder("x").line_number(true).build();
 
     assert_eq!(
-      vec!["Rust:", "Trust me."],
-      search_case_insensitive(query, contents)
+      "f:1:x1\nf:3:x3\n",
+      render_file(&config, "f", "x1\ny\nx3\n", true)
     );
   }
-}
 
-pub struct Config {
-  pub query: String,
-  pub filename: String,
-  pub case_sensitive: bool,
-}
+  #[test]
+  fn byte_offsets_follow_the_line_number() {
+    let config = Config::builder("x")
+      .line_number(true)
+      .byte_offset(true)
+      .build();
 
-impl Config {
-  pub fn new(args: &[String]) -> Result<Config, &'static str> {
-    if args.len() < 3 {
-      return Err("not enough arguments");
-    }
+    assert_eq!(
+      "f:1:0:x1\nf:3:10:abx3\n",
+      render_file(&config, "f", "x1\r\nyyy\nabx3\n", true)
+    );
+  }
 
-    let query = args[1].clone();
-    let filename = args[2].clone();
+  #[test]
+  fn color_highlights_each_match() {
+    let config = Config::builder("o").color(ColorChoice::Always).build();
 
-    let case_sensitive = env::var("CASE_INSENSITIVE").is_err();
+    assert_eq!(
+      "\x1b[35mf\x1b[0m:n\x1b[1;31mo\x1b[0mb\x1b[1;31mo\x1b[0mdy\n",
+      render_file(&config, "f", "nobody\n", true)
+    );
+  }
 
-    Ok(Config {
-      query,
-      filename,
-      case_sensitive,
-    })
+  #[test]
+  fn search_file_tags_matches_with_the_file() {
+    let config = Config::builder("x").build();
+    let matches = search_file(&config, "f.txt", "a\nxy\n");
+
+    assert_eq!(
+      vec![Match {
+        file: Some("f.txt"),
+        line_number: 2,
+        offset: 2,
+        range: 0..1,
+        hits: vec![Hit {
+          pattern: 0,
+          range: 0..1
+        }],
+        line: "xy",
+      }],
+      matches
+    );
   }
-}
 
-pub fn run(config: Config) -> Result<(), Box<dyn Error>> {
-  let contents = fs::read_to_string(config.filename)?;
+  #[test]
+  fn counts_respect_max_count() {
+    let config = Config::builder("x").count(true).max_count(2).build();
 
-  let results = if config.case_sensitive {
-    search(&config.query, &contents)
-  } else {
-    search_case_insensitive(&config.query, &contents)
-  };
+    assert_eq!("2\n", render_file(&config, "f", "x\nx\nx\n", false));
+    assert_eq!("f:0\n", render_file(&config, "f", "y\n", true));
+  }
+
+  #[test]
+  fn mmap_and_buffered_reads_print_the_same() {
+    let path = env::temp_dir().join(format!("minigrep-mmap-{}.txt", std::process::id()));
+    let mut contents = String::new();
+    while (contents.len() as u64) < MMAP_THRESHOLD * 2 {
+      contents.push_str("some hay\nthe needle\r\nmore hay\n");
+    }
+    fs::write(&path, &contents).unwrap();
+
+    let search = |mmap| {
+      let config = Config::builder("needle")
+        .line_number(true)
+        .max_count(100)
+        .mmap(mmap)
+        .build();
+      let mut out = Vec::new();
+      search_path(&config, &path, true, &mut out).unwrap();
+      String::from_utf8(out).unwrap()
+    };
+    let buffered = search(false);
+    let mapped = search(true);
+    fs::remove_file(&path).unwrap();
 
-  for line in results {
-    println!("{}", line);
+    assert_eq!(100, buffered.lines().count());
+    assert_eq!(buffered, mapped);
   }
 
-  Ok(())
-}
+  #[test]
+  fn mmap_rejects_invalid_utf8() {
+    let path = env::temp_dir().join(format!("minigrep-mmap-bad-{}.txt", std::process::id()));
+    let mut bytes = vec![b'a'; MMAP_THRESHOLD as usize];
+    bytes.push(0xff);
+    fs::write(&path, &bytes).unwrap();
 
-pub fn search<'a>(query: &str, contents: &'a str) -> Vec<&'a str> {
-  let mut results = Vec::new();
+    let config = Config::builder("a").mmap(true).build();
+    let err = search_path(&config, &path, false, &mut io::sink()).unwrap_err();
+    fs::remove_file(&path).unwrap();
 
-  for line in contents.lines() {
-    if line.contains(query) {
-      results.push(line);
+    match err {
+      MinigrepError::Io {
+        path: Some(failed),
+        source,
+      } => {
+        assert_eq!(path, failed);
+        assert_eq!(io::ErrorKind::InvalidData, source.kind());
+      }
+      other => panic!("expected a read error, got {:?}", other),
     }
   }
 
-  results
-}
+  #[test]
+  fn searches_utf16_files_and_latin1_when_asked() {
+    let mut utf16 = vec![0xff, 0xfe];
+    utf16.extend(
+      "safe, fast\nproductive.\n"
+        .encode_utf16()
+        .flat_map(u16::to_le_bytes),
+    );
+    let config = Config::builder("fast").build();
+    let mut out = Vec::new();
+    write_file(&config, "f", &utf16[..], false, &mut out).unwrap();
+    assert_eq!("safe, fast\n", String::from_utf8(out).unwrap());
 
-pub fn search_case_insensitive<'a>(query: &str, contents: &'a str) -> Vec<&'a str> {
-  let mut results = Vec::new();
-  let query = query.to_lowercase();
+    let config = Config::builder("café").encoding(Encoding::Latin1).build();
+    let mut out = Vec::new();
+    write_file(
+      &config,
+      "f",
+      &b"un caf\xe9\nun th\xe9\n"[..],
+      false,
+      &mut out,
+    )
+    .unwrap();
+    assert_eq!("un café\n", String::from_utf8(out).unwrap());
+  }
 
-  for line in contents.lines() {
-    if line.to_lowercase().contains(&query) {
-      results.push(line);
-    }
+  #[test]
+  fn lists_files_with_and_without_matches() {
+    let config = Config::builder("x")
+      .list_files(ListFiles::WithMatches)
+      .build();
+    assert_eq!("f\n", render_file(&config, "f", "x\nx\n", false));
+    assert_eq!("", render_file(&config, "f", "y\n", true));
+
+    let config = Config::builder("x")
+      .list_files(ListFiles::WithoutMatch)
+      .count(true)
+      .build();
+    assert_eq!("", render_file(&config, "f", "x\n", true));
+    assert_eq!("f\n", render_file(&config, "f", "y\n", false));
+  }
+
+  #[test]
+  fn null_ends_file_names_and_lines_with_a_nul_byte() {
+    let config = Config::builder("x").null(true).build();
+    assert_eq!(
+      "f\0x1\0f\0x2\0",
+      render_file(&config, "f", "x1\ny\nx2\n", true)
+    );
+
+    let config = Config::builder("x").null(true).count(true).build();
+    assert_eq!("f\x002\n", render_file(&config, "f", "x\nx\n", true));
+
+    let config = Config::builder("x")
+      .list_files(ListFiles::WithMatches)
+      .null(true)
+      .build();
+    assert_eq!("f\0", render_file(&config, "f", "x\n", false));
   }
 
-  results
+  #[test]
+  fn context_surrounds_matches_in_separated_groups() {
+    let contents = "a\nx1\nb\nc\nd\ne\nx2\nx3\nf\n";
+    let config = Config::builder("x")
+      .before_context(1)
+      .after_context(1)
+      .line_number(true)
+      .build();
+
+    assert_eq!(
+      "f-1-a\nf:2:x1\nf-3-b\n--\nf-6-e\nf:7:x2\nf:8:x3\nf-9-f\n",
+      render_file(&config, "f", contents, true)
+    );
+    // Groups that touch are printed as one
+    assert_eq!(
+      "a\nx1\nb\nc\nd\ne\nx2\nx3\nf\n",
+      render_file(
+        &Config::builder("x")
+          .before_context(2)
+          .after_context(2)
+          .build(),
+        "f",
+        contents,
+        false
+      )
+    );
+  }
+
+  #[test]
+  fn group_separators_can_be_changed_or_left_out() {
+    let contents = "x1\na\nb\nx2\n";
+    let config = |separator| {
+      Config::builder("x")
+        .after_context(0)
+        .before_context(1)
+        .group_separator(separator)
+        .build()
+    };
+
+    assert_eq!(
+      "x1\n--\nb\nx2\n",
+      render_file(&config(Some("--")), "f", contents, false)
+    );
+    assert_eq!(
+      "x1\n==\nb\nx2\n",
+      render_file(&config(Some("==")), "f", contents, false)
+    );
+    assert_eq!(
+      "x1\nb\nx2\n",
+      render_file(&config(None), "f", contents, false)
+    );
+
+    // Without context there are no groups to separate
+    let config = Config::builder("x").build();
+    assert_eq!("x1\nx2\n", render_file(&config, "f", contents, false));
+
+    let config = Config::builder("x").after_context(1).null(true).build();
+    assert_eq!(
+      "f\0x1\0f\0a\0--\0f\0x2\0",
+      render_file(&config, "f", contents, true)
+    );
+  }
+
+  #[test]
+  fn context_follows_the_last_match_max_count_allows() {
+    let config = Config::builder("x").max_count(1).after_context(2).build();
+
+    assert_eq!(
+      "x1\nx2\na\n",
+      render_file(&config, "f", "x1\nx2\na\nb\n", false)
+    );
+  }
+
+  #[test]
+  fn mmap_and_buffered_reads_print_the_same_context() {
+    let contents = "a\nx1\nb\nc\nd\nx2\n";
+    let config = Config::builder("x")
+      .before_context(1)
+      .after_context(1)
+      .byte_offset(true)
+      .build();
+    let mut in_memory = Vec::new();
+    write_contents(&config, "f", contents, true, &mut in_memory).unwrap();
+
+    assert_eq!(
+      render_file(&config, "f", contents, true),
+      String::from_utf8(in_memory).unwrap()
+    );
+  }
+
+  #[test]
+  fn listing_stops_at_the_first_match() {
+    let config = Config::builder("needle")
+      .list_files(ListFiles::WithMatches)
+      .build();
+    let mut out = Vec::new();
+    let mut printer = Printer::new(&config, "f", false, &mut out);
+    let m = Match {
+      file: None,
+      line_number: 1,
+      offset: 0,
+      range: 0..6,
+      hits: Vec::new(),
+      line: "needle",
+    };
+
+    assert!(!printer.on_match(&m));
+    assert_eq!(1, printer.finish().unwrap());
+  }
+
+  #[test]
+  fn stats_count_the_lines_read() {
+    let contents = "x\ny\nx\ny\nx\n";
+    let stats = |config: &Config| {
+      let streamed = write_file(config, "f", contents.as_bytes(), false, &mut io::sink()).unwrap();
+      let in_memory = write_contents(config, "f", contents, false, &mut io::sink()).unwrap();
+      assert_eq!(streamed, in_memory);
+      streamed
+    };
+
+    assert_eq!(Stats::for_file(5, 3), stats(&Config::builder("x").build()));
+    assert_eq!(
+      Stats::for_file(3, 2),
+      stats(&Config::builder("x").max_count(2).build())
+    );
+    assert_eq!(Stats::for_file(5, 0), stats(&Config::builder("z").build()));
+  }
 }
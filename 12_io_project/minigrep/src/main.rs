@@ -1,7 +1,7 @@
 use std::env;
 use std::process;
 
-use minigrep::Config;
+use minigrep::{ArgsError, Config, MinigrepError};
 
 // Nice documentation on project structuring for binary projects:
 // https://doc.rust-lang.org/book/ch12-03-improving-error-handling-and-modularity.html#separation-of-concerns-for-binary-projects
@@ -18,14 +18,37 @@ fn main() {
     // which is an anonymous function we define and pass as an argument to
     // unwrap_or_else
     let config = Config::new(&args).unwrap_or_else(|err| {
-        // eprintln writes to stderr
-        eprintln!("Problem parsing arguments: {}", err);
-        process::exit(1);
+        // --help isn't a failure: print the usage to stdout and stop
+        if err == ArgsError::HelpRequested {
+            print!("{}", minigrep::usage());
+            process::exit(0);
+        }
+
+        fail(MinigrepError::from(err));
     });
 
-    if let Err(e) = minigrep::run(config) {
-        eprintln!("Application error: {}", e);
+    // Exit codes follow grep: 0 when a line was selected, 1 when none was,
+    // and 2 or more when something went wrong
+    match minigrep::run(config) {
+        Ok(true) => {}
+        Ok(false) => process::exit(1),
+        Err(e) => fail(e),
+    }
+}
 
-        process::exit(1);
+// Explains what went wrong and exits with the status for that kind of error
+fn fail(err: MinigrepError) -> ! {
+    // eprintln writes to stderr
+    match &err {
+        MinigrepError::BadArgs(_) => {
+            eprintln!("Problem parsing arguments: {}", err);
+            eprintln!("Run `minigrep --help` to see the available options.");
+        }
+        MinigrepError::InvalidPattern { .. } => eprintln!("Problem with the query: {}", err),
+        // Each file was already reported as it failed
+        MinigrepError::FilesFailed(_) => eprintln!("minigrep: {}", err),
+        MinigrepError::Io { .. } => eprintln!("Application error: {}", err),
     }
+
+    process::exit(err.exit_code());
 }
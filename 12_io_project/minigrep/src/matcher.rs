@@ -0,0 +1,247 @@
+// An Aho-Corasick automaton for finding many literal queries in one pass.
+//
+// Trying every query at every position of a line costs
+// O(queries × line length). The automaton is a trie of all the queries plus
+// a "failure" link on every node, pointing at the longest proper suffix of
+// that node's text that is also in the trie. Walking the line byte by byte
+// and following failure links on a mismatch finds every occurrence of every
+// query without ever stepping backwards, so a line is scanned once however
+// many queries there are.
+
+use std::cmp::Reverse;
+use std::collections::VecDeque;
+
+use crate::search::Hit;
+
+const ROOT: usize = 0;
+
+#[derive(Default)]
+struct Node {
+  // Edges to child nodes, one per byte
+  next: Vec<(u8, usize)>,
+  fail: usize,
+  // Every query that ends at this node, including the ones reached through
+  // failure links, so a match never needs a second walk to report
+  outputs: Vec<usize>,
+}
+
+pub struct AhoCorasick {
+  nodes: Vec<Node>,
+  // Length in bytes of each query, to turn a match's end into its start
+  lengths: Vec<usize>,
+}
+
+impl AhoCorasick {
+  // Builds the automaton. Empty queries are kept for their index, but never
+  // match anything.
+  pub fn new<S: AsRef<[u8]>>(queries: &[S]) -> AhoCorasick {
+    let mut nodes = vec![Node::default()];
+    let mut lengths = Vec::new();
+
+    for (i, query) in queries.iter().enumerate() {
+      let query = query.as_ref();
+      lengths.push(query.len());
+      if query.is_empty() {
+        continue;
+      }
+
+      let mut state = ROOT;
+      for &byte in query {
+        state = match child(&nodes[state], byte) {
+          Some(next) => next,
+          None => {
+            nodes.push(Node::default());
+            let next = nodes.len() - 1;
+            nodes[state].next.push((byte, next));
+            next
+          }
+        };
+      }
+      nodes[state].outputs.push(i);
+    }
+
+    // Failure links are filled in breadth first, so a node's link always
+    // points at a shallower node whose link is already known
+    let mut queue: VecDeque<usize> = nodes[ROOT].next.iter().map(|&(_, n)| n).collect();
+
+    while let Some(state) = queue.pop_front() {
+      for i in 0..nodes[state].next.len() {
+        let (byte, next) = nodes[state].next[i];
+
+        let mut fail = nodes[state].fail;
+        let target = loop {
+          if let Some(target) = child(&nodes[fail], byte) {
+            break target;
+          }
+          if fail == ROOT {
+            break ROOT;
+          }
+          fail = nodes[fail].fail;
+        };
+
+        nodes[next].fail = target;
+        let inherited = nodes[target].outputs.clone();
+        nodes[next].outputs.extend(inherited);
+        queue.push_back(next);
+      }
+    }
+
+    AhoCorasick { nodes, lengths }
+  }
+
+  // Every occurrence of every query in `haystack`, overlapping ones
+  // included, in the order they end
+  pub fn find_overlapping(&self, haystack: &[u8]) -> Vec<Hit> {
+    let mut hits = Vec::new();
+    let mut state = ROOT;
+
+    for (i, &byte) in haystack.iter().enumerate() {
+      state = self.step(state, byte);
+
+      for &pattern in &self.nodes[state].outputs {
+        hits.push(Hit {
+          pattern,
+          range: i + 1 - self.lengths[pattern]..i + 1,
+        });
+      }
+    }
+
+    hits
+  }
+
+  // Non-overlapping matches, picked from left to right. Where several start
+  // at the same place the longest wins, and on a tie the query given first.
+  //
+  // `accept` sees every candidate first and can reject it (by returning
+  // None) or move it, e.g. to translate its range into another string; the
+  // picking works on whatever it returns.
+  pub fn find_leftmost_longest<F>(&self, haystack: &[u8], accept: F) -> Vec<Hit>
+  where
+    F: FnMut(Hit) -> Option<Hit>,
+  {
+    let mut candidates: Vec<Hit> = self
+      .find_overlapping(haystack)
+      .into_iter()
+      .filter_map(accept)
+      .collect();
+    candidates.sort_by_key(|hit| (hit.range.start, Reverse(hit.range.end), hit.pattern));
+
+    let mut hits: Vec<Hit> = Vec::new();
+    for hit in candidates {
+      if hits
+        .last()
+        .is_none_or(|last| hit.range.start >= last.range.end)
+      {
+        hits.push(hit);
+      }
+    }
+
+    hits
+  }
+
+  fn step(&self, mut state: usize, byte: u8) -> usize {
+    loop {
+      if let Some(next) = child(&self.nodes[state], byte) {
+        return next;
+      }
+      if state == ROOT {
+        return ROOT;
+      }
+      state = self.nodes[state].fail;
+    }
+  }
+}
+
+fn child(node: &Node, byte: u8) -> Option<usize> {
+  node
+    .next
+    .iter()
+    .find(|&&(edge, _)| edge == byte)
+    .map(|&(_, next)| next)
+}
+
+#[cfg(test)]
+mod tests {
+  use super::*;
+
+  fn found(hits: Vec<Hit>) -> Vec<(usize, usize, usize)> {
+    hits
+      .into_iter()
+      .map(|hit| (hit.pattern, hit.range.start, hit.range.end))
+      .collect()
+  }
+
+  #[test]
+  fn finds_overlapping_queries() {
+    let automaton = AhoCorasick::new(&["he", "she", "his", "hers"]);
+
+    assert_eq!(
+      vec![(1, 1, 4), (0, 2, 4), (3, 2, 6)],
+      found(automaton.find_overlapping(b"ushers"))
+    );
+  }
+
+  #[test]
+  fn finds_queries_that_are_suffixes_of_each_other() {
+    let automaton = AhoCorasick::new(&["a", "aa", "aaa"]);
+
+    assert_eq!(
+      vec![
+        (0, 0, 1),
+        (1, 0, 2),
+        (0, 1, 2),
+        (2, 0, 3),
+        (1, 1, 3),
+        (0, 2, 3)
+      ],
+      found(automaton.find_overlapping(b"aaa"))
+    );
+  }
+
+  #[test]
+  fn leftmost_longest_skips_overlaps() {
+    let automaton = AhoCorasick::new(&["he", "she", "his", "hers"]);
+
+    assert_eq!(
+      vec![(1, 1, 4)],
+      found(automaton.find_leftmost_longest(b"ushers", Some))
+    );
+    assert_eq!(
+      vec![(3, 0, 4), (2, 5, 8)],
+      found(automaton.find_leftmost_longest(b"hers his", Some))
+    );
+  }
+
+  #[test]
+  fn ties_go_to_the_first_query() {
+    let automaton = AhoCorasick::new(&["", "ab", "ab", "abc"]);
+
+    assert_eq!(
+      vec![(1, 0, 2), (3, 2, 5)],
+      found(automaton.find_leftmost_longest(b"ababc", Some))
+    );
+  }
+
+  #[test]
+  fn rejected_candidates_make_way_for_others() {
+    let automaton = AhoCorasick::new(&["abc", "bcd"]);
+
+    assert_eq!(
+      vec![(1, 1, 4)],
+      found(
+        automaton
+          .find_leftmost_longest(b"abcd", |hit| { Some(hit).filter(|hit| hit.pattern != 0) })
+      )
+    );
+  }
+
+  #[test]
+  fn matches_multi_byte_text() {
+    let automaton = AhoCorasick::new(&["é", "café"]);
+
+    assert_eq!(
+      vec![(1, 0, 5), (0, 8, 10)],
+      found(automaton.find_leftmost_longest("café thé".as_bytes(), Some))
+    );
+  }
+}
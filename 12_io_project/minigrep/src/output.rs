@@ -0,0 +1,194 @@
+// Output formats for printing search results.
+//
+// The text format is the plain "one matching line per output line" that
+// minigrep has always printed. The JSON format prints one object per line
+// (JSON Lines) so other tools can read the results without scraping text.
+
+use std::fmt::Write;
+
+use crate::error::MinigrepError;
+use crate::search::Hit;
+
+// ANSI escape sequences, using the same colors as grep. Matches of the
+// first query are red like in grep; the next ones take the other colors in
+// MATCH_COLORS in turn, so the queries can be told apart.
+const MATCH_COLORS: &[&str] = &["\x1b[1;31m", "\x1b[1;33m", "\x1b[1;34m", "\x1b[1;36m"];
+const FILENAME_COLOR: &str = "\x1b[35m";
+const LINE_NUMBER_COLOR: &str = "\x1b[32m";
+const RESET: &str = "\x1b[0m";
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum OutputFormat {
+  Text,
+  Json,
+}
+
+impl OutputFormat {
+  pub fn parse(name: &str) -> Result<OutputFormat, MinigrepError> {
+    match name {
+      "text" => Ok(OutputFormat::Text),
+      "json" => Ok(OutputFormat::Json),
+      _ => Err(MinigrepError::BadArgs(format!(
+        "unknown output format `{}`, expected `text` or `json`",
+        name
+      ))),
+    }
+  }
+}
+
+// The prefixes below end in `sep`: a colon on matching lines and a dash on
+// context lines, like grep
+
+// The "file:" prefix used when several files are searched. With --null the
+// caller passes a NUL byte as `sep` instead (like grep -Z), so a name with
+// a colon in it can't be mistaken for part of the line.
+pub fn filename(name: &str, color: bool, sep: char) -> String {
+  if color {
+    format!("{}{}{}{}", FILENAME_COLOR, name, RESET, sep)
+  } else {
+    format!("{}{}", name, sep)
+  }
+}
+
+// The "12:" prefix printed with -n
+pub fn line_number(number: usize, color: bool, sep: char) -> String {
+  if color {
+    format!("{}{}{}{}", LINE_NUMBER_COLOR, number, RESET, sep)
+  } else {
+    format!("{}{}", number, sep)
+  }
+}
+
+// The "1024:" prefix printed with -b, styled like a line number
+pub fn byte_offset(offset: usize, color: bool, sep: char) -> String {
+  line_number(offset, color, sep)
+}
+
+// Wraps every hit in `line` in the color of the query it matched
+pub fn highlight(line: &str, hits: &[Hit]) -> String {
+  let mut out = String::new();
+  let mut last = 0;
+
+  for hit in hits {
+    out.push_str(&line[last..hit.range.start]);
+    out.push_str(MATCH_COLORS[hit.pattern % MATCH_COLORS.len()]);
+    out.push_str(&line[hit.range.clone()]);
+    out.push_str(RESET);
+    last = hit.range.end;
+  }
+
+  out.push_str(&line[last..]);
+  out
+}
+
+// Builds a single JSON object describing one matching line. Every hit is
+// listed with its byte range and the index of the query that matched.
+pub fn json_line(filename: &str, line_number: usize, line: &str, hits: &[Hit]) -> String {
+  let mut out = String::new();
+
+  out.push_str("{\"file\":");
+  push_json_string(&mut out, filename);
+  write!(out, ",\"line_number\":{},\"line\":", line_number).unwrap();
+  push_json_string(&mut out, line);
+  out.push_str(",\"matches\":[");
+
+  for (i, hit) in hits.iter().enumerate() {
+    if i > 0 {
+      out.push(',');
+    }
+    write!(
+      out,
+      "{{\"start\":{},\"end\":{},\"pattern\":{}}}",
+      hit.range.start, hit.range.end, hit.pattern
+    )
+    .unwrap();
+  }
+
+  out.push_str("]}");
+  out
+}
+
+// Writes `s` as a quoted JSON string, escaping the characters JSON
+// doesn't allow to appear raw
+fn push_json_string(out: &mut String, s: &str) {
+  out.push('"');
+
+  for c in s.chars() {
+    match c {
+      '"' => out.push_str("\\\""),
+      '\\' => out.push_str("\\\\"),
+      '\n' => out.push_str("\\n"),
+      '\r' => out.push_str("\\r"),
+      '\t' => out.push_str("\\t"),
+      c if (c as u32) < 0x20 => write!(out, "\\u{:04x}", c as u32).unwrap(),
+      c => out.push(c),
+    }
+  }
+
+  out.push('"');
+}
+
+#[cfg(test)]
+mod tests {
+  use super::*;
+
+  fn hit(pattern: usize, start: usize, end: usize) -> Hit {
+    Hit {
+      pattern,
+      range: start..end,
+    }
+  }
+
+  #[test]
+  fn parses_format_names() {
+    assert_eq!(OutputFormat::Text, OutputFormat::parse("text").unwrap());
+    assert_eq!(OutputFormat::Json, OutputFormat::parse("json").unwrap());
+    assert_eq!(
+      "unknown output format `xml`, expected `text` or `json`",
+      OutputFormat::parse("xml").unwrap_err().to_string()
+    );
+  }
+
+  #[test]
+  fn formats_a_match_as_json() {
+    assert_eq!(
+      r#"{"file":"poem.txt","line_number":2,"line":"Are you nobody, too?","matches":[{"start":8,"end":14,"pattern":0}]}"#,
+      json_line("poem.txt", 2, "Are you nobody, too?", &[hit(0, 8, 14)])
+    );
+  }
+
+  #[test]
+  fn escapes_special_characters() {
+    assert_eq!(
+      r#"{"file":"a\\b.txt","line_number":1,"line":"say \"hi\"\t\u0001","matches":[]}"#,
+      json_line("a\\b.txt", 1, "say \"hi\"\t\u{1}", &[])
+    );
+  }
+
+  #[test]
+  fn highlights_matches_and_keeps_the_rest() {
+    assert_eq!("plain", highlight("plain", &[]));
+    assert_eq!(
+      "\x1b[1;31mto\x1b[0m go \x1b[1;31mto\x1b[0m",
+      highlight("to go to", &[hit(0, 0, 2), hit(0, 6, 8)])
+    );
+  }
+
+  #[test]
+  fn each_query_gets_its_own_color() {
+    assert_eq!(
+      "\x1b[1;31mto\x1b[0m \x1b[1;33mgo\x1b[0m",
+      highlight("to go", &[hit(0, 0, 2), hit(1, 3, 5)])
+    );
+  }
+
+  #[test]
+  fn prefixes_with_and_without_color() {
+    assert_eq!("poem.txt:", filename("poem.txt", false, ':'));
+    assert_eq!("\x1b[35mpoem.txt\x1b[0m:", filename("poem.txt", true, ':'));
+    assert_eq!("poem.txt\0", filename("poem.txt", false, '\0'));
+    assert_eq!("7:", line_number(7, false, ':'));
+    assert_eq!("\x1b[32m7\x1b[0m:", line_number(7, true, ':'));
+    assert_eq!("7-", line_number(7, false, '-'));
+  }
+}
@@ -0,0 +1,124 @@
+// A small worker pool, the same shape as the ThreadPool from the chapter 20
+// web server: a fixed set of threads pulling boxed jobs off a shared channel.
+
+use std::panic::{self, AssertUnwindSafe};
+use std::sync::{mpsc, Arc, Mutex};
+use std::thread;
+
+type Job = Box<dyn FnOnce() + Send + 'static>;
+
+enum Message {
+  NewJob(Job),
+  Terminate,
+}
+
+pub struct ThreadPool {
+  workers: Vec<Worker>,
+  sender: mpsc::Sender<Message>,
+}
+
+impl ThreadPool {
+  /// Create a new ThreadPool with `size` worker threads.
+  ///
+  /// # Panics
+  ///
+  /// The `new` function will panic if the size is zero.
+  pub fn new(size: usize) -> ThreadPool {
+    assert!(size > 0);
+
+    let (sender, receiver) = mpsc::channel();
+    let receiver = Arc::new(Mutex::new(receiver));
+
+    let mut workers = Vec::with_capacity(size);
+    for _ in 0..size {
+      workers.push(Worker::new(Arc::clone(&receiver)));
+    }
+
+    ThreadPool { workers, sender }
+  }
+
+  pub fn execute<F>(&self, f: F)
+  where
+    F: FnOnce() + Send + 'static,
+  {
+    self.sender.send(Message::NewJob(Box::new(f))).unwrap();
+  }
+}
+
+// Dropping the pool waits for every queued job to finish
+impl Drop for ThreadPool {
+  fn drop(&mut self) {
+    for _ in &self.workers {
+      self.sender.send(Message::Terminate).unwrap();
+    }
+
+    for worker in &mut self.workers {
+      if let Some(thread) = worker.thread.take() {
+        thread.join().unwrap();
+      }
+    }
+  }
+}
+
+struct Worker {
+  thread: Option<thread::JoinHandle<()>>,
+}
+
+impl Worker {
+  fn new(receiver: Arc<Mutex<mpsc::Receiver<Message>>>) -> Worker {
+    let thread = thread::spawn(move || loop {
+      let message = receiver.lock().unwrap().recv().unwrap();
+
+      match message {
+        // A job that panics only loses its own work; the worker goes on
+        // to the next one rather than dying with it
+        Message::NewJob(job) => {
+          let _ = panic::catch_unwind(AssertUnwindSafe(job));
+        }
+        Message::Terminate => break,
+      }
+    });
+
+    Worker {
+      thread: Some(thread),
+    }
+  }
+}
+
+#[cfg(test)]
+mod tests {
+  use super::*;
+
+  #[test]
+  fn runs_every_job_before_dropping() {
+    let (tx, rx) = mpsc::channel();
+
+    {
+      let pool = ThreadPool::new(3);
+      for i in 0..10 {
+        let tx = tx.clone();
+        pool.execute(move || tx.send(i).unwrap());
+      }
+    }
+    drop(tx);
+
+    let mut got: Vec<i32> = rx.iter().collect();
+    got.sort();
+    assert_eq!((0..10).collect::<Vec<_>>(), got);
+  }
+
+  #[test]
+  fn a_panicking_job_leaves_the_workers_running() {
+    let (tx, rx) = mpsc::channel();
+
+    {
+      let pool = ThreadPool::new(1);
+      pool.execute(|| panic!("this job fails"));
+      let tx = tx.clone();
+      pool.execute(move || tx.send(1).unwrap());
+    }
+    drop(tx);
+
+    assert_eq!(vec![1], rx.iter().collect::<Vec<_>>());
+  }
+}
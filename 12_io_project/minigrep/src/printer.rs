@@ -0,0 +1,232 @@
+// Turns matches into minigrep's output. The same Printer is fed by the
+// streaming search and by the in-memory search over a memory-mapped file,
+// so both print exactly the same thing.
+
+use std::collections::VecDeque;
+use std::io::{self, Write};
+use std::mem;
+
+use crate::config::{Config, ListFiles};
+use crate::output::{self, OutputFormat};
+use crate::search::{Hit, Line, Match};
+
+pub struct Printer<'a, W: Write> {
+  config: &'a Config,
+  filename: &'a str,
+  // The file name prefixes for matching lines and for context lines
+  prefix: String,
+  context_prefix: String,
+  color: bool,
+  found: usize,
+  // The last few lines that weren't printed, as (line number, offset,
+  // line), in case a match turns up that wants them as context before it
+  before: VecDeque<(usize, usize, String)>,
+  // How many more lines to print as context after the last match
+  after_left: usize,
+  // The number of the last line printed, to tell where a group of context
+  // lines needs a separator in front of it
+  last_printed: Option<usize>,
+  out: &'a mut W,
+  // Write errors can't be returned from inside a search callback, so the
+  // first one is kept here and stops the search
+  error: Option<io::Error>,
+}
+
+impl<'a, W: Write> Printer<'a, W> {
+  // With `with_filename` set, each text line is prefixed by the file name
+  // the way grep does when it searches more than one file
+  pub fn new(
+    config: &'a Config,
+    filename: &'a str,
+    with_filename: bool,
+    out: &'a mut W,
+  ) -> Printer<'a, W> {
+    let color = config.use_color();
+    let prefix = |sep| {
+      if !with_filename {
+        String::new()
+      } else if config.null {
+        output::filename(filename, color, '\0')
+      } else {
+        output::filename(filename, color, sep)
+      }
+    };
+
+    Printer {
+      config,
+      filename,
+      prefix: prefix(':'),
+      context_prefix: prefix('-'),
+      color,
+      found: 0,
+      before: VecDeque::new(),
+      after_left: 0,
+      last_printed: None,
+      out,
+      error: None,
+    }
+  }
+
+  // Hands a line from search::search_lines to on_match or on_other
+  pub fn on_line(&mut self, line: Line) -> bool {
+    match line {
+      Line::Selected(m) => self.on_match(&m),
+      Line::Other(m) => self.on_other(&m),
+    }
+  }
+
+  // Prints one match. Returns false once the search should stop, either
+  // because --max-count was reached (and any context after the last match
+  // printed) or because writing failed.
+  pub fn on_match(&mut self, m: &Match) -> bool {
+    if self.error.is_some() {
+      return false;
+    }
+    // Like grep, lines after the last match --max-count allows can only
+    // show up as context
+    if self.found >= self.config.limit() {
+      return self.on_other(m);
+    }
+    self.found += 1;
+
+    // One match settles the exit status, and for -l and -L whether the
+    // file gets listed, so those modes can stop right away
+    if self.config.quiet || self.config.list_files.is_some() {
+      return false;
+    }
+
+    if !self.config.count {
+      if let Err(e) = self.write_match(m) {
+        self.error = Some(e);
+        return false;
+      }
+    }
+
+    self.keep_going()
+  }
+
+  // Takes a line the search didn't select, printing it if it's context
+  // after a match and otherwise holding on to it in case it turns out to
+  // be context before one. Returns false once the search should stop.
+  pub fn on_other(&mut self, m: &Match) -> bool {
+    if self.error.is_some() {
+      return false;
+    }
+    if !self.config.shows_context() {
+      return self.keep_going();
+    }
+
+    if self.after_left > 0 {
+      self.after_left -= 1;
+      if let Err(e) = self.write_line(m.line_number, m.offset, m.line, &[], false) {
+        self.error = Some(e);
+        return false;
+      }
+    } else if self.config.before_context > 0 {
+      if self.before.len() == self.config.before_context {
+        self.before.pop_front();
+      }
+      self
+        .before
+        .push_back((m.line_number, m.offset, m.line.to_string()));
+    }
+
+    self.keep_going()
+  }
+
+  // Whether there's anything left to look for: more matches, or context
+  // still to print after the last one
+  fn keep_going(&self) -> bool {
+    self.found < self.config.limit() || self.after_left > 0
+  }
+
+  // Reports any write error, and prints the file name for -l and -L or the
+  // count for -c. Returns how many lines were selected.
+  pub fn finish(self) -> io::Result<usize> {
+    if let Some(e) = self.error {
+      return Err(e);
+    }
+    if self.config.quiet {
+      return Ok(self.found);
+    }
+
+    let end = self.config.line_end();
+
+    match self.config.list_files {
+      Some(ListFiles::WithMatches) if self.found > 0 => {
+        write!(self.out, "{}{}", self.filename, end)?;
+      }
+      Some(ListFiles::WithoutMatch) if self.found == 0 => {
+        write!(self.out, "{}{}", self.filename, end)?;
+      }
+      None if self.config.count => {
+        writeln!(self.out, "{}{}", self.prefix, self.found)?;
+      }
+      _ => {}
+    }
+
+    Ok(self.found)
+  }
+
+  fn write_match(&mut self, m: &Match) -> io::Result<()> {
+    if self.config.format == OutputFormat::Json {
+      return writeln!(
+        self.out,
+        "{}",
+        output::json_line(self.filename, m.line_number, m.line, &m.hits)
+      );
+    }
+
+    for (line_number, offset, line) in mem::take(&mut self.before) {
+      self.write_line(line_number, offset, &line, &[], false)?;
+    }
+    self.write_line(m.line_number, m.match_offset(), m.line, &m.hits, true)?;
+    self.after_left = self.config.after_context;
+
+    Ok(())
+  }
+
+  // Writes one line of text output. Matching lines get a `:` after each
+  // prefix and context lines a `-`; `offset` is printed with -b.
+  fn write_line(
+    &mut self,
+    line_number: usize,
+    offset: usize,
+    line: &str,
+    hits: &[Hit],
+    matching: bool,
+  ) -> io::Result<()> {
+    let end = self.config.line_end();
+
+    // A gap since the last printed line starts a new group of context
+    if let (Some(last), Some(separator)) = (self.last_printed, &self.config.group_separator) {
+      if self.config.shows_context() && line_number > last + 1 {
+        write!(self.out, "{}{}", separator, end)?;
+      }
+    }
+    self.last_printed = Some(line_number);
+
+    let (prefix, sep) = if matching {
+      (&self.prefix, ':')
+    } else {
+      (&self.context_prefix, '-')
+    };
+    self.out.write_all(prefix.as_bytes())?;
+    if self.config.line_number {
+      let number = output::line_number(line_number, self.color, sep);
+      self.out.write_all(number.as_bytes())?;
+    }
+    if self.config.byte_offset {
+      let offset = output::byte_offset(offset, self.color, sep);
+      self.out.write_all(offset.as_bytes())?;
+    }
+    if self.color && !self.config.invert_match {
+      self
+        .out
+        .write_all(output::highlight(line, hits).as_bytes())?;
+    } else {
+      self.out.write_all(line.as_bytes())?;
+    }
+    self.out.write_all(end.as_bytes())
+  }
+}
@@ -0,0 +1,293 @@
+// Default options from a config file.
+//
+// Before the command line is parsed, minigrep reads `~/.minigreprc` and
+// then `minigrep.toml` in the current directory. Both use the same small
+// subset of TOML: one `option = value` per line, where the option is the
+// long name of a command line flag and `#` starts a comment:
+//
+//     # always highlight, and search files .gitignore would skip
+//     color = "always"
+//     no-ignore = true
+//     glob = ["*.rs", "*.toml"]
+//     max-count = 20
+//
+// Settings are applied in file order as if they were flags given before
+// the real ones, so the project file beats the home one and the command
+// line beats both. `--no-config` skips the files altogether.
+
+use std::env;
+use std::fs;
+use std::io;
+use std::path::{Path, PathBuf};
+
+// One `option = value` entry. Arrays turn into one Setting per element, so
+// a repeatable flag like --glob can be given several times.
+#[derive(Debug, Clone, PartialEq)]
+pub struct Setting {
+  // Where the setting came from, as `file:line`, for error messages
+  pub source: String,
+  pub name: String,
+  pub value: Value,
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub enum Value {
+  Switch(bool),
+  Text(String),
+}
+
+// The config files to read, lowest precedence first
+pub fn config_files() -> Vec<PathBuf> {
+  let home = env::var_os("HOME").or_else(|| env::var_os("USERPROFILE"));
+
+  home
+    .map(|home| Path::new(&home).join(".minigreprc"))
+    .into_iter()
+    .chain(Some(PathBuf::from("minigrep.toml")))
+    .collect()
+}
+
+// Reads every one of `files` that exists, usually the config_files. A
+// missing file is fine; one that can't be read or parsed is an error.
+pub fn load(files: &[PathBuf]) -> Result<Vec<Setting>, String> {
+  let mut settings = Vec::new();
+
+  for path in files {
+    let contents = match fs::read_to_string(path) {
+      Ok(contents) => contents,
+      Err(e) if e.kind() == io::ErrorKind::NotFound => continue,
+      Err(e) => return Err(format!("{}: {}", path.display(), e)),
+    };
+
+    settings.extend(parse(&contents, &path.display().to_string())?);
+  }
+
+  Ok(settings)
+}
+
+// Parses the contents of one config file; `name` is used in error messages
+pub fn parse(contents: &str, name: &str) -> Result<Vec<Setting>, String> {
+  let mut settings = Vec::new();
+
+  for (i, line) in contents.lines().enumerate() {
+    let source = format!("{}:{}", name, i + 1);
+    let line = strip_comment(line).trim();
+    if line.is_empty() {
+      continue;
+    }
+
+    let (key, value) = line
+      .split_once('=')
+      .ok_or_else(|| format!("{}: expected `option = value`", source))?;
+    let key = key.trim();
+    if key.is_empty() {
+      return Err(format!("{}: missing the option name", source));
+    }
+
+    let values = parse_value(value.trim()).map_err(|e| format!("{}: {}", source, e))?;
+    settings.extend(values.into_iter().map(|value| Setting {
+      source: source.clone(),
+      name: key.to_string(),
+      value,
+    }));
+  }
+
+  Ok(settings)
+}
+
+// Cuts off a `#` comment, leaving `#` inside quoted strings alone
+fn strip_comment(line: &str) -> &str {
+  let mut quote = None;
+  let mut escaped = false;
+
+  for (i, c) in line.char_indices() {
+    match (quote, c) {
+      (Some('"'), '\\') if !escaped => {
+        escaped = true;
+        continue;
+      }
+      (Some(q), c) if c == q && !escaped => quote = None,
+      (None, '"') | (None, '\'') => quote = Some(c),
+      (None, '#') => return &line[..i],
+      _ => {}
+    }
+    escaped = false;
+  }
+
+  line
+}
+
+fn parse_value(value: &str) -> Result<Vec<Value>, String> {
+  if let Some(inner) = value.strip_prefix('[') {
+    let inner = inner
+      .strip_suffix(']')
+      .ok_or("an array has to end with `]` on the same line")?;
+
+    return split_array(inner)
+      .into_iter()
+      .map(|item| match parse_scalar(item.trim())? {
+        Value::Text(text) => Ok(Value::Text(text)),
+        Value::Switch(_) => Err(String::from("arrays can only hold strings")),
+      })
+      .collect();
+  }
+
+  Ok(vec![parse_scalar(value)?])
+}
+
+// Splits the inside of an array at the commas that aren't in a string. A
+// trailing comma is allowed, like in TOML.
+fn split_array(inner: &str) -> Vec<&str> {
+  let mut items = Vec::new();
+  let mut quote = None;
+  let mut escaped = false;
+  let mut start = 0;
+
+  for (i, c) in inner.char_indices() {
+    match (quote, c) {
+      (Some('"'), '\\') if !escaped => {
+        escaped = true;
+        continue;
+      }
+      (Some(q), c) if c == q && !escaped => quote = None,
+      (None, '"') | (None, '\'') => quote = Some(c),
+      (None, ',') => {
+        items.push(&inner[start..i]);
+        start = i + 1;
+      }
+      _ => {}
+    }
+    escaped = false;
+  }
+  items.push(&inner[start..]);
+
+  items.retain(|item| !item.trim().is_empty());
+  items
+}
+
+fn parse_scalar(value: &str) -> Result<Value, String> {
+  match value {
+    "true" => return Ok(Value::Switch(true)),
+    "false" => return Ok(Value::Switch(false)),
+    _ => {}
+  }
+
+  if let Some(literal) = value.strip_prefix('\'') {
+    // Literal strings take everything up to the closing quote as it is
+    return match literal.strip_suffix('\'') {
+      Some(text) if !text.contains('\'') => Ok(Value::Text(text.to_string())),
+      _ => Err(format!("malformed string {}", value)),
+    };
+  }
+
+  if let Some(quoted) = value.strip_prefix('"') {
+    return unescape(quoted).map(Value::Text);
+  }
+
+  if !value.is_empty() && value.chars().all(|c| c.is_ascii_digit()) {
+    return Ok(Value::Text(value.to_string()));
+  }
+
+  Err(format!(
+    "expected true, false, a number or a quoted string, found `{}`",
+    value
+  ))
+}
+
+// Decodes the rest of a basic string after its opening quote
+fn unescape(quoted: &str) -> Result<String, String> {
+  let mut text = String::new();
+  let mut chars = quoted.chars();
+
+  while let Some(c) = chars.next() {
+    match c {
+      '"' if chars.as_str().is_empty() => return Ok(text),
+      '"' => return Err(String::from("unexpected text after a string")),
+      '\\' => match chars.next() {
+        Some('"') => text.push('"'),
+        Some('\\') => text.push('\\'),
+        Some('n') => text.push('\n'),
+        Some('t') => text.push('\t'),
+        other => {
+          let escape = other.map(String::from).unwrap_or_default();
+          return Err(format!("unknown escape `\\{}`", escape));
+        }
+      },
+      c => text.push(c),
+    }
+  }
+
+  Err(String::from("a string is missing its closing quote"))
+}
+
+#[cfg(test)]
+mod tests {
+  use super::*;
+
+  fn setting(line: usize, name: &str, value: Value) -> Setting {
+    Setting {
+      source: format!("rc:{}", line),
+      name: name.to_string(),
+      value,
+    }
+  }
+
+  fn text(value: &str) -> Value {
+    Value::Text(value.to_string())
+  }
+
+  #[test]
+  fn parses_every_kind_of_value() {
+    let contents = "\
+# defaults for minigrep
+color = \"always\"   # highlight even in pipes
+
+no-ignore = true
+ignore-case = false
+max-count = 20
+glob = ['*.rs', \"src/#*.md\",]
+";
+
+    assert_eq!(
+      Ok(vec![
+        setting(2, "color", text("always")),
+        setting(4, "no-ignore", Value::Switch(true)),
+        setting(5, "ignore-case", Value::Switch(false)),
+        setting(6, "max-count", text("20")),
+        setting(7, "glob", text("*.rs")),
+        setting(7, "glob", text("src/#*.md")),
+      ]),
+      parse(contents, "rc")
+    );
+  }
+
+  #[test]
+  fn unescapes_basic_strings() {
+    assert_eq!(
+      Ok(vec![setting(1, "glob", text("a \"b\" \\ c"))]),
+      parse(r#"glob = "a \"b\" \\ c""#, "rc")
+    );
+  }
+
+  #[test]
+  fn reports_where_a_bad_line_is() {
+    assert_eq!(
+      Err(String::from("rc:2: expected `option = value`")),
+      parse("color = \"never\"\n[table]", "rc")
+    );
+    assert_eq!(
+      Err(String::from(
+        "rc:1: expected true, false, a number or a quoted string, found `always`"
+      )),
+      parse("color = always", "rc")
+    );
+    assert_eq!(
+      Err(String::from("rc:1: a string is missing its closing quote")),
+      parse("color = \"always", "rc")
+    );
+    assert_eq!(
+      Err(String::from("rc:1: arrays can only hold strings")),
+      parse("glob = [true]", "rc")
+    );
+  }
+}
@@ -0,0 +1,238 @@
+// Byte-level scanning for searching text that is already in memory.
+//
+// Splitting the whole input into lines and testing each one means touching
+// every line even though most of them don't match. For a plain
+// case-sensitive query it's much cheaper to look for the query in the raw
+// bytes and only work out the line around each occurrence. The scanning is
+// done like the memchr function from C: eight bytes are compared at once by
+// treating them as one u64, and the byte-by-byte work is left for the word
+// that actually holds what we're looking for.
+
+use std::convert::TryInto;
+
+// 0x01 and 0x80 repeated in every byte of a word
+const LO: u64 = u64::from_ne_bytes([0x01; 8]);
+const HI: u64 = u64::from_ne_bytes([0x80; 8]);
+
+fn word(chunk: &[u8]) -> u64 {
+  u64::from_ne_bytes(chunk.try_into().unwrap())
+}
+
+// True when one of the bytes of `word` is zero. Subtracting 1 from every
+// byte only sets a byte's high bit out of nowhere (without it having been
+// set before) when the byte was zero.
+fn has_zero_byte(word: u64) -> bool {
+  word.wrapping_sub(LO) & !word & HI != 0
+}
+
+// How many bytes of `word` are zero. Unlike has_zero_byte this can't let a
+// borrow run from one byte into the next, so every byte is counted right.
+fn count_zero_bytes(word: u64) -> usize {
+  let low_bits = !HI;
+  let nonzero = ((word & low_bits) + low_bits) | word;
+
+  (!nonzero & HI).count_ones() as usize
+}
+
+// The index of the first `needle` byte in `haystack`
+pub fn memchr(needle: u8, haystack: &[u8]) -> Option<usize> {
+  let repeated = LO * needle as u64;
+  let mut start = 0;
+
+  // XOR turns every byte equal to needle into a zero byte
+  for chunk in haystack.chunks_exact(8) {
+    if has_zero_byte(word(chunk) ^ repeated) {
+      break;
+    }
+    start += 8;
+  }
+
+  haystack[start..]
+    .iter()
+    .position(|&byte| byte == needle)
+    .map(|i| start + i)
+}
+
+// How many times `needle` appears in `haystack`
+pub fn count(needle: u8, haystack: &[u8]) -> usize {
+  let repeated = LO * needle as u64;
+  let chunks = haystack.chunks_exact(8);
+  let rest = chunks.remainder();
+
+  chunks
+    .map(|chunk| count_zero_bytes(word(chunk) ^ repeated))
+    .sum::<usize>()
+    + rest.iter().filter(|&&byte| byte == needle).count()
+}
+
+// The index of the first occurrence of `needle` in `haystack`. Looking
+// for just the first byte of the needle stops far too often in ordinary
+// text, so eight positions at a time are checked for both the first and the
+// last byte of the needle being in place. Only positions where both are
+// get compared in full.
+pub fn find(needle: &[u8], haystack: &[u8]) -> Option<usize> {
+  let (&first, &last) = (needle.first()?, needle.last()?);
+  let gap = needle.len() - 1;
+  let (first_repeated, last_repeated) = (LO * first as u64, LO * last as u64);
+  let is_at = |at: usize| haystack[at..].starts_with(needle);
+  let mut start = 0;
+
+  while start + gap + 8 <= haystack.len() {
+    let firsts = word(&haystack[start..start + 8]) ^ first_repeated;
+    let lasts = word(&haystack[start + gap..start + gap + 8]) ^ last_repeated;
+
+    // A zero byte means both the first and the last byte matched there
+    if has_zero_byte(firsts | lasts) {
+      if let Some(at) = (start..start + 8).find(|&at| is_at(at)) {
+        return Some(at);
+      }
+    }
+    start += 8;
+  }
+
+  (start..haystack.len()).find(|&at| is_at(at))
+}
+
+// How many lines str::lines would split `contents` into
+pub fn count_lines(contents: &str) -> usize {
+  let newlines = count(b'\n', contents.as_bytes());
+
+  if contents.is_empty() || contents.ends_with('\n') {
+    newlines
+  } else {
+    newlines + 1
+  }
+}
+
+// The lines of `contents` that contain `needle`, numbered from 0 and cut
+// exactly like str::lines would cut them. Only the lines around an
+// occurrence are ever split out; everything in between is skipped by
+// counting its newlines. `needle` must not contain \n or \r, or an
+// occurrence could straddle two lines.
+pub struct LinesContaining<'n, 'a> {
+  needle: &'n str,
+  contents: &'a str,
+  // Where the search for the next occurrence starts
+  next: usize,
+  // How many lines start before `counted`, which is always the start of a
+  // line, so newlines are never counted twice
+  lines_before: usize,
+  counted: usize,
+}
+
+pub fn lines_containing<'n, 'a>(needle: &'n str, contents: &'a str) -> LinesContaining<'n, 'a> {
+  LinesContaining {
+    needle,
+    contents,
+    next: 0,
+    lines_before: 0,
+    counted: 0,
+  }
+}
+
+impl<'n, 'a> Iterator for LinesContaining<'n, 'a> {
+  type Item = (usize, &'a str);
+
+  fn next(&mut self) -> Option<(usize, &'a str)> {
+    let bytes = self.contents.as_bytes();
+    let at = self.next + find(self.needle.as_bytes(), &bytes[self.next..])?;
+
+    // Newlines are ASCII, so the line's ends are always char boundaries
+    let start = bytes[..at]
+      .iter()
+      .rposition(|&byte| byte == b'\n')
+      .map_or(0, |i| i + 1);
+    let end = memchr(b'\n', &bytes[at..]).map_or(bytes.len(), |i| at + i);
+
+    self.lines_before += count(b'\n', &bytes[self.counted..start]);
+    self.counted = start;
+    // Carry on after the line, so it isn't reported once per occurrence
+    self.next = (end + 1).min(bytes.len());
+
+    // Like str::lines, drop the \r of a \r\n line ending
+    let line = &self.contents[start..end];
+    let line = if end < bytes.len() {
+      line.strip_suffix('\r').unwrap_or(line)
+    } else {
+      line
+    };
+
+    Some((self.lines_before, line))
+  }
+}
+
+#[cfg(test)]
+mod tests {
+  use super::*;
+
+  #[test]
+  fn memchr_finds_bytes_in_and_after_whole_words() {
+    let haystack = b"0123456789abcdefghij";
+
+    for (i, &byte) in haystack.iter().enumerate() {
+      assert_eq!(Some(i), memchr(byte, haystack));
+    }
+    assert_eq!(None, memchr(b'z', haystack));
+    assert_eq!(None, memchr(b'a', b""));
+  }
+
+  #[test]
+  fn counts_every_occurrence() {
+    assert_eq!(0, count(b'\n', b""));
+    assert_eq!(3, count(b'\n', b"\n\n\n"));
+    assert_eq!(4, count(b'x', b"x.......x.......xx..."));
+    // Bytes next to the one being counted mustn't be miscounted
+    assert_eq!(
+      8,
+      count(
+        0x80,
+        &[0x80, 0x81, 0x80, 0x7f, 0x80, 0x80, 0x01, 0x80, 0x80, 0x80, 0x80]
+      )
+    );
+  }
+
+  #[test]
+  fn finds_the_whole_needle() {
+    assert_eq!(Some(9), find(b"needle", b"needs no needle"));
+    assert_eq!(Some(0), find(b"n", b"n"));
+    assert_eq!(None, find(b"needle", b"needl"));
+    assert_eq!(None, find(b"", b"anything"));
+  }
+
+  #[test]
+  fn counts_lines_like_str_lines() {
+    for contents in ["", "a", "a\n", "a\nb", "a\n\nb\n", "\n", "a\r\nb\r\n"] {
+      assert_eq!(
+        contents.lines().count(),
+        count_lines(contents),
+        "{:?}",
+        contents
+      );
+    }
+  }
+
+  #[test]
+  fn cuts_lines_like_str_lines() {
+    let contents = "to be\r\nor not\nto be\n\ntomato\r";
+    let expected: Vec<(usize, &str)> = contents
+      .lines()
+      .enumerate()
+      .filter(|(_, line)| line.contains("to"))
+      .collect();
+
+    assert_eq!(
+      expected,
+      lines_containing("to", contents).collect::<Vec<_>>()
+    );
+  }
+
+  #[test]
+  fn reports_each_line_once() {
+    let contents = "aaaa\nb\naa\nçaç";
+
+    assert_eq!(
+      vec![(0, "aaaa"), (2, "aa"), (3, "çaç")],
+      lines_containing("a", contents).collect::<Vec<_>>()
+    );
+  }
+}
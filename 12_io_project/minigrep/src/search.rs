@@ -0,0 +1,1010 @@
+// The search side of minigrep: deciding which lines match and where.
+
+use std::cmp::Reverse;
+use std::io::{self, BufRead};
+use std::ops::Range;
+
+use crate::matcher::AhoCorasick;
+use crate::scan;
+
+// One line that a search picked out.
+#[derive(Debug, Clone, PartialEq)]
+pub struct Match<'a> {
+  // The file the line came from, when the search knows it
+  pub file: Option<&'a str>,
+  // Counted from 1, like editors and grep do
+  pub line_number: usize,
+  // Byte offset of the start of `line` within the whole input
+  pub offset: usize,
+  // Byte range of the first hit within `line`. Inverted searches select
+  // lines without a hit, so for them the range is empty (0..0).
+  pub range: Range<usize>,
+  // Every hit in `line`, left to right. Hits never overlap (see
+  // Pattern::hits), and there are none for inverted searches or when an
+  // empty query is what matched.
+  pub hits: Vec<Hit>,
+  pub line: &'a str,
+}
+
+impl<'a> Match<'a> {
+  // The text the query matched
+  pub fn matched_text(&self) -> &'a str {
+    &self.line[self.range.clone()]
+  }
+
+  // Byte offset of the first hit within the whole input; for inverted
+  // searches, of the start of the line
+  pub fn match_offset(&self) -> usize {
+    self.offset + self.range.start
+  }
+
+  // The column span of every hit, counting characters from 1 the way
+  // editors do, with the end just past the last matched character
+  pub fn columns(&self) -> Vec<Range<usize>> {
+    let column = |byte: usize| self.line[..byte].chars().count() + 1;
+
+    self
+      .hits
+      .iter()
+      .map(|hit| column(hit.range.start)..column(hit.range.end))
+      .collect()
+  }
+}
+
+// One place in a line where a query matched. `pattern` is the index of the
+// query that matched, in the order the queries were given, so output can
+// tell the queries apart (for example by highlighting them differently).
+#[derive(Debug, Clone, PartialEq)]
+pub struct Hit {
+  pub pattern: usize,
+  pub range: Range<usize>,
+}
+
+// One or more queries together with the options that change what counts as
+// a match. A line matches when it contains any of the queries. The queries
+// are lowercased once up front for case-insensitive searches, a char at a
+// time the same way lines are, since str::to_lowercase would turn a final
+// Σ into ς where the lines get σ.
+pub struct Pattern {
+  queries: Vec<String>,
+  case_sensitive: bool,
+  whole_word: bool,
+  // With more than one query, an automaton finds all of them in one pass
+  automaton: Option<AhoCorasick>,
+}
+
+impl Pattern {
+  pub fn new(query: &str, case_sensitive: bool) -> Pattern {
+    Pattern::any(&[query], case_sensitive)
+  }
+
+  // A pattern matching lines that contain any of `queries` (like grep with
+  // several -e options)
+  pub fn any<S: AsRef<str>>(queries: &[S], case_sensitive: bool) -> Pattern {
+    let queries: Vec<String> = queries
+      .iter()
+      .map(|query| {
+        if case_sensitive {
+          query.as_ref().to_string()
+        } else {
+          query.as_ref().chars().flat_map(char::to_lowercase).collect()
+        }
+      })
+      .collect();
+    let automaton = if queries.len() > 1 {
+      Some(AhoCorasick::new(&queries))
+    } else {
+      None
+    };
+
+    Pattern {
+      queries,
+      case_sensitive,
+      whole_word: false,
+      automaton,
+    }
+  }
+
+  // Only match the query where it isn't part of a longer word (like grep -w)
+  pub fn whole_word(mut self, whole_word: bool) -> Pattern {
+    self.whole_word = whole_word;
+    self
+  }
+
+  // The byte range of the first match in `line`. An empty query matches
+  // at the very start of every line.
+  pub fn find(&self, line: &str) -> Option<Range<usize>> {
+    self.find_all(line).map(|hits| first_range(&hits))
+  }
+
+  // Every hit in `line`, or None when the line doesn't match at all. A line
+  // can match without any hits when one of the queries is empty.
+  pub fn find_all(&self, line: &str) -> Option<Vec<Hit>> {
+    let hits = self.hits(line);
+
+    if hits.is_empty() && !self.queries.iter().any(String::is_empty) {
+      None
+    } else {
+      Some(hits)
+    }
+  }
+
+  // The query itself, when a line can only match by containing exactly
+  // these bytes. That's the case for a single case-sensitive query, which
+  // lets whole inputs be scanned for it (see the scan module) instead of
+  // testing them line by line. Whole word searches still qualify, since a
+  // whole word match contains the query too.
+  fn literal(&self) -> Option<&str> {
+    match &self.queries[..] {
+      [query] if self.case_sensitive && !query.is_empty() && !query.contains(&['\n', '\r'][..]) => {
+        Some(query)
+      }
+      _ => None,
+    }
+  }
+
+  // Goes through find_all, so counting lines with -c agrees with the lines
+  // that get printed
+  pub fn is_match(&self, line: &str) -> bool {
+    self.find_all(line).is_some()
+  }
+
+  // Finds the byte range of every non-overlapping match in `line`. The
+  // ranges always point into the original line, even when the search is
+  // case insensitive and lowercasing would change byte lengths.
+  pub fn offsets(&self, line: &str) -> Vec<(usize, usize)> {
+    self
+      .hits(line)
+      .into_iter()
+      .map(|hit| (hit.range.start, hit.range.end))
+      .collect()
+  }
+
+  // Like offsets, but also says which query each match belongs to. Where
+  // several queries match at the same place the longest one wins, and on a
+  // tie the one given first.
+  pub fn hits(&self, line: &str) -> Vec<Hit> {
+    if let Some(automaton) = &self.automaton {
+      return self.automaton_hits(automaton, line);
+    }
+
+    if let [query] = &self.queries[..] {
+      if query.is_empty() {
+        return Vec::new();
+      }
+
+      if self.case_sensitive && !self.whole_word {
+        return line
+          .match_indices(query.as_str())
+          .map(|(start, hit)| Hit {
+            pattern: 0,
+            range: start..start + hit.len(),
+          })
+          .collect();
+      }
+    }
+
+    let mut hits = Vec::new();
+    let mut start = 0;
+
+    while start < line.len() {
+      let best = self
+        .queries
+        .iter()
+        .enumerate()
+        .filter(|(_, query)| !query.is_empty())
+        .filter_map(|(i, query)| Some((i, match_at(query, self.case_sensitive, line, start)?)))
+        .filter(|&(_, end)| !self.whole_word || is_word_bounded(line, start, end))
+        .max_by_key(|&(i, end)| (end, Reverse(i)));
+
+      match best {
+        Some((pattern, end)) => {
+          hits.push(Hit {
+            pattern,
+            range: start..end,
+          });
+          start = end;
+        }
+        None => {
+          // Step to the next character boundary
+          start += line[start..].chars().next().unwrap().len_utf8();
+        }
+      }
+    }
+
+    hits
+  }
+
+  fn automaton_hits(&self, automaton: &AhoCorasick, line: &str) -> Vec<Hit> {
+    let bounded = |hit: Hit| {
+      if self.whole_word && !is_word_bounded(line, hit.range.start, hit.range.end) {
+        None
+      } else {
+        Some(hit)
+      }
+    };
+
+    if self.case_sensitive {
+      return automaton.find_leftmost_longest(line.as_bytes(), bounded);
+    }
+
+    // Search the lowercased line, then translate the ranges back. A hit
+    // that starts or ends inside the lowercase form of a single character
+    // doesn't count, just like in match_at.
+    let (lowered, positions) = lowercase_with_positions(line);
+    automaton.find_leftmost_longest(lowered.as_bytes(), |hit| {
+      let start = positions[hit.range.start]?;
+      let end = positions[hit.range.end]?;
+
+      bounded(Hit {
+        pattern: hit.pattern,
+        range: start..end,
+      })
+    })
+  }
+}
+
+// Lowercases `line`, and maps every byte index of the result where a
+// character of `line` starts (or `line` ends) back to the byte index in
+// `line`. Indexes in the middle of a character's lowercase form map to None.
+fn lowercase_with_positions(line: &str) -> (String, Vec<Option<usize>>) {
+  let mut lowered = String::with_capacity(line.len());
+  let mut positions = Vec::with_capacity(line.len() + 1);
+
+  for (i, c) in line.char_indices() {
+    positions.push(Some(i));
+    lowered.extend(c.to_lowercase());
+    positions.resize(lowered.len(), None);
+  }
+  positions.push(Some(line.len()));
+
+  (lowered, positions)
+}
+
+// Checks whether `query` matches `line` starting at byte `start`, returning
+// the byte index just past the match. For case-insensitive searches the
+// query must already be lowercase.
+fn match_at(query: &str, case_sensitive: bool, line: &str, start: usize) -> Option<usize> {
+  if case_sensitive {
+    return if line[start..].starts_with(query) {
+      Some(start + query.len())
+    } else {
+      None
+    };
+  }
+
+  let mut wanted = query.chars();
+
+  for (i, c) in line[start..].char_indices() {
+    for lower in c.to_lowercase() {
+      if wanted.next() != Some(lower) {
+        return None;
+      }
+    }
+
+    if wanted.as_str().is_empty() {
+      return Some(start + i + c.len_utf8());
+    }
+  }
+
+  None
+}
+
+// Letters, digits (in any script) and underscores make up words
+fn is_word_char(c: char) -> bool {
+  c.is_alphanumeric() || c == '_'
+}
+
+// True when the text at line[start..end] isn't glued to a word character on
+// either side. Looking at whole chars rather than bytes keeps this correct
+// for multi-byte UTF-8 text.
+fn is_word_bounded(line: &str, start: usize, end: usize) -> bool {
+  let before = line[..start].chars().next_back();
+  let after = line[end..].chars().next();
+
+  !before.is_some_and(is_word_char) && !after.is_some_and(is_word_char)
+}
+
+// The lines of `contents` worth testing against `pattern`, numbered from 0.
+// When the pattern is a literal, only lines containing it come out, found
+// by scanning the raw bytes; otherwise every line has to be looked at.
+// Inverted searches want the other lines, so they always get all of them.
+fn candidate_lines<'p, 'a: 'p>(
+  pattern: &'p Pattern,
+  contents: &'a str,
+  invert_match: bool,
+) -> Box<dyn Iterator<Item = (usize, &'a str)> + 'p> {
+  match pattern.literal() {
+    Some(literal) if !invert_match => Box::new(scan::lines_containing(literal, contents)),
+    _ => Box::new(contents.lines().enumerate()),
+  }
+}
+
+// Lazily yields a Match for every selected line of `contents`. Nothing is
+// searched until the iterator is pulled, so callers can stop early, for
+// example with `take` when only the first N hits matter.
+pub fn find_matches<'p, 'a: 'p>(
+  pattern: &'p Pattern,
+  contents: &'a str,
+  invert_match: bool,
+) -> impl Iterator<Item = Match<'a>> + 'p {
+  candidate_lines(pattern, contents, invert_match).filter_map(move |(i, line)| {
+    let hits = match (pattern.find_all(line), invert_match) {
+      (Some(hits), false) => hits,
+      (None, true) => Vec::new(),
+      _ => return None,
+    };
+
+    Some(Match {
+      file: None,
+      line_number: i + 1,
+      // `line` is a slice of `contents`, so the distance between their
+      // start pointers is the line's offset
+      offset: line.as_ptr() as usize - contents.as_ptr() as usize,
+      range: first_range(&hits),
+      hits,
+      line,
+    })
+  })
+}
+
+fn first_range(hits: &[Hit]) -> Range<usize> {
+  hits.first().map_or(0..0, |hit| hit.range.clone())
+}
+
+// The streaming version of find_matches: reads `reader` one line at a time
+// and calls `on_match` for each selected line, so memory use stays at about
+// one line however big the input is. Returning false from `on_match` stops
+// the search without reading the rest of the input. Returns how many lines
+// were read.
+pub fn search_reader<R, F>(
+  pattern: &Pattern,
+  reader: R,
+  invert_match: bool,
+  mut on_match: F,
+) -> io::Result<usize>
+where
+  R: BufRead,
+  F: FnMut(Match) -> bool,
+{
+  search_lines(pattern, reader, invert_match, |line| match line {
+    Line::Selected(m) => on_match(m),
+    Line::Other(_) => true,
+  })
+}
+
+// Every line search_lines reads, sorted by whether the search selected it
+#[derive(Debug, PartialEq)]
+pub enum Line<'a> {
+  Selected(Match<'a>),
+  // A line that wasn't selected, which can still be printed as context.
+  // It has no hits, so its range is empty.
+  Other(Match<'a>),
+}
+
+// Like search_reader, but hands over the lines that weren't selected as
+// well, for printing context around the ones that were
+pub fn search_lines<R, F>(
+  pattern: &Pattern,
+  mut reader: R,
+  invert_match: bool,
+  mut on_line: F,
+) -> io::Result<usize>
+where
+  R: BufRead,
+  F: FnMut(Line) -> bool,
+{
+  let mut buffer = String::new();
+  let mut line_number = 0;
+  // Bytes read so far, line endings included, so each line knows where in
+  // the input it starts
+  let mut consumed = 0;
+
+  loop {
+    buffer.clear();
+    let read = reader.read_line(&mut buffer)?;
+    if read == 0 {
+      return Ok(line_number);
+    }
+    line_number += 1;
+    let offset = consumed;
+    consumed += read;
+
+    // Strip the line ending the same way str::lines does
+    let line = buffer.strip_suffix('\n').unwrap_or(&buffer);
+    let line = line.strip_suffix('\r').unwrap_or(line);
+
+    let (selected, hits) = match (pattern.find_all(line), invert_match) {
+      (Some(hits), false) => (true, hits),
+      (None, true) => (true, Vec::new()),
+      _ => (false, Vec::new()),
+    };
+
+    let m = Match {
+      file: None,
+      line_number,
+      offset,
+      range: first_range(&hits),
+      hits,
+      line,
+    };
+    let keep_going = on_line(if selected {
+      Line::Selected(m)
+    } else {
+      Line::Other(m)
+    });
+    if !keep_going {
+      return Ok(line_number);
+    }
+  }
+}
+
+pub fn search<'a>(query: &str, contents: &'a str) -> Vec<Match<'a>> {
+  find_matches(&Pattern::new(query, true), contents, false).collect()
+}
+
+pub fn search_case_insensitive<'a>(query: &str, contents: &'a str) -> Vec<Match<'a>> {
+  find_matches(&Pattern::new(query, false), contents, false).collect()
+}
+
+// Smart case: a query with an uppercase letter in it is searched for case
+// sensitively, an all-lowercase one case insensitively
+pub fn is_smart_case_sensitive(query: &str) -> bool {
+  query.chars().any(char::is_uppercase)
+}
+
+// Picks search or search_case_insensitive for `query` by smart case
+pub fn search_smart_case<'a>(query: &str, contents: &'a str) -> Vec<Match<'a>> {
+  if is_smart_case_sensitive(query) {
+    search(query, contents)
+  } else {
+    search_case_insensitive(query, contents)
+  }
+}
+
+// Returns every line that the matching search would have left out, using
+// the same case-sensitivity rules as search and search_case_insensitive
+pub fn search_inverted<'a>(query: &str, contents: &'a str, case_sensitive: bool) -> Vec<Match<'a>> {
+  find_matches(&Pattern::new(query, case_sensitive), contents, true).collect()
+}
+
+// Like search, but the query has to appear as a whole word
+pub fn search_whole_word<'a>(
+  query: &str,
+  contents: &'a str,
+  case_sensitive: bool,
+) -> Vec<Match<'a>> {
+  let pattern = Pattern::new(query, case_sensitive).whole_word(true);
+
+  find_matches(&pattern, contents, false).collect()
+}
+
+// Counts the matching lines without collecting them into a Vec, so -c
+// doesn't pay for a result list it never prints
+pub fn count_matches(
+  query: &str,
+  contents: &str,
+  case_sensitive: bool,
+  invert_match: bool,
+) -> usize {
+  count_pattern_matches(&Pattern::new(query, case_sensitive), contents, invert_match)
+}
+
+pub fn count_pattern_matches(pattern: &Pattern, contents: &str, invert_match: bool) -> usize {
+  candidate_lines(pattern, contents, invert_match)
+    .filter(|(_, line)| pattern.is_match(line) != invert_match)
+    .count()
+}
+
+pub fn match_offsets(query: &str, line: &str, case_sensitive: bool) -> Vec<(usize, usize)> {
+  Pattern::new(query, case_sensitive).offsets(line)
+}
+
+#[cfg(test)]
+mod tests {
+  use super::*;
+
+  fn lines<'a>(matches: Vec<Match<'a>>) -> Vec<&'a str> {
+    matches.into_iter().map(|m| m.line).collect()
+  }
+
+  fn ranges(hits: &[Hit]) -> Vec<Range<usize>> {
+    hits.iter().map(|hit| hit.range.clone()).collect()
+  }
+
+  #[test]
+  fn case_sensitive() {
+    let query = "duct";
+    let contents = "\
+Rust:
+safe, fast, productive.
+Pick three.
+Duct tape.";
+
+    assert_eq!(
+      vec!["safe, fast, productive."],
+      lines(search(query, contents))
+    );
+  }
+
+  #[test]
+  fn case_insensitive() {
+    let query = "rUsT";
+    let contents = "\
+Rust:
+safe, fast, productive.
+Pick three.
+Trust me.";
+
+    assert_eq!(
+      vec!["Rust:", "Trust me."],
+      lines(search_case_insensitive(query, contents))
+    );
+  }
+
+  #[test]
+  fn inverted_case_sensitive() {
+    let query = "duct";
+    let contents = "\
+Rust:
+safe, fast, productive.
+Pick three.
+Duct tape.";
+
+    assert_eq!(
+      vec!["Rust:", "Pick three.", "Duct tape."],
+      lines(search_inverted(query, contents, true))
+    );
+  }
+
+  #[test]
+  fn inverted_case_insensitive() {
+    let query = "rUsT";
+    let contents = "\
+Rust:
+safe, fast, productive.
+Pick three.
+Trust me.";
+
+    assert_eq!(
+      vec!["safe, fast, productive.", "Pick three."],
+      lines(search_inverted(query, contents, false))
+    );
+  }
+
+  #[test]
+  fn inverted_keeps_duplicate_lines() {
+    let contents = "\
+same
+same
+other";
+
+    assert_eq!(
+      vec!["same", "same"],
+      lines(search_inverted("other", contents, true))
+    );
+  }
+
+  #[test]
+  fn counts_matching_lines() {
+    let contents = "\
+Rust:
+safe, fast, productive.
+Pick three.
+Trust me.";
+
+    assert_eq!(1, count_matches("Rust", contents, true, false));
+    assert_eq!(2, count_matches("rUsT", contents, false, false));
+    assert_eq!(2, count_matches("rUsT", contents, false, true));
+    assert_eq!(0, count_matches("missing", contents, true, false));
+  }
+
+  #[test]
+  fn count_agrees_with_search() {
+    let contents = "\
+I'm nobody! Who are you?
+Are you nobody, too?
+Then there's a pair of us - don't tell!";
+
+    assert_eq!(
+      search("nobody", contents).len(),
+      count_matches("nobody", contents, true, false)
+    );
+    assert_eq!(
+      search_inverted("nobody", contents, true).len(),
+      count_matches("nobody", contents, true, true)
+    );
+  }
+
+  #[test]
+  fn offsets_for_case_sensitive_matches() {
+    assert_eq!(vec![(0, 2), (6, 8)], match_offsets("to", "to go to", true));
+    assert!(match_offsets("To", "to go to", true).is_empty());
+  }
+
+  #[test]
+  fn offsets_for_case_insensitive_matches() {
+    assert_eq!(
+      vec![(0, 4), (7, 11)],
+      match_offsets("rust", "Rust & RUST", false)
+    );
+    // 'É' is two bytes, so the hit must start after it
+    assert_eq!(vec![(2, 5)], match_offsets("ABC", "Éabc", false));
+  }
+
+  #[test]
+  fn whole_word_only_matches_at_boundaries() {
+    let contents = "\
+Rust:
+Trust me.
+rusty nails
+use rust_lang;
+rust-lang";
+
+    assert_eq!(
+      vec!["Rust:"],
+      lines(search_whole_word("Rust", contents, true))
+    );
+    assert_eq!(
+      vec!["Rust:", "rust-lang"],
+      lines(search_whole_word("rust", contents, false))
+    );
+  }
+
+  #[test]
+  fn whole_word_boundaries_are_utf8_aware() {
+    let pattern = Pattern::new("café", false).whole_word(true);
+
+    assert_eq!(vec![(0, 5)], pattern.offsets("Café au lait"));
+    assert!(!pattern.is_match("cafés"));
+    // 'é' and 'ß' are word characters, not boundaries
+    assert!(!Pattern::new("ab", true).whole_word(true).is_match("éab"));
+    assert!(!Pattern::new("ab", true).whole_word(true).is_match("abß"));
+    assert!(Pattern::new("ab", true).whole_word(true).is_match("«ab»"));
+  }
+
+  #[test]
+  fn whole_word_skips_embedded_hits_before_a_real_one() {
+    let pattern = Pattern::new("to", true).whole_word(true);
+
+    assert_eq!(vec![(11, 13)], pattern.offsets("tomato, go to"));
+  }
+
+  #[test]
+  fn matches_carry_line_numbers_and_ranges() {
+    let contents = "\
+Rust:
+safe, fast, productive.
+Pick three.";
+
+    assert_eq!(
+      vec![Match {
+        file: None,
+        line_number: 2,
+        offset: 6,
+        range: 15..19,
+        hits: vec![Hit {
+          pattern: 0,
+          range: 15..19
+        }],
+        line: "safe, fast, productive.",
+      }],
+      search("duct", contents)
+    );
+    assert_eq!("duct", search("duct", contents)[0].matched_text());
+    assert_eq!(21, search("duct", contents)[0].match_offset());
+  }
+
+  #[test]
+  fn case_insensitive_ranges_point_into_the_original_line() {
+    let matches = search_case_insensitive("RUST", "Trust me.");
+
+    assert_eq!(1..5, matches[0].range);
+    assert_eq!("rust", matches[0].matched_text());
+  }
+
+  #[test]
+  fn matches_list_every_hit_in_the_line() {
+    let m = &search("to", "to go to")[0];
+
+    assert_eq!(vec![0..2, 6..8], ranges(&m.hits));
+    assert_eq!(vec![1..3, 7..9], m.columns());
+  }
+
+  #[test]
+  fn case_insensitive_hits_count_columns_in_characters() {
+    let m = &search_case_insensitive("É", "Café ÉTÉ")[0];
+
+    assert_eq!(vec![3..5, 6..8, 9..11], ranges(&m.hits));
+    assert_eq!(vec![4..5, 6..7, 8..9], m.columns());
+  }
+
+  #[test]
+  fn a_final_sigma_matches_itself_case_insensitively() {
+    let contents = "ΟΔΟΣ\nοδός\nΣΟΦΙΑ";
+
+    let matches = search_case_insensitive("ΟΔΟΣ", contents);
+    assert_eq!(vec!["ΟΔΟΣ"], lines(matches));
+
+    let pattern = Pattern::new("ΟΔΟΣ", false);
+    assert_eq!(1, count_pattern_matches(&pattern, contents, false));
+    assert!(pattern.is_match("ΟΔΟΣ"));
+    assert!(Pattern::new("Σ", false).is_match("ΣΟΦΙΑ"));
+  }
+
+  #[test]
+  fn overlapping_occurrences_become_separate_hits() {
+    let m = &search("aa", "aaaaa")[0];
+    assert_eq!(vec![0..2, 2..4], ranges(&m.hits));
+
+    // Where queries overlap, the leftmost longest one takes the text
+    let pattern = Pattern::any(&["ab", "abc", "bc"], true);
+    let m = find_matches(&pattern, "abcbc", false).next().unwrap();
+    assert_eq!(
+      vec![
+        Hit {
+          pattern: 1,
+          range: 0..3
+        },
+        Hit {
+          pattern: 2,
+          range: 3..5
+        },
+      ],
+      m.hits
+    );
+  }
+
+  #[test]
+  fn inverted_matches_have_empty_ranges() {
+    let matches = search_inverted("x", "x\ny", true);
+
+    assert_eq!(2, matches[0].line_number);
+    assert_eq!(0..0, matches[0].range);
+    assert!(matches[0].hits.is_empty());
+  }
+
+  #[test]
+  fn find_matches_is_lazy() {
+    let pattern = Pattern::new("x", true);
+    let contents = "x1\nx2\nx3\nx4";
+
+    assert_eq!(
+      vec![1, 2],
+      find_matches(&pattern, contents, false)
+        .take(2)
+        .map(|m| m.line_number)
+        .collect::<Vec<_>>()
+    );
+  }
+
+  #[test]
+  fn scanning_for_a_literal_cuts_lines_like_going_line_by_line() {
+    let contents = "to be\r\nnot\nauto tomato\n\nStop\nto";
+    let literal = Pattern::new("to", true);
+    let matches: Vec<Match> = find_matches(&literal, contents, false).collect();
+
+    assert_eq!(
+      vec![(1, 0), (3, 11), (5, 24), (6, 29)],
+      matches
+        .iter()
+        .map(|m| (m.line_number, m.offset))
+        .collect::<Vec<_>>()
+    );
+    assert_eq!(vec!["to be", "auto tomato", "Stop", "to"], lines(matches));
+    assert_eq!(4, count_pattern_matches(&literal, contents, false));
+    assert_eq!(
+      vec!["to be", "to"],
+      lines(find_matches(&literal.whole_word(true), contents, false).collect())
+    );
+  }
+
+  #[test]
+  fn empty_query_matches_every_line() {
+    assert_eq!(vec!["a", "b"], lines(search("", "a\nb")));
+  }
+
+  #[test]
+  fn reader_search_agrees_with_in_memory_search() {
+    let contents = "\
+Rust:\r
+safe, fast, productive.
+Pick three.
+Trust me.";
+    let pattern = Pattern::new("rust", false);
+
+    let mut streamed = Vec::new();
+    search_reader(&pattern, contents.as_bytes(), false, |m| {
+      streamed.push((m.line_number, m.range, m.line.to_string()));
+      true
+    })
+    .unwrap();
+
+    let in_memory: Vec<_> = find_matches(&pattern, contents, false)
+      .map(|m| (m.line_number, m.range, m.line.to_string()))
+      .collect();
+    assert_eq!(in_memory, streamed);
+  }
+
+  #[test]
+  fn reader_search_stops_when_asked() {
+    let pattern = Pattern::new("x", true);
+    let mut seen = 0;
+
+    search_reader(&pattern, "x\nx\nx\n".as_bytes(), false, |_| {
+      seen += 1;
+      seen < 2
+    })
+    .unwrap();
+
+    assert_eq!(2, seen);
+  }
+
+  #[test]
+  fn line_search_hands_over_every_line() {
+    let sorted = |invert_match: bool| {
+      let mut lines = Vec::new();
+      search_lines(
+        &Pattern::new("x", true),
+        "x\ny\nxx\n".as_bytes(),
+        invert_match,
+        |line| {
+          lines.push(match line {
+            Line::Selected(m) => (true, m.line_number, m.line.to_string()),
+            Line::Other(m) => (false, m.line_number, m.line.to_string()),
+          });
+          true
+        },
+      )
+      .unwrap();
+      lines
+    };
+
+    assert_eq!(
+      vec![
+        (true, 1, String::from("x")),
+        (false, 2, String::from("y")),
+        (true, 3, String::from("xx"))
+      ],
+      sorted(false)
+    );
+    assert_eq!(
+      vec![(false, 1), (true, 2), (false, 3)],
+      sorted(true)
+        .into_iter()
+        .map(|(selected, number, _)| (selected, number))
+        .collect::<Vec<_>>()
+    );
+  }
+
+  #[test]
+  fn any_query_can_match() {
+    let pattern = Pattern::any(&["duct", "Trust"], true);
+    let contents = "\
+Rust:
+safe, fast, productive.
+Pick three.
+Trust me.";
+
+    assert_eq!(
+      vec!["safe, fast, productive.", "Trust me."],
+      lines(find_matches(&pattern, contents, false).collect())
+    );
+    assert_eq!(
+      vec!["Rust:", "Pick three."],
+      lines(find_matches(&pattern, contents, true).collect())
+    );
+  }
+
+  #[test]
+  fn hits_say_which_query_matched() {
+    let pattern = Pattern::any(&["fast", "SAFE"], false);
+
+    assert_eq!(
+      vec![
+        Hit {
+          pattern: 1,
+          range: 0..4
+        },
+        Hit {
+          pattern: 0,
+          range: 6..10
+        },
+        Hit {
+          pattern: 1,
+          range: 16..20
+        },
+      ],
+      pattern.hits("Safe, fast, and safe")
+    );
+  }
+
+  #[test]
+  fn longest_query_wins_where_queries_overlap() {
+    let pattern = Pattern::any(&["pro", "product", "duct"], true);
+
+    assert_eq!(
+      vec![Hit {
+        pattern: 1,
+        range: 0..7
+      }],
+      pattern.hits("productive")
+    );
+    assert_eq!(
+      vec![(0, 3)],
+      Pattern::any(&["pro", "pro"], true).offsets("pro")
+    );
+  }
+
+  #[test]
+  fn whole_word_applies_to_every_query() {
+    let pattern = Pattern::any(&["to", "go"], true).whole_word(true);
+
+    assert_eq!(vec![(7, 9)], pattern.offsets("tomato go"));
+    assert!(!pattern.is_match("tomatoes and gophers"));
+  }
+
+  #[test]
+  fn many_queries_match_case_insensitively() {
+    let pattern = Pattern::any(&["İ", "STRASSE", "é"], false);
+
+    assert_eq!(
+      vec![(0, 2), (3, 10), (14, 16)],
+      pattern.offsets("İ strasse caf\u{e9}")
+    );
+    assert!(pattern.is_match("CAFÉ"));
+    assert!(!pattern.is_match("cafe"));
+  }
+
+  #[test]
+  fn many_queries_agree_with_trying_each_one() {
+    let queries = ["to", "body", "nobody", "e", "ar"];
+    let pattern = Pattern::any(&queries, false).whole_word(true);
+    let poem = "I'm nobody! Who are you?\nAre you nobody, too?\nThen there's a pair of us - don't tell!\nThey'd banish us, you know.";
+
+    for line in poem.lines() {
+      let mut expected: Vec<(usize, usize)> = queries
+        .iter()
+        .flat_map(|query| Pattern::new(query, false).whole_word(true).offsets(line))
+        .collect();
+      expected.sort_unstable();
+
+      assert_eq!(expected, pattern.offsets(line), "{}", line);
+    }
+  }
+
+  #[test]
+  fn reader_offsets_count_every_line_ending() {
+    let contents = "a\r\nbb\n\nneedle\r\nx needle\n";
+    let pattern = Pattern::new("needle", true);
+    let mut streamed = Vec::new();
+    search_reader(&pattern, contents.as_bytes(), false, |m| {
+      streamed.push((m.offset, m.match_offset()));
+      true
+    })
+    .unwrap();
+
+    let in_memory: Vec<_> = find_matches(&pattern, contents, false)
+      .map(|m| (m.offset, m.match_offset()))
+      .collect();
+
+    assert_eq!(vec![(7, 7), (15, 17)], streamed);
+    assert_eq!(streamed, in_memory);
+  }
+
+  #[test]
+  fn smart_case_follows_the_query() {
+    let contents = "\
+Rust:
+safe, fast, productive.
+Trust me.";
+
+    assert_eq!(
+      vec!["Rust:", "Trust me."],
+      lines(search_smart_case("rust", contents))
+    );
+    assert_eq!(vec!["Rust:"], lines(search_smart_case("Rust", contents)));
+    assert!(!is_smart_case_sensitive("straße 42"));
+    assert!(is_smart_case_sensitive("Ärger"));
+  }
+}
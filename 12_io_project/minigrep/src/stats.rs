@@ -0,0 +1,94 @@
+// Counters for --stats. Every file search produces a Stats of its own;
+// they're added up as the results come in, whichever thread they came from.
+
+use std::fmt;
+use std::ops::AddAssign;
+use std::time::Duration;
+
+#[derive(Debug, Default, Clone, Copy, PartialEq)]
+pub struct Stats {
+  // Files that were searched to the end (or until the search could stop);
+  // files that couldn't be read don't count
+  pub files_searched: usize,
+  // Files with at least one selected line
+  pub files_with_matches: usize,
+  // Lines read, which is fewer than the file has when the search stopped
+  // early because of --max-count, -q, -l or -L
+  pub lines_scanned: usize,
+  // Selected lines: the ones that match, or with -v the ones that don't
+  pub matching_lines: usize,
+}
+
+impl Stats {
+  // The stats for one searched file
+  pub fn for_file(lines_scanned: usize, matching_lines: usize) -> Stats {
+    Stats {
+      files_searched: 1,
+      files_with_matches: if matching_lines > 0 { 1 } else { 0 },
+      lines_scanned,
+      matching_lines,
+    }
+  }
+
+  // Ties the stats to how long the search took, for printing
+  pub fn summary(&self, elapsed: Duration) -> Summary<'_> {
+    Summary {
+      stats: self,
+      elapsed,
+    }
+  }
+}
+
+impl AddAssign for Stats {
+  fn add_assign(&mut self, other: Stats) {
+    self.files_searched += other.files_searched;
+    self.files_with_matches += other.files_with_matches;
+    self.lines_scanned += other.lines_scanned;
+    self.matching_lines += other.matching_lines;
+  }
+}
+
+pub struct Summary<'a> {
+  stats: &'a Stats,
+  elapsed: Duration,
+}
+
+impl fmt::Display for Summary<'_> {
+  fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+    writeln!(f, "{} files searched", self.stats.files_searched)?;
+    writeln!(
+      f,
+      "{} files contained matches",
+      self.stats.files_with_matches
+    )?;
+    writeln!(f, "{} lines scanned", self.stats.lines_scanned)?;
+    writeln!(f, "{} matching lines", self.stats.matching_lines)?;
+    writeln!(f, "{:.6} seconds", self.elapsed.as_secs_f64())
+  }
+}
+
+#[cfg(test)]
+mod tests {
+  use super::*;
+
+  #[test]
+  fn adds_up_and_prints_a_summary() {
+    let mut total = Stats::default();
+    total += Stats::for_file(10, 2);
+    total += Stats::for_file(4, 0);
+
+    assert_eq!(
+      Stats {
+        files_searched: 2,
+        files_with_matches: 1,
+        lines_scanned: 14,
+        matching_lines: 2,
+      },
+      total
+    );
+    assert_eq!(
+      "2 files searched\n1 files contained matches\n14 lines scanned\n2 matching lines\n0.250000 seconds\n",
+      total.summary(Duration::from_millis(250)).to_string()
+    );
+  }
+}
@@ -0,0 +1,386 @@
+// Expands the paths given on the command line into the list of files to
+// search. Directories are walked recursively; their entries are sorted so
+// the output order doesn't depend on the file system.
+//
+// Unless `respect_ignore` is off, the walk skips `.git` directories and
+// anything matched by a .gitignore found along the way. When `globs` isn't
+// empty, only files matching at least one of them are kept. Paths named
+// directly on the command line are always searched.
+//
+// A directory or entry that can't be read is recorded as an error and the
+// walk goes on without it, like grep -r does.
+
+use std::fs;
+use std::io;
+use std::path::{Path, PathBuf};
+
+use crate::error::MinigrepError;
+use crate::glob::Glob;
+use crate::ignore::{self, Gitignore};
+
+// What identifies a directory however it was reached: its (device, inode)
+// pair on Unix. Elsewhere the canonical path has to do.
+#[cfg(unix)]
+type DirId = (u64, u64);
+#[cfg(not(unix))]
+type DirId = PathBuf;
+
+#[cfg(unix)]
+fn dir_id(path: &Path) -> io::Result<DirId> {
+  use std::os::unix::fs::MetadataExt;
+
+  let metadata = fs::metadata(path)?;
+  Ok((metadata.dev(), metadata.ino()))
+}
+
+#[cfg(not(unix))]
+fn dir_id(path: &Path) -> io::Result<DirId> {
+  fs::canonicalize(path)
+}
+
+// The files found by collect_files
+pub struct Walk {
+  pub files: Vec<PathBuf>,
+  // Problems that didn't stop the walk, such as symlink loops
+  pub warnings: Vec<String>,
+  // Paths that couldn't be read. The walk carried on without them, but
+  // like files that can't be read, they make the search fail in the end.
+  pub errors: Vec<MinigrepError>,
+}
+
+// State shared by the whole recursive walk of one directory
+struct Walker {
+  respect_ignore: bool,
+  follow: bool,
+  // The .gitignore files of the directories being walked
+  ignores: Vec<Gitignore>,
+  // The directories being walked, outermost first. Following a symlink
+  // back to one of them would walk in circles forever.
+  ancestors: Vec<DirId>,
+  files: Vec<PathBuf>,
+  warnings: Vec<String>,
+  errors: Vec<MinigrepError>,
+}
+
+// Symlinks met during the walk are skipped unless `follow` is set, like
+// with `grep -r`; paths given on the command line are always followed.
+pub fn collect_files(paths: &[String], respect_ignore: bool, follow: bool, globs: &[Glob]) -> Walk {
+  let mut files = Vec::new();
+  let mut warnings = Vec::new();
+  let mut errors = Vec::new();
+
+  for path in paths {
+    let path = Path::new(path);
+
+    if path.is_dir() {
+      let mut walker = Walker {
+        respect_ignore,
+        follow,
+        ignores: Vec::new(),
+        ancestors: Vec::new(),
+        files: Vec::new(),
+        warnings: Vec::new(),
+        errors: Vec::new(),
+      };
+      walker.walk_dir(path);
+
+      files.extend(
+        walker
+          .files
+          .into_iter()
+          .filter(|file| globs.is_empty() || matches_any(globs, path, file)),
+      );
+      warnings.extend(walker.warnings);
+      errors.extend(walker.errors);
+    } else {
+      // Missing files are reported later, when we try to read them
+      files.push(path.to_path_buf());
+    }
+  }
+
+  Walk {
+    files,
+    warnings,
+    errors,
+  }
+}
+
+fn matches_any(globs: &[Glob], root: &Path, file: &Path) -> bool {
+  let relative = file
+    .strip_prefix(root)
+    .unwrap_or(file)
+    .components()
+    .map(|c| c.as_os_str().to_string_lossy())
+    .collect::<Vec<_>>()
+    .join("/");
+
+  globs.iter().any(|glob| glob.is_match(&relative))
+}
+
+impl Walker {
+  fn error(&mut self, path: &Path, e: io::Error) {
+    self.errors.push(MinigrepError::io(path, e));
+  }
+
+  fn walk_dir(&mut self, dir: &Path) {
+    // Everything that can fail for the directory as a whole is done before
+    // anything is pushed, so an error can just skip the directory
+    let id = if self.follow {
+      match dir_id(dir) {
+        Ok(id) if self.ancestors.contains(&id) => {
+          self
+            .warnings
+            .push(format!("{}: symlink loop, not following it", dir.display()));
+          return;
+        }
+        Ok(id) => Some(id),
+        Err(e) => return self.error(dir, e),
+      }
+    } else {
+      None
+    };
+
+    let read = match fs::read_dir(dir) {
+      Ok(read) => read,
+      Err(e) => return self.error(dir, e),
+    };
+    let loaded = if self.respect_ignore {
+      match Gitignore::load(dir) {
+        Ok(loaded) => loaded,
+        // Walking on without the rules could search files meant to be
+        // skipped, so the whole directory is left out
+        Err(e) => return self.error(&dir.join(".gitignore"), e),
+      }
+    } else {
+      None
+    };
+
+    let mut entries = Vec::new();
+    for entry in read {
+      match entry {
+        Ok(entry) => entries.push(entry.path()),
+        Err(e) => self.error(dir, e),
+      }
+    }
+    entries.sort();
+
+    let pushed = loaded.is_some();
+    self.ignores.extend(loaded);
+    self.ancestors.extend(id);
+
+    for path in entries {
+      let metadata = match fs::symlink_metadata(&path) {
+        Ok(metadata) => metadata,
+        Err(e) => {
+          self.error(&path, e);
+          continue;
+        }
+      };
+      let is_dir = if metadata.file_type().is_symlink() {
+        if !self.follow {
+          continue;
+        }
+        match fs::metadata(&path) {
+          Ok(target) => target.is_dir(),
+          Err(e) => {
+            self
+              .warnings
+              .push(format!("{}: broken symlink: {}", path.display(), e));
+            continue;
+          }
+        }
+      } else {
+        path.is_dir()
+      };
+
+      if self.respect_ignore {
+        if is_dir && path.file_name() == Some(".git".as_ref()) {
+          continue;
+        }
+        if ignore::is_ignored(&self.ignores, &path, is_dir) {
+          continue;
+        }
+      }
+
+      if is_dir {
+        self.walk_dir(&path);
+      } else {
+        self.files.push(path);
+      }
+    }
+
+    if pushed {
+      self.ignores.pop();
+    }
+    if self.follow {
+      self.ancestors.pop();
+    }
+  }
+}
+
+#[cfg(test)]
+mod tests {
+  use super::*;
+  use std::env;
+
+  #[test]
+  fn walks_directories_in_sorted_order() {
+    let root = env::temp_dir().join(format!("minigrep-walk-{}", std::process::id()));
+    fs::create_dir_all(root.join("b")).unwrap();
+    fs::write(root.join("c.txt"), "").unwrap();
+    fs::write(root.join("a.txt"), "").unwrap();
+    fs::write(root.join("b").join("inner.txt"), "").unwrap();
+
+    let files = collect_files(&[root.to_str().unwrap().to_string()], true, false, &[]).files;
+
+    assert_eq!(
+      vec![
+        root.join("a.txt"),
+        root.join("b").join("inner.txt"),
+        root.join("c.txt")
+      ],
+      files
+    );
+
+    fs::remove_dir_all(&root).unwrap();
+  }
+
+  #[test]
+  fn keeps_plain_files_as_given() {
+    let files = collect_files(&["missing.txt".to_string()], true, false, &[]).files;
+
+    assert_eq!(vec![PathBuf::from("missing.txt")], files);
+  }
+
+  #[test]
+  fn skips_gitignored_paths_unless_told_not_to() {
+    let root = env::temp_dir().join(format!("minigrep-ignore-{}", std::process::id()));
+    fs::create_dir_all(root.join("target")).unwrap();
+    fs::create_dir_all(root.join("src").join(".git")).unwrap();
+    fs::write(root.join(".gitignore"), "target/\n*.log\n").unwrap();
+    fs::write(root.join("src").join(".gitignore"), "!keep.log\n").unwrap();
+    fs::write(root.join("target").join("out.txt"), "").unwrap();
+    fs::write(root.join("debug.log"), "").unwrap();
+    fs::write(root.join("src").join("keep.log"), "").unwrap();
+    fs::write(root.join("src").join("main.rs"), "").unwrap();
+    fs::write(root.join("src").join(".git").join("HEAD"), "").unwrap();
+    let paths = [root.to_str().unwrap().to_string()];
+
+    assert_eq!(
+      vec![
+        root.join(".gitignore"),
+        root.join("src").join(".gitignore"),
+        root.join("src").join("keep.log"),
+        root.join("src").join("main.rs"),
+      ],
+      collect_files(&paths, true, false, &[]).files
+    );
+    assert_eq!(7, collect_files(&paths, false, false, &[]).files.len());
+
+    fs::remove_dir_all(&root).unwrap();
+  }
+
+  #[test]
+  fn globs_filter_walked_files_only() {
+    let root = env::temp_dir().join(format!("minigrep-glob-{}", std::process::id()));
+    fs::create_dir_all(root.join("src")).unwrap();
+    fs::write(root.join("README.md"), "").unwrap();
+    fs::write(root.join("src").join("lib.rs"), "").unwrap();
+    fs::write(root.join("src").join("notes.txt"), "").unwrap();
+    let paths = [
+      "explicit.txt".to_string(),
+      root.to_str().unwrap().to_string(),
+    ];
+
+    assert_eq!(
+      vec![
+        PathBuf::from("explicit.txt"),
+        root.join("src").join("lib.rs")
+      ],
+      collect_files(&paths, true, false, &[Glob::new("*.rs")]).files
+    );
+    assert_eq!(
+      vec![
+        PathBuf::from("explicit.txt"),
+        root.join("README.md"),
+        root.join("src").join("lib.rs"),
+      ],
+      collect_files(&paths, true, false, &[Glob::new("*.rs"), Glob::new("*.md")]).files
+    );
+    assert_eq!(
+      vec![
+        PathBuf::from("explicit.txt"),
+        root.join("src").join("notes.txt")
+      ],
+      collect_files(&paths, true, false, &[Glob::new("src/*.txt")]).files
+    );
+
+    fs::remove_dir_all(&root).unwrap();
+  }
+
+  #[test]
+  fn keeps_walking_past_a_directory_it_cannot_read() {
+    let root = env::temp_dir().join(format!("minigrep-unreadable-{}", std::process::id()));
+    fs::create_dir_all(root.join("a")).unwrap();
+    fs::create_dir_all(root.join("b")).unwrap();
+    fs::write(root.join("a").join("file.txt"), "").unwrap();
+    fs::write(root.join("b").join("file.txt"), "").unwrap();
+    fs::write(root.join("c.txt"), "").unwrap();
+    // A .gitignore that isn't UTF-8 can't be read, so a/ can't be walked
+    fs::write(root.join("a").join(".gitignore"), b"\xff\xfe*.txt\n").unwrap();
+
+    let walk = collect_files(&[root.to_str().unwrap().to_string()], true, false, &[]);
+
+    assert_eq!(
+      vec![root.join("b").join("file.txt"), root.join("c.txt")],
+      walk.files
+    );
+    assert_eq!(1, walk.errors.len());
+    assert!(
+      walk.errors[0]
+        .to_string()
+        .starts_with(&root.join("a").join(".gitignore").display().to_string()),
+      "{}",
+      walk.errors[0]
+    );
+
+    fs::remove_dir_all(&root).unwrap();
+  }
+
+  #[cfg(unix)]
+  #[test]
+  fn follows_symlinks_only_when_asked_and_stops_at_loops() {
+    use std::os::unix::fs::symlink;
+
+    let root = env::temp_dir().join(format!("minigrep-follow-{}", std::process::id()));
+    let elsewhere = env::temp_dir().join(format!("minigrep-elsewhere-{}", std::process::id()));
+    fs::create_dir_all(root.join("a")).unwrap();
+    fs::create_dir_all(&elsewhere).unwrap();
+    fs::write(root.join("a").join("file.txt"), "").unwrap();
+    fs::write(elsewhere.join("other.txt"), "").unwrap();
+    // a/up points back at the root: following it would never end
+    symlink(&root, root.join("a").join("up")).unwrap();
+    symlink(&elsewhere, root.join("linked")).unwrap();
+    symlink(root.join("missing"), root.join("broken")).unwrap();
+    let paths = [root.to_str().unwrap().to_string()];
+
+    let walk = collect_files(&paths, true, false, &[]);
+    assert_eq!(vec![root.join("a").join("file.txt")], walk.files);
+    assert!(walk.warnings.is_empty());
+
+    let walk = collect_files(&paths, true, true, &[]);
+    assert_eq!(
+      vec![
+        root.join("a").join("file.txt"),
+        root.join("linked").join("other.txt")
+      ],
+      walk.files
+    );
+    assert_eq!(2, walk.warnings.len());
+    assert!(walk.warnings[0].ends_with("symlink loop, not following it"));
+    assert!(walk.warnings[1].contains("broken symlink"));
+
+    fs::remove_dir_all(&root).unwrap();
+    fs::remove_dir_all(&elsewhere).unwrap();
+  }
+}
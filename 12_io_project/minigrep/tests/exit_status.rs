@@ -0,0 +1,110 @@
+// Runs the minigrep binary to check its exit status, which scripts rely on
+// the same way they rely on grep's.
+
+use std::env;
+use std::fs;
+use std::process::{Command, Output};
+
+fn minigrep(args: &[&str]) -> Output {
+  Command::new(env!("CARGO_BIN_EXE_minigrep"))
+    .current_dir(env!("CARGO_MANIFEST_DIR"))
+    // So a developer's own ~/.minigreprc can't change what's tested
+    .arg("--no-config")
+    .args(args)
+    .output()
+    .unwrap()
+}
+
+#[test]
+fn exits_zero_when_a_line_matches() {
+  let output = minigrep(&["nobody", "poem.txt"]);
+
+  assert_eq!(Some(0), output.status.code());
+  assert!(!output.stdout.is_empty());
+}
+
+#[test]
+fn exits_one_when_nothing_matches() {
+  let output = minigrep(&["frog-free", "poem.txt"]);
+
+  assert_eq!(Some(1), output.status.code());
+  assert!(output.stdout.is_empty());
+}
+
+#[test]
+fn each_kind_of_error_has_its_own_exit_code() {
+  assert_eq!(
+    Some(2),
+    minigrep(&["--bogus", "to", "poem.txt"]).status.code()
+  );
+  assert_eq!(Some(3), minigrep(&["-e", "a\nb", "poem.txt"]).status.code());
+  assert_eq!(Some(4), minigrep(&["to", "missing.txt"]).status.code());
+  assert_eq!(
+    Some(4),
+    minigrep(&["to", "poem.txt", "missing.txt"]).status.code()
+  );
+}
+
+#[test]
+fn read_errors_name_the_file() {
+  let output = minigrep(&["to", "missing.txt"]);
+  let stderr = String::from_utf8(output.stderr).unwrap();
+
+  assert!(
+    stderr.starts_with("Application error: missing.txt: "),
+    "{}",
+    stderr
+  );
+}
+
+#[test]
+fn a_file_beside_an_unreadable_directory_keeps_its_name() {
+  let dir = env::temp_dir().join(format!("minigrep-unreadable-arg-{}", std::process::id()));
+  fs::create_dir_all(&dir).unwrap();
+  // A .gitignore that isn't UTF-8 can't be read, so the walk leaves the
+  // directory out
+  fs::write(dir.join(".gitignore"), b"\xff\xfe*.txt\n").unwrap();
+
+  let output = minigrep(&["nobody", "poem.txt", dir.to_str().unwrap()]);
+  fs::remove_dir_all(&dir).unwrap();
+
+  assert_eq!(Some(4), output.status.code());
+  let stdout = String::from_utf8(output.stdout).unwrap();
+  assert!(!stdout.is_empty());
+  assert!(
+    stdout.lines().all(|line| line.starts_with("poem.txt:")),
+    "{}",
+    stdout
+  );
+}
+
+#[test]
+fn quiet_prints_nothing() {
+  let output = minigrep(&["-q", "nobody", "poem.txt"]);
+
+  assert_eq!(Some(0), output.status.code());
+  assert!(output.stdout.is_empty());
+  assert_eq!(
+    Some(1),
+    minigrep(&["-qc", "frog-free", "poem.txt"]).status.code()
+  );
+}
+
+#[test]
+fn quiet_match_wins_over_errors_in_other_files() {
+  let output = minigrep(&["-q", "nobody", "missing.txt", "poem.txt"]);
+
+  assert_eq!(Some(0), output.status.code());
+  assert!(!output.stderr.is_empty());
+  assert_eq!(
+    Some(4),
+    minigrep(&["-q", "frog-free", "missing.txt", "poem.txt"])
+      .status
+      .code()
+  );
+}
+
+#[test]
+fn help_is_not_an_error() {
+  assert_eq!(Some(0), minigrep(&["--help"]).status.code());
+}
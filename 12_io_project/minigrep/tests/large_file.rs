@@ -0,0 +1,110 @@
+// Integration tests for searching inputs too big to comfortably hold in
+// memory. The inputs are generated on the fly by a reader, so the only way
+// these tests can pass is if minigrep really streams line by line.
+
+use std::env;
+use std::fs::{self, File};
+use std::io::{self, BufReader, BufWriter, Read, Write};
+use std::process::Command;
+
+use minigrep::{search_reader, Pattern};
+
+// Produces `lines` numbered lines (or never stops, with None) without ever
+// building the whole text. Every tenth line contains "needle".
+struct GeneratedLines {
+  next: u64,
+  lines: Option<u64>,
+  pending: Vec<u8>,
+}
+
+impl GeneratedLines {
+  fn new(lines: Option<u64>) -> GeneratedLines {
+    GeneratedLines {
+      next: 0,
+      lines,
+      pending: Vec::new(),
+    }
+  }
+}
+
+impl Read for GeneratedLines {
+  fn read(&mut self, buf: &mut [u8]) -> io::Result<usize> {
+    // Top up the pending bytes a batch of lines at a time
+    while self.pending.len() < buf.len() && self.lines != Some(self.next) {
+      let word = if self.next.is_multiple_of(10) {
+        "needle"
+      } else {
+        "hay"
+      };
+      writeln!(self.pending, "line {} with some {} in it", self.next, word)?;
+      self.next += 1;
+    }
+
+    let n = buf.len().min(self.pending.len());
+    buf[..n].copy_from_slice(&self.pending[..n]);
+    self.pending.drain(..n);
+    Ok(n)
+  }
+}
+
+#[test]
+fn stops_reading_an_endless_input() {
+  let pattern = Pattern::new("needle", true);
+  let mut found = Vec::new();
+
+  search_reader(
+    &pattern,
+    BufReader::new(GeneratedLines::new(None)),
+    false,
+    |m| {
+      found.push(m.line_number);
+      found.len() < 3
+    },
+  )
+  .unwrap();
+
+  assert_eq!(vec![1, 11, 21], found);
+}
+
+#[test]
+fn streams_a_quarter_million_lines() {
+  let pattern = Pattern::new("NEEDLE", false);
+  let mut count = 0;
+  let mut last = 0;
+
+  let lines = search_reader(
+    &pattern,
+    BufReader::new(GeneratedLines::new(Some(250_000))),
+    false,
+    |m| {
+      count += 1;
+      last = m.line_number;
+      true
+    },
+  )
+  .unwrap();
+
+  assert_eq!(250_000, lines);
+  assert_eq!(25_000, count);
+  assert_eq!(249_991, last);
+}
+
+#[test]
+fn binary_counts_matches_in_a_large_file() {
+  let path = env::temp_dir().join(format!("minigrep-large-{}.txt", std::process::id()));
+  {
+    let mut file = BufWriter::new(File::create(&path).unwrap());
+    io::copy(&mut GeneratedLines::new(Some(500_000)), &mut file).unwrap();
+    file.flush().unwrap();
+  }
+
+  let output = Command::new(env!("CARGO_BIN_EXE_minigrep"))
+    .args(["--no-config", "-c", "needle"])
+    .arg(&path)
+    .output()
+    .unwrap();
+  fs::remove_file(&path).unwrap();
+
+  assert!(output.status.success());
+  assert_eq!("50000\n", String::from_utf8(output.stdout).unwrap());
+}
@@ -0,0 +1,656 @@
+use std::collections::HashMap;
+use std::fmt::Display;
+use std::fs;
+use std::hash::Hash;
+use std::io::{self, ErrorKind};
+use std::path::{Path, PathBuf};
+use std::str::FromStr;
+use std::time::{Duration, Instant, SystemTime, UNIX_EPOCH};
+
+/// Where a Cacher gets the time from, to tell how old its values are.
+/// Anything but the system clock is mostly for tests, which can move the
+/// time along without waiting for it.
+pub trait Clock {
+    fn now(&self) -> Instant;
+}
+
+/// What a Cacher over a closure that can fail does with the errors.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum ErrorPolicy {
+    /// Keep them like any other value
+    Cache,
+    /// Keep them, but only for this long, whatever the TTL for the rest
+    CacheFor(Duration),
+    /// Never keep them: every time the value's asked for, the closure is
+    /// tried again until it works
+    Retry,
+}
+
+/// The real time, from `Instant::now`.
+#[derive(Debug, Clone, Copy, Default)]
+pub struct SystemClock;
+
+impl Clock for SystemClock {
+    fn now(&self) -> Instant {
+        Instant::now()
+    }
+}
+
+// A borrowed clock is as good as the clock, so a test can keep hold of its
+// clock and still hand it to a Cacher
+impl<C: Clock + ?Sized> Clock for &C {
+    fn now(&self) -> Instant {
+        (**self).now()
+    }
+}
+
+/// Memoizes a closure: it's only called the first time it's asked for the
+/// value for an argument, and after that the value it gave is handed back.
+///
+/// The closure can be `FnMut`, and change what it's captured. It's called
+/// only when a value is missing, has outlived the TTL, or is refreshed,
+/// so whatever it does happens that often and no more. A value that's
+/// handed back is the one from the call that made it, even if the closure
+/// would give a different one now.
+///
+/// The argument is borrowed, like `HashMap::get` takes its key, so a
+/// closure over a `&str` or a `&[u8]` can be looked up without making an
+/// owned String or Vec first. Only the first call with an argument pays
+/// for an owned copy of it, made by `ToOwned`, to keep as the key.
+pub struct Cacher<T, A, V, C = SystemClock>
+where
+    T: FnMut(&A) -> V,
+    // The Fn traits are provided by the standard library. All closures
+    // implement at least one of the traits: Fn, FnMut, or FnOnce. FnMut
+    // takes the most closures that can be called more than once, and
+    // value() takes &mut self anyway.
+    A: ?Sized + ToOwned,
+{
+    calculation: T,
+    // One result for each argument the closure has been called with, since
+    // a different argument can give a different result
+    values: HashMap<A::Owned, Entry<V>>,
+    // How long a value is good for, if it isn't good forever
+    ttl: Option<Duration>,
+    // How long a value is_error picks out is good for instead, if it's
+    // different. Only a closure that returns a Result has errors.
+    error_ttl: Option<Duration>,
+    is_error: fn(&V) -> bool,
+    clock: C,
+}
+
+// What is_error is until there's an error policy
+fn never_an_error<V>(_: &V) -> bool {
+    false
+}
+
+// A value the closure gave, and when
+struct Entry<V> {
+    value: V,
+    calculated: Instant,
+}
+
+impl<V> Entry<V> {
+    fn is_fresh(&self, now: Instant, ttl: Option<Duration>) -> bool {
+        match ttl {
+            Some(ttl) => now.duration_since(self.calculated) < ttl,
+            None => true,
+        }
+    }
+}
+
+impl<T, A, V> Cacher<T, A, V>
+where
+    T: FnMut(&A) -> V,
+    A: ?Sized + ToOwned + Hash + Eq,
+    A::Owned: Hash + Eq,
+{
+    /// A Cacher that keeps every value it's calculated for good.
+    pub fn new(calculation: T) -> Cacher<T, A, V> {
+        Cacher {
+            calculation,
+            values: HashMap::new(),
+            ttl: None,
+            error_ttl: None,
+            is_error: never_an_error,
+            clock: SystemClock,
+        }
+    }
+}
+
+impl<T, A, R, E, C> Cacher<T, A, Result<R, E>, C>
+where
+    T: FnMut(&A) -> Result<R, E>,
+    A: ?Sized + ToOwned + Hash + Eq,
+    A::Owned: Hash + Eq,
+    C: Clock,
+{
+    /// Decides what happens to the errors the closure returns. Without a
+    /// policy they're cached like everything else.
+    pub fn with_error_policy(mut self, policy: ErrorPolicy) -> Cacher<T, A, Result<R, E>, C> {
+        self.error_ttl = match policy {
+            ErrorPolicy::Cache => None,
+            ErrorPolicy::CacheFor(ttl) => Some(ttl),
+            // Nothing is younger than no time at all
+            ErrorPolicy::Retry => Some(Duration::ZERO),
+        };
+        self.is_error = Result::is_err;
+        self
+    }
+}
+
+impl<T, A, V, C> Cacher<T, A, V, C>
+where
+    T: FnMut(&A) -> V,
+    // A::Owned is what ToOwned makes of A, and it can be borrowed as an A
+    // again, which is what lets the map be searched with a &A
+    A: ?Sized + ToOwned + Hash + Eq,
+    A::Owned: Hash + Eq,
+    C: Clock,
+{
+    /// Keeps each value for `ttl` only. The first time it's asked for
+    /// after that, the closure is called again for a new one.
+    pub fn with_ttl(mut self, ttl: Duration) -> Cacher<T, A, V, C> {
+        self.ttl = Some(ttl);
+        self
+    }
+
+    /// Tells the time by `clock` instead of the system clock.
+    pub fn with_clock<D: Clock>(self, clock: D) -> Cacher<T, A, V, D> {
+        // Times from the old clock mean nothing to the new one, so any
+        // values so far are dropped
+        Cacher {
+            calculation: self.calculation,
+            values: HashMap::new(),
+            ttl: self.ttl,
+            error_ttl: self.error_ttl,
+            is_error: self.is_error,
+            clock,
+        }
+    }
+
+    /// The value for `arg`, calling the closure for it only if there
+    /// isn't one yet, or the one there is has outlived the TTL.
+    pub fn value(&mut self, arg: &A) -> &V {
+        // Checking first rather than matching on peek() keeps the borrow
+        // from the lookup out of the way of the insert
+        if self.peek(arg).is_none() {
+            let now = self.clock.now();
+            self.calculate(arg, now);
+        }
+        &self.values[arg].value
+    }
+
+    /// The value for `arg` if there's one that's still good, without ever
+    /// calling the closure.
+    pub fn peek(&self, arg: &A) -> Option<&V> {
+        let now = self.clock.now();
+        self.values
+            .get(arg)
+            .filter(|entry| entry.is_fresh(now, self.ttl_for(&entry.value)))
+            .map(|entry| &entry.value)
+    }
+
+    /// Calculates the values for all of `args` now, so nothing has to wait
+    /// for them later. Ones there are good values for already are skipped.
+    pub fn precompute<'a, I>(&mut self, args: I)
+    where
+        I: IntoIterator<Item = &'a A>,
+        A: 'a,
+    {
+        for arg in args {
+            self.value(arg);
+        }
+    }
+
+    /// Calls the closure for `arg` again and keeps the new value, whether
+    /// or not the old one was still good.
+    pub fn refresh(&mut self, arg: &A) -> &V {
+        let now = self.clock.now();
+        self.calculate(arg, now);
+        &self.values[arg].value
+    }
+
+    /// Forgets the value for `arg`, so the next time it's asked for the
+    /// closure is called again. The value that was forgotten, if there
+    /// was one.
+    pub fn invalidate(&mut self, arg: &A) -> Option<V> {
+        self.values.remove(arg).map(|entry| entry.value)
+    }
+
+    /// Forgets every value.
+    pub fn clear(&mut self) {
+        self.values.clear();
+    }
+
+    // How long `value` is good for
+    fn ttl_for(&self, value: &V) -> Option<Duration> {
+        match self.error_ttl {
+            Some(ttl) if (self.is_error)(value) => Some(ttl),
+            _ => self.ttl,
+        }
+    }
+
+    fn calculate(&mut self, arg: &A, now: Instant) {
+        let value = (self.calculation)(arg);
+        let entry = Entry {
+            value,
+            calculated: now,
+        };
+        self.values.insert(arg.to_owned(), entry);
+    }
+}
+
+/// The file the values for the cache called `name` are kept in between
+/// runs, in the system's temporary directory.
+pub fn cache_file(name: &str) -> PathBuf {
+    std::env::temp_dir().join(format!("{}.cache", name))
+}
+
+impl<T, A, V, C> Cacher<T, A, V, C>
+where
+    T: FnMut(&A) -> V,
+    A: ?Sized + ToOwned + Hash + Eq,
+    // Written out with Display and read back with FromStr
+    A::Owned: Hash + Eq + Display + FromStr,
+    V: Display + FromStr,
+    C: Clock,
+{
+    /// Adds the values saved at `path`, so the closure doesn't have to be
+    /// called for them again. They're as old as they were when they were
+    /// saved plus the time since, as far as the TTL is concerned. A missing
+    /// file has no values yet. A file that doesn't make sense is an
+    /// `InvalidData` error, naming the first line that doesn't.
+    pub fn load(&mut self, path: impl AsRef<Path>) -> io::Result<()> {
+        let text = match fs::read_to_string(path) {
+            Ok(text) => text,
+            Err(e) if e.kind() == ErrorKind::NotFound => return Ok(()),
+            Err(e) => return Err(e),
+        };
+
+        let now = self.clock.now();
+        for (number, line) in text.lines().enumerate() {
+            let (saved, key, value) = parse_line(line).ok_or_else(|| {
+                io::Error::new(
+                    ErrorKind::InvalidData,
+                    format!("line {} isn't a cached value: `{}`", number + 1, line),
+                )
+            })?;
+            let age = SystemTime::now()
+                .duration_since(UNIX_EPOCH + Duration::from_secs(saved))
+                .unwrap_or(Duration::ZERO);
+            // A value older than the clock can count back is past any TTL,
+            // and without one it's no loss to calculate it again
+            if let Some(calculated) = now.checked_sub(age) {
+                self.values.insert(key, Entry { value, calculated });
+            }
+        }
+        Ok(())
+    }
+
+    /// Writes every value to `path`, one per line as `time<TAB>arg<TAB>
+    /// value`, where `time` is when it was calculated, in seconds since
+    /// the Unix epoch. The file is written next to the old one first and
+    /// then moved over it, so a crash halfway through can't leave half a
+    /// file behind. An argument or value with a tab or a line break in it
+    /// can't be written, and is an `InvalidInput` error.
+    pub fn save(&self, path: impl AsRef<Path>) -> io::Result<()> {
+        let path = path.as_ref();
+        let (now, system_now) = (self.clock.now(), SystemTime::now());
+
+        let mut text = String::new();
+        for (key, entry) in &self.values {
+            let (key, value) = (key.to_string(), entry.value.to_string());
+            if [&key, &value]
+                .iter()
+                .any(|field| field.contains(&['\t', '\n', '\r'][..]))
+            {
+                return Err(io::Error::new(
+                    ErrorKind::InvalidInput,
+                    format!("can't save `{}`: `{}` on one line", key, value),
+                ));
+            }
+            let saved = system_now
+                .checked_sub(now.duration_since(entry.calculated))
+                .and_then(|calculated| calculated.duration_since(UNIX_EPOCH).ok())
+                .map_or(0, |since| since.as_secs());
+            text += &format!("{}\t{}\t{}\n", saved, key, value);
+        }
+
+        let partial = path.with_extension("partial");
+        fs::write(&partial, text)?;
+        fs::rename(partial, path)
+    }
+}
+
+fn parse_line<K: FromStr, V: FromStr>(line: &str) -> Option<(u64, K, V)> {
+    let mut fields = line.split('\t');
+    let saved = fields.next()?.parse().ok()?;
+    let key = fields.next()?.parse().ok()?;
+    let value = fields.next()?.parse().ok()?;
+    if fields.next().is_some() {
+        return None;
+    }
+    Some((saved, key, value))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::cell::Cell;
+
+    // A clock that only moves when it's told to
+    struct TestClock {
+        start: Instant,
+        elapsed: Cell<Duration>,
+    }
+
+    impl TestClock {
+        fn new() -> TestClock {
+            TestClock {
+                start: Instant::now(),
+                elapsed: Cell::new(Duration::ZERO),
+            }
+        }
+
+        fn advance(&self, secs: u64) {
+            self.elapsed
+                .set(self.elapsed.get() + Duration::from_secs(secs));
+        }
+    }
+
+    impl Clock for TestClock {
+        fn now(&self) -> Instant {
+            self.start + self.elapsed.get()
+        }
+    }
+
+    #[test]
+    fn call_with_different_values() {
+        let mut c = Cacher::new(|&a: &u32| a);
+
+        assert_eq!(1, *c.value(&1));
+        assert_eq!(2, *c.value(&2));
+    }
+
+    #[test]
+    fn calculates_once_per_argument() {
+        let calls = Cell::new(0);
+        let mut c = Cacher::new(|&a: &u32| {
+            calls.set(calls.get() + 1);
+            a * 2
+        });
+
+        assert_eq!(2, *c.value(&1));
+        assert_eq!(4, *c.value(&2));
+        assert_eq!(2, *c.value(&1));
+        assert_eq!(4, *c.value(&2));
+        assert_eq!(2, calls.get());
+    }
+
+    #[test]
+    fn looks_up_borrowed_arguments() {
+        let calls = Cell::new(0);
+        let mut c = Cacher::new(|s: &str| {
+            calls.set(calls.get() + 1);
+            s.len()
+        });
+
+        let owned = String::from("hello");
+        assert_eq!(5, *c.value("hello"));
+        assert_eq!(5, *c.value(&owned));
+        assert_eq!(3, *c.value("hey"));
+        assert_eq!(2, calls.get());
+
+        let mut sums = Cacher::new(|bytes: &[u8]| bytes.iter().map(|&b| u32::from(b)).sum::<u32>());
+        assert_eq!(6, *sums.value(&[1, 2, 3][..]));
+        assert_eq!(0, *sums.value(&[][..]));
+    }
+
+    #[test]
+    fn memoizes_closures_that_change_their_state() {
+        let mut calls = 0;
+        let mut c = Cacher::new(|&a: &u32| {
+            calls += 1;
+            a * calls
+        });
+
+        assert_eq!(1, *c.value(&1));
+        assert_eq!(4, *c.value(&2));
+        assert_eq!(1, *c.value(&1));
+        assert_eq!(3, *c.refresh(&1));
+        drop(c);
+        assert_eq!(3, calls);
+    }
+
+    #[test]
+    fn peeks_without_calculating() {
+        let clock = TestClock::new();
+        let calls = Cell::new(0);
+        let mut c = Cacher::new(|&a: &u32| {
+            calls.set(calls.get() + 1);
+            a
+        })
+        .with_ttl(Duration::from_secs(10))
+        .with_clock(&clock);
+
+        assert_eq!(None, c.peek(&1));
+        assert_eq!(0, calls.get());
+        c.value(&1);
+        assert_eq!(Some(&1), c.peek(&1));
+        // A value past its TTL is as good as none
+        clock.advance(10);
+        assert_eq!(None, c.peek(&1));
+        assert_eq!(1, calls.get());
+    }
+
+    #[test]
+    fn precomputes_values_up_front() {
+        let calls = Cell::new(0);
+        let mut c = Cacher::new(|s: &str| {
+            calls.set(calls.get() + 1);
+            s.len()
+        });
+
+        c.value("one");
+        c.precompute(vec!["one", "two", "three"]);
+        assert_eq!(3, calls.get());
+        assert_eq!(Some(&5), c.peek("three"));
+    }
+
+    #[test]
+    fn forgets_values_it_is_told_to() {
+        let calls = Cell::new(0);
+        let mut c = Cacher::new(|s: &str| {
+            calls.set(calls.get() + 1);
+            s.len()
+        });
+
+        c.value("one");
+        c.value("three");
+        assert_eq!(Some(3), c.invalidate("one"));
+        assert_eq!(None, c.invalidate("one"));
+        c.value("one");
+        c.value("three");
+        assert_eq!(3, calls.get());
+
+        c.clear();
+        c.value("one");
+        c.value("three");
+        assert_eq!(5, calls.get());
+    }
+
+    #[test]
+    fn refreshes_a_value_that_is_still_good() {
+        let calls = Cell::new(0);
+        let mut c = Cacher::new(|&a: &u32| {
+            calls.set(calls.get() + 1);
+            a + calls.get()
+        });
+
+        assert_eq!(2, *c.value(&1));
+        assert_eq!(3, *c.refresh(&1));
+        assert_eq!(3, *c.value(&1));
+        assert_eq!(2, calls.get());
+    }
+
+    // A closure that fails the first `failures` times it's called, and
+    // counts the calls in `calls`
+    fn flaky(calls: &Cell<u32>, failures: u32) -> impl FnMut(&u32) -> Result<u32, String> + '_ {
+        move |&a| {
+            calls.set(calls.get() + 1);
+            if calls.get() <= failures {
+                Err(format!("failure {}", calls.get()))
+            } else {
+                Ok(a)
+            }
+        }
+    }
+
+    #[test]
+    fn caches_errors_by_default() {
+        let calls = Cell::new(0);
+        let mut c = Cacher::new(flaky(&calls, 1));
+
+        assert_eq!(Err(String::from("failure 1")), *c.value(&7));
+        assert_eq!(Err(String::from("failure 1")), *c.value(&7));
+        assert_eq!(1, calls.get());
+    }
+
+    #[test]
+    fn retries_errors_until_it_works() {
+        let calls = Cell::new(0);
+        let mut c = Cacher::new(flaky(&calls, 2)).with_error_policy(ErrorPolicy::Retry);
+
+        assert_eq!(Err(String::from("failure 1")), *c.value(&7));
+        assert_eq!(Err(String::from("failure 2")), *c.value(&7));
+        assert_eq!(Ok(7), *c.value(&7));
+        assert_eq!(Ok(7), *c.value(&7));
+        assert_eq!(3, calls.get());
+    }
+
+    #[test]
+    fn keeps_errors_for_their_own_ttl() {
+        let clock = TestClock::new();
+        let calls = Cell::new(0);
+        let mut c = Cacher::new(flaky(&calls, 1))
+            .with_ttl(Duration::from_secs(60))
+            .with_error_policy(ErrorPolicy::CacheFor(Duration::from_secs(5)))
+            .with_clock(&clock);
+
+        assert!(c.value(&7).is_err());
+        clock.advance(4);
+        assert!(c.value(&7).is_err());
+        clock.advance(1);
+        assert_eq!(Ok(7), *c.value(&7));
+        // The value that worked is kept for the usual TTL
+        clock.advance(59);
+        assert_eq!(Ok(7), *c.value(&7));
+        assert_eq!(2, calls.get());
+    }
+
+    fn temp_file(name: &str) -> PathBuf {
+        std::env::temp_dir().join(format!("closures-{}-{}.cache", name, std::process::id()))
+    }
+
+    #[test]
+    fn saves_and_loads_values() {
+        let path = temp_file("saved");
+        let calls = Cell::new(0);
+        let length = |s: &str| {
+            calls.set(calls.get() + 1);
+            s.len()
+        };
+
+        let mut c = Cacher::new(length);
+        c.load(&path).unwrap();
+        c.value("hello");
+        c.value("hey");
+        c.save(&path).unwrap();
+
+        let mut c = Cacher::new(length);
+        c.load(&path).unwrap();
+        assert_eq!(5, *c.value("hello"));
+        assert_eq!(3, *c.value("hey"));
+        assert_eq!(2, calls.get());
+        fs::remove_file(path).unwrap();
+    }
+
+    #[test]
+    fn loaded_values_keep_their_age() {
+        let path = temp_file("aged");
+        let clock = TestClock::new();
+        let calls = Cell::new(0);
+        let counted = |&a: &u32| {
+            calls.set(calls.get() + 1);
+            a
+        };
+
+        let mut c = Cacher::new(counted).with_clock(&clock);
+        c.value(&1);
+        clock.advance(100);
+        c.value(&2);
+        c.save(&path).unwrap();
+
+        let mut c = Cacher::new(counted).with_ttl(Duration::from_secs(50));
+        c.load(&path).unwrap();
+        c.value(&1);
+        c.value(&2);
+        assert_eq!(3, calls.get());
+        fs::remove_file(path).unwrap();
+    }
+
+    #[test]
+    fn refuses_files_it_cant_make_sense_of() {
+        let path = temp_file("corrupt");
+        fs::write(&path, "0\t1\t1\n0\tone\t1\n").unwrap();
+
+        let mut c = Cacher::new(|&a: &u32| a);
+        let err = c.load(&path).unwrap_err();
+        assert_eq!(ErrorKind::InvalidData, err.kind());
+        assert!(err.to_string().contains("line 2"));
+
+        let mut c = Cacher::new(|s: &str| s.to_string());
+        c.value("two\tfields");
+        assert_eq!(ErrorKind::InvalidInput, c.save(&path).unwrap_err().kind());
+        fs::remove_file(path).unwrap();
+    }
+
+    #[test]
+    fn calculates_again_once_the_ttl_is_up() {
+        let clock = TestClock::new();
+        let calls = Cell::new(0);
+        let mut c = Cacher::new(|&a: &u32| {
+            calls.set(calls.get() + 1);
+            a + calls.get()
+        })
+        .with_ttl(Duration::from_secs(10))
+        .with_clock(&clock);
+
+        assert_eq!(2, *c.value(&1));
+        clock.advance(9);
+        assert_eq!(2, *c.value(&1));
+        clock.advance(1);
+        assert_eq!(3, *c.value(&1));
+        // The new value has a TTL of its own
+        clock.advance(9);
+        assert_eq!(3, *c.value(&1));
+        assert_eq!(2, calls.get());
+    }
+
+    #[test]
+    fn keeps_values_for_good_without_a_ttl() {
+        let clock = TestClock::new();
+        let calls = Cell::new(0);
+        let mut c = Cacher::new(|&a: &u32| {
+            calls.set(calls.get() + 1);
+            a
+        })
+        .with_clock(&clock);
+
+        c.value(&1);
+        clock.advance(1_000_000);
+        c.value(&1);
+        assert_eq!(1, calls.get());
+    }
+}
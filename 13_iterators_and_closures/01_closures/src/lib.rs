@@ -0,0 +1,5 @@
+mod cacher;
+mod shared_cacher;
+
+pub use cacher::{cache_file, Cacher, Clock, ErrorPolicy, SystemClock};
+pub use shared_cacher::SharedCacher;
@@ -1,6 +1,8 @@
 use std::thread;
 use std::time::Duration;
 
+use closures::{cache_file, Cacher};
+
 // Closures are anonymous functions you can save in variables
 // or pass as arguments to functions. Unlike functions, these
 // can capture values from the scope in which they are defined
@@ -59,60 +61,46 @@ use std::time::Duration;
 //
 // MEMOIZATION-BASED APPROACH
 //
-struct Cacher<T>
-where
-    T: Fn(u32) -> u32,
-    // The Fn traits are provided by the standard library. All closures
-    // implement at least one of the traits: Fn, FnMut, or FnOnce.
-{
-    calculation: T,
-    value: Option<u32>,
-}
-
-impl<T> Cacher<T>
-where
-    T: Fn(u32) -> u32,
-{
-    fn new(calculation: T) -> Cacher<T> {
-        Cacher {
-            calculation,
-            value: None,
-        }
-    }
-
-    fn value(&mut self, arg: u32) -> u32 {
-        match self.value {
-            Some(v) => v,
-            None => {
-                let v = (self.calculation)(arg);
-                self.value = Some(v);
-                v
-            }
-        }
-    }
-}
+// Cacher, in cacher.rs, holds on to the closure and calls it only the
+// first time the value for an argument is asked for
 
 fn generate_workout(intensity: u32, random_number: u32) {
-    // The result gets cached on the first call
-    let mut expensive_result = Cacher::new(|num| {
+    // The result for each intensity gets cached on the first call with it,
+    // and kept in a file so the next run doesn't have to wait for it either
+    let cache = cache_file("workout");
+    let mut expensive_result = Cacher::new(|&num| {
         println!("calculating slowly...");
         thread::sleep(Duration::from_secs(2));
         num
     });
+    // Without the saved values it's only slower, so it's no reason to stop
+    if let Err(e) = expensive_result.load(&cache) {
+        eprintln!("Couldn't load {}: {}", cache.display(), e);
+    }
+    // Calculating up front means the workout comes out all at once, rather
+    // than stopping partway through. A day off doesn't need it at all.
+    let day_off = intensity >= 25 && random_number == 3;
+    if !day_off {
+        expensive_result.precompute(&[intensity]);
+    }
 
     if intensity < 25 {
-        println!("Today, do {} pushups!", expensive_result.value(intensity));
-        println!("Next, do {} situps!", expensive_result.value(intensity));
+        println!("Today, do {} pushups!", expensive_result.value(&intensity));
+        println!("Next, do {} situps!", expensive_result.value(&intensity));
     } else {
         if random_number == 3 {
             println!("Take a break today! Remember to stay hydrated!");
         } else {
             println!(
                 "Today, run for {} minutes!",
-                expensive_result.value(intensity)
+                expensive_result.value(&intensity)
             );
         }
     }
+
+    if let Err(e) = expensive_result.save(&cache) {
+        eprintln!("Couldn't save {}: {}", cache.display(), e);
+    }
 }
 
 fn main() {
@@ -0,0 +1,198 @@
+use std::borrow::Borrow;
+use std::collections::HashMap;
+use std::hash::Hash;
+use std::sync::{Arc, OnceLock, RwLock};
+use std::thread::{self, JoinHandle};
+
+/// A Cacher that can be shared between threads, say in an `Arc` handed to
+/// each of a thread pool's workers. The closure still only runs once for
+/// each argument, however many threads ask for it at the same time.
+///
+/// Values are handed back as clones, since a reference into the map
+/// couldn't outlive the lock on it. Unlike Cacher's, the closure has to be
+/// `Fn`: threads can call it at the same time, for different arguments,
+/// so it can't be given the `&mut` an `FnMut` needs.
+pub struct SharedCacher<T, A, V>
+where
+    T: Fn(&A) -> V,
+    A: ?Sized + ToOwned,
+{
+    calculation: T,
+    // Each argument gets a cell of its own, which the value goes in once
+    // it's been calculated. The lock on the map is only held long enough
+    // to find the cell, so a slow calculation holds up the threads waiting
+    // for that one value and nobody else.
+    values: RwLock<HashMap<A::Owned, Arc<OnceLock<V>>>>,
+}
+
+impl<T, A, V> SharedCacher<T, A, V>
+where
+    T: Fn(&A) -> V,
+    A: ?Sized + ToOwned + Hash + Eq,
+    A::Owned: Hash + Eq,
+    V: Clone,
+{
+    /// A SharedCacher that keeps every value it's calculated for good.
+    pub fn new(calculation: T) -> SharedCacher<T, A, V> {
+        SharedCacher {
+            calculation,
+            values: RwLock::new(HashMap::new()),
+        }
+    }
+
+    /// The value for `arg`, calling the closure for it only if no thread
+    /// has yet. Anyone else asking for it in the meantime waits for that
+    /// call to finish.
+    pub fn value(&self, arg: &A) -> V {
+        self.cell(arg)
+            .get_or_init(|| (self.calculation)(arg))
+            .clone()
+    }
+
+    /// The value for `arg` if one's been calculated, without ever calling
+    /// the closure or waiting for another thread to finish calling it.
+    pub fn peek(&self, arg: &A) -> Option<V> {
+        let values = self.values.read().unwrap();
+        values.get(arg)?.get().cloned()
+    }
+
+    /// Calculates the values for all of `args` on a thread of its own, so
+    /// they're ready, or at least on their way, by the time anyone asks.
+    /// Anyone who asks for one before it's done waits for it, rather than
+    /// calling the closure a second time. The handle is for waiting until
+    /// they're all done.
+    pub fn precompute<I>(self: &Arc<Self>, args: I) -> JoinHandle<()>
+    where
+        I: IntoIterator<Item = A::Owned>,
+        // The thread needs a cacher and arguments it can keep for as long
+        // as it likes
+        Self: Send + Sync + 'static,
+        A::Owned: Send + 'static,
+    {
+        let cacher = Arc::clone(self);
+        let args: Vec<A::Owned> = args.into_iter().collect();
+        thread::spawn(move || {
+            for arg in args {
+                cacher.value(arg.borrow());
+            }
+        })
+    }
+
+    /// Calls the closure for `arg` again and keeps the new value, whether
+    /// or not there was one. Threads that already have the old value
+    /// keep it.
+    pub fn refresh(&self, arg: &A) -> V {
+        let value = (self.calculation)(arg);
+        let cell = Arc::new(OnceLock::from(value.clone()));
+        self.values.write().unwrap().insert(arg.to_owned(), cell);
+        value
+    }
+
+    /// Forgets the value for `arg`, so the next time it's asked for the
+    /// closure is called again. The value that was forgotten, if there
+    /// was one.
+    pub fn invalidate(&self, arg: &A) -> Option<V> {
+        let cell = self.values.write().unwrap().remove(arg)?;
+        cell.get().cloned()
+    }
+
+    /// Forgets every value.
+    pub fn clear(&self) {
+        self.values.write().unwrap().clear();
+    }
+
+    // The cell for `arg`'s value, which starts out empty
+    fn cell(&self, arg: &A) -> Arc<OnceLock<V>> {
+        // The closure never runs with the lock held, so a panic in it
+        // can't poison the lock, and unwrap is safe
+
+        // Most of the time the cell's there already, and any number of
+        // threads can read at once
+        if let Some(cell) = self.values.read().unwrap().get(arg) {
+            return Arc::clone(cell);
+        }
+        // Another thread may have made the cell between the two locks, in
+        // which case entry() finds that one
+        let mut values = self.values.write().unwrap();
+        Arc::clone(values.entry(arg.to_owned()).or_default())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::sync::atomic::{AtomicUsize, Ordering};
+    use std::time::Duration;
+
+    #[test]
+    fn caches_like_a_cacher() {
+        let c = SharedCacher::new(|s: &str| s.len());
+
+        assert_eq!(5, c.value("hello"));
+        assert_eq!(3, c.value("hey"));
+        assert_eq!(5, c.value(&String::from("hello")));
+    }
+
+    #[test]
+    fn forgets_and_refreshes_values() {
+        let calls = AtomicUsize::new(0);
+        let c = SharedCacher::new(|&n: &u32| n + calls.fetch_add(1, Ordering::SeqCst) as u32);
+
+        assert_eq!(1, c.value(&1));
+        assert_eq!(Some(1), c.invalidate(&1));
+        assert_eq!(None, c.invalidate(&1));
+        assert_eq!(2, c.value(&1));
+        assert_eq!(3, c.refresh(&1));
+        assert_eq!(3, c.value(&1));
+
+        c.clear();
+        assert_eq!(4, c.value(&1));
+    }
+
+    #[test]
+    fn precomputes_on_another_thread() {
+        let calls = Arc::new(AtomicUsize::new(0));
+        let counted = Arc::clone(&calls);
+        let cacher = Arc::new(SharedCacher::new(move |s: &str| {
+            counted.fetch_add(1, Ordering::SeqCst);
+            s.len()
+        }));
+
+        assert_eq!(None, cacher.peek("hello"));
+        let handle = cacher.precompute(vec![String::from("hello"), String::from("hey")]);
+        assert_eq!(5, cacher.value("hello"));
+        handle.join().unwrap();
+
+        assert_eq!(Some(3), cacher.peek("hey"));
+        assert_eq!(2, calls.load(Ordering::SeqCst));
+    }
+
+    #[test]
+    fn calculates_once_per_argument_across_threads() {
+        let calls = Arc::new(AtomicUsize::new(0));
+        let counted = Arc::clone(&calls);
+        let cacher = Arc::new(SharedCacher::new(move |&n: &u32| {
+            counted.fetch_add(1, Ordering::SeqCst);
+            // Slow enough that the threads are sure to pile up waiting
+            thread::sleep(Duration::from_millis(5));
+            n * n
+        }));
+
+        let handles: Vec<_> = (0..8)
+            .map(|_| {
+                let cacher = Arc::clone(&cacher);
+                thread::spawn(move || {
+                    for i in 0..1000 {
+                        let n = i % 10;
+                        assert_eq!(n * n, cacher.value(&n));
+                    }
+                })
+            })
+            .collect();
+        for handle in handles {
+            handle.join().unwrap();
+        }
+
+        assert_eq!(10, calls.load(Ordering::SeqCst));
+    }
+}
@@ -0,0 +1,532 @@
+use std::convert::TryFrom;
+use std::iter::{Cycle, FusedIterator};
+
+/// A number a Counter can count in: any of the integer types.
+pub trait Step: Copy + PartialOrd {
+    /// Nothing at all, which no step can be.
+    const ZERO: Self;
+    /// The smallest step there is.
+    const ONE: Self;
+
+    /// This number and `step` more, or None if that's more than the type
+    /// can hold.
+    fn forward(self, step: Self) -> Option<Self>;
+
+    /// This number less `step`, or None if that's less than the type can
+    /// hold.
+    fn backward(self, step: Self) -> Option<Self>;
+
+    /// The last number counting from this one in `step`s gets to without
+    /// going past `end`, which is never past it.
+    fn last_step(self, end: Self, step: Self) -> Self;
+
+    /// How many numbers there are counting from this one to `last` in
+    /// `step`s, both included, if there are few enough for a usize.
+    fn counts_to(self, last: Self, step: Self) -> Option<usize>;
+}
+
+// Every integer type steps the same way. Taken as u128s, the difference
+// between two numbers is right for the signed types too, even when it's
+// too big for the type itself.
+macro_rules! impl_step {
+    ($($t:ty)*) => {
+        $(
+            impl Step for $t {
+                const ZERO: $t = 0;
+                const ONE: $t = 1;
+
+                fn forward(self, step: $t) -> Option<$t> {
+                    self.checked_add(step)
+                }
+
+                fn backward(self, step: $t) -> Option<$t> {
+                    self.checked_sub(step)
+                }
+
+                fn last_step(self, end: $t, step: $t) -> $t {
+                    // Whatever's left over is less than a step, so it fits
+                    let over = (end as u128).wrapping_sub(self as u128) % step as u128;
+                    end - over as $t
+                }
+
+                fn counts_to(self, last: $t, step: $t) -> Option<usize> {
+                    let steps = (last as u128).wrapping_sub(self as u128) / step as u128;
+                    usize::try_from(steps).ok()?.checked_add(1)
+                }
+            }
+        )*
+    };
+}
+
+impl_step! { u8 u16 u32 u64 u128 usize i8 i16 i32 i64 i128 isize }
+
+/// Counts from a start to an end, both included, going up by a step each
+/// time. It counts in u32s unless it's told otherwise.
+#[derive(Debug, Clone)]
+pub struct Counter<T = u32> {
+    // The numbers next() and next_back() hand out next. Both are always a
+    // whole number of steps from the start, so when the two meet in the
+    // middle they meet on the same number, and that's the last one.
+    front: T,
+    back: T,
+    step: T,
+    // Set once front and back have met, rather than stepping one past the
+    // other, where it could go past the largest or smallest T. A step that
+    // would overflow anyway finishes the count too, the same in debug and
+    // release builds, rather than panicking or wrapping around.
+    finished: bool,
+}
+
+impl Counter {
+    /// A Counter from 1 to 5.
+    pub fn new() -> Counter {
+        Counter::with_range(1, 5, 1)
+    }
+
+    /// A Counter from 1 to 5 that goes back to 1 after 5, forever. Any
+    /// other Counter can do the same with `cycle()`.
+    ///
+    /// It never runs out, so anything that wants all of it never finishes:
+    /// `take` or `zip` with something that does run out is the way to use
+    /// it.
+    pub fn cycling() -> Cycle<Counter> {
+        Counter::new().cycle()
+    }
+}
+
+impl<T: Step> Counter<T> {
+    /// A Counter from `start` to `end`, going up by `step`. The end is
+    /// only counted if a step lands on it, and a start past the end counts
+    /// nothing at all.
+    ///
+    /// # Panics
+    ///
+    /// Panics if `step` isn't more than 0, since the count would never get
+    /// anywhere.
+    pub fn with_range(start: T, end: T, step: T) -> Counter<T> {
+        assert!(
+            step > T::ZERO,
+            "a Counter can't count in steps of 0 or less"
+        );
+        if start > end {
+            return Counter {
+                front: start,
+                back: end,
+                step,
+                finished: true,
+            };
+        }
+        Counter {
+            front: start,
+            back: start.last_step(end, step),
+            step,
+            finished: false,
+        }
+    }
+}
+
+impl Default for Counter {
+    fn default() -> Counter {
+        Counter::new()
+    }
+}
+
+// Implementing the Iterator trait
+impl<T: Step> Iterator for Counter<T> {
+    type Item = T;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        if self.finished {
+            return None;
+        }
+        let count = self.front;
+        match count.forward(self.step) {
+            Some(next) if count != self.back => self.front = next,
+            _ => self.finished = true,
+        }
+        Some(count)
+    }
+
+    // Exact, unless there are too many left to count in a usize
+    fn size_hint(&self) -> (usize, Option<usize>) {
+        if self.finished {
+            return (0, Some(0));
+        }
+        match self.front.counts_to(self.back, self.step) {
+            Some(left) => (left, Some(left)),
+            None => (usize::MAX, None),
+        }
+    }
+}
+
+// Only for the types where there can never be more to count than fit in a
+// usize, like the standard library's inclusive ranges
+macro_rules! impl_exact_size {
+    ($($t:ty)*) => {
+        $(impl ExactSizeIterator for Counter<$t> {})*
+    };
+}
+
+impl_exact_size! { u8 u16 i8 i16 }
+#[cfg(target_pointer_width = "64")]
+impl_exact_size! { u32 i32 }
+
+// Once finished is set it stays set, so after the first None there's only
+// ever None
+impl<T: Step> FusedIterator for Counter<T> {}
+
+// Counting down from the end, as far as the front has got
+impl<T: Step> DoubleEndedIterator for Counter<T> {
+    fn next_back(&mut self) -> Option<Self::Item> {
+        if self.finished {
+            return None;
+        }
+        let count = self.back;
+        match count.backward(self.step) {
+            Some(next) if count != self.front => self.back = next,
+            _ => self.finished = true,
+        }
+        Some(count)
+    }
+}
+
+/// The makings of a Counter, set one at a time, for a `for` loop to count
+/// over like a range:
+///
+/// ```
+/// use iterators::CounterConfig;
+///
+/// let mut evens = Vec::new();
+/// for i in CounterConfig::new().start(10).end(20).step(2) {
+///     evens.push(i);
+/// }
+/// assert_eq!(vec![10, 12, 14, 16, 18, 20], evens);
+/// ```
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct CounterConfig<T = u32> {
+    start: T,
+    end: T,
+    step: T,
+    // Whether the end is counted, if a step lands on it
+    inclusive: bool,
+}
+
+impl CounterConfig {
+    /// Counts from 1 to 5 in steps of 1, like `Counter::new`, until it's
+    /// told otherwise.
+    pub fn new() -> CounterConfig {
+        CounterConfig::between(1, 5)
+    }
+}
+
+impl Default for CounterConfig {
+    fn default() -> CounterConfig {
+        CounterConfig::new()
+    }
+}
+
+impl<T: Step> CounterConfig<T> {
+    /// Counts from `start` to `end`, both included, in steps of 1 until
+    /// it's told otherwise.
+    pub fn between(start: T, end: T) -> CounterConfig<T> {
+        CounterConfig {
+            start,
+            end,
+            step: T::ONE,
+            inclusive: true,
+        }
+    }
+
+    pub fn start(mut self, start: T) -> CounterConfig<T> {
+        self.start = start;
+        self
+    }
+
+    pub fn end(mut self, end: T) -> CounterConfig<T> {
+        self.end = end;
+        self
+    }
+
+    /// # Panics
+    ///
+    /// Panics if `step` isn't more than 0, like `Counter::with_range`.
+    pub fn step(mut self, step: T) -> CounterConfig<T> {
+        assert!(
+            step > T::ZERO,
+            "a Counter can't count in steps of 0 or less"
+        );
+        self.step = step;
+        self
+    }
+
+    /// Whether to count the end, if a step lands on it. Without it, the
+    /// count stops short of the end the way `start..end` does.
+    pub fn inclusive(mut self, inclusive: bool) -> CounterConfig<T> {
+        self.inclusive = inclusive;
+        self
+    }
+}
+
+impl<T: Step> IntoIterator for CounterConfig<T> {
+    type Item = T;
+    type IntoIter = Counter<T>;
+
+    fn into_iter(self) -> Counter<T> {
+        if self.inclusive {
+            return Counter::with_range(self.start, self.end, self.step);
+        }
+        // Leaving out the end is counting up to the number before it.
+        // With nothing before the start, there's nothing to count.
+        match self.end.backward(T::ONE) {
+            Some(last) if self.start < self.end => Counter::with_range(self.start, last, self.step),
+            _ => Counter::with_range(T::ONE, T::ZERO, self.step),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn calling_next_directly() {
+        let mut counter = Counter::new();
+
+        assert_eq!(counter.next(), Some(1));
+        assert_eq!(counter.next(), Some(2));
+        assert_eq!(counter.next(), Some(3));
+        assert_eq!(counter.next(), Some(4));
+        assert_eq!(counter.next(), Some(5));
+        assert_eq!(counter.next(), None);
+    }
+
+    #[test]
+    fn using_other_iterator_trait_methods() {
+        let sum: u32 = Counter::new()
+            .zip(Counter::new().skip(1))
+            .map(|(a, b)| a * b)
+            .filter(|x| x % 3 == 0)
+            .sum();
+        assert_eq!(18, sum);
+    }
+
+    #[test]
+    fn counts_over_any_range_in_any_step() {
+        let counted: Vec<u32> = Counter::with_range(10, 20, 5).collect();
+        assert_eq!(vec![10, 15, 20], counted);
+
+        let counted: Vec<u32> = Counter::with_range(3, 10, 4).collect();
+        assert_eq!(vec![3, 7], counted);
+
+        assert_eq!(vec![7], Counter::with_range(7, 7, 1).collect::<Vec<u32>>());
+        assert_eq!(0, Counter::with_range(8, 7, 1).count());
+    }
+
+    #[test]
+    fn chains_adaptors_over_other_ranges() {
+        let sum: u32 = Counter::with_range(2, 20, 2)
+            .zip(Counter::with_range(1, 100, 3))
+            .map(|(a, b)| a * b)
+            .filter(|x| x % 2 == 0)
+            .sum();
+        // (2, 1), (4, 4), ... (20, 28): every product of an even number
+        assert_eq!((1..=10).map(|i| 2 * i * (3 * i - 2)).sum::<u32>(), sum);
+    }
+
+    // Like the largest from chapter 10, for anything a Counter can count
+    fn largest<T: PartialOrd + Copy>(list: &[T]) -> T {
+        let mut largest = list[0];
+        for &item in list {
+            if item > largest {
+                largest = item;
+            }
+        }
+        largest
+    }
+
+    #[test]
+    fn counts_in_any_integer_type() {
+        let bytes: Vec<u8> = Counter::with_range(240, 250, 4).collect();
+        assert_eq!(vec![240, 244, 248], bytes);
+
+        let signed: Vec<i64> = Counter::with_range(-5, 5, 3).collect();
+        assert_eq!(vec![-5, -2, 1, 4], signed);
+        assert_eq!(4, largest(&signed));
+
+        let big: u128 = Counter::with_range(1 << 100, (1 << 100) + 10, 5).sum();
+        assert_eq!(3 * (1 << 100) + 15, big);
+    }
+
+    #[test]
+    fn counts_backwards_too() {
+        let counted: Vec<u32> = Counter::with_range(3, 20, 4).rev().collect();
+        assert_eq!(vec![19, 15, 11, 7, 3], counted);
+
+        assert_eq!(
+            Some(15),
+            Counter::with_range(3, 20, 4).rfind(|n| n % 5 == 0)
+        );
+        assert_eq!(Some(0), Counter::with_range(0, 10, 1).rfind(|&n| n == 0));
+        assert_eq!(None, Counter::with_range(8, 7, 1).next_back());
+    }
+
+    #[test]
+    fn meets_in_the_middle() {
+        // An odd number of counts meets on one in the middle
+        let mut counter = Counter::new();
+        assert_eq!(Some(1), counter.next());
+        assert_eq!(Some(5), counter.next_back());
+        assert_eq!(Some(4), counter.next_back());
+        assert_eq!(Some(2), counter.next());
+        assert_eq!(Some(3), counter.next_back());
+        assert_eq!(None, counter.next());
+        assert_eq!(None, counter.next_back());
+
+        // and an even number between two
+        let mut counter = Counter::with_range(-3i64, 3, 2);
+        assert_eq!(Some(3), counter.next_back());
+        assert_eq!(Some(-3), counter.next());
+        assert_eq!(Some(-1), counter.next());
+        assert_eq!(Some(1), counter.next_back());
+        assert_eq!(None, counter.next_back());
+        assert_eq!(None, counter.next());
+    }
+
+    #[test]
+    fn counts_one_number_once() {
+        let mut counter = Counter::with_range(0u8, 0, 1);
+        assert_eq!(Some(0), counter.next_back());
+        assert_eq!(None, counter.next());
+    }
+
+    #[test]
+    fn knows_how_many_are_left() {
+        let mut counter = Counter::with_range(3, 20, 4);
+        assert_eq!(5, counter.len());
+        assert_eq!((5, Some(5)), counter.size_hint());
+
+        counter.next();
+        assert_eq!(4, counter.len());
+        counter.next_back();
+        assert_eq!(3, counter.len());
+        counter.by_ref().for_each(drop);
+        assert_eq!(0, counter.len());
+
+        assert_eq!(0, Counter::with_range(8, 7, 1).len());
+        assert_eq!(256, Counter::with_range(0u8, 255, 1).len());
+        assert_eq!(201, Counter::with_range(-100i8, 100, 1).len());
+    }
+
+    #[test]
+    fn hints_what_it_can() {
+        let counter = Counter::with_range(i64::MIN, i64::MAX, 1 << 62);
+        assert_eq!((4, Some(4)), counter.size_hint());
+
+        // More than a usize can count
+        let counter = Counter::with_range(0, u128::MAX, 1);
+        assert_eq!((usize::MAX, None), counter.size_hint());
+
+        let pairs = Counter::new().zip(Counter::with_range(0, 1000, 1));
+        assert_eq!((5, Some(5)), pairs.size_hint());
+        let counted: Vec<u32> = Counter::with_range(1, 1000, 10).collect();
+        assert!(counted.capacity() >= 100);
+    }
+
+    #[test]
+    fn cycles_around_forever() {
+        let counted: Vec<u32> = Counter::cycling().take(12).collect();
+        assert_eq!(vec![1, 2, 3, 4, 5, 1, 2, 3, 4, 5, 1, 2], counted);
+        assert_eq!((usize::MAX, None), Counter::cycling().size_hint());
+
+        // zip stops at the end of the shorter one
+        let days = ["mon", "tue", "wed", "thu", "fri", "sat", "sun"];
+        let shifts: Vec<_> = days
+            .iter()
+            .zip(Counter::with_range(1, 3, 1).cycle())
+            .collect();
+        assert_eq!((&"sun", 1), shifts[6]);
+
+        // Nothing to count goes round nothing, and stops
+        assert_eq!(None, Counter::with_range(8, 7, 1).cycle().next());
+    }
+
+    #[test]
+    fn stops_at_the_largest_number_without_overflowing() {
+        let counted: Vec<u8> = Counter::with_range(250, u8::MAX, 1).collect();
+        assert_eq!(vec![250, 251, 252, 253, 254, 255], counted);
+
+        let mut counter = Counter::with_range(u8::MAX, u8::MAX, 1);
+        assert_eq!(Some(255), counter.next());
+        assert_eq!(None, counter.next());
+        assert_eq!(None, counter.next());
+
+        let counted: Vec<u8> = Counter::with_range(0, u8::MAX, 100).collect();
+        assert_eq!(vec![0, 100, 200], counted);
+        let counted: Vec<u8> = Counter::with_range(55, u8::MAX, 100).collect();
+        assert_eq!(vec![55, 155, 255], counted);
+        assert_eq!(256, Counter::with_range(0, u8::MAX, 1).count());
+        let counted: Vec<u8> = Counter::with_range(0, u8::MAX, u8::MAX).collect();
+        assert_eq!(vec![0, 255], counted);
+    }
+
+    #[test]
+    fn stops_at_the_smallest_number_without_overflowing() {
+        let counted: Vec<i8> = Counter::with_range(i8::MIN, -126, 1).rev().collect();
+        assert_eq!(vec![-126, -127, -128], counted);
+
+        let counted: Vec<u8> = Counter::with_range(0, 255, 128).rev().collect();
+        assert_eq!(vec![128, 0], counted);
+        assert_eq!(256, Counter::with_range(i8::MIN, i8::MAX, 1).rev().count());
+    }
+
+    #[test]
+    fn counts_what_its_configured_to() {
+        let counted: Vec<u32> = CounterConfig::new().into_iter().collect();
+        assert_eq!(Counter::new().collect::<Vec<_>>(), counted);
+
+        let config = CounterConfig::new().start(10).end(20).step(5);
+        assert_eq!(vec![10, 15, 20], config.into_iter().collect::<Vec<_>>());
+        let counted: Vec<u32> = config.inclusive(false).into_iter().collect();
+        assert_eq!(vec![10, 15], counted);
+
+        let mut total = 0;
+        for i in CounterConfig::between(-3i64, 3).step(3) {
+            total += i;
+        }
+        assert_eq!(0, total);
+    }
+
+    #[test]
+    fn leaves_out_the_end_like_a_range() {
+        let config = CounterConfig::between(0u8, 0).inclusive(false);
+        assert_eq!(0, config.into_iter().count());
+        assert_eq!(0, config.end(u8::MIN).into_iter().count());
+        assert_eq!(255, config.end(u8::MAX).into_iter().len());
+        assert_eq!(
+            (0..10).step_by(3).collect::<Vec<_>>(),
+            CounterConfig::between(0, 10)
+                .step(3)
+                .inclusive(false)
+                .into_iter()
+                .collect::<Vec<_>>()
+        );
+    }
+
+    #[test]
+    #[should_panic(expected = "steps of 0")]
+    fn refuses_a_step_of_zero_to_configure() {
+        CounterConfig::new().step(0);
+    }
+
+    #[test]
+    #[should_panic(expected = "steps of 0")]
+    fn refuses_a_step_of_zero() {
+        Counter::with_range(1, 5, 0);
+    }
+
+    #[test]
+    #[should_panic(expected = "steps of 0 or less")]
+    fn refuses_a_step_backwards() {
+        Counter::with_range(5, 1, -1);
+    }
+}
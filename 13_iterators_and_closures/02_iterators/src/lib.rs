@@ -0,0 +1,5 @@
+mod counter;
+mod shoes;
+
+pub use counter::{Counter, CounterConfig, Step};
+pub use shoes::{shoes_in_my_size, Shoe};
@@ -1,3 +1,6 @@
+// The examples are about iterating over vectors, arrays would miss the point
+#![allow(clippy::useless_vec)]
+
 // Rust iterators are lazy: they have no effect until you call
 // methods that consume the iterator to use it up
 fn main() {
@@ -36,17 +39,6 @@ fn main() {
     assert_eq!(v2, vec![2, 3, 4]);
 }
 
-#[derive(PartialEq, Debug)]
-struct Shoe {
-    size: u32,
-    style: String,
-}
-
-// takes ownership of shoes vector
-fn shoes_in_my_size(shoes: Vec<Shoe>, shoe_size: u32) -> Vec<Shoe> {
-    shoes.into_iter().filter(|s| s.size == shoe_size).collect()
-}
-
 // All iterators implement the Iterator trait
 // // pub trait Iterator {
 //     type Item; // Associated type
@@ -56,34 +48,11 @@ fn shoes_in_my_size(shoes: Vec<Shoe>, shoe_size: u32) -> Vec<Shoe> {
 //     // methods with default implementations elided
 // }
 
-struct Counter {
-    count: u32,
-}
-
-impl Counter {
-    fn new() -> Counter {
-        Counter { count: 0 }
-    }
-}
-
-// Implementing the Iterator trait
-impl Iterator for Counter {
-    type Item = u32;
-
-    fn next(&mut self) -> Option<Self::Item> {
-        if self.count < 5 {
-            self.count += 1;
-            Some(self.count)
-        } else {
-            None
-        }
-    }
-}
+// Counter, in counter.rs, is an iterator of our own, and shoes.rs filters
+// a vector of shoes with one from the standard library
 
 #[cfg(test)]
 mod tests {
-    use super::*;
-
     // Methods that call next are called consuming adaptors, because calling
     // them uses up the iterator. One example is the sum method, which takes
     // ownership of the iterator and iterates through the items by repeatedly
@@ -101,60 +70,4 @@ mod tests {
         // iterator we call it on
         assert_eq!(total, 6);
     }
-
-    #[test]
-    fn filters_by_size() {
-        let shoes = vec![
-            Shoe {
-                size: 10,
-                style: String::from("sneaker"),
-            },
-            Shoe {
-                size: 13,
-                style: String::from("sandal"),
-            },
-            Shoe {
-                size: 10,
-                style: String::from("boot"),
-            },
-        ];
-
-        let in_my_size = shoes_in_my_size(shoes, 10);
-
-        assert_eq!(
-            in_my_size,
-            vec![
-                Shoe {
-                    size: 10,
-                    style: String::from("sneaker")
-                },
-                Shoe {
-                    size: 10,
-                    style: String::from("boot")
-                },
-            ]
-        );
-    }
-
-    #[test]
-    fn calling_next_directly() {
-        let mut counter = Counter::new();
-
-        assert_eq!(counter.next(), Some(1));
-        assert_eq!(counter.next(), Some(2));
-        assert_eq!(counter.next(), Some(3));
-        assert_eq!(counter.next(), Some(4));
-        assert_eq!(counter.next(), Some(5));
-        assert_eq!(counter.next(), None);
-    }
-
-    #[test]
-    fn using_other_iterator_trait_methods() {
-        let sum: u32 = Counter::new()
-            .zip(Counter::new().skip(1))
-            .map(|(a, b)| a * b)
-            .filter(|x| x % 3 == 0)
-            .sum();
-        assert_eq!(18, sum);
-    }
 }
@@ -0,0 +1,129 @@
+// Checks that Counter behaves the way every iterator is expected to, on its
+// own and inside the standard library's adaptors, by holding it up against
+// the standard library's own stepped ranges.
+
+use std::fmt::Debug;
+use std::iter::FusedIterator;
+
+use iterators::{Counter, CounterConfig};
+
+// Counters over all sorts of ranges, each with what it should count
+fn cases() -> Vec<(Counter<i64>, Vec<i64>)> {
+    let ranges = [
+        (1, 5, 1),
+        (3, 20, 4),
+        (7, 7, 1),
+        (8, 7, 1),
+        (-10, 10, 3),
+        (i64::MAX - 10, i64::MAX, 4),
+        (i64::MIN, i64::MIN + 10, 5),
+    ];
+    ranges
+        .iter()
+        .map(|&(start, end, step)| {
+            let expected = (start..=end).step_by(step as usize).collect();
+            (Counter::with_range(start, end, step), expected)
+        })
+        .collect()
+}
+
+// Nothing but None after the first None, from either end
+fn assert_exhausted<I>(iter: &mut I)
+where
+    I: DoubleEndedIterator + FusedIterator,
+    I::Item: Debug,
+{
+    for _ in 0..3 {
+        assert!(iter.next().is_none());
+        assert!(iter.next_back().is_none());
+    }
+    assert_eq!((0, Some(0)), iter.size_hint());
+}
+
+#[test]
+fn counts_what_a_stepped_range_counts() {
+    for (counter, expected) in cases() {
+        assert_eq!(expected, counter.clone().collect::<Vec<_>>());
+
+        let mut reversed = expected.clone();
+        reversed.reverse();
+        assert_eq!(reversed, counter.rev().collect::<Vec<_>>());
+    }
+}
+
+#[test]
+fn stays_finished() {
+    for (mut counter, _) in cases() {
+        counter.by_ref().for_each(drop);
+        assert_exhausted(&mut counter);
+    }
+    for (mut counter, _) in cases() {
+        while counter.next_back().is_some() {}
+        assert_exhausted(&mut counter);
+    }
+}
+
+#[test]
+fn hints_exactly_all_the_way_through() {
+    for (mut counter, expected) in cases() {
+        for left in (0..=expected.len()).rev() {
+            assert_eq!((left, Some(left)), counter.size_hint());
+            // Alternate ends, so both have to keep the count right
+            if left % 2 == 0 {
+                counter.next();
+            } else {
+                counter.next_back();
+            }
+        }
+    }
+}
+
+#[test]
+fn picks_up_where_by_ref_left_off() {
+    for (mut counter, expected) in cases() {
+        let first: Vec<_> = counter.by_ref().take(2).collect();
+        let rest: Vec<_> = counter.collect();
+
+        assert_eq!(expected, [first, rest].concat());
+    }
+}
+
+#[test]
+fn chains_from_either_end() {
+    for (counter, expected) in cases() {
+        let twice: Vec<_> = counter.clone().chain(counter.clone()).collect();
+        assert_eq!([&expected[..], &expected[..]].concat(), twice);
+
+        let mut backwards: Vec<_> = counter.clone().chain(counter).rev().collect();
+        backwards.reverse();
+        assert_eq!(twice, backwards);
+    }
+
+    // An empty one in the middle doesn't stop the chain
+    let chained: Vec<u32> = Counter::new()
+        .chain(Counter::with_range(8, 7, 1))
+        .chain(CounterConfig::between(6, 7))
+        .collect();
+    assert_eq!(vec![1, 2, 3, 4, 5, 6, 7], chained);
+}
+
+#[test]
+fn peeks_without_using_anything_up() {
+    for (counter, expected) in cases() {
+        let mut peekable = counter.peekable();
+        let mut counted = Vec::new();
+        while let Some(&next) = peekable.peek() {
+            assert_eq!(Some(&next), peekable.peek());
+            assert_eq!(Some(next), peekable.next());
+            counted.push(next);
+        }
+        assert_eq!(expected, counted);
+        assert_eq!(None, peekable.peek());
+        assert_eq!(None, peekable.next());
+    }
+
+    let mut peekable = Counter::new().peekable();
+    assert_eq!(Some(1), peekable.next_if(|&n| n < 2));
+    assert_eq!(None, peekable.next_if(|&n| n < 2));
+    assert_eq!(Some(2), peekable.next());
+}
@@ -0,0 +1,363 @@
+use std::fmt;
+use std::iter::FromIterator;
+use std::mem::{self, ManuallyDrop};
+use std::ptr;
+
+// A cons list is a data structure
+// Each item in a cons list contains two elements: the value of the current
+// item and the next item. The last item contains only Nil, without a next item.
+// It is constructed by recursively calling the cons function.
+
+/// A singly linked list, as a cons list. The methods use it as a stack,
+/// with the front as the top, so nobody has to nest the `Cons` cells by
+/// hand.
+#[derive(Debug)]
+pub enum List<T> {
+    // This type would have, essentially, an infinite size. Using Box<T>
+    // makes the size known: it's the size of a pointer
+    Cons(T, Box<List<T>>),
+    Nil,
+}
+
+use List::{Cons, Nil};
+
+impl<T> List<T> {
+    /// An empty list.
+    pub fn new() -> List<T> {
+        Nil
+    }
+
+    /// Puts `value` at the front, ahead of everything already there.
+    pub fn push_front(&mut self, value: T) {
+        // The list so far moves into the box behind the new cell, leaving
+        // Nil where it was for a moment
+        let rest = mem::replace(self, Nil);
+        *self = Cons(value, Box::new(rest));
+    }
+
+    /// Takes the value off the front, or None if the list is empty.
+    pub fn pop_front(&mut self) -> Option<T> {
+        let rest = match self {
+            Cons(_, rest) => mem::replace(&mut **rest, Nil),
+            Nil => return None,
+        };
+        // List has a Drop impl, so a cell can't be taken apart by moving
+        // its value out of it. Instead it's kept from being dropped, and
+        // both fields are read out of it once each.
+        let cell = ManuallyDrop::new(mem::replace(self, rest));
+        let (value, next) = match &*cell {
+            Cons(value, next) => unsafe { (ptr::read(value), ptr::read(next)) },
+            Nil => unreachable!(),
+        };
+        // Only Nil is left behind it now
+        drop(next);
+        Some(value)
+    }
+
+    /// How many values there are. It counts them one cell at a time, so
+    /// it takes longer the longer the list is.
+    pub fn len(&self) -> usize {
+        let mut len = 0;
+        let mut list = self;
+        while let Cons(_, rest) = list {
+            len += 1;
+            list = rest;
+        }
+        len
+    }
+
+    pub fn is_empty(&self) -> bool {
+        matches!(self, Nil)
+    }
+
+    /// The values from front to back, borrowed.
+    pub fn iter(&self) -> Iter<'_, T> {
+        Iter { list: self }
+    }
+
+    /// The values from front to back, borrowed so they can be changed.
+    pub fn iter_mut(&mut self) -> IterMut<'_, T> {
+        IterMut { list: Some(self) }
+    }
+}
+
+/// Borrows each value of a List in turn, from `List::iter`.
+pub struct Iter<'a, T> {
+    // The cells not visited yet
+    list: &'a List<T>,
+}
+
+impl<'a, T> Iterator for Iter<'a, T> {
+    type Item = &'a T;
+
+    fn next(&mut self) -> Option<&'a T> {
+        match self.list {
+            Cons(value, rest) => {
+                self.list = rest;
+                Some(value)
+            }
+            Nil => None,
+        }
+    }
+}
+
+/// Mutably borrows each value of a List in turn, from `List::iter_mut`.
+pub struct IterMut<'a, T> {
+    // A &mut can't be copied like the & in Iter, so it's taken out of the
+    // Option to split it into the value and the rest
+    list: Option<&'a mut List<T>>,
+}
+
+impl<'a, T> Iterator for IterMut<'a, T> {
+    type Item = &'a mut T;
+
+    fn next(&mut self) -> Option<&'a mut T> {
+        match self.list.take()? {
+            Cons(value, rest) => {
+                self.list = Some(rest);
+                Some(value)
+            }
+            Nil => None,
+        }
+    }
+}
+
+/// Takes each value off the front of a List in turn, from `into_iter`.
+pub struct IntoIter<T>(List<T>);
+
+impl<T> Iterator for IntoIter<T> {
+    type Item = T;
+
+    fn next(&mut self) -> Option<T> {
+        self.0.pop_front()
+    }
+}
+
+impl<T> IntoIterator for List<T> {
+    type Item = T;
+    type IntoIter = IntoIter<T>;
+
+    fn into_iter(self) -> IntoIter<T> {
+        IntoIter(self)
+    }
+}
+
+impl<'a, T> IntoIterator for &'a List<T> {
+    type Item = &'a T;
+    type IntoIter = Iter<'a, T>;
+
+    fn into_iter(self) -> Iter<'a, T> {
+        self.iter()
+    }
+}
+
+impl<'a, T> IntoIterator for &'a mut List<T> {
+    type Item = &'a mut T;
+    type IntoIter = IterMut<'a, T>;
+
+    fn into_iter(self) -> IterMut<'a, T> {
+        self.iter_mut()
+    }
+}
+
+impl<T> Default for List<T> {
+    fn default() -> List<T> {
+        List::new()
+    }
+}
+
+// Compared a value at a time rather than deriving it, which would recurse
+// once per cell
+impl<T: PartialEq> PartialEq for List<T> {
+    fn eq(&self, other: &List<T>) -> bool {
+        self.iter().eq(other.iter())
+    }
+}
+
+impl<T: Eq> Eq for List<T> {}
+
+/// Shows the values front to back, like `[1 -> 2 -> 3]`.
+// Dropping the first cell would drop each box from inside the one before
+// it, which overflows the stack on a long list. Unlinking the cells one at
+// a time doesn't.
+impl<T> Drop for List<T> {
+    fn drop(&mut self) {
+        let mut next = match self {
+            Cons(_, rest) => mem::replace(&mut **rest, Nil),
+            Nil => return,
+        };
+        while let Cons(_, rest) = &mut next {
+            // The cell dropped here has only Nil behind it
+            next = mem::replace(&mut **rest, Nil);
+        }
+    }
+}
+
+impl<T: fmt::Display> fmt::Display for List<T> {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(f, "[")?;
+        for (i, value) in self.iter().enumerate() {
+            if i > 0 {
+                write!(f, " -> ")?;
+            }
+            write!(f, "{}", value)?;
+        }
+        write!(f, "]")
+    }
+}
+
+impl<T> FromIterator<T> for List<T> {
+    fn from_iter<I: IntoIterator<Item = T>>(iter: I) -> List<T> {
+        let mut list = Nil;
+        // Where the next value goes, so the values keep their order
+        let mut tail = &mut list;
+        for value in iter {
+            *tail = Cons(value, Box::new(Nil));
+            tail = match tail {
+                Cons(_, rest) => rest,
+                Nil => unreachable!(),
+            };
+        }
+        list
+    }
+}
+
+impl<T> From<Vec<T>> for List<T> {
+    fn from(values: Vec<T>) -> List<T> {
+        values.into_iter().collect()
+    }
+}
+
+impl<T: Clone> From<&List<T>> for Vec<T> {
+    fn from(list: &List<T>) -> Vec<T> {
+        list.iter().cloned().collect()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn works_as_a_stack() {
+        let mut list = List::new();
+        assert!(list.is_empty());
+        assert_eq!(0, list.len());
+
+        list.push_front(1);
+        list.push_front(2);
+        list.push_front(3);
+        assert!(!list.is_empty());
+        assert_eq!(3, list.len());
+
+        assert_eq!(Some(3), list.pop_front());
+        assert_eq!(Some(2), list.pop_front());
+        list.push_front(4);
+        assert_eq!(Some(4), list.pop_front());
+        assert_eq!(Some(1), list.pop_front());
+        assert_eq!(None, list.pop_front());
+        assert!(list.is_empty());
+    }
+
+    // 1, 2, 3, 4, front to back
+    fn one_to_four() -> List<i32> {
+        List::from(vec![1, 2, 3, 4])
+    }
+
+    #[test]
+    fn iterates_over_borrowed_values() {
+        let list = one_to_four();
+
+        assert_eq!(10, list.iter().sum::<i32>());
+        let doubled: Vec<i32> = list.iter().map(|x| x * 2).collect();
+        assert_eq!(vec![2, 4, 6, 8], doubled);
+        let mut evens = Vec::new();
+        for value in &list {
+            if value % 2 == 0 {
+                evens.push(*value);
+            }
+        }
+        assert_eq!(vec![2, 4], evens);
+        assert_eq!(0, List::<i32>::new().iter().count());
+    }
+
+    #[test]
+    fn changes_values_in_place() {
+        let mut list = one_to_four();
+        for value in list.iter_mut() {
+            *value *= 10;
+        }
+        for value in &mut list {
+            *value += 1;
+        }
+
+        assert_eq!(vec![11, 21, 31, 41], Vec::from(&list));
+    }
+
+    #[test]
+    fn gives_up_its_values() {
+        let words: List<String> = {
+            let mut list = List::new();
+            list.push_front(String::from("world"));
+            list.push_front(String::from("hello"));
+            list
+        };
+
+        let sentence: Vec<String> = words.into_iter().collect();
+        assert_eq!(vec!["hello", "world"], sentence);
+        assert_eq!(
+            vec![(1, 'a'), (2, 'b')],
+            one_to_four()
+                .into_iter()
+                .zip("ab".chars())
+                .collect::<Vec<_>>()
+        );
+    }
+
+    #[test]
+    fn converts_to_and_from_a_vec() {
+        let list: List<i32> = vec![1, 2, 3].into_iter().collect();
+        assert_eq!(3, list.len());
+        assert_eq!(vec![1, 2, 3], Vec::from(&list));
+
+        let mut list = List::from(vec!['a', 'b']);
+        assert_eq!(Some('a'), list.pop_front());
+        assert_eq!(vec!['b'], Vec::from(&list));
+
+        let empty: List<i32> = List::from(Vec::new());
+        assert!(empty.is_empty());
+        assert!(Vec::from(&empty).is_empty());
+    }
+
+    #[test]
+    fn compares_and_shows_the_values() {
+        assert_eq!(one_to_four(), (1..=4).collect());
+        assert_ne!(one_to_four(), (1..=3).collect());
+        assert_ne!(one_to_four(), (2..=5).collect());
+        assert_eq!(List::<i32>::new(), List::new());
+
+        assert_eq!("[1 -> 2 -> 3 -> 4]", one_to_four().to_string());
+        assert_eq!("[a]", List::from(vec!["a"]).to_string());
+        assert_eq!("[]", List::<i32>::new().to_string());
+    }
+
+    #[test]
+    fn pushes_onto_a_list_built_by_hand() {
+        let mut list = Cons(2, Box::new(Cons(3, Box::new(Nil))));
+        list.push_front(1);
+
+        assert_eq!(3, list.len());
+        assert_eq!(List::from(vec![1, 2, 3]), list);
+    }
+
+    #[test]
+    fn drops_a_long_list() {
+        let list: List<i32> = (0..1_000_000).collect();
+        assert_eq!(1_000_000, list.len());
+        drop(list);
+
+        let mut list = List::from(vec![0; 1_000_000]);
+        list.push_front(1);
+        assert_eq!(Some(1), list.pop_front());
+    }
+}
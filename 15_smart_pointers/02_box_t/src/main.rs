@@ -15,17 +15,9 @@
 // However, boxes have a known size, so by inserting a box in a recursive type
 // definition, you can have recursive types.
 
-// A cons list is a data structure
-// Each item in a cons list contains two elements: the value of the current
-// item and the next item. The last item contains only Nil, without a next item.
-// It is constructed by recursively calling the cons function.
-
-// This type has, essentially, an infinite size
-enum List {
-    // Using Box<T> makes the size known: it's the size of a pointer
-    Cons(i32, Box<List>),
-    Nil,
-}
+// The cons list, List, is in list.rs
+
+use box_t::List;
 
 fn main() {
     // Store data on the heap
@@ -37,6 +29,20 @@ fn main() {
         1,
         Box::new(List::Cons(2, Box::new(List::Cons(3, Box::new(List::Nil))))),
     );
+    println!("list has {} items", list.len());
+
+    // The same list, without nesting the boxes by hand
+    let mut list = List::new();
+    list.push_front(3);
+    list.push_front(2);
+    list.push_front(1);
+    println!("list has {} items", list.len());
+    println!("the first is {:?}", list.pop_front());
+
+    // Or straight from a Vec, and back
+    let list = List::from(vec![1, 2, 3]);
+    println!("list holds {:?}", Vec::from(&list));
+    println!("list is {}", list);
 }
 
 // Boxes provide indirection and heap allocation, but don't have any other
@@ -0,0 +1,3 @@
+mod my_box;
+
+pub use my_box::MyBox;
@@ -1,29 +1,9 @@
 // Implementing the `Deref` trait allows customizing the behavior of the
 // dereference operator *
 
-struct MyBox<T>(T);
+// MyBox, and its Deref implementation, are in my_box.rs
 
-impl<T> MyBox<T> {
-    fn new(x: T) -> MyBox<T> {
-        MyBox(x)
-    }
-}
-
-impl<T> std::ops::Deref for MyBox<T> {
-    type Target = T;
-
-    fn deref(&self) -> &T {
-        &self.0
-    }
-}
-
-// The reason the deref method returns a reference to a value, and that the
-// plain dereference outside the parentheses in *(y.deref()) is still
-// necessary, is the ownership system.
-// If the deref method returned the value directly instead of a reference to
-// the value, the value would be moved out of self. We don’t want to take
-// ownership of the inner value inside MyBox<T> in this case or in most cases
-// where we use the dereference operator.
+use trait_deref::MyBox;
 
 fn main() {
     let x = 5;
@@ -39,6 +19,10 @@ fn main() {
     // Defer coercion due to the Deref implementation on String
     hello(&(*m)[..]); // Same as
     hello(&m); // This
+
+    // Taking the String back out of the box
+    let s = MyBox::into_inner(m);
+    println!("{} is back out of the box", s);
 }
 
 // Deref coercion is a convenience that Rust performs on arguments to functions
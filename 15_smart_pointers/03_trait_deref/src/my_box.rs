@@ -0,0 +1,76 @@
+/// A box of our own, like Box<T> but holding its value in place rather than
+/// on the heap.
+///
+/// Comparing, cloning and printing a MyBox do the same to the value inside.
+#[derive(Debug, Clone, Default, PartialEq, Eq, PartialOrd, Ord)]
+pub struct MyBox<T>(T);
+
+impl<T> MyBox<T> {
+    /// Puts `x` in a box.
+    pub fn new(x: T) -> MyBox<T> {
+        MyBox(x)
+    }
+
+    /// Takes the value back out of the box.
+    ///
+    /// Called as `MyBox::into_inner(b)` rather than `b.into_inner()`, so it
+    /// can't get mixed up with a method of the same name on T.
+    pub fn into_inner(b: MyBox<T>) -> T {
+        b.0
+    }
+}
+
+impl<T> std::ops::Deref for MyBox<T> {
+    type Target = T;
+
+    fn deref(&self) -> &T {
+        &self.0
+    }
+}
+
+// The reason the deref method returns a reference to a value, and that the
+// plain dereference outside the parentheses in *(y.deref()) is still
+// necessary, is the ownership system.
+// If the deref method returned the value directly instead of a reference to
+// the value, the value would be moved out of self. We don’t want to take
+// ownership of the inner value inside MyBox<T> in this case or in most cases
+// where we use the dereference operator.
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn acts_like_the_value_inside() {
+        let b = MyBox::new(String::from("Rust"));
+
+        assert_eq!(MyBox::new(String::from("Rust")), b);
+        assert_eq!(b, b.clone());
+        assert_eq!("MyBox(\"Rust\")", format!("{:?}", b));
+        assert_eq!(4, b.len());
+        assert_eq!(MyBox::new(String::new()), MyBox::default());
+        assert_eq!(0, *MyBox::<i32>::default());
+    }
+
+    #[test]
+    fn orders_by_the_value_inside() {
+        assert!(MyBox::new(1) < MyBox::new(2));
+        assert!(MyBox::new("b") > MyBox::new("a"));
+        assert_eq!(
+            Some(std::cmp::Ordering::Less),
+            MyBox::new(1.0).partial_cmp(&MyBox::new(2.0))
+        );
+        assert_eq!(None, MyBox::new(f64::NAN).partial_cmp(&MyBox::new(1.0)));
+
+        let mut boxes = vec![MyBox::new(3), MyBox::new(1), MyBox::new(2)];
+        boxes.sort();
+        assert_eq!(vec![MyBox::new(1), MyBox::new(2), MyBox::new(3)], boxes);
+    }
+
+    #[test]
+    fn gives_its_value_back() {
+        let b = MyBox::new(vec![1, 2, 3]);
+        let v: Vec<i32> = MyBox::into_inner(b);
+        assert_eq!(vec![1, 2, 3], v);
+    }
+}
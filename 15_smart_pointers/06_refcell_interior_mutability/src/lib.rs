@@ -1,3 +1,7 @@
+mod list;
+
+pub use list::{Iter, List};
+
 // Use case for Interior MutabilityL Mock Objects
 
 pub trait Messenger {
@@ -14,7 +18,7 @@ impl<'a, T> LimitTracker<'a, T>
 where
     T: Messenger,
 {
-    pub fn new(messenger: &T, max: usize) -> LimitTracker<T> {
+    pub fn new(messenger: &T, max: usize) -> LimitTracker<'_, T> {
         LimitTracker {
             messenger,
             value: 0,
@@ -0,0 +1,81 @@
+use std::cell::RefCell;
+use std::rc::Rc;
+
+/// A cons list whose values can be changed through any of the lists
+/// sharing them. The Rc lets several lists own the same tail, and the
+/// RefCell lets each value be borrowed mutably even so.
+#[derive(Debug)]
+pub enum List {
+    Cons(Rc<RefCell<i32>>, Rc<List>),
+    Nil,
+}
+
+use List::{Cons, Nil};
+
+impl List {
+    /// The cells of the list from front to back. Each value is handed out
+    /// as its shared RefCell, so it can be read with `borrow` or changed
+    /// with `borrow_mut` even though the list itself is borrowed immutably.
+    pub fn iter(&self) -> Iter<'_> {
+        Iter { list: self }
+    }
+}
+
+/// Hands out each value of a List in turn, from `List::iter`.
+pub struct Iter<'a> {
+    // The cells not visited yet
+    list: &'a List,
+}
+
+impl<'a> Iterator for Iter<'a> {
+    type Item = &'a Rc<RefCell<i32>>;
+
+    fn next(&mut self) -> Option<&'a Rc<RefCell<i32>>> {
+        match self.list {
+            Cons(value, rest) => {
+                self.list = rest;
+                Some(value)
+            }
+            Nil => None,
+        }
+    }
+}
+
+impl<'a> IntoIterator for &'a List {
+    type Item = &'a Rc<RefCell<i32>>;
+    type IntoIter = Iter<'a>;
+
+    fn into_iter(self) -> Iter<'a> {
+        self.iter()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn cell(value: i32) -> Rc<RefCell<i32>> {
+        Rc::new(RefCell::new(value))
+    }
+
+    #[test]
+    fn changes_values_through_a_shared_list() {
+        let shared = Rc::new(Cons(cell(5), Rc::new(Nil)));
+        let b = Cons(cell(3), Rc::clone(&shared));
+        let c = Cons(cell(4), Rc::clone(&shared));
+
+        for value in b.iter() {
+            *value.borrow_mut() *= 10;
+        }
+        for value in &c {
+            *value.borrow_mut() += 1;
+        }
+
+        // b's change to the shared tail shows up in c too
+        let values: Vec<i32> = c.iter().map(|value| *value.borrow()).collect();
+        assert_eq!(vec![5, 51], values);
+        assert_eq!(81, b.iter().map(|value| *value.borrow()).sum::<i32>());
+        assert_eq!(51, *shared.iter().next().unwrap().borrow());
+        assert_eq!(0, Nil.iter().count());
+    }
+}
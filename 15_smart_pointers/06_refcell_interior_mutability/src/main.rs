@@ -45,13 +45,9 @@
 // RefCell<T>, you can get a value that can have multiple owners and
 // that you can mutate!
 
-#[derive(Debug)]
-enum List {
-    Cons(Rc<RefCell<i32>>, Rc<List>),
-    Nil,
-}
+// The list, List, is in list.rs
 
-use crate::List::{Cons, Nil};
+use refcell_interior_mutability::List::{Cons, Nil};
 use std::cell::RefCell;
 use std::rc::Rc;
 
@@ -72,6 +68,16 @@ fn main() {
     // a after = Cons(RefCell { value: 15 }, Nil)
     // b after = Cons(RefCell { value: 3 }, Cons(RefCell { value: 15 }, Nil))
     // c after = Cons(RefCell { value: 4 }, Cons(RefCell { value: 15 }, Nil))
+
+    // Going over a list hands out each shared value, so it can be changed
+    // along the way
+    for value in b.iter() {
+        *value.borrow_mut() *= 2;
+    }
+    let sum: i32 = c.iter().map(|value| *value.borrow()).sum();
+    println!("c sums to {}", sum);
+    // OUTPUT
+    // c sums to 34
 }
 
 // This technique is pretty neat! By using RefCell<T>, we have an
@@ -0,0 +1,224 @@
+use std::cell::RefCell;
+use std::rc::{Rc, Weak};
+
+// Each node owns the one after it through an Rc, and only points back at the
+// one before it through a Weak. If prev were an Rc too, every pair of
+// neighbours would be a reference cycle and no node would ever be dropped.
+type Link<T> = Rc<RefCell<Node<T>>>;
+
+struct Node<T> {
+    value: T,
+    next: Option<Link<T>>,
+    prev: Weak<RefCell<Node<T>>>,
+}
+
+/// A list that can be pushed onto and popped from at both ends.
+pub struct DoublyLinkedList<T> {
+    head: Option<Link<T>>,
+    // Weak as well, so the list's only strong hold on a node is through
+    // head and the next links
+    tail: Weak<RefCell<Node<T>>>,
+    len: usize,
+}
+
+impl<T> DoublyLinkedList<T> {
+    /// An empty list.
+    pub fn new() -> DoublyLinkedList<T> {
+        DoublyLinkedList {
+            head: None,
+            tail: Weak::new(),
+            len: 0,
+        }
+    }
+
+    /// Puts `value` at the front of the list.
+    pub fn push_front(&mut self, value: T) {
+        let node = Rc::new(RefCell::new(Node {
+            value,
+            next: self.head.take(),
+            prev: Weak::new(),
+        }));
+        match &node.borrow().next {
+            Some(old_head) => old_head.borrow_mut().prev = Rc::downgrade(&node),
+            None => self.tail = Rc::downgrade(&node),
+        }
+        self.head = Some(node);
+        self.len += 1;
+    }
+
+    /// Puts `value` at the back of the list.
+    pub fn push_back(&mut self, value: T) {
+        let node = Rc::new(RefCell::new(Node {
+            value,
+            next: None,
+            prev: self.tail.clone(),
+        }));
+        match self.tail.upgrade() {
+            Some(old_tail) => old_tail.borrow_mut().next = Some(Rc::clone(&node)),
+            None => self.head = Some(Rc::clone(&node)),
+        }
+        self.tail = Rc::downgrade(&node);
+        self.len += 1;
+    }
+
+    /// Takes the value off the front of the list, or `None` if it's empty.
+    pub fn pop_front(&mut self) -> Option<T> {
+        let node = self.head.take()?;
+        match node.borrow_mut().next.take() {
+            Some(next) => {
+                next.borrow_mut().prev = Weak::new();
+                self.head = Some(next);
+            }
+            None => self.tail = Weak::new(),
+        }
+        self.len -= 1;
+        Some(into_value(node))
+    }
+
+    /// Takes the value off the back of the list, or `None` if it's empty.
+    pub fn pop_back(&mut self) -> Option<T> {
+        let node = self.tail.upgrade()?;
+        match node.borrow().prev.upgrade() {
+            Some(prev) => {
+                prev.borrow_mut().next = None;
+                self.tail = Rc::downgrade(&prev);
+            }
+            None => {
+                self.head = None;
+                self.tail = Weak::new();
+            }
+        }
+        self.len -= 1;
+        Some(into_value(node))
+    }
+
+    /// How many values are in the list.
+    pub fn len(&self) -> usize {
+        self.len
+    }
+
+    /// Whether the list has no values.
+    pub fn is_empty(&self) -> bool {
+        self.len == 0
+    }
+}
+
+// Once a node is unlinked, the Rc in hand is the only strong one left
+fn into_value<T>(node: Link<T>) -> T {
+    Rc::into_inner(node)
+        .expect("an unlinked node has no other owners")
+        .into_inner()
+        .value
+}
+
+impl<T> Default for DoublyLinkedList<T> {
+    fn default() -> DoublyLinkedList<T> {
+        DoublyLinkedList::new()
+    }
+}
+
+// Dropping the head would drop each next node from inside the one before
+// it, which can overflow the stack on a long list. Unlinking them one at a
+// time doesn't.
+impl<T> Drop for DoublyLinkedList<T> {
+    fn drop(&mut self) {
+        while self.pop_front().is_some() {}
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    // A Weak to each node, front to back, so looking doesn't add an owner
+    fn nodes<T>(list: &DoublyLinkedList<T>) -> Vec<Weak<RefCell<Node<T>>>> {
+        let mut nodes = Vec::new();
+        let mut node = list.head.as_ref().map(Rc::downgrade);
+        while let Some(weak) = node {
+            let current = weak.upgrade().unwrap();
+            node = current.borrow().next.as_ref().map(Rc::downgrade);
+            nodes.push(weak);
+        }
+        nodes
+    }
+
+    #[test]
+    fn pushes_and_pops_at_both_ends() {
+        let mut list = DoublyLinkedList::new();
+        assert!(list.is_empty());
+        assert_eq!(None, list.pop_front());
+        assert_eq!(None, list.pop_back());
+
+        list.push_back(2);
+        list.push_front(1);
+        list.push_back(3);
+        assert_eq!(3, list.len());
+
+        assert_eq!(Some(3), list.pop_back());
+        assert_eq!(Some(1), list.pop_front());
+        list.push_front(0);
+        assert_eq!(Some(2), list.pop_back());
+        assert_eq!(Some(0), list.pop_back());
+        assert_eq!(None, list.pop_front());
+        assert!(list.is_empty());
+
+        list.push_front(4);
+        assert_eq!(Some(4), list.pop_back());
+        list.push_back(5);
+        assert_eq!(Some(5), list.pop_front());
+        assert_eq!(0, list.len());
+    }
+
+    #[test]
+    fn links_both_ways() {
+        let mut list = DoublyLinkedList::new();
+        for value in 1..=4 {
+            list.push_back(value);
+        }
+
+        let mut values = Vec::new();
+        let mut node = list.tail.upgrade();
+        while let Some(current) = node {
+            values.push(current.borrow().value);
+            node = current.borrow().prev.upgrade();
+        }
+        assert_eq!(vec![4, 3, 2, 1], values);
+    }
+
+    #[test]
+    fn does_not_leak() {
+        let mut list = DoublyLinkedList::new();
+        for value in 1..=3 {
+            list.push_back(value);
+            list.push_front(-value);
+        }
+
+        let nodes = nodes(&list);
+        assert_eq!(6, nodes.len());
+        for node in &nodes {
+            // Owned only by the list, and pointed back at only by the next
+            // node or the tail, besides the Weak kept here
+            assert_eq!(1, node.strong_count());
+            assert_eq!(2, node.weak_count());
+        }
+
+        let first = nodes[0].clone();
+        let last = nodes[5].clone();
+        list.pop_front();
+        list.pop_back();
+        assert_eq!(0, first.strong_count());
+        assert_eq!(0, last.strong_count());
+        assert_eq!(1, nodes[1].strong_count());
+
+        drop(list);
+        assert!(nodes.iter().all(|node| node.upgrade().is_none()));
+    }
+
+    #[test]
+    fn drops_a_long_list() {
+        let mut list = DoublyLinkedList::new();
+        for value in 0..1_000_000 {
+            list.push_back(value);
+        }
+    }
+}
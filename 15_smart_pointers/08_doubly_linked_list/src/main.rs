@@ -0,0 +1,26 @@
+// A doubly linked list, using what the earlier sections built up to
+//
+// Every node needs to be reachable from both of its neighbours, so no single
+// owner will do: Rc<T> lets the node before it and the node after it both
+// hold on to it, and RefCell<T> lets either of them change its links.
+// But a node pointing forward with an Rc and backward with another Rc would
+// make a reference cycle, and the list would never be cleaned up.
+// So only the forward links (next) are Rc<RefCell<Node>>, and the backward
+// links (prev) are Weak, which don't keep a node alive.
+//
+// The list itself is in list.rs
+
+use doubly_linked_list::DoublyLinkedList;
+
+fn main() {
+    let mut list = DoublyLinkedList::new();
+    list.push_back(2);
+    list.push_back(3);
+    list.push_front(1);
+    println!("list has {} items", list.len()); // 3
+
+    println!("front = {:?}", list.pop_front()); // Some(1)
+    println!("back = {:?}", list.pop_back()); // Some(3)
+    println!("back = {:?}", list.pop_back()); // Some(2)
+    println!("back = {:?}", list.pop_back()); // None
+}
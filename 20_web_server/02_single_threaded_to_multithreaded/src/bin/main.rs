@@ -1,48 +1,682 @@
-use std::fs;
+use std::env;
 use std::io::prelude::*;
+use std::io::{self, BufReader};
 use std::net::TcpListener;
 use std::net::TcpStream;
+use std::net::{IpAddr, SocketAddr};
+use std::process;
+use std::sync::Arc;
 use std::thread;
-use std::time::Duration;
+use std::time::{Duration, Instant, SystemTime};
 
-use hello::ThreadPool;
+use hello::{
+    escape_html, read_head, AccessEntry, AccessLog, BodyError, Compressor, ConnectionLimit, Cors,
+    EventLoop, FileCache, HeadError, Json, Metrics, PoolStats, QueuePolicy, Request, Response,
+    Router, ServerConfig, ServerMode, Sessions, Shutdown, Site, StaticFiles, StdoutLogger,
+    Template, ThreadPool, TlsAcceptor, ToJson, TrailingSlash, VirtualHosts,
+};
 
 fn main() {
-    let listener = TcpListener::bind("127.0.0.1:7878").unwrap();
-    let pool = ThreadPool::new(4);
-
-    // Quit after two successful requests according to the take(2)
-    for stream in listener.incoming().take(2) {
-        let stream = stream.unwrap();
+    let args: Vec<String> = env::args().skip(1).collect();
+    let config = ServerConfig::parse(&args, |name| env::var(name).ok()).unwrap_or_else(|err| {
+        eprintln!("Problem parsing arguments: {}", err);
+        process::exit(2);
+    });
+    if config.mode == ServerMode::EventLoop && config.tls.is_some() {
+        eprintln!("Problem parsing arguments: HTTPS isn't served in event-loop mode");
+        process::exit(2);
+    }
 
-        pool.execute(|| {
-            handle_connection(stream);
+    // Each listener, with the acceptor for the ones serving HTTPS
+    let mut listeners: Vec<(TcpListener, Option<TlsAcceptor>)> =
+        config.bind.iter().map(|&addr| (bind(addr), None)).collect();
+    if let Some(files) = &config.tls {
+        let acceptor = TlsAcceptor::from_pem_files(&files.cert, &files.key).unwrap_or_else(|err| {
+            eprintln!("Problem loading the TLS certificate: {}", err);
+            process::exit(2);
         });
+        for &addr in &config.tls_bind {
+            listeners.push((bind(addr), Some(acceptor.clone())));
+        }
+    }
+    for (listener, tls) in &listeners {
+        // With port 0 the system picks the port, so this is the only place
+        // to find out which
+        let scheme = if tls.is_some() { "https" } else { "http" };
+        println!(
+            "Listening on {}://{}.",
+            scheme,
+            listener.local_addr().unwrap()
+        );
+    }
+    let pool = ThreadPool::with_config(config.pool);
+    println!("Serving with {} threads.", pool.size());
+    let metrics = Arc::new(Metrics::new(pool.stats()));
+    // In dev mode pages are read again whenever they're edited, instead of
+    // once for good
+    let pages = if config.dev {
+        println!("Dev mode: reloading pages when they change.");
+        FileCache::watching()
+    } else {
+        FileCache::new()
+    };
+    let limit = config
+        .max_connections
+        .map(|max| ConnectionLimit::new(max.get()));
+    let mut router = routes(
+        pool.size(),
+        pool.stats(),
+        Arc::clone(&metrics),
+        limit.clone(),
+        &pages,
+    );
+    // The API can be called from pages served anywhere else
+    let cors = config
+        .cors_origins
+        .iter()
+        .fold(Cors::new(), |cors, origin| cors.allow_origin(origin));
+    router.cors("/api/*path", cors.max_age(Duration::from_secs(600)));
+    let mut hosts = VirtualHosts::new(router);
+    for site in &config.sites {
+        hosts.add(&site.host, site_routes(site));
+        println!("Serving {} from {}.", site.host, site.root.display());
+    }
+    // Every worker needs the routes and settings, so they're shared behind
+    // an Arc
+    let server = Arc::new(Server {
+        hosts,
+        access_log: config
+            .access_log
+            .map(|format| AccessLog::new(format, StdoutLogger)),
+        read_timeout: config.read_timeout,
+        write_timeout: config.write_timeout,
+        max_head: config.max_head,
+        max_body: config.max_body,
+        compressor: Compressor::default(),
+        metrics,
+        pages,
+    });
+
+    // Ctrl-C stops every listener from taking new connections; the
+    // requests already handed to the pool still get answered
+    let shutdown = Shutdown::for_listeners(listeners.iter().map(|(listener, _)| listener)).unwrap();
+    let handle = shutdown.clone();
+    ctrlc::set_handler(move || {
+        println!("Received Ctrl-C, finishing the requests in flight.");
+        handle.trigger();
+    })
+    .expect("couldn't set the Ctrl-C handler");
+    #[cfg(unix)]
+    report_workers_on_signal(pool.stats());
+
+    match config.mode {
+        // Each listener accepts on its own thread, and they all hand their
+        // connections to the same pool
+        ServerMode::Pool => {
+            let when_full = config.when_full;
+            thread::scope(|scope| {
+                for (listener, tls) in &listeners {
+                    let (shutdown, pool, server) = (&shutdown, &pool, &server);
+                    let limit = limit.as_ref();
+                    scope.spawn(move || {
+                        accept(
+                            listener,
+                            shutdown,
+                            pool,
+                            server,
+                            when_full,
+                            limit,
+                            tls.as_ref(),
+                        )
+                    });
+                }
+            });
+        }
+        // The pool sits idle, and everything is served from this thread
+        ServerMode::EventLoop => {
+            println!("Serving from an event loop.");
+            let listeners = listeners.into_iter().map(|(listener, _)| listener);
+            if let Err(err) = run_event_loop(listeners.collect(), &shutdown, &server, limit) {
+                eprintln!("Problem running the event loop: {}", err);
+            }
+        }
     }
 
     println!("Shutting down.");
+
+    // Dropping the pool waits for every worker to finish its current job
+    drop(pool);
+    println!("Server stopped.");
 }
 
-fn handle_connection(mut stream: TcpStream) {
-    let mut buffer = [0; 1024];
-    stream.read(&mut buffer).unwrap();
+fn bind(addr: SocketAddr) -> TcpListener {
+    TcpListener::bind(addr).unwrap_or_else(|err| {
+        eprintln!("Problem listening on {}: {}", addr, err);
+        process::exit(2);
+    })
+}
 
-    let get = b"GET / HTTP/1.1\r\n";
-    let sleep = b"GET /sleep HTTP/1.1\r\n";
+// Hands each connection `listener` accepts to the pool, until shutdown is
+// requested. With an acceptor, the connections are TLS ones. With a limit,
+// each connection holds a place under it until it's closed.
+fn accept(
+    listener: &TcpListener,
+    shutdown: &Shutdown,
+    pool: &ThreadPool,
+    server: &Arc<Server>,
+    when_full: QueuePolicy,
+    limit: Option<&ConnectionLimit>,
+    tls: Option<&TlsAcceptor>,
+) {
+    for stream in listener.incoming() {
+        if shutdown.is_requested() {
+            break;
+        }
 
-    let (status_line, filename) = if buffer.starts_with(get) {
-        ("HTTP/1.1 200 OK\r\n\r\n", "hello.html")
-    } else if buffer.starts_with(sleep) {
-        thread::sleep(Duration::from_secs(5));
-        ("HTTP/1.1 200 OK\r\n\r\n", "hello.html")
-    } else {
-        ("HTTP/1.1 404 NOT FOUND\r\n\r\n", "404.html")
+        let stream = match stream {
+            Ok(stream) => stream,
+            Err(e) => {
+                eprintln!("Couldn't accept a connection: {}", e);
+                continue;
+            }
+        };
+        // Blocking here stops any more connections from being accepted
+        // until one closes. A shutdown can't wake it up the way it wakes
+        // up accepting, so it's checked while waiting.
+        let permit = match (limit, when_full) {
+            (None, _) => None,
+            (Some(limit), QueuePolicy::Block) => {
+                let every = Duration::from_millis(100);
+                match limit.acquire_unless(every, || shutdown.is_requested()) {
+                    Some(permit) => Some(permit),
+                    None => break,
+                }
+            }
+            (Some(limit), QueuePolicy::Reject) => match limit.try_acquire() {
+                Some(permit) => Some(permit),
+                None => {
+                    turn_away(stream, tls.is_none(), &server.metrics);
+                    continue;
+                }
+            },
+        };
+        // Kept back to answer with in case the queue turns out to be full.
+        // Cloning needs another file descriptor, which an overloaded server
+        // can run out of; then the connection is just closed.
+        let busy = if when_full == QueuePolicy::Reject {
+            match stream.try_clone() {
+                Ok(busy) => Some(busy),
+                Err(e) => {
+                    eprintln!("Couldn't take on a connection: {}", e);
+                    continue;
+                }
+            }
+        } else {
+            None
+        };
+        let (plain, metrics) = (tls.is_none(), Arc::clone(&server.metrics));
+        let server = Arc::clone(server);
+        let tls = tls.cloned();
+        let job = move || {
+            let _permit = permit;
+            serve(stream, &server, tls.as_ref())
+        };
+
+        match busy {
+            None => {
+                pool.execute(job);
+            }
+            Some(busy) => {
+                if pool.try_execute(job).is_err() {
+                    turn_away(busy, plain, &metrics);
+                }
+            }
+        }
+    }
+}
+
+// Answers a connection there's no room for with 503 Service Unavailable.
+// Over TLS there's no answering without a handshake, which would hold up
+// the accepting thread, so those are just closed.
+fn turn_away(mut stream: TcpStream, plain: bool, metrics: &Metrics) {
+    if !plain {
+        return;
+    }
+    // Answered from the accepting thread, so a client that won't take the
+    // answer mustn't hold up accepting the next connection. If the timeout
+    // can't be set, the answer goes out without one.
+    let _ = stream.set_write_timeout(Some(Duration::from_secs(1)));
+    let _ = Response::new(503, "Service Unavailable")
+        .header("Retry-After", "1")
+        .header("Connection", "close")
+        .write_to(&mut stream);
+    metrics.record(503);
+}
+
+// What every connection is handled with
+struct Server {
+    hosts: VirtualHosts,
+    access_log: Option<AccessLog>,
+    read_timeout: Duration,
+    write_timeout: Duration,
+    max_head: usize,
+    max_body: usize,
+    compressor: Compressor,
+    metrics: Arc<Metrics>,
+    pages: FileCache,
+}
+
+fn routes(
+    threads: usize,
+    stats: PoolStats,
+    metrics: Arc<Metrics>,
+    limit: Option<ConnectionLimit>,
+    pages: &FileCache,
+) -> Router {
+    let mut router = Router::new();
+    let started = Instant::now();
+    // A handler that answers with one of the pages
+    let page = |status: u16, filename: &'static str| {
+        let pages = pages.clone();
+        move |_: &Request| html(&pages, status, filename)
     };
 
-    let contents = fs::read_to_string(filename).unwrap();
+    let hello = {
+        let (pages, metrics) = (pages.clone(), Arc::clone(&metrics));
+        move |req: &Request| hello_page(&pages, &metrics, req)
+    };
+    let sleepy = hello.clone();
+    router.get("/", hello);
+    router.get("/sleep", move |req| {
+        thread::sleep(Duration::from_secs(5));
+        sleepy(req)
+    });
+    router.get("/users/:id", |req| {
+        Response::ok(format!("Hello, user {}!", req.param("id").unwrap()))
+    });
+    router.get("/search", |req| {
+        let terms: Vec<&str> = req.query.get_all("q").collect();
+        let limit = req.query.get_as::<usize>("limit").unwrap_or(10);
+
+        Response::ok(format!(
+            "Searching for {:?}, showing up to {} results",
+            terms, limit
+        ))
+    });
+    // Sends back whatever was posted to it
+    router.post("/echo", |req| {
+        let content_type = req
+            .header("Content-Type")
+            .unwrap_or("application/octet-stream");
+
+        Response::ok(req.body.clone()).header("Content-Type", content_type)
+    });
+    // Counts each visitor's visits, kept in their session
+    let sessions = Sessions::new(Duration::from_secs(30 * 60));
+    router.get(
+        "/visits",
+        sessions.handler(|_, session| {
+            let visits = session.get_as::<u64>("visits").unwrap_or(0) + 1;
+            session.set("visits", visits);
+            let times = if visits == 1 { "time" } else { "times" };
+            Response::ok(format!("You've been here {} {}.", visits, times))
+        }),
+    );
+    // Where /old used to be
+    router.get("/old", |_| Response::redirect(301, "/new"));
+    router.get("/new", |_| Response::ok("This page used to be at /old."));
+    router.get("/form", page(200, "form.html"));
+    router.post("/form", |req| match req.form() {
+        Some(fields) => {
+            let name = fields.get("name").filter(|name| !name.is_empty());
+            let message = fields.get("message").unwrap_or("");
+
+            Response::ok(thanks_page(name.unwrap_or("stranger"), message))
+                .header("Content-Type", "text/html; charset=utf-8")
+        }
+        None => Response::new(400, "Expected a form"),
+    });
+    let workers = stats.clone();
+    router.get("/api/status", move |_| {
+        Response::json(&Status {
+            version: env!("CARGO_PKG_VERSION"),
+            uptime: started.elapsed(),
+            threads,
+            panics_total: stats.panics_total(),
+            connections: limit.as_ref().map(|limit| (limit.open(), limit.max())),
+        })
+    });
+    router.get("/debug/workers", move |_| {
+        Response::ok(worker_report(&workers)).header("Content-Type", "text/plain; charset=utf-8")
+    });
+    router.get("/metrics", move |_| {
+        Response::ok(metrics.render()).header("Content-Type", "text/plain; version=0.0.4")
+    });
+    // Sends a few server-sent events, a second apart, as one streamed body
+    router.get("/events", |_| {
+        Response::stream(200, |out| {
+            for tick in 1..=5 {
+                let event = format!("data: tick {}\n\n", tick);
+                out.write_all(event.as_bytes())?;
+                thread::sleep(Duration::from_secs(1));
+            }
+            Ok(())
+        })
+        .header("Content-Type", "text/event-stream")
+        .header("Cache-Control", "no-cache")
+    });
+    let files = StaticFiles::new("static").unwrap();
+    router.get("/static/*path", move |req| {
+        files.respond(req, req.param("path").unwrap())
+    });
+    // Each page has one address, the one without a slash at the end
+    router.trailing_slash(TrailingSlash::Strip);
+    router.not_found(page(404, "404.html"));
+    router.server_error(page(500, "500.html"));
+
+    router
+}
+
+// What each of the pool's workers has been up to, one line each
+fn worker_report(stats: &PoolStats) -> String {
+    let mut report = format!("{} jobs waiting in the queue.\n", stats.queue_depth());
+    for worker in stats.workers() {
+        let last_job = worker.last_job.map_or_else(
+            || String::from("-"),
+            |took| format!("{:.3}s", took.as_secs_f64()),
+        );
+        report.push_str(&format!(
+            "Worker {}: {} jobs, busy for {:.3}s, last job took {}.\n",
+            worker.id,
+            worker.jobs,
+            worker.busy.as_secs_f64(),
+            last_job
+        ));
+    }
+    report
+}
+
+// Prints the worker report whenever the process gets SIGUSR1, as in
+// `kill -USR1 <pid>`
+#[cfg(unix)]
+fn report_workers_on_signal(stats: PoolStats) {
+    use signal_hook::consts::SIGUSR1;
+    use signal_hook::iterator::Signals;
+
+    let mut signals = Signals::new([SIGUSR1]).expect("couldn't set the SIGUSR1 handler");
+    thread::spawn(move || {
+        for _ in signals.forever() {
+            print!("{}", worker_report(&stats));
+        }
+    });
+}
+
+// The routes for a site given with --site, which is just its files, with
+// index.html standing in for the root
+fn site_routes(site: &Site) -> Router {
+    let mut router = Router::new();
+    let files = StaticFiles::new(&site.root).unwrap_or_else(|err| {
+        eprintln!("Problem opening {}: {}", site.root.display(), err);
+        process::exit(2);
+    });
+
+    let index = files.clone();
+    router.get("/", move |req| index.respond(req, "index.html"));
+    router.get("/*path", move |req| {
+        files.respond(req, req.param("path").unwrap())
+    });
+
+    router
+}
+
+// What /api/status reports about the running server
+struct Status {
+    version: &'static str,
+    uptime: Duration,
+    threads: usize,
+    panics_total: usize,
+    // How many connections are open, and the most that may be, when
+    // there's a limit
+    connections: Option<(usize, usize)>,
+}
+
+impl ToJson for Status {
+    fn to_json(&self) -> Json {
+        Json::object(vec![
+            ("status", "ok".to_json()),
+            ("version", self.version.to_json()),
+            ("uptime_secs", self.uptime.as_secs().to_json()),
+            ("threads", self.threads.to_json()),
+            ("panics_total", self.panics_total.to_json()),
+            (
+                "connections_open",
+                self.connections.map(|(open, _)| open).to_json(),
+            ),
+            (
+                "max_connections",
+                self.connections.map(|(_, max)| max).to_json(),
+            ),
+        ])
+    }
+}
+
+// The home page, filled in with who's asking and how busy the server's been
+fn hello_page(pages: &FileCache, metrics: &Metrics, req: &Request) -> Response {
+    let text = String::from_utf8(pages.read("hello.html").unwrap()).unwrap();
+    let template = Template::parse(&text).unwrap_or_else(|e| panic!("hello.html: {}", e));
+    let client = req.client.map(|ip| ip.to_string());
+    let links: Vec<Json> = [
+        ("/form", "Leave a message"),
+        ("/visits", "Count your visits"),
+        ("/api/status", "Server status"),
+        ("/metrics", "Metrics"),
+        ("/events", "A stream of events"),
+    ]
+    .iter()
+    .map(|&(href, title)| Json::object(vec![("href", href.to_json()), ("title", title.to_json())]))
+    .collect();
+
+    let data = Json::object(vec![
+        ("client", client.to_json()),
+        // Counting this one, which isn't recorded until it's sent
+        ("requests", (metrics.requests_total() + 1).to_json()),
+        ("links", Json::Array(links)),
+    ]);
+    Response::ok(template.render(&data)).header("Content-Type", "text/html; charset=utf-8")
+}
+
+// A response with the HTML page in `filename`
+fn html(pages: &FileCache, status: u16, filename: &str) -> Response {
+    Response::new(status, pages.read(filename).unwrap())
+        .header("Content-Type", "text/html; charset=utf-8")
+}
 
-    let response = format!("{}{}", status_line, contents);
+// The page shown after the form on /form is sent
+fn thanks_page(name: &str, message: &str) -> String {
+    let mut html = String::from("<!DOCTYPE html>\n<html lang=\"en\">\n\n");
+    html.push_str("<head>\n    <meta charset=\"utf-8\">\n    <title>Thanks!</title>\n</head>\n\n");
+    html.push_str(&format!(
+        "<body>\n    <h1>Thanks, {}!</h1>\n",
+        escape_html(name)
+    ));
+    html.push_str(&format!("    <p>You said: {}</p>\n", escape_html(message)));
+    html.push_str("    <p><a href=\"/form\">Say something else</a></p>\n</body>\n\n</html>\n");
+    html
+}
+
+fn serve(stream: TcpStream, server: &Server, tls: Option<&TlsAcceptor>) {
+    let _in_flight = server.metrics.connection();
+    // A client that goes quiet, or stops taking the response, ties up a
+    // worker, so give up on it after a while
+    stream.set_read_timeout(Some(server.read_timeout)).unwrap();
+    stream
+        .set_write_timeout(Some(server.write_timeout))
+        .unwrap();
+    let client = stream.peer_addr().ok().map(|addr| addr.ip());
+
+    match tls {
+        Some(acceptor) => match acceptor.accept(stream) {
+            Ok(stream) => handle_connection(stream, client, server),
+            Err(e) => eprintln!("Couldn't start a TLS session: {}", e),
+        },
+        None => handle_connection(stream, client, server),
+    }
+}
+
+fn handle_connection<S: Read + Write>(stream: S, client: Option<IpAddr>, server: &Server) {
+    // Responses are written through the reader, which owns the stream
+    let mut reader = BufReader::new(stream);
+
+    loop {
+        let head = match read_head(&mut reader, server.max_head) {
+            Ok(Some(head)) => head,
+            // Let the client know why the connection is going away. This
+            // is best effort: it may well not be listening anymore.
+            Err(HeadError::Io(e)) if timed_out(&e) => {
+                let response = request_timeout().header("Connection", "close");
+                let _ = response.write_to(reader.get_mut());
+                server.metrics.record(408);
+                return;
+            }
+            // The rest of the head is still waiting to be read, so the
+            // connection can't be used for another request
+            Err(e @ HeadError::TooLarge) => {
+                let response = Response::new(e.status(), e.to_string());
+                let _ = response
+                    .header("Connection", "close")
+                    .write_to(reader.get_mut());
+                server.metrics.record(e.status());
+                return;
+            }
+            // The client hung up, or broke off in the middle of a request
+            Ok(None) | Err(HeadError::Io(_)) => return,
+        };
+        let (time, started) = (SystemTime::now(), Instant::now());
+
+        let request = Request::parse(&head);
+        // HEAD is answered like GET, but without the body
+        let head_only = request.as_ref().is_some_and(|r| r.method == "HEAD");
+        // Kept for the access log, since the router takes the request
+        let (method, path, version) = request.as_ref().map_or_else(Default::default, |r| {
+            (r.method.clone(), r.path.clone(), r.version.clone())
+        });
+
+        let (response, keep_alive) = match request {
+            Some(mut request) => match request.read_body(&mut reader, server.max_body) {
+                // Without a Content-Length there's no telling where a
+                // chunked body ends and the next request starts
+                Ok(()) => {
+                    let keep_alive =
+                        request.keep_alive() && request.header("Transfer-Encoding").is_none();
+                    let accept_encoding = request.header("Accept-Encoding").map(String::from);
+                    request.client = client;
+                    let response = server.hosts.handle(request);
+                    let response = server
+                        .compressor
+                        .apply(accept_encoding.as_deref(), response);
+                    (response, keep_alive)
+                }
+                Err(BodyError::Io(e)) if timed_out(&e) => (request_timeout(), false),
+                Err(BodyError::Io(_)) => return,
+                // The body, or what's left of it, is still waiting to be
+                // read, so the connection can't be used for another request
+                Err(e) => (Response::new(e.status(), e.to_string()), false),
+            },
+            // There's no telling where a garbled request ends, so the
+            // connection can't be trusted with another
+            None => (html(&server.pages, 400, "400.html"), false),
+        };
+        let response = if keep_alive {
+            response
+        } else {
+            response.header("Connection", "close")
+        };
+
+        let status = response.status;
+        let sent = if head_only {
+            response.write_head_to(reader.get_mut()).map(|()| 0)
+        } else {
+            response.write_to(reader.get_mut())
+        };
+        server.metrics.record(status);
+        if let Some(access_log) = &server.access_log {
+            access_log.record(&AccessEntry {
+                client,
+                time,
+                method,
+                path,
+                version,
+                status,
+                size: *sent.as_ref().unwrap_or(&0),
+                latency: started.elapsed(),
+            });
+        }
+
+        if sent.is_err() || !keep_alive {
+            return;
+        }
+    }
+}
+
+// Serves every connection on this thread, with the same routes, logging and
+// metrics as the pool
+fn run_event_loop(
+    listeners: Vec<TcpListener>,
+    shutdown: &Shutdown,
+    server: &Server,
+    limit: Option<ConnectionLimit>,
+) -> io::Result<()> {
+    let mut event_loop = EventLoop::new(listeners)?
+        .max_head(server.max_head)
+        .max_body(server.max_body)
+        .idle_timeout(server.read_timeout);
+    if let Some(limit) = limit {
+        event_loop = event_loop.connection_limit(limit);
+    }
+
+    event_loop.run(shutdown, |request| {
+        let (time, started) = (SystemTime::now(), Instant::now());
+        let (client, method, path, version) = (
+            request.client,
+            request.method.clone(),
+            request.path.clone(),
+            request.version.clone(),
+        );
+        let accept_encoding = request.header("Accept-Encoding").map(String::from);
+        let response = server.hosts.handle(request);
+        let response = server
+            .compressor
+            .apply(accept_encoding.as_deref(), response);
+
+        server.metrics.record(response.status);
+        if let Some(access_log) = &server.access_log {
+            // The response hasn't been sent yet, so this is the size of its
+            // body rather than what went out: HEAD requests and streamed
+            // bodies are logged a little off
+            access_log.record(&AccessEntry {
+                client,
+                time,
+                method,
+                path,
+                version,
+                status: response.status,
+                size: response.body.len() as u64,
+                latency: started.elapsed(),
+            });
+        }
+        response
+    })
+}
+
+fn request_timeout() -> Response {
+    Response::new(408, "Request Timeout")
+}
 
-    stream.write(response.as_bytes()).unwrap();
-    stream.flush().unwrap();
+// Whether a read or write gave up because of the socket's timeout, which
+// shows up as WouldBlock on some platforms and TimedOut on others
+fn timed_out(e: &io::Error) -> bool {
+    matches!(
+        e.kind(),
+        io::ErrorKind::WouldBlock | io::ErrorKind::TimedOut
+    )
 }
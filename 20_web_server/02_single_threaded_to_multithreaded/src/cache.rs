@@ -0,0 +1,132 @@
+use std::collections::HashMap;
+use std::fs;
+use std::io;
+use std::path::{Path, PathBuf};
+use std::sync::{Arc, Mutex};
+use std::time::SystemTime;
+
+// A file as it was when it was last read
+#[derive(Debug)]
+struct Cached {
+    contents: Arc<Vec<u8>>,
+    modified: Option<SystemTime>,
+}
+
+/// Keeps the contents of files in memory, so each is only read from disk
+/// once.
+///
+/// A cache made with `watching` checks a file's modification time every
+/// time it's asked for, and reads it again if it has changed. That's for
+/// development, where pages are being edited while the server runs.
+///
+/// Cloning is cheap, and clones share the same cache.
+#[derive(Debug, Clone)]
+pub struct FileCache {
+    files: Arc<Mutex<HashMap<PathBuf, Cached>>>,
+    watch: bool,
+}
+
+impl FileCache {
+    /// A cache that keeps what it read for good.
+    pub fn new() -> FileCache {
+        FileCache {
+            files: Arc::new(Mutex::new(HashMap::new())),
+            watch: false,
+        }
+    }
+
+    /// A cache that reads files again once they've changed.
+    pub fn watching() -> FileCache {
+        FileCache {
+            watch: true,
+            ..FileCache::new()
+        }
+    }
+
+    /// The contents of the file at `path`.
+    pub fn read(&self, path: impl AsRef<Path>) -> io::Result<Vec<u8>> {
+        let path = path.as_ref();
+        let modified = if self.watch {
+            fs::metadata(path)?.modified().ok()
+        } else {
+            None
+        };
+
+        if let Some(cached) = self.files.lock().unwrap().get(path) {
+            // Without a modification time there's no telling whether it
+            // changed, so it's read again
+            if !self.watch || (modified.is_some() && cached.modified == modified) {
+                return Ok(cached.contents.to_vec());
+            }
+        }
+
+        // Read without holding the lock, so other files can be had in the
+        // meantime. Two threads may both read a file that just changed,
+        // which is harmless.
+        let contents = Arc::new(fs::read(path)?);
+        self.files.lock().unwrap().insert(
+            path.to_path_buf(),
+            Cached {
+                contents: Arc::clone(&contents),
+                modified,
+            },
+        );
+        Ok(contents.to_vec())
+    }
+}
+
+impl Default for FileCache {
+    fn default() -> FileCache {
+        FileCache::new()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::env;
+    use std::fs::File;
+    use std::time::Duration;
+
+    // Writes `contents` to `path`, dated `secs` seconds after the epoch so
+    // the change shows up however coarse the filesystem's clock is
+    fn write(path: &Path, contents: &str, secs: u64) {
+        fs::write(path, contents).unwrap();
+        let file = File::options().write(true).open(path).unwrap();
+        file.set_modified(SystemTime::UNIX_EPOCH + Duration::from_secs(secs))
+            .unwrap();
+    }
+
+    fn temp_file(name: &str) -> PathBuf {
+        env::temp_dir().join(format!("hello-cache-{}-{}", name, std::process::id()))
+    }
+
+    #[test]
+    fn keeps_what_it_read() {
+        let path = temp_file("keeps");
+        write(&path, "first", 1_000_000);
+        let cache = FileCache::new();
+
+        assert_eq!(b"first".to_vec(), cache.read(&path).unwrap());
+        write(&path, "second", 2_000_000);
+        assert_eq!(b"first".to_vec(), cache.read(&path).unwrap());
+        // Clones share what's cached
+        assert_eq!(b"first".to_vec(), cache.clone().read(&path).unwrap());
+
+        fs::remove_file(path).unwrap();
+    }
+
+    #[test]
+    fn watching_picks_up_changes() {
+        let path = temp_file("watching");
+        write(&path, "first", 1_000_000);
+        let cache = FileCache::watching();
+
+        assert_eq!(b"first".to_vec(), cache.read(&path).unwrap());
+        write(&path, "second", 2_000_000);
+        assert_eq!(b"second".to_vec(), cache.read(&path).unwrap());
+
+        fs::remove_file(&path).unwrap();
+        assert!(cache.read(&path).is_err());
+    }
+}
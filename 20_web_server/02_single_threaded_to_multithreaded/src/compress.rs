@@ -0,0 +1,223 @@
+use std::io::{self, Write};
+
+use flate2::write::GzEncoder;
+
+use crate::http::Response;
+
+/// A way of encoding response bodies, named by the token that goes in
+/// `Accept-Encoding` and `Content-Encoding`.
+pub trait Encoder: Send + Sync {
+    fn name(&self) -> &'static str;
+
+    fn encode(&self, body: &[u8]) -> io::Result<Vec<u8>>;
+}
+
+/// Leaves bodies as they are.
+#[derive(Debug, Clone, Copy, Default)]
+pub struct Identity;
+
+impl Encoder for Identity {
+    fn name(&self) -> &'static str {
+        "identity"
+    }
+
+    fn encode(&self, body: &[u8]) -> io::Result<Vec<u8>> {
+        Ok(body.to_vec())
+    }
+}
+
+/// Compresses bodies with gzip.
+#[derive(Debug, Clone, Copy)]
+pub struct Gzip {
+    /// From 0, no compression, to 9, the most and slowest
+    pub level: u32,
+}
+
+impl Default for Gzip {
+    fn default() -> Gzip {
+        Gzip { level: 6 }
+    }
+}
+
+impl Encoder for Gzip {
+    fn name(&self) -> &'static str {
+        "gzip"
+    }
+
+    fn encode(&self, body: &[u8]) -> io::Result<Vec<u8>> {
+        let mut encoder = GzEncoder::new(Vec::new(), flate2::Compression::new(self.level));
+        encoder.write_all(body)?;
+        encoder.finish()
+    }
+}
+
+/// Encodes responses with the first of its encoders the client accepts.
+///
+/// Only responses worth it are touched: ones with a text-like Content-Type
+/// and a body of at least `min_size` bytes, that aren't streamed, encoded
+/// already, or just part of a body.
+pub struct Compressor {
+    encoders: Vec<Box<dyn Encoder>>,
+    min_size: usize,
+}
+
+impl Compressor {
+    /// A compressor that tries `encoders` in order of preference.
+    pub fn new(encoders: Vec<Box<dyn Encoder>>, min_size: usize) -> Compressor {
+        Compressor { encoders, min_size }
+    }
+
+    /// Encodes `response` for a client that sent `accept_encoding`, the
+    /// value of its Accept-Encoding header, if any.
+    pub fn apply(&self, accept_encoding: Option<&str>, response: Response) -> Response {
+        if !self.worth_encoding(&response) {
+            return response;
+        }
+        // Whatever the client says, the answer depends on what it says
+        let response = response.header("Vary", "Accept-Encoding");
+
+        let encoder = match accept_encoding.and_then(|accept| {
+            self.encoders
+                .iter()
+                .find(|encoder| accepts(accept, encoder.name()))
+        }) {
+            Some(encoder) if encoder.name() != "identity" => encoder,
+            _ => return response,
+        };
+
+        let mut response = response;
+        // Sending the body as it is always works, so a failure isn't fatal
+        if let Ok(body) = encoder.encode(&response.body) {
+            response.body = body;
+            response = response.header("Content-Encoding", encoder.name());
+        }
+        response
+    }
+
+    fn worth_encoding(&self, response: &Response) -> bool {
+        // A part of a body is a part of it as it is, not compressed
+        response.status != 206
+            && response.body.len() >= self.min_size
+            && !response.is_stream()
+            && response.header_value("Content-Encoding").is_none()
+            && response
+                .header_value("Content-Type")
+                .is_some_and(is_compressible)
+    }
+}
+
+impl Default for Compressor {
+    /// Gzip, for bodies of 1 KiB and up.
+    fn default() -> Compressor {
+        Compressor::new(vec![Box::new(Gzip::default())], 1024)
+    }
+}
+
+// Whether there's anything to gain from compressing this type of content.
+// Images other than SVG, archives and the like are compressed already.
+fn is_compressible(content_type: &str) -> bool {
+    let mime = content_type.split(';').next().unwrap().trim();
+
+    mime.starts_with("text/")
+        || matches!(
+            mime,
+            "application/json" | "application/javascript" | "application/xml" | "image/svg+xml"
+        )
+}
+
+// Whether an Accept-Encoding value like `gzip;q=0.8, br, *;q=0` allows
+// `name`. An encoding named outright goes by its own weight, anything else
+// by the weight of `*`.
+fn accepts(accept_encoding: &str, name: &str) -> bool {
+    let mut wildcard = None;
+
+    for item in accept_encoding.split(',') {
+        let mut parts = item.split(';');
+        let token = parts.next().unwrap().trim();
+        let weight = parts
+            .filter_map(|param| param.trim().strip_prefix("q="))
+            .filter_map(|q| q.trim().parse::<f32>().ok())
+            .next()
+            .unwrap_or(1.0);
+
+        if token.eq_ignore_ascii_case(name) {
+            return weight > 0.0;
+        }
+        if token == "*" {
+            wildcard = Some(weight > 0.0);
+        }
+    }
+
+    wildcard.unwrap_or(false)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use flate2::read::GzDecoder;
+    use std::io::Read;
+
+    fn page(size: usize) -> Response {
+        Response::ok("<p>hello</p>".repeat(size / 12 + 1)).header("Content-Type", "text/html")
+    }
+
+    fn encoding(response: &Response) -> Option<&str> {
+        response.header_value("Content-Encoding")
+    }
+
+    #[test]
+    fn gzips_text_for_clients_that_accept_it() {
+        let original = page(4096).body;
+        let response = Compressor::default().apply(Some("gzip, deflate"), page(4096));
+
+        assert_eq!(Some("gzip"), encoding(&response));
+        assert_eq!(Some("Accept-Encoding"), response.header_value("Vary"));
+        assert!(response.body.len() < original.len());
+
+        let mut decoded = Vec::new();
+        GzDecoder::new(&response.body[..])
+            .read_to_end(&mut decoded)
+            .unwrap();
+        assert_eq!(original, decoded);
+    }
+
+    #[test]
+    fn leaves_alone_what_isnt_worth_it() {
+        let compressor = Compressor::default();
+
+        assert_eq!(None, encoding(&compressor.apply(Some("gzip"), page(100))));
+        assert_eq!(None, encoding(&compressor.apply(None, page(4096))));
+        assert_eq!(None, encoding(&compressor.apply(Some("br"), page(4096))));
+
+        let png = Response::ok(vec![0; 4096]).header("Content-Type", "image/png");
+        assert_eq!(None, encoding(&compressor.apply(Some("gzip"), png)));
+        let untyped = Response::ok(vec![b'a'; 4096]);
+        assert_eq!(None, encoding(&compressor.apply(Some("gzip"), untyped)));
+    }
+
+    #[test]
+    fn identity_is_interchangeable_with_gzip() {
+        let compressor = Compressor::new(vec![Box::new(Identity), Box::new(Gzip::default())], 0);
+
+        // Identity comes first, so it wins whenever it's acceptable
+        assert_eq!(
+            None,
+            encoding(&compressor.apply(Some("identity, gzip"), page(4096)))
+        );
+        assert_eq!(
+            Some("gzip"),
+            encoding(&compressor.apply(Some("gzip, identity;q=0"), page(4096)))
+        );
+    }
+
+    #[test]
+    fn reads_weights() {
+        assert!(accepts("gzip", "gzip"));
+        assert!(accepts("deflate, GZIP;q=0.5", "gzip"));
+        assert!(!accepts("gzip;q=0", "gzip"));
+        assert!(accepts("*", "gzip"));
+        assert!(!accepts("*;q=0, br", "gzip"));
+        assert!(!accepts("gzip;q=0, *", "gzip"));
+        assert!(!accepts("", "gzip"));
+    }
+}
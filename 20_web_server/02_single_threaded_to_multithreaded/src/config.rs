@@ -0,0 +1,623 @@
+use std::net::SocketAddr;
+use std::num::NonZeroUsize;
+use std::path::PathBuf;
+use std::str::FromStr;
+use std::time::Duration;
+
+use crate::log::LogFormat;
+use crate::PoolConfig;
+
+/// Settings for running the server, taken from the command line and the
+/// environment.
+#[derive(Debug, Clone, PartialEq)]
+pub struct ServerConfig {
+    /// Where to listen for plain HTTP, at least one address. Port 0 leaves
+    /// the choice of port to the system.
+    pub bind: Vec<SocketAddr>,
+    /// Where to listen for HTTPS, when it's turned on
+    pub tls_bind: Vec<SocketAddr>,
+    pub pool: PoolConfig,
+    /// How to write the access log, or None to not keep one
+    pub access_log: Option<LogFormat>,
+    /// How long to wait on a client for the next bit of a request, or for
+    /// the next request on a kept-alive connection
+    pub read_timeout: Duration,
+    /// How long to wait on a client that isn't taking the response
+    pub write_timeout: Duration,
+    /// The most bytes a request line and headers may take up together
+    pub max_head: usize,
+    /// The most bytes a request body may take up
+    pub max_body: usize,
+    /// What to do with new connections while the pool's queue is full, or
+    /// while max_connections are already open
+    pub when_full: QueuePolicy,
+    /// The most connections that may be open at once, or None for no limit
+    pub max_connections: Option<NonZeroUsize>,
+    /// Where to find the certificate and key for serving HTTPS, or None to
+    /// only serve plain HTTP
+    pub tls: Option<TlsFiles>,
+    /// Other sites to serve from the same listeners, each picked by the
+    /// Host header of the request
+    pub sites: Vec<Site>,
+    /// The origins whose scripts may call the API routes, or if empty, any
+    pub cors_origins: Vec<String>,
+    /// How connections are served
+    pub mode: ServerMode,
+    /// Whether to run in development mode, where pages are read again
+    /// whenever they change instead of once
+    pub dev: bool,
+}
+
+/// The PEM files HTTPS is served with.
+#[derive(Debug, Clone, PartialEq)]
+pub struct TlsFiles {
+    /// The certificate chain, the server's own certificate first
+    pub cert: PathBuf,
+    pub key: PathBuf,
+}
+
+/// A site served from a directory of static files, for requests to one
+/// host name.
+#[derive(Debug, Clone, PartialEq)]
+pub struct Site {
+    pub host: String,
+    /// The document root
+    pub root: PathBuf,
+}
+
+impl FromStr for Site {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Site, String> {
+        match s.split_once('=') {
+            Some((host, root)) if !host.is_empty() && !root.is_empty() => Ok(Site {
+                host: host.to_string(),
+                root: PathBuf::from(root),
+            }),
+            _ => Err(format!("invalid site `{}`, expected `HOST=DIR`", s)),
+        }
+    }
+}
+
+/// The design the server runs with.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum ServerMode {
+    /// Each connection is handed to a worker of the ThreadPool, which
+    /// serves it with blocking reads and writes
+    Pool,
+    /// A single thread serves every connection with non-blocking sockets,
+    /// see EventLoop. There's no HTTPS in this mode.
+    EventLoop,
+}
+
+impl FromStr for ServerMode {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<ServerMode, String> {
+        match s {
+            "pool" => Ok(ServerMode::Pool),
+            "event-loop" => Ok(ServerMode::EventLoop),
+            _ => Err(format!(
+                "unknown mode `{}`, expected `pool` or `event-loop`",
+                s
+            )),
+        }
+    }
+}
+
+/// What the server does with a new connection when every worker is busy
+/// and the job queue is full, or when the most connections allowed are
+/// already open.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum QueuePolicy {
+    /// Wait for room in the queue, which stops new connections from being
+    /// accepted in the meantime
+    Block,
+    /// Answer 503 Service Unavailable straight away
+    Reject,
+}
+
+impl FromStr for QueuePolicy {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<QueuePolicy, String> {
+        match s {
+            "block" => Ok(QueuePolicy::Block),
+            "reject" => Ok(QueuePolicy::Reject),
+            _ => Err(format!(
+                "unknown policy `{}`, expected `block` or `reject`",
+                s
+            )),
+        }
+    }
+}
+
+impl ServerConfig {
+    /// Reads the settings from `args` (without the program name) and from
+    /// the environment variables POOL_SIZE and BIND_ADDR, looked up with
+    /// `env`. The command line wins over the environment, and anything not
+    /// given keeps its default.
+    ///
+    /// The options are, each written `--name value` or `--name=value`:
+    ///
+    /// - `--dev`: development mode, the one option without a value
+    /// - `--bind ADDR:PORT`: where to listen, `127.0.0.1:7878` by default.
+    ///   IPv6 addresses go in brackets, like `[::1]:7878`, and port 0
+    ///   picks any free port. Given more than once, or as a comma
+    ///   separated list like BIND_ADDR, the server listens on all of them.
+    /// - `--tls-bind ADDR:PORT`: where to listen for HTTPS,
+    ///   `127.0.0.1:7443` by default; can be given more than once too
+    /// - `--mode pool|event-loop`: serve connections on the ThreadPool, the
+    ///   default, or all on one thread with an event loop
+    /// - `--threads N`: the pool size
+    /// - `--queue N`: how many connections can wait for a free worker
+    /// - `--max-connections N`: the most connections open at once; no
+    ///   limit by default
+    /// - `--when-full block|reject`: what to do with a connection when
+    ///   the queue is full or the connections are at their limit; `block`
+    ///   by default
+    /// - `--access-log common|json|off`: the access log format, `common`
+    ///   by default
+    /// - `--read-timeout SECS`, `--write-timeout SECS`: the timeouts, 5
+    ///   seconds by default
+    /// - `--max-head BYTES`, `--max-body BYTES`: the request size limits,
+    ///   8 KiB and 1 MiB by default
+    /// - `--tls-cert PATH`, `--tls-key PATH`: PEM files to serve HTTPS
+    ///   with, alongside plain HTTP; given both or neither
+    /// - `--site HOST=DIR`: serve the files in DIR to requests for HOST,
+    ///   instead of the usual routes; can be given more than once
+    /// - `--cors-origin ORIGIN`: let scripts from ORIGIN call the API
+    ///   routes; can be given more than once, and any origin may if it
+    ///   isn't given at all
+    pub fn parse(
+        args: &[String],
+        env: impl Fn(&str) -> Option<String>,
+    ) -> Result<ServerConfig, String> {
+        let mut config = ServerConfig {
+            bind: vec![SocketAddr::from(([127, 0, 0, 1], 7878))],
+            tls_bind: vec![SocketAddr::from(([127, 0, 0, 1], 7443))],
+            pool: PoolConfig::default(),
+            access_log: Some(LogFormat::Common),
+            read_timeout: DEFAULT_TIMEOUT,
+            write_timeout: DEFAULT_TIMEOUT,
+            max_head: 8 * 1024,
+            max_body: 1024 * 1024,
+            when_full: QueuePolicy::Block,
+            max_connections: None,
+            tls: None,
+            sites: Vec::new(),
+            cors_origins: Vec::new(),
+            mode: ServerMode::Pool,
+            dev: false,
+        };
+        let (mut tls_cert, mut tls_key) = (None, None);
+        // Addresses on the command line replace the defaults, rather than
+        // adding to them
+        let (mut bind, mut tls_bind) = (Vec::new(), Vec::new());
+
+        if let Some(value) = env("POOL_SIZE") {
+            config.pool.size = parse_size(&value).map_err(|e| format!("POOL_SIZE: {}", e))?;
+        }
+        if let Some(value) = env("BIND_ADDR") {
+            config.bind = parse_addrs(&value).map_err(|e| format!("BIND_ADDR: {}", e))?;
+        }
+
+        let mut args = args.iter();
+        while let Some(arg) = args.next() {
+            if arg == "--dev" {
+                config.dev = true;
+                continue;
+            }
+
+            // Options are written either `--name value` or `--name=value`
+            let (name, value) = match arg.split_once('=') {
+                Some((name, value)) => (name, value),
+                None => (
+                    arg.as_str(),
+                    match args.next() {
+                        Some(value) => value.as_str(),
+                        None if is_option(arg) => return Err(format!("`{}` expects a value", arg)),
+                        None => return Err(format!("unknown argument `{}`", arg)),
+                    },
+                ),
+            };
+
+            match name {
+                "--bind" => {
+                    bind.extend(parse_addrs(value).map_err(|e| format!("--bind: {}", e))?);
+                }
+                "--tls-bind" => {
+                    tls_bind.extend(parse_addrs(value).map_err(|e| format!("--tls-bind: {}", e))?);
+                }
+                "--mode" => {
+                    config.mode = value.parse().map_err(|e| format!("--mode: {}", e))?;
+                }
+                "--threads" => {
+                    config.pool.size =
+                        parse_size(value).map_err(|e| format!("--threads: {}", e))?;
+                }
+                "--queue" => {
+                    config.pool.queue_capacity = value.trim().parse().map_err(|_| {
+                        format!(
+                            "--queue: invalid capacity `{}`, expected a whole number",
+                            value
+                        )
+                    })?;
+                }
+                "--max-connections" => {
+                    config.max_connections = Some(value.trim().parse().map_err(|_| {
+                        format!(
+                            "--max-connections: invalid limit `{}`, expected a whole number above zero",
+                            value
+                        )
+                    })?);
+                }
+                "--when-full" => {
+                    config.when_full = value.parse().map_err(|e| format!("--when-full: {}", e))?;
+                }
+                "--access-log" => {
+                    config.access_log = match value {
+                        "off" => None,
+                        _ => Some(value.parse().map_err(|e| format!("--access-log: {}", e))?),
+                    };
+                }
+                "--read-timeout" => {
+                    config.read_timeout =
+                        parse_timeout(value).map_err(|e| format!("--read-timeout: {}", e))?;
+                }
+                "--write-timeout" => {
+                    config.write_timeout =
+                        parse_timeout(value).map_err(|e| format!("--write-timeout: {}", e))?;
+                }
+                "--max-head" => {
+                    config.max_head =
+                        parse_limit(value).map_err(|e| format!("--max-head: {}", e))?;
+                }
+                "--max-body" => {
+                    config.max_body =
+                        parse_limit(value).map_err(|e| format!("--max-body: {}", e))?;
+                }
+                "--tls-cert" => tls_cert = Some(PathBuf::from(value)),
+                "--tls-key" => tls_key = Some(PathBuf::from(value)),
+                "--cors-origin" => config.cors_origins.push(value.to_string()),
+                "--site" => {
+                    config
+                        .sites
+                        .push(value.parse().map_err(|e| format!("--site: {}", e))?);
+                }
+                _ => return Err(format!("unknown argument `{}`", arg)),
+            }
+        }
+
+        if !bind.is_empty() {
+            config.bind = bind;
+        }
+        if !tls_bind.is_empty() {
+            config.tls_bind = tls_bind;
+        }
+        config.tls = match (tls_cert, tls_key) {
+            (Some(cert), Some(key)) => Some(TlsFiles { cert, key }),
+            (None, None) => None,
+            _ => return Err(String::from("`--tls-cert` and `--tls-key` go together")),
+        };
+
+        Ok(config)
+    }
+}
+
+const DEFAULT_TIMEOUT: Duration = Duration::from_secs(5);
+
+const OPTIONS: [&str; 16] = [
+    "--bind",
+    "--tls-bind",
+    "--mode",
+    "--threads",
+    "--queue",
+    "--max-connections",
+    "--when-full",
+    "--access-log",
+    "--read-timeout",
+    "--write-timeout",
+    "--max-head",
+    "--max-body",
+    "--tls-cert",
+    "--tls-key",
+    "--site",
+    "--cors-origin",
+];
+
+fn is_option(name: &str) -> bool {
+    OPTIONS.contains(&name)
+}
+
+// One address, or several separated by commas
+fn parse_addrs(value: &str) -> Result<Vec<SocketAddr>, String> {
+    value
+        .split(',')
+        .map(|addr| {
+            addr.trim().parse().map_err(|_| {
+                format!(
+                    "invalid address `{}`, expected an IP address and port like 127.0.0.1:7878",
+                    addr.trim()
+                )
+            })
+        })
+        .collect()
+}
+
+fn parse_size(value: &str) -> Result<NonZeroUsize, String> {
+    value.trim().parse().map_err(|_| {
+        format!(
+            "invalid pool size `{}`, expected a whole number above zero",
+            value
+        )
+    })
+}
+
+// A timeout of zero would mean none at all to the socket, so it isn't allowed
+fn parse_timeout(value: &str) -> Result<Duration, String> {
+    match value.trim().parse() {
+        Ok(secs) if secs > 0 => Ok(Duration::from_secs(secs)),
+        _ => Err(format!(
+            "invalid timeout `{}`, expected a whole number of seconds above zero",
+            value
+        )),
+    }
+}
+
+fn parse_limit(value: &str) -> Result<usize, String> {
+    match value.trim().parse() {
+        Ok(bytes) if bytes > 0 => Ok(bytes),
+        _ => Err(format!(
+            "invalid limit `{}`, expected a whole number of bytes above zero",
+            value
+        )),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn args(list: &[&str]) -> Vec<String> {
+        list.iter().map(|arg| arg.to_string()).collect()
+    }
+
+    fn no_env(_: &str) -> Option<String> {
+        None
+    }
+
+    // An environment with just POOL_SIZE, if it's given
+    fn threads(list: &[&str], env: Option<&str>) -> Result<usize, String> {
+        let env = |name: &str| env.filter(|_| name == "POOL_SIZE").map(String::from);
+        ServerConfig::parse(&args(list), env).map(|config| config.pool.size.get())
+    }
+
+    #[test]
+    fn defaults_to_four_threads() {
+        assert_eq!(Ok(4), threads(&[], None));
+    }
+
+    #[test]
+    fn command_line_beats_environment() {
+        assert_eq!(Ok(2), threads(&[], Some("2")));
+        assert_eq!(Ok(8), threads(&["--threads", "8"], Some("2")));
+        assert_eq!(Ok(1), threads(&["--threads=1"], None));
+    }
+
+    #[test]
+    fn picks_the_access_log_format() {
+        let format = |list: &[&str]| ServerConfig::parse(&args(list), no_env).map(|c| c.access_log);
+
+        assert_eq!(Ok(Some(LogFormat::Common)), format(&[]));
+        assert_eq!(Ok(Some(LogFormat::Json)), format(&["--access-log", "json"]));
+        assert_eq!(Ok(None), format(&["--access-log=off", "--threads", "2"]));
+        assert!(format(&["--access-log", "xml"])
+            .unwrap_err()
+            .starts_with("--access-log: "));
+    }
+
+    #[test]
+    fn reads_timeouts() {
+        let config = ServerConfig::parse(&args(&["--read-timeout", "30"]), no_env).unwrap();
+        assert_eq!(Duration::from_secs(30), config.read_timeout);
+        assert_eq!(Duration::from_secs(5), config.write_timeout);
+
+        let config = ServerConfig::parse(&args(&["--write-timeout=1"]), no_env).unwrap();
+        assert_eq!(Duration::from_secs(1), config.write_timeout);
+
+        assert!(ServerConfig::parse(&args(&["--read-timeout", "0"]), no_env).is_err());
+        assert!(ServerConfig::parse(&args(&["--write-timeout", "soon"]), no_env).is_err());
+        assert!(ServerConfig::parse(&args(&["--read-timeout"]), no_env).is_err());
+    }
+
+    #[test]
+    fn reads_size_limits() {
+        let config = ServerConfig::parse(&args(&[]), no_env).unwrap();
+        assert_eq!((8192, 1_048_576), (config.max_head, config.max_body));
+
+        let config =
+            ServerConfig::parse(&args(&["--max-head", "512", "--max-body=100"]), no_env).unwrap();
+        assert_eq!((512, 100), (config.max_head, config.max_body));
+
+        assert!(ServerConfig::parse(&args(&["--max-body", "0"]), no_env).is_err());
+        assert!(ServerConfig::parse(&args(&["--max-head", "-1"]), no_env).is_err());
+    }
+
+    #[test]
+    fn reads_the_queue_settings() {
+        let config = ServerConfig::parse(&args(&[]), no_env).unwrap();
+        assert_eq!(64, config.pool.queue_capacity);
+        assert_eq!(QueuePolicy::Block, config.when_full);
+
+        let config =
+            ServerConfig::parse(&args(&["--queue", "0", "--when-full=reject"]), no_env).unwrap();
+        assert_eq!(0, config.pool.queue_capacity);
+        assert_eq!(QueuePolicy::Reject, config.when_full);
+
+        assert!(ServerConfig::parse(&args(&["--queue", "lots"]), no_env).is_err());
+        assert!(ServerConfig::parse(&args(&["--when-full", "drop"]), no_env).is_err());
+    }
+
+    #[test]
+    fn reads_the_connection_limit() {
+        let limit = |list: &[&str]| {
+            ServerConfig::parse(&args(list), no_env).map(|c| c.max_connections.map(usize::from))
+        };
+
+        assert_eq!(Ok(None), limit(&[]));
+        assert_eq!(Ok(Some(100)), limit(&["--max-connections", "100"]));
+        assert!(limit(&["--max-connections=0"]).is_err());
+    }
+
+    #[test]
+    fn takes_tls_files_in_pairs() {
+        let config = ServerConfig::parse(
+            &args(&["--tls-cert", "cert.pem", "--tls-key=key.pem"]),
+            no_env,
+        )
+        .unwrap();
+        assert_eq!(
+            Some(TlsFiles {
+                cert: PathBuf::from("cert.pem"),
+                key: PathBuf::from("key.pem"),
+            }),
+            config.tls
+        );
+        assert_eq!(None, ServerConfig::parse(&args(&[]), no_env).unwrap().tls);
+        assert!(ServerConfig::parse(&args(&["--tls-cert", "cert.pem"]), no_env).is_err());
+    }
+
+    #[test]
+    fn collects_sites() {
+        let config = ServerConfig::parse(
+            &args(&[
+                "--site",
+                "docs.localhost=docs",
+                "--site=blog.localhost=blog",
+            ]),
+            no_env,
+        )
+        .unwrap();
+        assert_eq!(
+            vec![
+                Site {
+                    host: String::from("docs.localhost"),
+                    root: PathBuf::from("docs"),
+                },
+                Site {
+                    host: String::from("blog.localhost"),
+                    root: PathBuf::from("blog"),
+                },
+            ],
+            config.sites
+        );
+        assert!(ServerConfig::parse(&args(&["--site", "docs"]), no_env).is_err());
+        assert!(ServerConfig::parse(&args(&["--site", "=docs"]), no_env).is_err());
+    }
+
+    #[test]
+    fn collects_cors_origins() {
+        assert!(ServerConfig::parse(&args(&[]), no_env)
+            .unwrap()
+            .cors_origins
+            .is_empty());
+
+        let config = ServerConfig::parse(
+            &args(&[
+                "--cors-origin",
+                "https://a.com",
+                "--cors-origin=https://b.com",
+            ]),
+            no_env,
+        )
+        .unwrap();
+        assert_eq!(vec!["https://a.com", "https://b.com"], config.cors_origins);
+    }
+
+    #[test]
+    fn reads_bind_addresses() {
+        let addrs = |list: &[SocketAddr]| {
+            list.iter()
+                .map(|addr| addr.to_string())
+                .collect::<Vec<_>>()
+                .join(",")
+        };
+        let config = ServerConfig::parse(&args(&[]), no_env).unwrap();
+        assert_eq!("127.0.0.1:7878", addrs(&config.bind));
+        assert_eq!("127.0.0.1:7443", addrs(&config.tls_bind));
+
+        let config = ServerConfig::parse(
+            &args(&["--bind", "[::1]:0", "--tls-bind=0.0.0.0:8443"]),
+            no_env,
+        )
+        .unwrap();
+        assert_eq!("[::1]:0", addrs(&config.bind));
+        assert_eq!("0.0.0.0:8443", addrs(&config.tls_bind));
+
+        let env = |name: &str| {
+            Some(String::from(if name == "BIND_ADDR" {
+                "0.0.0.0:80"
+            } else {
+                "2"
+            }))
+        };
+        let config = ServerConfig::parse(&args(&[]), env).unwrap();
+        assert_eq!("0.0.0.0:80", addrs(&config.bind));
+        let config = ServerConfig::parse(&args(&["--bind", "127.0.0.1:1"]), env).unwrap();
+        assert_eq!("127.0.0.1:1", addrs(&config.bind));
+
+        // Several at once, on the command line or in the environment
+        let config = ServerConfig::parse(
+            &args(&["--bind", "127.0.0.1:1", "--bind=[::1]:1, 127.0.0.1:2"]),
+            no_env,
+        )
+        .unwrap();
+        assert_eq!("127.0.0.1:1,[::1]:1,127.0.0.1:2", addrs(&config.bind));
+        let env = |name: &str| (name == "BIND_ADDR").then(|| String::from("[::]:80,0.0.0.0:81"));
+        let config = ServerConfig::parse(&args(&["--threads", "1"]), env).unwrap();
+        assert_eq!("[::]:80,0.0.0.0:81", addrs(&config.bind));
+        assert!(ServerConfig::parse(&args(&["--bind", "127.0.0.1:1,"]), no_env).is_err());
+
+        assert!(ServerConfig::parse(&args(&["--bind", "localhost:80"]), no_env).is_err());
+        assert!(ServerConfig::parse(&args(&["--bind", "::1:80"]), no_env).is_err());
+        assert!(ServerConfig::parse(&args(&["--bind", "127.0.0.1"]), no_env).is_err());
+    }
+
+    #[test]
+    fn picks_the_mode() {
+        let mode = |list: &[&str]| ServerConfig::parse(&args(list), no_env).map(|c| c.mode);
+
+        assert_eq!(Ok(ServerMode::Pool), mode(&[]));
+        assert_eq!(Ok(ServerMode::EventLoop), mode(&["--mode", "event-loop"]));
+        assert_eq!(Ok(ServerMode::Pool), mode(&["--mode=pool"]));
+        assert!(mode(&["--mode", "async"])
+            .unwrap_err()
+            .starts_with("--mode: "));
+    }
+
+    #[test]
+    fn dev_mode_is_a_flag() {
+        assert!(!ServerConfig::parse(&args(&[]), no_env).unwrap().dev);
+
+        let config = ServerConfig::parse(&args(&["--dev", "--threads", "2"]), no_env).unwrap();
+        assert!(config.dev);
+        assert_eq!(2, config.pool.size.get());
+        assert!(ServerConfig::parse(&args(&["--dev=yes"]), no_env).is_err());
+    }
+
+    #[test]
+    fn rejects_bad_sizes() {
+        assert_eq!(
+            Err(String::from(
+                "--threads: invalid pool size `0`, expected a whole number above zero"
+            )),
+            threads(&["--threads", "0"], None)
+        );
+        assert!(threads(&[], Some("many"))
+            .unwrap_err()
+            .starts_with("POOL_SIZE: "));
+        assert!(threads(&["--threads"], None).is_err());
+        assert!(threads(&["--fast"], None).is_err());
+    }
+}
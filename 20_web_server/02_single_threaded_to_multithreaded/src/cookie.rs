@@ -0,0 +1,174 @@
+use std::collections::HashMap;
+use std::fmt;
+use std::time::Duration;
+
+/// A cookie for a response to set, built up like a Response and sent with
+/// `Response::set_cookie`.
+///
+/// ```
+/// use std::time::Duration;
+/// use hello::{Cookie, SameSite};
+///
+/// let cookie = Cookie::new("theme", "dark")
+///     .path("/")
+///     .max_age(Duration::from_secs(3600))
+///     .http_only()
+///     .same_site(SameSite::Lax);
+///
+/// assert_eq!(
+///     "theme=dark; Path=/; Max-Age=3600; HttpOnly; SameSite=Lax",
+///     cookie.to_string()
+/// );
+/// ```
+#[derive(Debug, Clone, PartialEq)]
+pub struct Cookie {
+    name: String,
+    value: String,
+    path: Option<String>,
+    max_age: Option<Duration>,
+    http_only: bool,
+    secure: bool,
+    same_site: Option<SameSite>,
+}
+
+/// Whether browsers send a cookie along with requests that other sites
+/// start.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum SameSite {
+    /// Never
+    Strict,
+    /// Only when following a link to this site
+    Lax,
+    /// Always, which browsers only allow for Secure cookies
+    None,
+}
+
+impl Cookie {
+    /// A cookie that lasts until the browser is closed, sent back only to
+    /// paths under the one the response is for.
+    pub fn new(name: &str, value: &str) -> Cookie {
+        Cookie {
+            name: name.to_string(),
+            value: value.to_string(),
+            path: None,
+            max_age: None,
+            http_only: false,
+            secure: false,
+            same_site: None,
+        }
+    }
+
+    /// A cookie that makes the browser forget the one called `name`. The
+    /// path has to be the same as the one it was set with.
+    pub fn removal(name: &str) -> Cookie {
+        Cookie::new(name, "").max_age(Duration::ZERO)
+    }
+
+    /// Sends the cookie back with requests for `path` and anything under it.
+    pub fn path(mut self, path: &str) -> Cookie {
+        self.path = Some(path.to_string());
+        self
+    }
+
+    /// Keeps the cookie for `max_age`, even after the browser is closed.
+    pub fn max_age(mut self, max_age: Duration) -> Cookie {
+        self.max_age = Some(max_age);
+        self
+    }
+
+    /// Keeps the cookie out of reach of the page's scripts.
+    pub fn http_only(mut self) -> Cookie {
+        self.http_only = true;
+        self
+    }
+
+    /// Only sends the cookie back over HTTPS.
+    pub fn secure(mut self) -> Cookie {
+        self.secure = true;
+        self
+    }
+
+    pub fn same_site(mut self, same_site: SameSite) -> Cookie {
+        self.same_site = Some(same_site);
+        self
+    }
+}
+
+// As the value of a Set-Cookie header
+impl fmt::Display for Cookie {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(f, "{}={}", self.name, self.value)?;
+        if let Some(path) = &self.path {
+            write!(f, "; Path={}", path)?;
+        }
+        if let Some(max_age) = self.max_age {
+            write!(f, "; Max-Age={}", max_age.as_secs())?;
+        }
+        if self.http_only {
+            write!(f, "; HttpOnly")?;
+        }
+        if self.secure {
+            write!(f, "; Secure")?;
+        }
+        match self.same_site {
+            Some(SameSite::Strict) => write!(f, "; SameSite=Strict"),
+            Some(SameSite::Lax) => write!(f, "; SameSite=Lax"),
+            Some(SameSite::None) => write!(f, "; SameSite=None"),
+            None => Ok(()),
+        }
+    }
+}
+
+// Adds the `name=value` pairs of a Cookie header to `cookies`. Browsers put
+// the cookie for the most specific path first, so the first of a name wins.
+pub(crate) fn parse_cookies(header: &str, cookies: &mut HashMap<String, String>) {
+    for pair in header.split(';') {
+        if let Some((name, value)) = pair.split_once('=') {
+            let name = name.trim();
+            if name.is_empty() {
+                continue;
+            }
+            // Values may be quoted, and the quotes aren't part of them
+            let value = value.trim();
+            let value = value
+                .strip_prefix('"')
+                .and_then(|value| value.strip_suffix('"'))
+                .unwrap_or(value);
+            cookies
+                .entry(name.to_string())
+                .or_insert_with(|| value.to_string());
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn parses_cookie_headers() {
+        let mut cookies = HashMap::new();
+        parse_cookies(
+            "a=1; b=\"two\";c=; =nameless; broken; a=again",
+            &mut cookies,
+        );
+
+        assert_eq!(Some("1"), cookies.get("a").map(String::as_str));
+        assert_eq!(Some("two"), cookies.get("b").map(String::as_str));
+        assert_eq!(Some(""), cookies.get("c").map(String::as_str));
+        assert_eq!(3, cookies.len());
+    }
+
+    #[test]
+    fn writes_set_cookie_values() {
+        assert_eq!("id=7", Cookie::new("id", "7").to_string());
+        assert_eq!("id=; Max-Age=0", Cookie::removal("id").to_string());
+        assert_eq!(
+            "id=7; Secure; SameSite=None",
+            Cookie::new("id", "7")
+                .secure()
+                .same_site(SameSite::None)
+                .to_string()
+        );
+    }
+}
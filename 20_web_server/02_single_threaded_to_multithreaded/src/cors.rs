@@ -0,0 +1,206 @@
+use std::time::Duration;
+
+use crate::http::{Request, Response};
+
+/// Which other sites' scripts may call a set of routes, by Cross-Origin
+/// Resource Sharing.
+///
+/// Built up like a Response, and handed to `Router::cors` with the paths it
+/// covers:
+///
+/// ```no_run
+/// use std::time::Duration;
+/// use hello::{Cors, Router};
+///
+/// let mut router = Router::new();
+/// router.cors(
+///     "/api/*path",
+///     Cors::new()
+///         .allow_origin("https://example.com")
+///         .max_age(Duration::from_secs(600)),
+/// );
+/// ```
+#[derive(Debug, Clone, PartialEq)]
+pub struct Cors {
+    origins: Vec<String>,
+    headers: Vec<String>,
+    max_age: Option<Duration>,
+}
+
+impl Cors {
+    /// A policy that lets any origin in, sending Content-Type as its only
+    /// non-simple header.
+    pub fn new() -> Cors {
+        Cors {
+            origins: Vec::new(),
+            headers: vec![String::from("Content-Type")],
+            max_age: None,
+        }
+    }
+
+    /// Lets scripts from `origin`, like `https://example.com`, in. Once any
+    /// origin is given, only those given are let in.
+    pub fn allow_origin(mut self, origin: &str) -> Cors {
+        self.origins.push(origin.to_string());
+        self
+    }
+
+    /// Lets requests send the header `name`, beyond the ones browsers
+    /// always allow.
+    pub fn allow_header(mut self, name: &str) -> Cors {
+        self.headers.push(name.to_string());
+        self
+    }
+
+    /// How long browsers may keep the answer to a preflight request, rather
+    /// than asking again before every call.
+    pub fn max_age(mut self, max_age: Duration) -> Cors {
+        self.max_age = Some(max_age);
+        self
+    }
+
+    /// Whether `request` is a preflight request, which asks ahead of the
+    /// real one whether it may be sent.
+    pub fn is_preflight(request: &Request) -> bool {
+        request.method == "OPTIONS"
+            && request.header("Origin").is_some()
+            && request.header("Access-Control-Request-Method").is_some()
+    }
+
+    /// The answer to a preflight request for a path that allows `methods`.
+    /// Origins that aren't let in get an answer without any of the
+    /// Access-Control headers, which browsers take as a no.
+    pub fn preflight(&self, request: &Request, methods: &[&str]) -> Response {
+        let response = Response::new(204, "");
+        let origin = match request.header("Origin") {
+            Some(origin) if self.allows(origin) => origin,
+            _ => return response,
+        };
+
+        let response = self
+            .with_origin(response, origin)
+            .header("Access-Control-Allow-Methods", &methods.join(", "))
+            .header("Access-Control-Allow-Headers", &self.headers.join(", "));
+        match self.max_age {
+            Some(max_age) => {
+                response.header("Access-Control-Max-Age", &max_age.as_secs().to_string())
+            }
+            None => response,
+        }
+    }
+
+    /// Adds to `response` what lets the script that sent `request` read it,
+    /// if its origin is let in.
+    pub fn apply(&self, request: &Request, response: Response) -> Response {
+        match request.header("Origin") {
+            Some(origin) if self.allows(origin) => self.with_origin(response, origin),
+            _ => response,
+        }
+    }
+
+    fn allows(&self, origin: &str) -> bool {
+        self.origins.is_empty() || self.origins.iter().any(|allowed| allowed == origin)
+    }
+
+    fn with_origin(&self, response: Response, origin: &str) -> Response {
+        if self.origins.is_empty() {
+            response.header("Access-Control-Allow-Origin", "*")
+        } else {
+            // The answer names the origin, so it differs between them
+            response
+                .header("Access-Control-Allow-Origin", origin)
+                .header("Vary", "Origin")
+        }
+    }
+}
+
+impl Default for Cors {
+    fn default() -> Cors {
+        Cors::new()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn request(method: &str, headers: &str) -> Request {
+        let head = format!("{} /api HTTP/1.1\r\n{}\r\n", method, headers);
+        Request::parse(head.as_bytes()).unwrap()
+    }
+
+    fn preflight(origin: &str) -> Request {
+        request(
+            "OPTIONS",
+            &format!(
+                "Origin: {}\r\nAccess-Control-Request-Method: POST\r\n",
+                origin
+            ),
+        )
+    }
+
+    #[test]
+    fn tells_preflights_apart() {
+        assert!(Cors::is_preflight(&preflight("https://a.com")));
+        assert!(!Cors::is_preflight(&request("OPTIONS", "")));
+        assert!(!Cors::is_preflight(&request(
+            "GET",
+            "Origin: https://a.com\r\nAccess-Control-Request-Method: GET\r\n"
+        )));
+    }
+
+    #[test]
+    fn answers_preflights() {
+        let cors = Cors::new()
+            .allow_header("X-Token")
+            .max_age(Duration::from_secs(600));
+        let response = cors.preflight(&preflight("https://a.com"), &["GET", "POST"]);
+
+        assert_eq!(204, response.status);
+        assert_eq!(
+            Some("*"),
+            response.header_value("Access-Control-Allow-Origin")
+        );
+        assert_eq!(
+            Some("GET, POST"),
+            response.header_value("Access-Control-Allow-Methods")
+        );
+        assert_eq!(
+            Some("Content-Type, X-Token"),
+            response.header_value("Access-Control-Allow-Headers")
+        );
+        assert_eq!(Some("600"), response.header_value("Access-Control-Max-Age"));
+    }
+
+    #[test]
+    fn only_lets_in_the_listed_origins() {
+        let cors = Cors::new().allow_origin("https://a.com");
+
+        let response = cors.preflight(&preflight("https://a.com"), &["GET"]);
+        assert_eq!(
+            Some("https://a.com"),
+            response.header_value("Access-Control-Allow-Origin")
+        );
+        assert_eq!(Some("Origin"), response.header_value("Vary"));
+
+        let response = cors.preflight(&preflight("https://b.com"), &["GET"]);
+        assert_eq!(204, response.status);
+        assert!(response.headers.is_empty());
+    }
+
+    #[test]
+    fn marks_responses_for_allowed_origins() {
+        let cors = Cors::new().allow_origin("https://a.com");
+        let from = |origin: &str| request("GET", &format!("Origin: {}\r\n", origin));
+
+        let response = cors.apply(&from("https://a.com"), Response::ok("hi"));
+        assert_eq!(
+            Some("https://a.com"),
+            response.header_value("Access-Control-Allow-Origin")
+        );
+        let response = cors.apply(&from("https://b.com"), Response::ok("hi"));
+        assert!(response.headers.is_empty());
+        let response = cors.apply(&request("GET", ""), Response::ok("hi"));
+        assert!(response.headers.is_empty());
+    }
+}
@@ -0,0 +1,149 @@
+use std::time::{Duration, SystemTime, UNIX_EPOCH};
+
+pub(crate) const MONTHS: [&str; 12] = [
+    "Jan", "Feb", "Mar", "Apr", "May", "Jun", "Jul", "Aug", "Sep", "Oct", "Nov", "Dec",
+];
+
+// 1970-01-01 was a Thursday
+const WEEKDAYS: [&str; 7] = ["Thu", "Fri", "Sat", "Sun", "Mon", "Tue", "Wed"];
+
+pub(crate) fn unix_secs(time: SystemTime) -> u64 {
+    time.duration_since(UNIX_EPOCH)
+        .unwrap_or_default()
+        .as_secs()
+}
+
+/// A time the way HTTP headers like Last-Modified write it, e.g.
+/// `Sun, 06 Nov 1994 08:49:37 GMT`.
+pub(crate) fn http_date(time: SystemTime) -> String {
+    let secs = unix_secs(time);
+    let days = (secs / 86_400) as i64;
+    let (year, month, day) = civil_date(days);
+    let secs_of_day = secs % 86_400;
+
+    format!(
+        "{}, {:02} {} {} {:02}:{:02}:{:02} GMT",
+        WEEKDAYS[days.rem_euclid(7) as usize],
+        day,
+        MONTHS[month as usize - 1],
+        year,
+        secs_of_day / 3600,
+        secs_of_day / 60 % 60,
+        secs_of_day % 60
+    )
+}
+
+/// Reads a date written the way `http_date` writes it. The two older forms
+/// HTTP still allows aren't understood; a header using them is treated as
+/// though it wasn't there.
+pub(crate) fn parse_http_date(text: &str) -> Option<SystemTime> {
+    // The weekday is implied by the rest, so it's only checked for being
+    // there
+    let (_weekday, rest) = text.trim().split_once(", ")?;
+    let parts: Vec<&str> = rest.split(' ').collect();
+    let (day, month, year, time, zone) = match parts[..] {
+        [day, month, year, time, zone] => (day, month, year, time, zone),
+        _ => return None,
+    };
+    if zone != "GMT" || day.len() != 2 || year.len() != 4 {
+        return None;
+    }
+
+    let day: u32 = day.parse().ok()?;
+    let month = MONTHS.iter().position(|&name| name == month)? as u32 + 1;
+    let year: i64 = year.parse().ok()?;
+    let clock: Vec<u64> = time
+        .split(':')
+        .map(|part| part.parse().ok().filter(|_| part.len() == 2))
+        .collect::<Option<_>>()?;
+    let (hours, minutes, seconds) = match clock[..] {
+        [hours, minutes, seconds] if hours < 24 && minutes < 60 && seconds < 61 => {
+            (hours, minutes, seconds)
+        }
+        _ => return None,
+    };
+
+    let days = days_from_civil(year, month, day);
+    // Dates that don't exist, like 31 Feb, don't come back out the same
+    if days < 0 || civil_date(days) != (year, month, day) {
+        return None;
+    }
+
+    let secs = days as u64 * 86_400 + hours * 3600 + minutes * 60 + seconds;
+    Some(UNIX_EPOCH + Duration::from_secs(secs))
+}
+
+// The year, month and day of the date `days` days after 1970-01-01, in the
+// Gregorian calendar. This is Howard Hinnant's `civil_from_days`, which
+// counts in 400 year eras that always have the same number of days.
+pub(crate) fn civil_date(days: i64) -> (i64, u32, u32) {
+    let days = days + 719_468;
+    let era = days.div_euclid(146_097);
+    let day_of_era = days.rem_euclid(146_097);
+    let year_of_era =
+        (day_of_era - day_of_era / 1460 + day_of_era / 36_524 - day_of_era / 146_096) / 365;
+    let day_of_year = day_of_era - (365 * year_of_era + year_of_era / 4 - year_of_era / 100);
+    // Months counted from March, so the leap day comes last
+    let month = (5 * day_of_year + 2) / 153;
+    let day = day_of_year - (153 * month + 2) / 5 + 1;
+    let month = if month < 10 { month + 3 } else { month - 9 };
+    let year = year_of_era + era * 400 + if month <= 2 { 1 } else { 0 };
+
+    (year, month as u32, day as u32)
+}
+
+// The other way round from `civil_date`: how many days after 1970-01-01 a
+// date is. Hinnant's `days_from_civil`.
+fn days_from_civil(year: i64, month: u32, day: u32) -> i64 {
+    let (month, day) = (month as i64, day as i64);
+    let year = if month <= 2 { year - 1 } else { year };
+    let era = year.div_euclid(400);
+    let year_of_era = year.rem_euclid(400);
+    let month_from_march = if month > 2 { month - 3 } else { month + 9 };
+    let day_of_year = (153 * month_from_march + 2) / 5 + day - 1;
+    let day_of_era = year_of_era * 365 + year_of_era / 4 - year_of_era / 100 + day_of_year;
+
+    era * 146_097 + day_of_era - 719_468
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn converts_days_to_dates() {
+        assert_eq!((1970, 1, 1), civil_date(0));
+        assert_eq!((2000, 2, 29), civil_date(11_016));
+        assert_eq!((2000, 3, 1), civil_date(11_017));
+        assert_eq!((1969, 12, 31), civil_date(-1));
+
+        for days in [-1000, 0, 11_016, 20_000, 100_000] {
+            let (year, month, day) = civil_date(days);
+            assert_eq!(days, days_from_civil(year, month, day));
+        }
+    }
+
+    #[test]
+    fn writes_and_reads_http_dates() {
+        let time = UNIX_EPOCH + Duration::from_secs(784_111_777);
+
+        assert_eq!("Sun, 06 Nov 1994 08:49:37 GMT", http_date(time));
+        assert_eq!(Some(time), parse_http_date("Sun, 06 Nov 1994 08:49:37 GMT"));
+        assert_eq!("Thu, 01 Jan 1970 00:00:00 GMT", http_date(UNIX_EPOCH));
+    }
+
+    #[test]
+    fn refuses_other_date_forms() {
+        for text in [
+            "Sunday, 06-Nov-94 08:49:37 GMT",
+            "Sun Nov  6 08:49:37 1994",
+            "Sun, 06 Nov 1994 08:49:37 PST",
+            "Sun, 31 Feb 1994 08:49:37 GMT",
+            "Sun, 06 Nov 1994 8:49:37 GMT",
+            "Sun, 06 Nov 1994 25:00:00 GMT",
+            "",
+        ] {
+            assert_eq!(None, parse_http_date(text), "{}", text);
+        }
+    }
+}
@@ -0,0 +1,542 @@
+use std::collections::HashMap;
+use std::io::{self, Read, Write};
+use std::net::{self, IpAddr};
+use std::time::{Duration, Instant};
+
+use mio::net::{TcpListener, TcpStream};
+use mio::{Events, Interest, Poll, Token};
+
+use crate::http::{BodyError, HeadError, Request, Response};
+use crate::limit::{ConnectionLimit, Permit};
+use crate::shutdown::Shutdown;
+
+// How often the loop wakes up with nothing to do, to close connections that
+// have gone quiet
+const SWEEP_INTERVAL: Duration = Duration::from_millis(500);
+
+/// Serves connections on a single thread, by waiting for whichever socket
+/// is ready next instead of giving each connection a thread of its own.
+///
+/// This is the other way of building the server, to compare with the
+/// ThreadPool one. Sockets never block, so one slow client can't hold up
+/// the others, but a slow handler holds up everyone: handlers run on the
+/// loop's thread, and streamed bodies are finished before any of them is
+/// sent.
+///
+/// ```no_run
+/// use std::net::TcpListener;
+/// use hello::{EventLoop, Response, Shutdown};
+///
+/// let listener = TcpListener::bind("127.0.0.1:7878").unwrap();
+/// let shutdown = Shutdown::new(&listener).unwrap();
+/// let mut event_loop = EventLoop::new(vec![listener]).unwrap();
+///
+/// event_loop
+///     .run(&shutdown, |request| Response::ok(format!("You asked for {}", request.path)))
+///     .unwrap();
+/// ```
+pub struct EventLoop {
+    poll: Poll,
+    // Listener `i` has token `i`, and connections get the tokens after them
+    listeners: Vec<TcpListener>,
+    connections: HashMap<Token, Connection>,
+    next_token: usize,
+    max_head: usize,
+    max_body: usize,
+    idle_timeout: Duration,
+    limit: Option<ConnectionLimit>,
+}
+
+impl EventLoop {
+    /// An event loop for the connections `listeners` accept, with the same
+    /// limits as the server has by default.
+    pub fn new(listeners: Vec<net::TcpListener>) -> io::Result<EventLoop> {
+        let poll = Poll::new()?;
+        let listeners = listeners
+            .into_iter()
+            .enumerate()
+            .map(|(i, listener)| {
+                listener.set_nonblocking(true)?;
+                let mut listener = TcpListener::from_std(listener);
+                poll.registry()
+                    .register(&mut listener, Token(i), Interest::READABLE)?;
+                Ok(listener)
+            })
+            .collect::<io::Result<Vec<_>>>()?;
+
+        Ok(EventLoop {
+            poll,
+            next_token: listeners.len(),
+            listeners,
+            connections: HashMap::new(),
+            max_head: 8 * 1024,
+            max_body: 1024 * 1024,
+            idle_timeout: Duration::from_secs(5),
+            limit: None,
+        })
+    }
+
+    /// The most bytes a request line and headers may take up together.
+    pub fn max_head(mut self, bytes: usize) -> EventLoop {
+        self.max_head = bytes;
+        self
+    }
+
+    /// The most bytes a request body may take up.
+    pub fn max_body(mut self, bytes: usize) -> EventLoop {
+        self.max_body = bytes;
+        self
+    }
+
+    /// How long a connection may go without sending or taking anything
+    /// before it's closed.
+    pub fn idle_timeout(mut self, timeout: Duration) -> EventLoop {
+        self.idle_timeout = timeout;
+        self
+    }
+
+    /// Caps how many connections may be open at once. Ones beyond it are
+    /// answered with 503 Service Unavailable and closed straight away,
+    /// since waiting for room would hold up everyone else.
+    pub fn connection_limit(mut self, limit: ConnectionLimit) -> EventLoop {
+        self.limit = Some(limit);
+        self
+    }
+
+    /// Answers every request with what `handler` returns, until `shutdown`
+    /// is triggered. Requests already being answered then still get their
+    /// responses, and this returns once they have.
+    ///
+    /// Requests that can't be parsed, or are over the limits, are answered
+    /// by the loop itself and never reach `handler`.
+    pub fn run<F>(&mut self, shutdown: &Shutdown, mut handler: F) -> io::Result<()>
+    where
+        F: FnMut(Request) -> Response,
+    {
+        let mut events = Events::with_capacity(1024);
+
+        loop {
+            match self.poll.poll(&mut events, Some(SWEEP_INTERVAL)) {
+                Ok(()) => {}
+                Err(e) if e.kind() == io::ErrorKind::Interrupted => continue,
+                Err(e) => return Err(e),
+            }
+
+            for event in &events {
+                let token = event.token();
+                if token.0 < self.listeners.len() {
+                    self.accept(token.0);
+                } else {
+                    self.ready(token, &mut handler);
+                }
+            }
+
+            if shutdown.is_requested() && !self.listeners.is_empty() {
+                // Connections arriving from now on are refused
+                self.listeners.clear();
+            }
+            self.sweep(shutdown.is_requested());
+            if self.listeners.is_empty() && self.connections.is_empty() {
+                return Ok(());
+            }
+        }
+    }
+
+    fn accept(&mut self, listener: usize) {
+        loop {
+            let (mut stream, addr) = match self.listeners[listener].accept() {
+                Ok(accepted) => accepted,
+                Err(e) if e.kind() == io::ErrorKind::WouldBlock => return,
+                Err(e) => {
+                    eprintln!("Couldn't accept a connection: {}", e);
+                    return;
+                }
+            };
+
+            let permit = match &self.limit {
+                Some(limit) => match limit.try_acquire() {
+                    Some(permit) => Some(permit),
+                    None => {
+                        // A fresh socket has room for this much, and if it
+                        // doesn't the client is none the wiser either way
+                        let _ = Response::new(503, "Service Unavailable")
+                            .header("Retry-After", "1")
+                            .header("Connection", "close")
+                            .write_to(&mut stream);
+                        continue;
+                    }
+                },
+                None => None,
+            };
+
+            let token = Token(self.next_token);
+            self.next_token += 1;
+            // Interest in both from the start, since readiness is only
+            // reported when it changes
+            let interest = Interest::READABLE | Interest::WRITABLE;
+            if let Err(e) = self.poll.registry().register(&mut stream, token, interest) {
+                eprintln!("Couldn't watch a connection: {}", e);
+                continue;
+            }
+            let mut connection = Connection::new(stream, addr.ip());
+            connection.permit = permit;
+            self.connections.insert(token, connection);
+        }
+    }
+
+    fn ready<F>(&mut self, token: Token, handler: &mut F)
+    where
+        F: FnMut(Request) -> Response,
+    {
+        let (max_head, max_body) = (self.max_head, self.max_body);
+        let connection = match self.connections.get_mut(&token) {
+            Some(connection) => connection,
+            None => return,
+        };
+
+        let open = match connection.read() {
+            Ok(()) => {
+                connection.answer(max_head, max_body, handler);
+                connection.write().is_ok() && !connection.is_finished()
+            }
+            Err(_) => false,
+        };
+        if !open {
+            self.close(token);
+        }
+    }
+
+    // Closes the connections that have been quiet for too long, or all the
+    // ones with nothing left to send when shutting down
+    fn sweep(&mut self, shutting_down: bool) {
+        let (now, timeout) = (Instant::now(), self.idle_timeout);
+        let done: Vec<Token> = self
+            .connections
+            .iter_mut()
+            .filter_map(|(&token, connection)| {
+                let idle = now.duration_since(connection.last_active) >= timeout;
+                if idle {
+                    connection.time_out();
+                }
+                let quiet = shutting_down && connection.is_idle();
+                (idle || quiet).then_some(token)
+            })
+            .collect();
+
+        for token in done {
+            self.close(token);
+        }
+    }
+
+    fn close(&mut self, token: Token) {
+        if let Some(mut connection) = self.connections.remove(&token) {
+            let _ = self.poll.registry().deregister(&mut connection.stream);
+        }
+    }
+}
+
+// A connection being served, with what's been read of it and what's still
+// to be sent
+struct Connection {
+    stream: TcpStream,
+    client: IpAddr,
+    incoming: Vec<u8>,
+    outgoing: Vec<u8>,
+    // Once what's outgoing is sent, the connection is done with
+    closing: bool,
+    last_active: Instant,
+    // Its place under the connection limit, given back when it closes
+    permit: Option<Permit>,
+}
+
+impl Connection {
+    fn new(stream: TcpStream, client: IpAddr) -> Connection {
+        Connection {
+            stream,
+            client,
+            incoming: Vec::new(),
+            outgoing: Vec::new(),
+            closing: false,
+            last_active: Instant::now(),
+            permit: None,
+        }
+    }
+
+    // Reads everything there is to read right now
+    fn read(&mut self) -> io::Result<()> {
+        let mut buf = [0; 4096];
+        loop {
+            match self.stream.read(&mut buf) {
+                // The client is done sending, but may still want answers
+                Ok(0) => {
+                    self.closing = true;
+                    return Ok(());
+                }
+                Ok(n) => {
+                    self.incoming.extend_from_slice(&buf[..n]);
+                    self.last_active = Instant::now();
+                }
+                Err(e) if e.kind() == io::ErrorKind::WouldBlock => return Ok(()),
+                Err(e) if e.kind() == io::ErrorKind::Interrupted => {}
+                Err(e) => return Err(e),
+            }
+        }
+    }
+
+    // Sends as much of what's outgoing as the socket will take right now
+    fn write(&mut self) -> io::Result<()> {
+        while !self.outgoing.is_empty() {
+            match self.stream.write(&self.outgoing) {
+                Ok(0) => return Err(io::ErrorKind::WriteZero.into()),
+                Ok(n) => {
+                    self.outgoing.drain(..n);
+                    self.last_active = Instant::now();
+                }
+                Err(e) if e.kind() == io::ErrorKind::WouldBlock => return Ok(()),
+                Err(e) if e.kind() == io::ErrorKind::Interrupted => {}
+                Err(e) => return Err(e),
+            }
+        }
+        Ok(())
+    }
+
+    // Answers each whole request that's been read, in order
+    fn answer<F>(&mut self, max_head: usize, max_body: usize, handler: &mut F)
+    where
+        F: FnMut(Request) -> Response,
+    {
+        // Nothing after a request that closes the connection is answered
+        while !self.closing || !self.incoming.is_empty() {
+            let head_end = match find_head_end(&self.incoming) {
+                Some(end) if end <= max_head => end,
+                None if self.incoming.len() <= max_head => return,
+                _ => return self.refuse(HeadError::TooLarge.status(), "request head too large"),
+            };
+            // There's no telling where a garbled request ends
+            let mut request = match Request::parse(&self.incoming[..head_end]) {
+                Some(request) => request,
+                None => return self.refuse(400, "Bad Request"),
+            };
+            let length = match request.body_length(max_body) {
+                Ok(length) => length,
+                Err(e) => return self.refuse(e.status(), &e.to_string()),
+            };
+            if self.incoming.len() < head_end + length {
+                if self.closing {
+                    // The body never will arrive
+                    let e = BodyError::Io(io::ErrorKind::UnexpectedEof.into());
+                    return self.refuse(e.status(), &e.to_string());
+                }
+                return;
+            }
+
+            request.body = self.incoming[head_end..head_end + length].to_vec();
+            self.incoming.drain(..head_end + length);
+            request.client = Some(self.client);
+
+            // Without a Content-Length there's no telling where a chunked
+            // body ends and the next request starts
+            let keep_alive = request.keep_alive() && request.header("Transfer-Encoding").is_none();
+            let head_only = request.method == "HEAD";
+            let response = handler(request);
+            self.queue(response, keep_alive, head_only);
+            if !keep_alive {
+                return;
+            }
+        }
+    }
+
+    fn queue(&mut self, response: Response, keep_alive: bool, head_only: bool) {
+        let response = if keep_alive {
+            response
+        } else {
+            self.closing = true;
+            self.incoming.clear();
+            response.header("Connection", "close")
+        };
+
+        // Writing to a Vec only fails if a streamed body does
+        let written = if head_only {
+            response.write_head_to(&mut self.outgoing)
+        } else {
+            response.write_to(&mut self.outgoing).map(|_| ())
+        };
+        if written.is_err() {
+            self.closing = true;
+        }
+    }
+
+    // Answers with an error and closes the connection, since whatever is
+    // left to read can't be made sense of
+    fn refuse(&mut self, status: u16, message: &str) {
+        self.queue(Response::new(status, message.to_string()), false, false);
+    }
+
+    // Lets a client that's gone quiet know why the connection is closing.
+    // This is best effort: it may well not be listening anymore.
+    fn time_out(&mut self) {
+        if self.outgoing.is_empty() {
+            self.refuse(408, "Request Timeout");
+            let _ = self.write();
+        }
+    }
+
+    // Whether it's between requests, with nothing half read or unsent
+    fn is_idle(&self) -> bool {
+        self.incoming.is_empty() && self.outgoing.is_empty()
+    }
+
+    fn is_finished(&self) -> bool {
+        self.closing && self.outgoing.is_empty()
+    }
+}
+
+// Where the blank line after a request's head ends, if it's been read yet
+fn find_head_end(bytes: &[u8]) -> Option<usize> {
+    bytes
+        .windows(4)
+        .position(|window| window == b"\r\n\r\n")
+        .map(|i| i + 4)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::net::{Shutdown as Direction, SocketAddr};
+    use std::thread;
+
+    // Runs an event loop, set up by `configure`, that echoes each request's
+    // method, path and body until the returned Shutdown is triggered
+    fn serve<C>(configure: C) -> (SocketAddr, Shutdown, thread::JoinHandle<()>)
+    where
+        C: FnOnce(EventLoop) -> EventLoop + Send + 'static,
+    {
+        let listener = net::TcpListener::bind("127.0.0.1:0").unwrap();
+        let addr = listener.local_addr().unwrap();
+        let shutdown = Shutdown::new(&listener).unwrap();
+        let handle = shutdown.clone();
+
+        let server = thread::spawn(move || {
+            let mut event_loop = configure(EventLoop::new(vec![listener]).unwrap());
+            event_loop
+                .run(&shutdown, |request| {
+                    let mut body = format!("{} {} ", request.method, request.path).into_bytes();
+                    body.extend_from_slice(&request.body);
+                    Response::ok(body)
+                })
+                .unwrap();
+        });
+        (addr, handle, server)
+    }
+
+    // Sends `parts` one after the other, then reads until the server hangs up
+    fn exchange(addr: SocketAddr, parts: &[&str]) -> String {
+        let mut client = net::TcpStream::connect(addr).unwrap();
+        for part in parts {
+            client.write_all(part.as_bytes()).unwrap();
+            thread::sleep(Duration::from_millis(20));
+        }
+        client.shutdown(Direction::Write).unwrap();
+
+        let mut answer = String::new();
+        client.read_to_string(&mut answer).unwrap();
+        answer
+    }
+
+    #[test]
+    fn answers_pipelined_and_split_requests() {
+        let (addr, shutdown, server) = serve(|event_loop| event_loop);
+
+        let answer = exchange(
+            addr,
+            &[
+                "GET /a HTTP/1.1\r\n\r\nPOST /b HTTP/1.1\r\nContent-Le",
+                "ngth: 5\r\n\r\nhel",
+                "loGET /c HTTP/1.1\r\nConnection: close\r\n\r\nGET /never HTTP/1.1\r\n\r\n",
+            ],
+        );
+
+        assert_eq!(3, answer.matches("HTTP/1.1 200 OK").count());
+        assert!(answer.contains("GET /a "));
+        assert!(answer.contains("POST /b hello"));
+        assert!(answer.contains("Connection: close\r\n"));
+        assert!(answer.ends_with("GET /c "));
+        shutdown.trigger();
+        server.join().unwrap();
+    }
+
+    #[test]
+    fn refuses_what_it_cant_answer() {
+        let (addr, shutdown, server) = serve(|event_loop| event_loop.max_head(64));
+
+        let answer = exchange(addr, &["garbage\r\n\r\n"]);
+        assert!(answer.starts_with("HTTP/1.1 400 BAD REQUEST\r\n"));
+        let answer = exchange(
+            addr,
+            &[&format!("GET /{} HTTP/1.1\r\n\r\n", "a".repeat(100))],
+        );
+        assert!(answer.starts_with("HTTP/1.1 431 "));
+        let answer = exchange(addr, &["POST / HTTP/1.1\r\nContent-Length: 9\r\n\r\nshort"]);
+        assert!(answer.starts_with("HTTP/1.1 400 BAD REQUEST\r\n"));
+
+        shutdown.trigger();
+        server.join().unwrap();
+    }
+
+    #[test]
+    fn serves_many_connections_at_once() {
+        let (addr, shutdown, server) = serve(|event_loop| event_loop);
+
+        // All of them are open before any is answered
+        let mut clients: Vec<net::TcpStream> = (0..20)
+            .map(|_| net::TcpStream::connect(addr).unwrap())
+            .collect();
+        for (i, client) in clients.iter_mut().enumerate().rev() {
+            let request = format!("GET /{} HTTP/1.1\r\nConnection: close\r\n\r\n", i);
+            client.write_all(request.as_bytes()).unwrap();
+        }
+        for (i, client) in clients.iter_mut().enumerate() {
+            let mut answer = String::new();
+            client.read_to_string(&mut answer).unwrap();
+            assert!(answer.ends_with(&format!("GET /{} ", i)), "{}", answer);
+        }
+
+        shutdown.trigger();
+        server.join().unwrap();
+    }
+
+    #[test]
+    fn turns_away_connections_over_the_limit() {
+        let limit = ConnectionLimit::new(2);
+        let (addr, shutdown, server) = {
+            let limit = limit.clone();
+            serve(move |event_loop| event_loop.connection_limit(limit))
+        };
+
+        let mut open: Vec<net::TcpStream> = (0..2)
+            .map(|_| net::TcpStream::connect(addr).unwrap())
+            .collect();
+        // Something is answered on each, so they've surely been accepted
+        for client in &mut open {
+            client.write_all(b"GET / HTTP/1.1\r\n\r\n").unwrap();
+            client.read_exact(&mut [0; 12]).unwrap();
+        }
+        assert_eq!(2, limit.open());
+
+        let answer = exchange(addr, &[]);
+        assert!(answer.starts_with("HTTP/1.1 503 "), "{}", answer);
+        assert_eq!(2, limit.open());
+
+        // Room is made once the loop sees them hang up
+        drop(open);
+        while limit.open() > 0 {
+            thread::sleep(Duration::from_millis(10));
+        }
+        let answer = exchange(addr, &["GET /again HTTP/1.1\r\nConnection: close\r\n\r\n"]);
+        assert!(answer.ends_with("GET /again "), "{}", answer);
+
+        shutdown.trigger();
+        server.join().unwrap();
+        assert_eq!(0, limit.open());
+    }
+}
@@ -0,0 +1,373 @@
+use std::fs::{self, File};
+use std::io::{self, Read, Seek, SeekFrom};
+use std::path::{Component, Path, PathBuf};
+use std::time::SystemTime;
+
+use crate::date::{http_date, parse_http_date, unix_secs};
+use crate::http::{Request, Response};
+
+/// Serves the files inside one directory, the document root.
+///
+/// Paths are never allowed to reach outside the root: neither with `..`
+/// segments nor through symlinks that point elsewhere. Such requests get a
+/// 403.
+///
+/// A single byte range asked for with `Range: bytes=...` is answered with
+/// just that part of the file, so large downloads can be resumed and
+/// videos skipped through.
+///
+/// Files are sent with a weak ETag made from their size and modification
+/// time, and a Last-Modified date. A client that already has the current
+/// version, going by `If-None-Match` or `If-Modified-Since`, gets a 304
+/// with no body.
+#[derive(Debug, Clone)]
+pub struct StaticFiles {
+    root: PathBuf,
+}
+
+impl StaticFiles {
+    pub fn new(root: impl AsRef<Path>) -> io::Result<StaticFiles> {
+        Ok(StaticFiles {
+            root: root.as_ref().canonicalize()?,
+        })
+    }
+
+    /// The response to `request` for `path`, a path relative to the root
+    /// that's already percent-decoded, like the params of a request.
+    pub fn respond(&self, request: &Request, path: &str) -> Response {
+        let file = match self.resolve(path) {
+            Ok(file) => file,
+            Err(status) => return Response::new(status, ""),
+        };
+        let metadata = match fs::metadata(&file) {
+            Ok(metadata) => metadata,
+            Err(_) => return Response::not_found(""),
+        };
+        let len = metadata.len();
+        let modified = metadata.modified().ok();
+        let etag = format!("W/\"{:x}-{:x}\"", len, modified.map_or(0, unix_secs));
+
+        if is_fresh(request, &etag, modified) {
+            return with_validators(Response::new(304, ""), &etag, modified);
+        }
+
+        let response = match request.header("Range").map(|range| parse_range(range, len)) {
+            None | Some(Range::Whole) => match fs::read(&file) {
+                Ok(contents) => Response::ok(contents),
+                Err(_) => return Response::not_found(""),
+            },
+            Some(Range::Part(start, end)) => match read_part(&file, start, end) {
+                Ok(part) => Response::new(206, part)
+                    .header("Content-Range", &format!("bytes {}-{}/{}", start, end, len)),
+                Err(_) => return Response::not_found(""),
+            },
+            Some(Range::Unsatisfiable) => {
+                return Response::new(416, "").header("Content-Range", &format!("bytes */{}", len))
+            }
+        };
+
+        let response = response
+            .header("Content-Type", content_type(&file))
+            .header("Accept-Ranges", "bytes");
+        with_validators(response, &etag, modified)
+    }
+
+    // The file on disk that `path` stands for, or the status to answer with
+    // if there isn't one we're willing to serve
+    fn resolve(&self, path: &str) -> Result<PathBuf, u16> {
+        if path.contains('\0') {
+            return Err(400);
+        }
+
+        // Check the path as written first, so `..` is caught even when what
+        // it points to doesn't exist
+        let mut depth = 0usize;
+        for component in Path::new(path).components() {
+            match component {
+                Component::Normal(_) => depth += 1,
+                Component::ParentDir => depth = depth.checked_sub(1).ok_or(403u16)?,
+                Component::CurDir => {}
+                // A leading `/` or a Windows drive would replace the root
+                Component::RootDir | Component::Prefix(_) => return Err(403),
+            }
+        }
+
+        // Then the real location, which catches symlinks out of the root
+        let file = self.root.join(path).canonicalize().map_err(|_| 404u16)?;
+        if !file.starts_with(&self.root) {
+            return Err(403);
+        }
+        if !file.is_file() {
+            return Err(404);
+        }
+
+        Ok(file)
+    }
+}
+
+// Adds the headers a client can check its cached copy against later
+fn with_validators(response: Response, etag: &str, modified: Option<SystemTime>) -> Response {
+    let response = response.header("ETag", etag);
+
+    match modified {
+        Some(modified) => response.header("Last-Modified", &http_date(modified)),
+        None => response,
+    }
+}
+
+// Whether the copy the client has cached, going by the conditional headers
+// it sent, is still the current one
+fn is_fresh(request: &Request, etag: &str, modified: Option<SystemTime>) -> bool {
+    // The ETag is the more exact of the two, so when both are given it's the
+    // one that counts. Weak tags compare without their `W/`.
+    if let Some(tags) = request.header("If-None-Match") {
+        let bare = |tag: &str| tag.trim().trim_start_matches("W/").to_string();
+        return tags
+            .split(',')
+            .any(|tag| tag.trim() == "*" || bare(tag) == bare(etag));
+    }
+
+    match (
+        request
+            .header("If-Modified-Since")
+            .and_then(parse_http_date),
+        modified,
+    ) {
+        // Dates in headers only go down to the second
+        (Some(since), Some(modified)) => unix_secs(modified) <= unix_secs(since),
+        _ => false,
+    }
+}
+
+// What part of a file a Range header asks for
+#[derive(Debug, PartialEq)]
+enum Range {
+    // The header is of a kind that's ignored, so the whole file is sent
+    Whole,
+    // From the first byte to the second, both included
+    Part(u64, u64),
+    // The range starts past the end of the file
+    Unsatisfiable,
+}
+
+// Understands the three forms of a single range: `bytes=0-99`, `bytes=100-`
+// for everything from byte 100 and `bytes=-100` for the last 100 bytes.
+// Several ranges at once are allowed to be answered with the whole file,
+// and are, as are headers that don't make sense.
+fn parse_range(header: &str, len: u64) -> Range {
+    let spec = match header.trim().strip_prefix("bytes=") {
+        Some(spec) if !spec.contains(',') => spec.trim(),
+        _ => return Range::Whole,
+    };
+    let (start, end) = match spec.split_once('-') {
+        Some(bounds) => bounds,
+        None => return Range::Whole,
+    };
+    let number = |text: &str| text.parse::<u64>().ok();
+
+    match (number(start), number(end)) {
+        // The last `suffix` bytes
+        (None, Some(suffix)) if start.is_empty() => {
+            if suffix == 0 || len == 0 {
+                Range::Unsatisfiable
+            } else {
+                Range::Part(len.saturating_sub(suffix), len - 1)
+            }
+        }
+        (Some(start), end) if end.is_some() || spec.ends_with('-') => match end {
+            Some(end) if end < start => Range::Whole,
+            _ if start >= len => Range::Unsatisfiable,
+            // An end past the end of the file means up to the end
+            end => Range::Part(start, end.map_or(len - 1, |end| end.min(len - 1))),
+        },
+        _ => Range::Whole,
+    }
+}
+
+// Reads bytes `start` to `end`, both included, without reading the rest
+fn read_part(file: &Path, start: u64, end: u64) -> io::Result<Vec<u8>> {
+    let mut file = File::open(file)?;
+    file.seek(SeekFrom::Start(start))?;
+
+    let mut part = Vec::with_capacity((end - start + 1) as usize);
+    file.take(end - start + 1).read_to_end(&mut part)?;
+    Ok(part)
+}
+
+// The Content-Type for a file, going by its extension
+fn content_type(file: &Path) -> &'static str {
+    match file.extension().and_then(|extension| extension.to_str()) {
+        Some("html") => "text/html; charset=utf-8",
+        Some("css") => "text/css",
+        Some("js") => "text/javascript",
+        Some("txt") => "text/plain; charset=utf-8",
+        Some("json") => "application/json",
+        Some("png") => "image/png",
+        Some("jpg") | Some("jpeg") => "image/jpeg",
+        Some("svg") => "image/svg+xml",
+        _ => "application/octet-stream",
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::env;
+
+    // A document root with a secret file next to it, which must never be
+    // served
+    fn setup(name: &str) -> (PathBuf, StaticFiles) {
+        let dir = env::temp_dir().join(format!("hello-files-{}-{}", name, std::process::id()));
+        let root = dir.join("public");
+        fs::create_dir_all(root.join("css")).unwrap();
+        fs::write(root.join("index.html"), "<h1>hi</h1>").unwrap();
+        fs::write(root.join("css").join("site.css"), "h1 {}").unwrap();
+        fs::write(dir.join("secret.txt"), "secret").unwrap();
+
+        let files = StaticFiles::new(&root).unwrap();
+        (dir, files)
+    }
+
+    fn get(files: &StaticFiles, path: &str, headers: &str) -> Response {
+        let head = format!("GET /static HTTP/1.1\r\n{}\r\n", headers);
+        files.respond(&Request::parse(head.as_bytes()).unwrap(), path)
+    }
+
+    fn status(files: &StaticFiles, path: &str) -> u16 {
+        get(files, path, "").status
+    }
+
+    #[test]
+    fn serves_files_inside_the_root() {
+        let (dir, files) = setup("inside");
+
+        let response = get(&files, "index.html", "");
+        assert_eq!(200, response.status);
+        assert_eq!(b"<h1>hi</h1>".to_vec(), response.body);
+        assert_eq!(200, status(&files, "css/site.css"));
+        assert_eq!(200, status(&files, "css/../index.html"));
+        assert_eq!(404, status(&files, "missing.html"));
+        assert_eq!(404, status(&files, "css"));
+
+        fs::remove_dir_all(dir).unwrap();
+    }
+
+    #[test]
+    fn rejects_paths_out_of_the_root() {
+        let (dir, files) = setup("traversal");
+
+        for path in [
+            "../secret.txt",
+            "css/../../secret.txt",
+            "css/./../../secret.txt",
+            "../../../../etc/passwd",
+            "/etc/passwd",
+        ] {
+            assert_eq!(403, status(&files, path), "{}", path);
+        }
+
+        fs::remove_dir_all(dir).unwrap();
+    }
+
+    #[test]
+    fn takes_paths_as_already_decoded() {
+        let (dir, files) = setup("encoding");
+
+        assert_eq!(400, status(&files, "index\0.html"));
+        // The request decoded the path already, so this asks for a file
+        // really called `%2e%2e`, which doesn't exist
+        assert_eq!(404, status(&files, "%2e%2e/secret.txt"));
+
+        fs::remove_dir_all(dir).unwrap();
+    }
+
+    #[test]
+    fn serves_byte_ranges() {
+        let (dir, files) = setup("range");
+        // `<h1>hi</h1>` is 11 bytes
+        let range = |range: &str| get(&files, "index.html", &format!("Range: {}\r\n", range));
+
+        let response = range("bytes=1-2");
+        assert_eq!(206, response.status);
+        assert_eq!(b"h1".to_vec(), response.body);
+        assert_eq!(Some("bytes 1-2/11"), response.header_value("Content-Range"));
+        assert_eq!(b"hi</h1>".to_vec(), range("bytes=4-").body);
+        assert_eq!(b"h1>".to_vec(), range("bytes=-3").body);
+        assert_eq!(b"</h1>".to_vec(), range("bytes=6-100").body);
+        assert_eq!(11, range("bytes=-100").body.len());
+
+        let response = range("bytes=11-");
+        assert_eq!(416, response.status);
+        assert_eq!(Some("bytes */11"), response.header_value("Content-Range"));
+        assert_eq!(416, range("bytes=-0").status);
+
+        // Headers that can't be used get the whole file
+        for ignored in [
+            "bytes=5-2",
+            "bytes=0-1,4-5",
+            "lines=1-2",
+            "bytes=x-",
+            "bytes=-",
+        ] {
+            let response = range(ignored);
+            assert_eq!(200, response.status, "{}", ignored);
+            assert_eq!(11, response.body.len(), "{}", ignored);
+        }
+
+        fs::remove_dir_all(dir).unwrap();
+    }
+
+    #[test]
+    fn answers_304_when_the_cached_copy_is_current() {
+        let (dir, files) = setup("conditional");
+
+        let first = get(&files, "index.html", "");
+        let etag = first.header_value("ETag").unwrap().to_string();
+        let modified = first.header_value("Last-Modified").unwrap().to_string();
+        assert!(etag.starts_with("W/\"b-"));
+
+        let fresh = get(
+            &files,
+            "index.html",
+            &format!("If-None-Match: {}\r\n", etag),
+        );
+        assert_eq!(304, fresh.status);
+        assert!(fresh.body.is_empty());
+        assert_eq!(Some(etag.as_str()), fresh.header_value("ETag"));
+
+        let conditional =
+            |header: &str| get(&files, "index.html", &format!("{}\r\n", header)).status;
+        assert_eq!(304, conditional("If-None-Match: \"other\", *"));
+        assert_eq!(200, conditional("If-None-Match: W/\"other\""));
+        assert_eq!(
+            304,
+            conditional(&format!("If-Modified-Since: {}", modified))
+        );
+        assert_eq!(
+            200,
+            conditional("If-Modified-Since: Thu, 01 Jan 1970 00:00:00 GMT")
+        );
+        assert_eq!(200, conditional("If-Modified-Since: yesterday"));
+        // If-None-Match wins when both are given
+        assert_eq!(
+            200,
+            conditional(&format!(
+                "If-None-Match: \"other\"\r\nIf-Modified-Since: {}",
+                modified
+            ))
+        );
+
+        fs::remove_dir_all(dir).unwrap();
+    }
+
+    #[cfg(unix)]
+    #[test]
+    fn rejects_symlinks_out_of_the_root() {
+        let (dir, files) = setup("symlink");
+        std::os::unix::fs::symlink(dir.join("secret.txt"), files.root.join("link.txt")).unwrap();
+
+        assert_eq!(403, status(&files, "link.txt"));
+
+        fs::remove_dir_all(dir).unwrap();
+    }
+}
@@ -0,0 +1,785 @@
+use std::collections::HashMap;
+use std::fmt;
+use std::io::{self, BufRead, Read, Write};
+use std::net::IpAddr;
+
+use crate::cookie::{parse_cookies, Cookie};
+use crate::json::ToJson;
+use crate::query::Query;
+
+/// An HTTP request, as read off a connection.
+#[derive(Debug)]
+pub struct Request {
+    pub method: String,
+    /// The path the request is for, without any query string. It's already
+    /// percent-decoded and normalized, see `parse`.
+    pub path: String,
+    /// The query string after the `?` in the request's target, if any
+    pub query: Query,
+    pub version: String,
+    pub headers: Vec<(String, String)>,
+    /// The cookies sent in the Cookie headers, by name
+    pub cookies: HashMap<String, String>,
+    /// The body sent with the request; empty until `read_body` reads it
+    pub body: Vec<u8>,
+    /// Where the request came from, if the server filled it in; parsing
+    /// alone can't tell
+    pub client: Option<IpAddr>,
+    // Filled in by the router from the `:name` segments of the matching route
+    pub(crate) params: HashMap<String, String>,
+}
+
+impl Request {
+    /// Parses the request line and headers at the start of `bytes`. Returns
+    /// None if the request line isn't of the form `METHOD /path VERSION`,
+    /// or the path has a broken `%` escape.
+    ///
+    /// The path is decoded and then normalized, so routes see one spelling
+    /// of it: `/a//b/./c/../d` becomes `/a/b/d`, and `..` never climbs
+    /// above `/`.
+    pub fn parse(bytes: &[u8]) -> Option<Request> {
+        let text = String::from_utf8_lossy(bytes);
+        // Anything after the blank line is the body, see read_body
+        let head = text.split("\r\n\r\n").next().unwrap_or("");
+        let mut lines = head.split("\r\n");
+
+        let mut request_line = lines.next()?.split(' ');
+        let method = request_line.next()?;
+        let target = request_line.next()?;
+        let version = request_line.next()?;
+        if method.is_empty() || !target.starts_with('/') || request_line.next().is_some() {
+            return None;
+        }
+
+        let (path, query) = target.split_once('?').unwrap_or((target, ""));
+        let path = normalize_path(&percent_decode(path)?);
+
+        // Header names are case insensitive, so they're stored lowercased
+        let headers: Vec<(String, String)> = lines
+            .filter_map(|line| line.split_once(':'))
+            .map(|(name, value)| (name.trim().to_lowercase(), value.trim().to_string()))
+            .collect();
+        let mut cookies = HashMap::new();
+        for (_, value) in headers.iter().filter(|(name, _)| name == "cookie") {
+            parse_cookies(value, &mut cookies);
+        }
+
+        Some(Request {
+            method: method.to_string(),
+            path,
+            query: Query::parse(query),
+            version: version.to_string(),
+            headers,
+            cookies,
+            body: Vec::new(),
+            client: None,
+            params: HashMap::new(),
+        })
+    }
+
+    /// The value of the header called `name`, ignoring case.
+    pub fn header(&self, name: &str) -> Option<&str> {
+        let name = name.to_lowercase();
+
+        self.headers
+            .iter()
+            .find(|(header, _)| *header == name)
+            .map(|(_, value)| value.as_str())
+    }
+
+    /// The value of the cookie called `name`.
+    pub fn cookie(&self, name: &str) -> Option<&str> {
+        self.cookies.get(name).map(String::as_str)
+    }
+
+    /// Reads the body announced by the Content-Length header from
+    /// `reader`, which should be right after the request's head. A request
+    /// without the header has no body. Bodies longer than `limit` bytes are
+    /// refused before any of them is read.
+    pub fn read_body<R: Read>(&mut self, reader: &mut R, limit: usize) -> Result<(), BodyError> {
+        let length = self.body_length(limit)?;
+
+        let mut body = vec![0; length];
+        reader.read_exact(&mut body).map_err(BodyError::Io)?;
+        self.body = body;
+        Ok(())
+    }
+
+    /// How long the body is, going by the Content-Length header, as long
+    /// as that's no more than `limit` bytes.
+    pub fn body_length(&self, limit: usize) -> Result<usize, BodyError> {
+        let length = match self.header("Content-Length") {
+            Some(length) => length.parse().map_err(|_| BodyError::BadLength)?,
+            None => 0,
+        };
+        if length > limit {
+            return Err(BodyError::TooLarge);
+        }
+        Ok(length)
+    }
+
+    /// The fields of a form posted as `application/x-www-form-urlencoded`,
+    /// the way HTML forms send them by default. None if the body is of some
+    /// other type.
+    pub fn form(&self) -> Option<Query> {
+        let content_type = self.header("Content-Type")?;
+        let mime = content_type.split(';').next().unwrap().trim();
+        if !mime.eq_ignore_ascii_case("application/x-www-form-urlencoded") {
+            return None;
+        }
+
+        // The body is encoded just like a query string
+        let body = std::str::from_utf8(&self.body).ok()?;
+        Some(Query::parse(body))
+    }
+
+    /// Whether the client wants to send more requests on the same
+    /// connection. HTTP/1.1 connections stay open unless the client says
+    /// `Connection: close`; HTTP/1.0 ones only if it says `keep-alive`.
+    pub fn keep_alive(&self) -> bool {
+        let has_option = |option: &str| {
+            self.header("Connection").is_some_and(|value| {
+                value
+                    .split(',')
+                    .any(|token| token.trim().eq_ignore_ascii_case(option))
+            })
+        };
+
+        if self.version == "HTTP/1.0" {
+            has_option("keep-alive")
+        } else {
+            !has_option("close")
+        }
+    }
+
+    /// The part of the path matched by `:name` in the route that handled the
+    /// request, e.g. `id` for `/users/:id`.
+    pub fn param(&self, name: &str) -> Option<&str> {
+        self.params.get(name).map(String::as_str)
+    }
+}
+
+/// Why a request's body couldn't be read.
+#[derive(Debug)]
+pub enum BodyError {
+    /// The Content-Length header isn't a number
+    BadLength,
+    /// The body is over the size limit
+    TooLarge,
+    /// The connection failed or closed before the whole body arrived
+    Io(io::Error),
+}
+
+impl BodyError {
+    /// The status to answer with, if the connection is still usable for
+    /// answering at all.
+    pub fn status(&self) -> u16 {
+        match self {
+            BodyError::BadLength | BodyError::Io(_) => 400,
+            BodyError::TooLarge => 413,
+        }
+    }
+}
+
+impl fmt::Display for BodyError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            BodyError::BadLength => write!(f, "invalid Content-Length"),
+            BodyError::TooLarge => write!(f, "request body too large"),
+            BodyError::Io(e) => write!(f, "couldn't read the request body: {}", e),
+        }
+    }
+}
+
+impl std::error::Error for BodyError {}
+
+/// Why the head of a request couldn't be read.
+#[derive(Debug)]
+pub enum HeadError {
+    /// The request line and headers are over the size limit
+    TooLarge,
+    /// The connection failed, timed out, or closed in the middle of the head
+    Io(io::Error),
+}
+
+impl HeadError {
+    /// The status to answer with, if the connection is still usable for
+    /// answering at all.
+    pub fn status(&self) -> u16 {
+        match self {
+            HeadError::TooLarge => 431,
+            HeadError::Io(_) => 400,
+        }
+    }
+}
+
+impl fmt::Display for HeadError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            HeadError::TooLarge => write!(f, "request headers too large"),
+            HeadError::Io(e) => write!(f, "couldn't read the request: {}", e),
+        }
+    }
+}
+
+impl std::error::Error for HeadError {}
+
+/// Reads the head of the next request on a connection: the request line and
+/// the headers, up to and including the blank line after them. Returns None
+/// if the connection was closed before another request started. No more
+/// than `limit` bytes are read; a head that doesn't end by then is
+/// `TooLarge`.
+pub fn read_head<R: BufRead>(reader: &mut R, limit: usize) -> Result<Option<Vec<u8>>, HeadError> {
+    let mut head = Vec::new();
+
+    loop {
+        let start = head.len();
+        // One byte over the limit is enough to tell the head is too large.
+        // The limit can be as high as usize::MAX, with no byte past it.
+        let allowed = (limit.saturating_add(1) - start) as u64;
+        let read = reader
+            .take(allowed)
+            .read_until(b'\n', &mut head)
+            .map_err(HeadError::Io)?;
+        if head.len() > limit {
+            return Err(HeadError::TooLarge);
+        }
+        if read == 0 {
+            if head.is_empty() {
+                return Ok(None);
+            }
+            return Err(HeadError::Io(io::Error::new(
+                io::ErrorKind::UnexpectedEof,
+                "connection closed in the middle of a request",
+            )));
+        }
+
+        if head[start..] == b"\r\n"[..] || head[start..] == b"\n"[..] {
+            // Clients may send blank lines between requests; skip them
+            if start == 0 {
+                head.clear();
+                continue;
+            }
+            return Ok(Some(head));
+        }
+    }
+}
+
+// Drops empty and `.` segments and resolves `..` ones, the way RFC 3986
+// removes dot segments. A trailing slash is kept, since `/dir/` and `/dir`
+// aren't always the same thing.
+fn normalize_path(path: &str) -> String {
+    let mut segments = Vec::new();
+    for segment in path.split('/') {
+        match segment {
+            "" | "." => {}
+            ".." => {
+                segments.pop();
+            }
+            _ => segments.push(segment),
+        }
+    }
+
+    let mut normalized = format!("/{}", segments.join("/"));
+    let trailing = ["/", "/.", "/.."].iter().any(|end| path.ends_with(end));
+    if trailing && !segments.is_empty() {
+        normalized.push('/');
+    }
+    normalized
+}
+
+/// Decodes `%XX` escapes in part of a URL. Returns None if an escape is cut
+/// short or the decoded bytes aren't UTF-8.
+pub(crate) fn percent_decode(text: &str) -> Option<String> {
+    let mut bytes = Vec::with_capacity(text.len());
+    let mut rest = text.as_bytes();
+
+    while let Some((&byte, after)) = rest.split_first() {
+        if byte == b'%' {
+            let hex = after.get(..2)?;
+            let hex = std::str::from_utf8(hex).ok()?;
+            bytes.push(u8::from_str_radix(hex, 16).ok()?);
+            rest = &after[2..];
+        } else {
+            bytes.push(byte);
+            rest = after;
+        }
+    }
+
+    String::from_utf8(bytes).ok()
+}
+
+/// Escapes what can't appear as it is in part of a URL as `%XX`, leaving
+/// letters, digits, `-._~` and any of `keep` alone.
+pub(crate) fn percent_encode(text: &str, keep: &str) -> String {
+    let mut encoded = String::with_capacity(text.len());
+    for byte in text.bytes() {
+        let c = char::from(byte);
+        if c.is_ascii_alphanumeric() || "-._~".contains(c) || keep.contains(c) {
+            encoded.push(c);
+        } else {
+            encoded.push_str(&format!("%{:02X}", byte));
+        }
+    }
+    encoded
+}
+
+// Writes the body of a streaming response, see `Response::stream`
+type Stream = Box<dyn FnOnce(&mut ResponseWriter) -> io::Result<()> + Send>;
+
+/// An HTTP response, ready to be written to a connection with `write_to`.
+pub struct Response {
+    pub status: u16,
+    pub headers: Vec<(String, String)>,
+    pub body: Vec<u8>,
+    stream: Option<Stream>,
+}
+
+impl Response {
+    pub fn new(status: u16, body: impl Into<Vec<u8>>) -> Response {
+        Response {
+            status,
+            headers: Vec::new(),
+            body: body.into(),
+            stream: None,
+        }
+    }
+
+    /// A response whose body isn't known up front. It's sent with
+    /// `Transfer-Encoding: chunked`, and `write_body` is called once the
+    /// headers are out to write the body bit by bit. Every write to the
+    /// `ResponseWriter` goes out as one chunk, so formatting macros like
+    /// `write!`, which make several writes, are best avoided.
+    ///
+    /// ```no_run
+    /// use std::io::Write;
+    /// use hello::Response;
+    ///
+    /// let response = Response::stream(200, |out| {
+    ///     for i in 1..=3 {
+    ///         out.write_all(format!("line {}\n", i).as_bytes())?;
+    ///     }
+    ///     Ok(())
+    /// });
+    /// ```
+    pub fn stream<F>(status: u16, write_body: F) -> Response
+    where
+        F: FnOnce(&mut ResponseWriter) -> io::Result<()> + Send + 'static,
+    {
+        Response {
+            stream: Some(Box::new(write_body)),
+            ..Response::new(status, "")
+        }
+    }
+
+    pub fn ok(body: impl Into<Vec<u8>>) -> Response {
+        Response::new(200, body)
+    }
+
+    /// A response sending the client to `location`, which can be a path on
+    /// this server or a full URL. `status` should be one of the 3xx ones:
+    /// 301 or 308 for a move for good, 302 or 307 for now. 307 and 308 keep
+    /// the request's method, where the others may turn it into a GET.
+    pub fn redirect(status: u16, location: &str) -> Response {
+        Response::new(status, format!("Moved to {}", location)).header("Location", location)
+    }
+
+    pub fn not_found(body: impl Into<Vec<u8>>) -> Response {
+        Response::new(404, body)
+    }
+
+    /// A 200 response with `value` written out as JSON.
+    pub fn json<T: ToJson + ?Sized>(value: &T) -> Response {
+        Response::ok(value.to_json().to_string()).header("Content-Type", "application/json")
+    }
+
+    /// Adds a header to the response.
+    pub fn header(mut self, name: &str, value: &str) -> Response {
+        self.headers.push((name.to_string(), value.to_string()));
+        self
+    }
+
+    /// Adds a Set-Cookie header for `cookie`. Each cookie needs a header of
+    /// its own, so this can be called more than once.
+    pub fn set_cookie(self, cookie: &Cookie) -> Response {
+        self.header("Set-Cookie", &cookie.to_string())
+    }
+
+    /// The value of the first header called `name`, ignoring case.
+    pub fn header_value(&self, name: &str) -> Option<&str> {
+        self.headers
+            .iter()
+            .find(|(header, _)| header.eq_ignore_ascii_case(name))
+            .map(|(_, value)| value.as_str())
+    }
+
+    /// Whether this is a streaming response, made with `stream`.
+    pub fn is_stream(&self) -> bool {
+        self.stream.is_some()
+    }
+
+    /// The status line, headers and body, as they go over the wire.
+    /// Content-Length is always filled in from the body, so this is only
+    /// the whole response when it isn't a streaming one.
+    pub fn to_bytes(&self) -> Vec<u8> {
+        let mut bytes = self.head("Content-Length", &self.body.len().to_string());
+        bytes.extend_from_slice(&self.body);
+        bytes
+    }
+
+    /// Sends the response to `out`, running the body writer of a streaming
+    /// response as it goes, and gives back the size of the body that was
+    /// sent. If this fails partway, the connection can't be used for
+    /// anything else.
+    pub fn write_to<W: Write>(mut self, out: &mut W) -> io::Result<u64> {
+        let write_body = match self.stream.take() {
+            Some(write_body) => write_body,
+            None => {
+                out.write_all(&self.to_bytes())?;
+                out.flush()?;
+                return Ok(self.body.len() as u64);
+            }
+        };
+
+        out.write_all(&self.head("Transfer-Encoding", "chunked"))?;
+        let mut writer = ResponseWriter { out, sent: 0 };
+        write_body(&mut writer)?;
+        // A zero-length chunk marks the end of the body
+        writer.out.write_all(b"0\r\n\r\n")?;
+        writer.out.flush()?;
+        Ok(writer.sent)
+    }
+
+    /// Sends only the status line and headers, as the answer to a HEAD
+    /// request. The headers are the ones `write_to` would send, so
+    /// Content-Length still gives the size of the body that's left out.
+    pub fn write_head_to<W: Write>(&self, out: &mut W) -> io::Result<()> {
+        let head = match self.stream {
+            Some(_) => self.head("Transfer-Encoding", "chunked"),
+            None => self.head("Content-Length", &self.body.len().to_string()),
+        };
+
+        out.write_all(&head)?;
+        out.flush()
+    }
+
+    // The status line and headers, ending with the one that says how the
+    // body's length is given
+    fn head(&self, length_name: &str, length_value: &str) -> Vec<u8> {
+        let mut head = format!("HTTP/1.1 {} {}\r\n", self.status, reason(self.status));
+        for (name, value) in &self.headers {
+            head.push_str(&format!("{}: {}\r\n", name, value));
+        }
+        // These never have a body, so there's no length to give
+        if !matches!(self.status, 204 | 304) {
+            head.push_str(&format!("{}: {}\r\n", length_name, length_value));
+        }
+        head.push_str("\r\n");
+        head.into_bytes()
+    }
+}
+
+impl fmt::Debug for Response {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        f.debug_struct("Response")
+            .field("status", &self.status)
+            .field("headers", &self.headers)
+            .field("body", &self.body)
+            .field("stream", &self.stream.is_some())
+            .finish()
+    }
+}
+
+/// Where a streaming response writes its body. Each write is sent as its
+/// own chunk, straight away.
+pub struct ResponseWriter<'a> {
+    out: &'a mut dyn Write,
+    // The bytes of body written so far
+    sent: u64,
+}
+
+impl Write for ResponseWriter<'_> {
+    fn write(&mut self, buf: &[u8]) -> io::Result<usize> {
+        // An empty chunk would end the body early
+        if buf.is_empty() {
+            return Ok(0);
+        }
+
+        write!(self.out, "{:x}\r\n", buf.len())?;
+        self.out.write_all(buf)?;
+        self.out.write_all(b"\r\n")?;
+        self.out.flush()?;
+        self.sent += buf.len() as u64;
+        Ok(buf.len())
+    }
+
+    fn flush(&mut self) -> io::Result<()> {
+        self.out.flush()
+    }
+}
+
+// The reason phrase that goes with a status code in the status line
+fn reason(status: u16) -> &'static str {
+    match status {
+        200 => "OK",
+        204 => "NO CONTENT",
+        206 => "PARTIAL CONTENT",
+        301 => "MOVED PERMANENTLY",
+        302 => "FOUND",
+        304 => "NOT MODIFIED",
+        307 => "TEMPORARY REDIRECT",
+        308 => "PERMANENT REDIRECT",
+        400 => "BAD REQUEST",
+        403 => "FORBIDDEN",
+        404 => "NOT FOUND",
+        405 => "METHOD NOT ALLOWED",
+        408 => "REQUEST TIMEOUT",
+        413 => "PAYLOAD TOO LARGE",
+        416 => "RANGE NOT SATISFIABLE",
+        431 => "REQUEST HEADER FIELDS TOO LARGE",
+        500 => "INTERNAL SERVER ERROR",
+        503 => "SERVICE UNAVAILABLE",
+        _ => "",
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn parses_the_request_line_and_headers() {
+        let request =
+            Request::parse(b"GET /users/7?x=1 HTTP/1.1\r\nHost: localhost\r\n\r\n").unwrap();
+
+        assert_eq!("GET", request.method);
+        assert_eq!("/users/7", request.path);
+        assert_eq!(Some("1"), request.query.get("x"));
+        assert_eq!("HTTP/1.1", request.version);
+        assert_eq!(Some("localhost"), request.header("HOST"));
+        assert_eq!(None, request.header("Accept"));
+    }
+
+    #[test]
+    fn collects_cookies_from_every_cookie_header() {
+        let request =
+            Request::parse(b"GET / HTTP/1.1\r\nCookie: a=1; b=2\r\ncookie: c=3\r\n\r\n").unwrap();
+
+        assert_eq!(Some("1"), request.cookie("a"));
+        assert_eq!(Some("3"), request.cookie("c"));
+        assert_eq!(3, request.cookies.len());
+        assert!(Request::parse(b"GET / HTTP/1.1\r\n\r\n")
+            .unwrap()
+            .cookies
+            .is_empty());
+    }
+
+    #[test]
+    fn decodes_and_normalizes_paths() {
+        let path = |target: &str| {
+            Request::parse(format!("GET {} HTTP/1.1\r\n\r\n", target).as_bytes())
+                .map(|request| request.path)
+        };
+
+        assert_eq!(Some(String::from("/hello world")), path("/hello%20world"));
+        assert_eq!(Some(String::from("/a/b/c")), path("/a//b/./c"));
+        assert_eq!(Some(String::from("/a/c/")), path("/a/b/../c/"));
+        assert_eq!(Some(String::from("/")), path("/a/.."));
+        assert_eq!(Some(String::from("/")), path("//"));
+        assert_eq!(Some(String::from("/etc/passwd")), path("/../../etc/passwd"));
+        // Escaped slashes and dots are decoded before normalizing
+        assert_eq!(
+            Some(String::from("/secret.txt")),
+            path("/static/..%2f..%2Fsecret.txt")
+        );
+        assert_eq!(Some(String::from("/x")), path("/static/%2e%2e/x?a=%2e"));
+        assert_eq!(None, path("/index%2"));
+        assert_eq!(None, path("/%ff"));
+    }
+
+    #[test]
+    fn keep_alive_depends_on_version_and_connection_header() {
+        let keep_alive = |head: &str| Request::parse(head.as_bytes()).unwrap().keep_alive();
+
+        assert!(keep_alive("GET / HTTP/1.1\r\n\r\n"));
+        assert!(!keep_alive("GET / HTTP/1.1\r\nConnection: Close\r\n\r\n"));
+        assert!(!keep_alive("GET / HTTP/1.0\r\n\r\n"));
+        assert!(keep_alive(
+            "GET / HTTP/1.0\r\nConnection: Upgrade, keep-alive\r\n\r\n"
+        ));
+    }
+
+    #[test]
+    fn reads_one_request_head_at_a_time() {
+        let mut reader = &b"GET /a HTTP/1.1\r\nHost: x\r\n\r\n\r\nGET /b HTTP/1.1\r\n\r\n"[..];
+
+        assert_eq!(
+            b"GET /a HTTP/1.1\r\nHost: x\r\n\r\n".to_vec(),
+            read_head(&mut reader, 1024).unwrap().unwrap()
+        );
+        assert_eq!(
+            b"GET /b HTTP/1.1\r\n\r\n".to_vec(),
+            read_head(&mut reader, 1024).unwrap().unwrap()
+        );
+        assert!(read_head(&mut reader, 1024).unwrap().is_none());
+        assert!(read_head(&mut &b"GET / HT"[..], 1024).is_err());
+    }
+
+    #[test]
+    fn refuses_oversized_heads() {
+        let head = b"GET / HTTP/1.1\r\nHost: x\r\n\r\n";
+
+        assert!(read_head(&mut &head[..], head.len()).unwrap().is_some());
+        let error = read_head(&mut &head[..], head.len() - 1).unwrap_err();
+        assert!(matches!(error, HeadError::TooLarge));
+        assert_eq!(431, error.status());
+        // A single endless line is caught too, without reading all of it
+        let long_line = vec![b'a'; 100_000];
+        assert!(matches!(
+            read_head(&mut &long_line[..], 8192),
+            Err(HeadError::TooLarge)
+        ));
+        // The highest limit --max-head takes can't overflow
+        assert!(read_head(&mut &head[..], usize::MAX).unwrap().is_some());
+    }
+
+    #[test]
+    fn reads_the_body_content_length_announces() {
+        let mut reader = &b"hello, worldGET / HTTP/1.1"[..];
+        let mut request =
+            Request::parse(b"POST /echo HTTP/1.1\r\nContent-Length: 12\r\n\r\n").unwrap();
+
+        request.read_body(&mut reader, 100).unwrap();
+        assert_eq!(b"hello, world".to_vec(), request.body);
+        // The next request is left for the next read
+        assert_eq!(b"GET / HTTP/1.1".to_vec(), reader.to_vec());
+
+        let mut request = Request::parse(b"GET / HTTP/1.1\r\n\r\n").unwrap();
+        request.read_body(&mut reader, 100).unwrap();
+        assert!(request.body.is_empty());
+    }
+
+    #[test]
+    fn refuses_bad_and_oversized_bodies() {
+        let body_error = |head: &str, body: &[u8]| {
+            let mut request = Request::parse(head.as_bytes()).unwrap();
+            request.read_body(&mut &body[..], 4).unwrap_err()
+        };
+
+        let error = body_error("POST / HTTP/1.1\r\nContent-Length: 5\r\n\r\n", b"12345");
+        assert!(matches!(error, BodyError::TooLarge));
+        assert_eq!(413, error.status());
+        let error = body_error("POST / HTTP/1.1\r\nContent-Length: -1\r\n\r\n", b"");
+        assert!(matches!(error, BodyError::BadLength));
+        let error = body_error("POST / HTTP/1.1\r\nContent-Length: 4\r\n\r\n", b"12");
+        assert!(matches!(error, BodyError::Io(_)));
+    }
+
+    #[test]
+    fn parses_urlencoded_forms() {
+        let form = |content_type: &str, body: &str| {
+            let mut request = Request::parse(
+                format!(
+                    "POST /form HTTP/1.1\r\nContent-Type: {}\r\n\r\n",
+                    content_type
+                )
+                .as_bytes(),
+            )
+            .unwrap();
+            request.body = body.as_bytes().to_vec();
+            request.form()
+        };
+
+        let fields = form(
+            "application/x-www-form-urlencoded; charset=UTF-8",
+            "name=Ferris+the+crab&message=hi%21",
+        )
+        .unwrap();
+        assert_eq!(Some("Ferris the crab"), fields.get("name"));
+        assert_eq!(Some("hi!"), fields.get("message"));
+
+        assert!(form("text/plain", "name=Ferris").is_none());
+        assert!(Request::parse(b"POST / HTTP/1.1\r\n\r\n")
+            .unwrap()
+            .form()
+            .is_none());
+    }
+
+    #[test]
+    fn rejects_garbage() {
+        assert!(Request::parse(b"").is_none());
+        assert!(Request::parse(b"hello\r\n\r\n").is_none());
+        assert!(Request::parse(b"GET users HTTP/1.1\r\n\r\n").is_none());
+    }
+
+    #[test]
+    fn decodes_percent_escapes() {
+        assert_eq!(Some(String::from("a b/c")), percent_decode("a%20b%2Fc"));
+        assert_eq!(Some(String::from("café")), percent_decode("caf%C3%A9"));
+        assert_eq!(None, percent_decode("50%"));
+        assert_eq!(None, percent_decode("%zz"));
+        assert_eq!(None, percent_decode("%ff"));
+    }
+
+    #[test]
+    fn json_responses_say_so() {
+        let response = Response::json(&vec![1, 2]);
+
+        assert_eq!(b"[1,2]".to_vec(), response.body);
+        assert_eq!(
+            vec![(
+                String::from("Content-Type"),
+                String::from("application/json")
+            )],
+            response.headers
+        );
+    }
+
+    #[test]
+    fn writes_status_headers_and_body() {
+        let response = Response::ok("hi").header("Content-Type", "text/plain");
+
+        assert_eq!(
+            b"HTTP/1.1 200 OK\r\nContent-Type: text/plain\r\nContent-Length: 2\r\n\r\nhi".to_vec(),
+            response.to_bytes()
+        );
+    }
+
+    #[test]
+    fn not_modified_has_no_length() {
+        assert_eq!(
+            b"HTTP/1.1 304 NOT MODIFIED\r\nETag: \"x\"\r\n\r\n".to_vec(),
+            Response::new(304, "").header("ETag", "\"x\"").to_bytes()
+        );
+    }
+
+    #[test]
+    fn head_responses_keep_the_content_length() {
+        let mut sent = Vec::new();
+        Response::ok("hello").write_head_to(&mut sent).unwrap();
+
+        assert_eq!(
+            "HTTP/1.1 200 OK\r\nContent-Length: 5\r\n\r\n",
+            String::from_utf8(sent).unwrap()
+        );
+    }
+
+    #[test]
+    fn streams_bodies_in_chunks() {
+        let response = Response::stream(200, |out| {
+            out.write_all(b"hello ")?;
+            out.write_all(b"")?;
+            out.write_all(b"streaming world")
+        })
+        .header("Content-Type", "text/plain");
+        let mut sent = Vec::new();
+
+        assert_eq!(21, response.write_to(&mut sent).unwrap());
+
+        assert_eq!(
+            "HTTP/1.1 200 OK\r\nContent-Type: text/plain\r\nTransfer-Encoding: chunked\r\n\r\n\
+             6\r\nhello \r\nf\r\nstreaming world\r\n0\r\n\r\n",
+            String::from_utf8(sent).unwrap()
+        );
+    }
+}
@@ -0,0 +1,208 @@
+use std::fmt::{self, Write};
+
+/// A JSON value, built up by hand and written out with `to_string`.
+///
+/// This covers what the server needs to answer with JSON without pulling
+/// in a serialization library; types that want to be sent as JSON
+/// implement `ToJson`.
+#[derive(Debug, Clone, PartialEq)]
+pub enum Json {
+    Null,
+    Bool(bool),
+    Number(f64),
+    String(String),
+    Array(Vec<Json>),
+    /// Fields keep the order they were given in
+    Object(Vec<(String, Json)>),
+}
+
+impl Json {
+    /// An object with the given fields, e.g.
+    /// `Json::object(vec![("ok", true.to_json())])`.
+    pub fn object(fields: Vec<(&str, Json)>) -> Json {
+        Json::Object(
+            fields
+                .into_iter()
+                .map(|(name, value)| (name.to_string(), value))
+                .collect(),
+        )
+    }
+}
+
+impl fmt::Display for Json {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            Json::Null => f.write_str("null"),
+            Json::Bool(value) => write!(f, "{}", value),
+            // JSON has no way to write NaN or infinity
+            Json::Number(value) if !value.is_finite() => f.write_str("null"),
+            Json::Number(value) => write!(f, "{}", value),
+            Json::String(value) => write_string(f, value),
+            Json::Array(values) => {
+                f.write_char('[')?;
+                for (i, value) in values.iter().enumerate() {
+                    if i > 0 {
+                        f.write_char(',')?;
+                    }
+                    write!(f, "{}", value)?;
+                }
+                f.write_char(']')
+            }
+            Json::Object(fields) => {
+                f.write_char('{')?;
+                for (i, (name, value)) in fields.iter().enumerate() {
+                    if i > 0 {
+                        f.write_char(',')?;
+                    }
+                    write_string(f, name)?;
+                    write!(f, ":{}", value)?;
+                }
+                f.write_char('}')
+            }
+        }
+    }
+}
+
+// Writes `s` as a quoted JSON string, escaping the characters JSON doesn't
+// allow to appear raw
+fn write_string(f: &mut fmt::Formatter, s: &str) -> fmt::Result {
+    f.write_char('"')?;
+
+    for c in s.chars() {
+        match c {
+            '"' => f.write_str("\\\"")?,
+            '\\' => f.write_str("\\\\")?,
+            '\n' => f.write_str("\\n")?,
+            '\r' => f.write_str("\\r")?,
+            '\t' => f.write_str("\\t")?,
+            c if (c as u32) < 0x20 => write!(f, "\\u{:04x}", c as u32)?,
+            c => f.write_char(c)?,
+        }
+    }
+
+    f.write_char('"')
+}
+
+/// Types that can be turned into JSON, for `Response::json`.
+pub trait ToJson {
+    fn to_json(&self) -> Json;
+}
+
+impl ToJson for Json {
+    fn to_json(&self) -> Json {
+        self.clone()
+    }
+}
+
+impl ToJson for bool {
+    fn to_json(&self) -> Json {
+        Json::Bool(*self)
+    }
+}
+
+impl ToJson for str {
+    fn to_json(&self) -> Json {
+        Json::String(self.to_string())
+    }
+}
+
+impl ToJson for String {
+    fn to_json(&self) -> Json {
+        Json::String(self.clone())
+    }
+}
+
+// Numbers are all f64 in JSON, so every number type converts the same way
+macro_rules! number_to_json {
+    ($($number:ty),*) => {
+        $(
+            impl ToJson for $number {
+                fn to_json(&self) -> Json {
+                    Json::Number(*self as f64)
+                }
+            }
+        )*
+    };
+}
+
+number_to_json!(i8, i16, i32, i64, u8, u16, u32, u64, usize, isize, f32, f64);
+
+impl<T: ToJson> ToJson for Option<T> {
+    fn to_json(&self) -> Json {
+        match self {
+            Some(value) => value.to_json(),
+            None => Json::Null,
+        }
+    }
+}
+
+impl<T: ToJson> ToJson for [T] {
+    fn to_json(&self) -> Json {
+        Json::Array(self.iter().map(ToJson::to_json).collect())
+    }
+}
+
+impl<T: ToJson> ToJson for Vec<T> {
+    fn to_json(&self) -> Json {
+        self.as_slice().to_json()
+    }
+}
+
+impl<T: ToJson + ?Sized> ToJson for &T {
+    fn to_json(&self) -> Json {
+        (**self).to_json()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    struct Crate {
+        name: &'static str,
+        downloads: u64,
+        keywords: Vec<&'static str>,
+        license: Option<String>,
+    }
+
+    impl ToJson for Crate {
+        fn to_json(&self) -> Json {
+            Json::object(vec![
+                ("name", self.name.to_json()),
+                ("downloads", self.downloads.to_json()),
+                ("keywords", self.keywords.to_json()),
+                ("license", self.license.to_json()),
+            ])
+        }
+    }
+
+    #[test]
+    fn writes_structs_as_objects() {
+        let hello = Crate {
+            name: "hello",
+            downloads: 42,
+            keywords: vec!["web", "server"],
+            license: None,
+        };
+
+        assert_eq!(
+            r#"{"name":"hello","downloads":42,"keywords":["web","server"],"license":null}"#,
+            hello.to_json().to_string()
+        );
+    }
+
+    #[test]
+    fn writes_numbers() {
+        assert_eq!("1.5", 1.5.to_json().to_string());
+        assert_eq!("-3", (-3i32).to_json().to_string());
+        assert_eq!("null", f64::NAN.to_json().to_string());
+    }
+
+    #[test]
+    fn escapes_special_characters() {
+        assert_eq!(
+            r#""say \"hi\"\\\n\u0001""#,
+            "say \"hi\"\\\n\u{1}".to_json().to_string()
+        );
+    }
+}
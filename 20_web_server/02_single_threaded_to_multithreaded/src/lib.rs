@@ -1,16 +1,180 @@
+use std::any::Any;
+use std::fmt;
+use std::num::NonZeroUsize;
+use std::panic::{self, AssertUnwindSafe};
+use std::sync::atomic::{AtomicU64, AtomicUsize, Ordering};
 use std::sync::{mpsc, Arc, Mutex};
 use std::thread;
+use std::time::{Duration, Instant};
+
+mod cache;
+mod compress;
+mod config;
+mod cookie;
+mod cors;
+mod date;
+mod event_loop;
+mod files;
+mod http;
+mod json;
+mod limit;
+mod log;
+mod metrics;
+mod query;
+mod router;
+mod session;
+mod shutdown;
+mod template;
+mod tls;
+mod vhost;
+
+pub use cache::FileCache;
+pub use compress::{Compressor, Encoder, Gzip, Identity};
+pub use config::{QueuePolicy, ServerConfig, ServerMode, Site, TlsFiles};
+pub use cookie::{Cookie, SameSite};
+pub use cors::Cors;
+pub use event_loop::EventLoop;
+pub use files::StaticFiles;
+pub use http::{read_head, BodyError, HeadError, Request, Response, ResponseWriter};
+pub use json::{Json, ToJson};
+pub use limit::{ConnectionLimit, Permit};
+pub use log::{AccessEntry, AccessLog, LogFormat, Logger, StdoutLogger};
+pub use metrics::{InFlight, Metrics};
+pub use query::Query;
+pub use router::{Router, TrailingSlash};
+pub use session::{Session, Sessions};
+pub use shutdown::Shutdown;
+pub use template::{escape_html, Template, TemplateError};
+pub use tls::{TlsAcceptor, TlsStream};
+pub use vhost::VirtualHosts;
+
 enum Message {
     NewJob(Job),
     Terminate,
 }
 pub struct ThreadPool {
     workers: Vec<Worker>,
-    sender: mpsc::Sender<Message>,
+    sender: mpsc::SyncSender<Message>,
+    stats: PoolStats,
 }
 
 type Job = Box<dyn FnOnce() + Send + 'static>;
 
+/// How a ThreadPool is set up.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct PoolConfig {
+    /// The number of threads in the pool. Being NonZeroUsize, it can't be
+    /// zero, so `with_config` never has to panic.
+    pub size: NonZeroUsize,
+    /// How many jobs can wait for a free worker. Once that many are
+    /// waiting, `execute` blocks and `try_execute` fails until a worker
+    /// takes one. With zero, jobs are only ever handed straight to an idle
+    /// worker.
+    pub queue_capacity: usize,
+}
+
+impl Default for PoolConfig {
+    fn default() -> PoolConfig {
+        PoolConfig {
+            size: NonZeroUsize::new(4).unwrap(),
+            queue_capacity: 64,
+        }
+    }
+}
+
+/// Counts of what's happened in a ThreadPool, which can be read from
+/// anywhere while the pool runs. Clones share the same counts.
+#[derive(Debug, Clone, Default)]
+pub struct PoolStats {
+    panics: Arc<AtomicUsize>,
+    queued: Arc<AtomicUsize>,
+    // One for each worker, in the order of their ids
+    workers: Arc<Vec<WorkerCounters>>,
+}
+
+// What a worker updates as it runs jobs, with times in nanoseconds
+#[derive(Debug, Default)]
+struct WorkerCounters {
+    jobs: AtomicU64,
+    busy: AtomicU64,
+    last_job: AtomicU64,
+}
+
+/// What one worker of a ThreadPool has been up to.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct WorkerStats {
+    pub id: usize,
+    /// How many jobs it has run, counting ones that panicked
+    pub jobs: u64,
+    /// How long it has spent running them, all told
+    pub busy: Duration,
+    /// How long the last job it finished took, or None if it hasn't
+    /// finished any yet
+    pub last_job: Option<Duration>,
+}
+
+impl PoolStats {
+    /// How many jobs have panicked. The worker running a job that panics
+    /// carries on with the next one, so this is all that's left of them.
+    pub fn panics_total(&self) -> usize {
+        self.panics.load(Ordering::Relaxed)
+    }
+
+    /// How many jobs are waiting in the queue for a free worker.
+    pub fn queue_depth(&self) -> usize {
+        self.queued.load(Ordering::Relaxed)
+    }
+
+    /// What each worker has been up to, in the order of their ids. A job
+    /// still running shows up once it finishes.
+    pub fn workers(&self) -> Vec<WorkerStats> {
+        self.workers
+            .iter()
+            .enumerate()
+            .map(|(id, counters)| {
+                let jobs = counters.jobs.load(Ordering::Relaxed);
+                let nanos =
+                    |counter: &AtomicU64| Duration::from_nanos(counter.load(Ordering::Relaxed));
+                WorkerStats {
+                    id,
+                    jobs,
+                    busy: nanos(&counters.busy),
+                    last_job: (jobs > 0).then(|| nanos(&counters.last_job)),
+                }
+            })
+            .collect()
+    }
+
+    // Counts a job worker `id` took `took` to run
+    fn finished(&self, id: usize, took: Duration) {
+        let counters = &self.workers[id];
+        // A u64 of nanoseconds lasts over 500 years, so nothing is lost
+        let nanos = took.as_nanos() as u64;
+        counters.busy.fetch_add(nanos, Ordering::Relaxed);
+        counters.last_job.store(nanos, Ordering::Relaxed);
+        counters.jobs.fetch_add(1, Ordering::Relaxed);
+    }
+
+    fn for_workers(size: usize) -> PoolStats {
+        PoolStats {
+            workers: Arc::new((0..size).map(|_| WorkerCounters::default()).collect()),
+            ..PoolStats::default()
+        }
+    }
+}
+
+/// The error from `try_execute` when the job queue is full.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct QueueFull;
+
+impl fmt::Display for QueueFull {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(f, "the job queue is full")
+    }
+}
+
+impl std::error::Error for QueueFull {}
+
 impl ThreadPool {
     /// Create a new ThreadPool.
     ///
@@ -22,29 +186,170 @@ impl ThreadPool {
     pub fn new(size: usize) -> ThreadPool {
         assert!(size > 0);
 
-        let (sender, receiver) = mpsc::channel();
+        ThreadPool::with_config(PoolConfig {
+            size: NonZeroUsize::new(size).unwrap(),
+            ..PoolConfig::default()
+        })
+    }
+
+    /// Create a new ThreadPool set up as `config` says.
+    pub fn with_config(config: PoolConfig) -> ThreadPool {
+        let size = config.size.get();
+        let (sender, receiver) = mpsc::sync_channel(config.queue_capacity);
 
         let receiver = Arc::new(Mutex::new(receiver));
 
         let mut workers = Vec::with_capacity(size);
 
+        let stats = PoolStats::for_workers(size);
+
         for id in 0..size {
-            workers.push(Worker::new(id, Arc::clone(&receiver)));
+            workers.push(Worker::new(id, Arc::clone(&receiver), stats.clone()));
         }
 
-        ThreadPool { workers, sender }
+        ThreadPool {
+            workers,
+            sender,
+            stats,
+        }
     }
 
-    pub fn execute<F>(&self, f: F)
+    /// The number of threads in the pool.
+    pub fn size(&self) -> usize {
+        self.workers.len()
+    }
+
+    /// A handle on the pool's counts, which stays usable after the pool is
+    /// moved or dropped.
+    pub fn stats(&self) -> PoolStats {
+        self.stats.clone()
+    }
+
+    /// Runs `f` on the next free worker, waiting for room in the job queue
+    /// if it's full. What `f` returns can be had from the handle, which can
+    /// also just be dropped if it isn't wanted.
+    pub fn execute<F, T>(&self, f: F) -> JobHandle<T>
     where
-        F: FnOnce() + Send + 'static,
+        F: FnOnce() -> T + Send + 'static,
+        T: Send + 'static,
     {
-        let job = Box::new(f);
+        let (job, handle) = job_with_handle(f);
 
+        // Counted before it's sent, so a worker taking it straight away
+        // can't take the count below zero
+        self.stats.queued.fetch_add(1, Ordering::Relaxed);
         self.sender.send(Message::NewJob(job)).unwrap();
+        handle
     }
+
+    /// Like `execute`, but gives up straight away if the job queue is full,
+    /// dropping `f` without running it.
+    pub fn try_execute<F, T>(&self, f: F) -> Result<JobHandle<T>, QueueFull>
+    where
+        F: FnOnce() -> T + Send + 'static,
+        T: Send + 'static,
+    {
+        let (job, handle) = job_with_handle(f);
+
+        self.stats.queued.fetch_add(1, Ordering::Relaxed);
+        match self.sender.try_send(Message::NewJob(job)) {
+            Ok(()) => Ok(handle),
+            Err(mpsc::TrySendError::Full(_)) => {
+                self.stats.queued.fetch_sub(1, Ordering::Relaxed);
+                Err(QueueFull)
+            }
+            // The workers only stop once the pool is dropped
+            Err(mpsc::TrySendError::Disconnected(_)) => unreachable!(),
+        }
+    }
+}
+
+// Wraps `f` up as a job that sends its result, or its panic, to the handle
+fn job_with_handle<F, T>(f: F) -> (Job, JobHandle<T>)
+where
+    F: FnOnce() -> T + Send + 'static,
+    T: Send + 'static,
+{
+    let (sender, receiver) = mpsc::channel();
+
+    let job = Box::new(move || match panic::catch_unwind(AssertUnwindSafe(f)) {
+        // Nobody waiting on the handle is fine
+        Ok(value) => {
+            let _ = sender.send(Ok(value));
+        }
+        Err(payload) => {
+            let _ = sender.send(Err(JobError::Panicked(panic_message(&*payload))));
+            // Carry on panicking, so the worker counts it
+            panic::resume_unwind(payload);
+        }
+    });
+
+    (job, JobHandle { receiver })
 }
 
+fn panic_message(payload: &(dyn Any + Send)) -> String {
+    if let Some(message) = payload.downcast_ref::<&str>() {
+        message.to_string()
+    } else if let Some(message) = payload.downcast_ref::<String>() {
+        message.clone()
+    } else {
+        String::from("Box<dyn Any>")
+    }
+}
+
+/// Where the result of a job run by a ThreadPool turns up.
+///
+/// ```
+/// use hello::ThreadPool;
+///
+/// let pool = ThreadPool::new(2);
+/// let sum = pool.execute(|| (1..=10).sum::<u32>());
+///
+/// assert_eq!(Ok(55), sum.join());
+/// ```
+#[derive(Debug)]
+pub struct JobHandle<T> {
+    receiver: mpsc::Receiver<Result<T, JobError>>,
+}
+
+impl<T> JobHandle<T> {
+    /// Waits for the job to finish and returns what it returned.
+    pub fn join(self) -> Result<T, JobError> {
+        self.receiver.recv().unwrap_or(Err(JobError::Dropped))
+    }
+
+    /// The job's result if it has finished, without waiting. Once the
+    /// result has been taken, later calls say the job was dropped.
+    pub fn try_join(&self) -> Option<Result<T, JobError>> {
+        match self.receiver.try_recv() {
+            Ok(result) => Some(result),
+            Err(mpsc::TryRecvError::Empty) => None,
+            Err(mpsc::TryRecvError::Disconnected) => Some(Err(JobError::Dropped)),
+        }
+    }
+}
+
+/// Why a job didn't give back a result.
+#[derive(Debug, Clone, PartialEq)]
+pub enum JobError {
+    /// The job panicked, with this message
+    Panicked(String),
+    /// The job was dropped without finishing, or its result was already
+    /// taken
+    Dropped,
+}
+
+impl fmt::Display for JobError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            JobError::Panicked(message) => write!(f, "the job panicked: {}", message),
+            JobError::Dropped => write!(f, "the job's result is gone"),
+        }
+    }
+}
+
+impl std::error::Error for JobError {}
+
 // Implementing Drop on our threadpool ensures that each thread finishes their
 // work before the program exits
 impl Drop for ThreadPool {
@@ -73,13 +378,25 @@ struct Worker {
 }
 
 impl Worker {
-    fn new(id: usize, receiver: Arc<Mutex<mpsc::Receiver<Message>>>) -> Worker {
+    fn new(id: usize, receiver: Arc<Mutex<mpsc::Receiver<Message>>>, stats: PoolStats) -> Worker {
         let thread = thread::spawn(move || loop {
             let message = receiver.lock().unwrap().recv().unwrap();
             match message {
                 Message::NewJob(job) => {
+                    stats.queued.fetch_sub(1, Ordering::Relaxed);
                     println!("Worker {} got a job; executing.", id);
-                    job();
+
+                    // A panicking job would otherwise take the thread down
+                    // with it, and the pool would quietly shrink. The lock
+                    // on the receiver is already released by now, so the
+                    // panic can't poison it.
+                    let started = Instant::now();
+                    let panicked = panic::catch_unwind(AssertUnwindSafe(job)).is_err();
+                    stats.finished(id, started.elapsed());
+                    if panicked {
+                        stats.panics.fetch_add(1, Ordering::Relaxed);
+                        println!("Worker {} caught a panicking job; carrying on.", id);
+                    }
                 }
                 Message::Terminate => {
                     println!("Worker {} was told to terminate.", id);
@@ -95,3 +412,153 @@ impl Worker {
         }
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::collections::HashSet;
+
+    // Runs `jobs` jobs on a pool of `size` threads and returns the ids of
+    // the threads they ran on
+    fn run_jobs(size: usize, jobs: usize) -> HashSet<thread::ThreadId> {
+        let pool = ThreadPool::with_config(PoolConfig {
+            size: NonZeroUsize::new(size).unwrap(),
+            ..PoolConfig::default()
+        });
+        assert_eq!(size, pool.size());
+
+        let (sender, receiver) = mpsc::channel();
+        for _ in 0..jobs {
+            let sender = sender.clone();
+            pool.execute(move || sender.send(thread::current().id()).unwrap());
+        }
+        drop(sender);
+
+        receiver.iter().collect()
+    }
+
+    #[test]
+    fn one_worker_runs_every_job() {
+        assert_eq!(1, run_jobs(1, 20).len());
+    }
+
+    #[test]
+    fn many_workers_run_every_job() {
+        let used = run_jobs(8, 200);
+
+        assert!(!used.is_empty() && used.len() <= 8);
+    }
+
+    #[test]
+    fn try_execute_fails_once_the_queue_is_full() {
+        let pool = ThreadPool::with_config(PoolConfig {
+            size: NonZeroUsize::new(1).unwrap(),
+            queue_capacity: 1,
+        });
+
+        // Keep the only worker busy until told to go on
+        let (started, wait_for_start) = mpsc::channel();
+        let (finish, wait_to_finish) = mpsc::channel::<()>();
+        pool.execute(move || {
+            started.send(()).unwrap();
+            wait_to_finish.recv().unwrap();
+        });
+        wait_for_start.recv().unwrap();
+
+        let (ran, runs) = mpsc::channel();
+        let queued = ran.clone();
+        assert!(pool.try_execute(move || queued.send(1).unwrap()).is_ok());
+        assert_eq!(
+            QueueFull,
+            pool.try_execute(move || ran.send(2).unwrap()).unwrap_err()
+        );
+        let stats = pool.stats();
+        assert_eq!(1, stats.queue_depth());
+
+        finish.send(()).unwrap();
+        drop(pool);
+        assert_eq!(vec![1], runs.iter().collect::<Vec<_>>());
+        assert_eq!(0, stats.queue_depth());
+    }
+
+    #[test]
+    fn workers_survive_panicking_jobs() {
+        let pool = ThreadPool::new(1);
+        let stats = pool.stats();
+
+        pool.execute(|| panic!("job failed"));
+        pool.execute(|| panic!("job failed again"));
+        let (sender, receiver) = mpsc::channel();
+        pool.execute(move || sender.send("still here").unwrap());
+
+        assert_eq!(Ok("still here"), receiver.recv());
+        drop(pool);
+        assert_eq!(2, stats.panics_total());
+    }
+
+    #[test]
+    fn counts_each_workers_jobs() {
+        let pool = ThreadPool::new(2);
+        let stats = pool.stats();
+        assert!(stats
+            .workers()
+            .iter()
+            .all(|w| w.jobs == 0 && w.last_job.is_none()));
+
+        for _ in 0..3 {
+            pool.execute(|| thread::sleep(Duration::from_millis(10)));
+        }
+        pool.execute(|| panic!("counted all the same"));
+        drop(pool);
+
+        let workers = stats.workers();
+        assert_eq!(vec![0, 1], workers.iter().map(|w| w.id).collect::<Vec<_>>());
+        assert_eq!(4, workers.iter().map(|w| w.jobs).sum::<u64>());
+        let busy: Duration = workers.iter().map(|w| w.busy).sum();
+        assert!(busy >= Duration::from_millis(30));
+        for worker in workers.iter().filter(|w| w.jobs > 0) {
+            assert!(worker.last_job.unwrap() <= worker.busy);
+        }
+    }
+
+    #[test]
+    fn handles_give_back_results() {
+        let pool = ThreadPool::new(4);
+
+        let squares: Vec<_> = (1..=5u64).map(|n| pool.execute(move || n * n)).collect();
+        let failed = pool.execute(|| -> u64 { panic!("no result for you") });
+        let parsed = pool.execute(|| "x".parse::<u8>());
+
+        let squares: Vec<u64> = squares.into_iter().map(|h| h.join().unwrap()).collect();
+        assert_eq!(vec![1, 4, 9, 16, 25], squares);
+        assert_eq!(
+            Err(JobError::Panicked(String::from("no result for you"))),
+            failed.join()
+        );
+        // Errors the job returns come back as its result
+        assert!(parsed.join().unwrap().is_err());
+    }
+
+    #[test]
+    fn try_join_doesnt_wait() {
+        let pool = ThreadPool::new(1);
+        let (go, wait) = mpsc::channel::<()>();
+
+        let handle = pool.execute(move || {
+            wait.recv().unwrap();
+            "done"
+        });
+        assert_eq!(None, handle.try_join());
+
+        go.send(()).unwrap();
+        drop(pool);
+        assert_eq!(Some(Ok("done")), handle.try_join());
+        assert_eq!(Some(Err(JobError::Dropped)), handle.try_join());
+    }
+
+    #[test]
+    #[should_panic]
+    fn new_panics_on_zero() {
+        ThreadPool::new(0);
+    }
+}
@@ -0,0 +1,170 @@
+use std::sync::{Arc, Condvar, Mutex};
+use std::time::Duration;
+
+/// A cap on how many connections may be open at once.
+///
+/// Each connection holds a Permit for as long as it's open, and dropping
+/// the Permit makes room for the next one. Cloning is cheap, and clones
+/// share the same count.
+///
+/// ```
+/// use hello::ConnectionLimit;
+///
+/// let limit = ConnectionLimit::new(1);
+/// let permit = limit.try_acquire().unwrap();
+/// assert!(limit.try_acquire().is_none());
+///
+/// drop(permit);
+/// assert_eq!(0, limit.open());
+/// ```
+#[derive(Debug, Clone)]
+pub struct ConnectionLimit {
+    max: usize,
+    // How many are open, and a way to wake up whoever is waiting for that
+    // to go down
+    open: Arc<(Mutex<usize>, Condvar)>,
+}
+
+/// Room for one connection, given back when dropped.
+#[derive(Debug)]
+pub struct Permit {
+    limit: ConnectionLimit,
+}
+
+impl ConnectionLimit {
+    pub fn new(max: usize) -> ConnectionLimit {
+        ConnectionLimit {
+            max,
+            open: Arc::new((Mutex::new(0), Condvar::new())),
+        }
+    }
+
+    /// Room for a connection, or None if the limit has been reached.
+    pub fn try_acquire(&self) -> Option<Permit> {
+        let mut open = self.open.0.lock().unwrap();
+        if *open >= self.max {
+            return None;
+        }
+        *open += 1;
+        Some(self.permit())
+    }
+
+    /// Room for a connection, waiting for one to close first if the limit
+    /// has been reached.
+    pub fn acquire(&self) -> Permit {
+        let (lock, freed) = &*self.open;
+        let mut open = freed
+            .wait_while(lock.lock().unwrap(), |open| *open >= self.max)
+            .unwrap();
+        *open += 1;
+        self.permit()
+    }
+
+    /// Like `acquire`, but gives up and returns None once `stop` returns
+    /// true, which is checked every `every` while waiting. Nothing wakes a
+    /// waiting thread besides a connection closing, so this is how it can
+    /// notice something like a shutdown.
+    pub fn acquire_unless(&self, every: Duration, stop: impl Fn() -> bool) -> Option<Permit> {
+        let (lock, freed) = &*self.open;
+        let mut open = lock.lock().unwrap();
+        while *open >= self.max {
+            if stop() {
+                return None;
+            }
+            open = freed.wait_timeout(open, every).unwrap().0;
+        }
+        *open += 1;
+        Some(self.permit())
+    }
+
+    /// How many connections are open right now.
+    pub fn open(&self) -> usize {
+        *self.open.0.lock().unwrap()
+    }
+
+    /// The most connections that may be open at once.
+    pub fn max(&self) -> usize {
+        self.max
+    }
+
+    fn permit(&self) -> Permit {
+        Permit {
+            limit: self.clone(),
+        }
+    }
+}
+
+impl Drop for Permit {
+    fn drop(&mut self) {
+        let (lock, freed) = &*self.limit.open;
+        *lock.lock().unwrap() -= 1;
+        freed.notify_one();
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::thread;
+    use std::time::Duration;
+
+    #[test]
+    fn holds_to_the_limit() {
+        let limit = ConnectionLimit::new(2);
+
+        let first = limit.try_acquire().unwrap();
+        let second = limit.clone().try_acquire().unwrap();
+        assert_eq!(2, limit.open());
+        assert!(limit.try_acquire().is_none());
+
+        drop(first);
+        assert_eq!(1, limit.open());
+        let third = limit.try_acquire();
+        assert!(third.is_some());
+        drop((second, third));
+        assert_eq!(0, limit.open());
+    }
+
+    #[test]
+    fn acquire_waits_for_room() {
+        let limit = ConnectionLimit::new(1);
+        let permit = limit.acquire();
+
+        let waiting = {
+            let limit = limit.clone();
+            thread::spawn(move || {
+                let _permit = limit.acquire();
+                limit.open()
+            })
+        };
+        thread::sleep(Duration::from_millis(50));
+        assert!(!waiting.is_finished());
+
+        drop(permit);
+        assert_eq!(1, waiting.join().unwrap());
+        assert_eq!(0, limit.open());
+    }
+
+    #[test]
+    fn acquire_unless_stops_waiting_when_told() {
+        use std::sync::atomic::{AtomicBool, Ordering};
+
+        let limit = ConnectionLimit::new(1);
+        let every = Duration::from_millis(10);
+        let stop = Arc::new(AtomicBool::new(false));
+        // With room, there's no waiting and so nothing to stop
+        let _permit = limit.acquire_unless(every, || true).unwrap();
+
+        let waiting = {
+            let (limit, stop) = (limit.clone(), Arc::clone(&stop));
+            thread::spawn(move || limit.acquire_unless(every, || stop.load(Ordering::SeqCst)))
+        };
+        thread::sleep(Duration::from_millis(50));
+        assert!(!waiting.is_finished());
+
+        // Still full, but told to stop
+        stop.store(true, Ordering::SeqCst);
+        assert!(waiting.join().unwrap().is_none());
+        assert_eq!(1, limit.open());
+    }
+}
@@ -0,0 +1,246 @@
+use std::io::{self, Write};
+use std::net::IpAddr;
+use std::str::FromStr;
+use std::time::{Duration, SystemTime};
+
+use crate::date::{civil_date, unix_secs, MONTHS};
+use crate::json::{Json, ToJson};
+
+/// Somewhere to send log lines. Anything shared between worker threads can
+/// be one, so tests can collect the lines instead of printing them.
+pub trait Logger: Send + Sync {
+    /// Writes one line, which doesn't end in a newline.
+    fn log(&self, line: &str);
+}
+
+/// Logs to standard output.
+#[derive(Debug, Clone, Copy, Default)]
+pub struct StdoutLogger;
+
+impl Logger for StdoutLogger {
+    fn log(&self, line: &str) {
+        // Nowhere better to report a failure to log
+        let _ = writeln!(io::stdout().lock(), "{}", line);
+    }
+}
+
+/// How access log lines are written.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum LogFormat {
+    /// The Common Log Format, with the latency added on the end:
+    /// `127.0.0.1 - - [10/Oct/2000:13:55:36 +0000] "GET / HTTP/1.1" 200 2326 0.412ms`
+    Common,
+    /// One JSON object per line
+    Json,
+}
+
+impl FromStr for LogFormat {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<LogFormat, String> {
+        match s {
+            "common" => Ok(LogFormat::Common),
+            "json" => Ok(LogFormat::Json),
+            _ => Err(format!(
+                "unknown log format `{}`, expected `common` or `json`",
+                s
+            )),
+        }
+    }
+}
+
+/// What happened with one request, for the access log.
+#[derive(Debug, Clone, PartialEq)]
+pub struct AccessEntry {
+    /// The address of the client, if it's known
+    pub client: Option<IpAddr>,
+    /// When the request came in
+    pub time: SystemTime,
+    /// The request's method, path and version, all empty if the request
+    /// couldn't be parsed
+    pub method: String,
+    pub path: String,
+    pub version: String,
+    pub status: u16,
+    /// The size of the response body in bytes
+    pub size: u64,
+    /// How long the request took, from being read to the response being
+    /// sent
+    pub latency: Duration,
+}
+
+/// Writes one line per request to a `Logger`, in the chosen format.
+pub struct AccessLog {
+    format: LogFormat,
+    logger: Box<dyn Logger>,
+}
+
+impl AccessLog {
+    pub fn new(format: LogFormat, logger: impl Logger + 'static) -> AccessLog {
+        AccessLog {
+            format,
+            logger: Box::new(logger),
+        }
+    }
+
+    pub fn record(&self, entry: &AccessEntry) {
+        let line = match self.format {
+            LogFormat::Common => common_line(entry),
+            LogFormat::Json => entry.to_json().to_string(),
+        };
+
+        self.logger.log(&line);
+    }
+}
+
+fn common_line(entry: &AccessEntry) -> String {
+    let client = entry
+        .client
+        .map_or_else(|| String::from("-"), |ip| ip.to_string());
+    let request = if entry.method.is_empty() {
+        String::from("-")
+    } else {
+        format!("{} {} {}", entry.method, entry.path, entry.version)
+    };
+
+    format!(
+        "{} - - [{}] \"{}\" {} {} {:.3}ms",
+        client,
+        common_time(entry.time),
+        request,
+        entry.status,
+        entry.size,
+        millis(entry.latency)
+    )
+}
+
+impl ToJson for AccessEntry {
+    fn to_json(&self) -> Json {
+        let text = |s: &str| {
+            if s.is_empty() {
+                Json::Null
+            } else {
+                s.to_json()
+            }
+        };
+
+        Json::object(vec![
+            ("client", self.client.map(|ip| ip.to_string()).to_json()),
+            ("time", unix_secs(self.time).to_json()),
+            ("method", text(&self.method)),
+            ("path", text(&self.path)),
+            ("version", text(&self.version)),
+            ("status", self.status.to_json()),
+            ("size", self.size.to_json()),
+            ("latency_ms", millis(self.latency).to_json()),
+        ])
+    }
+}
+
+// Milliseconds, to the microsecond, which is plenty for a log and keeps
+// float noise out of the JSON
+fn millis(duration: Duration) -> f64 {
+    duration.as_micros() as f64 / 1000.0
+}
+
+// A time as the Common Log Format writes it, like `10/Oct/2000:13:55:36
+// +0000`, always in UTC
+fn common_time(time: SystemTime) -> String {
+    let secs = unix_secs(time);
+    let (year, month, day) = civil_date((secs / 86_400) as i64);
+    let secs_of_day = secs % 86_400;
+
+    format!(
+        "{:02}/{}/{}:{:02}:{:02}:{:02} +0000",
+        day,
+        MONTHS[month as usize - 1],
+        year,
+        secs_of_day / 3600,
+        secs_of_day / 60 % 60,
+        secs_of_day % 60
+    )
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::sync::{Arc, Mutex};
+    use std::time::UNIX_EPOCH;
+
+    // Keeps every line logged to it
+    #[derive(Clone, Default)]
+    struct Captured(Arc<Mutex<Vec<String>>>);
+
+    impl Logger for Captured {
+        fn log(&self, line: &str) {
+            self.0.lock().unwrap().push(line.to_string());
+        }
+    }
+
+    fn entry() -> AccessEntry {
+        AccessEntry {
+            client: Some("127.0.0.1".parse().unwrap()),
+            // 10 Oct 2000, 13:55:36 UTC
+            time: UNIX_EPOCH + Duration::from_secs(971_186_136),
+            method: String::from("GET"),
+            path: String::from("/apache_pb.gif"),
+            version: String::from("HTTP/1.0"),
+            status: 200,
+            size: 2326,
+            latency: Duration::from_micros(1500),
+        }
+    }
+
+    fn logged(format: LogFormat, entry: &AccessEntry) -> Vec<String> {
+        let captured = Captured::default();
+        AccessLog::new(format, captured.clone()).record(entry);
+
+        let lines = captured.0.lock().unwrap();
+        lines.clone()
+    }
+
+    #[test]
+    fn writes_common_log_format() {
+        assert_eq!(
+            vec![
+                "127.0.0.1 - - [10/Oct/2000:13:55:36 +0000] \"GET /apache_pb.gif HTTP/1.0\" 200 2326 1.500ms"
+            ],
+            logged(LogFormat::Common, &entry())
+        );
+    }
+
+    #[test]
+    fn writes_json() {
+        assert_eq!(
+            vec![
+                r#"{"client":"127.0.0.1","time":971186136,"method":"GET","path":"/apache_pb.gif","version":"HTTP/1.0","status":200,"size":2326,"latency_ms":1.5}"#
+            ],
+            logged(LogFormat::Json, &entry())
+        );
+    }
+
+    #[test]
+    fn marks_what_isnt_known() {
+        let unparsed = AccessEntry {
+            client: None,
+            method: String::new(),
+            path: String::new(),
+            version: String::new(),
+            status: 400,
+            size: 0,
+            ..entry()
+        };
+
+        assert!(logged(LogFormat::Common, &unparsed)[0].starts_with("- - - ["));
+        assert!(logged(LogFormat::Common, &unparsed)[0].contains("] \"-\" 400 0 "));
+        assert!(logged(LogFormat::Json, &unparsed)[0]
+            .starts_with(r#"{"client":null,"time":971186136,"method":null,"#));
+    }
+
+    #[test]
+    fn parses_formats() {
+        assert_eq!(Ok(LogFormat::Common), "common".parse());
+        assert_eq!(Ok(LogFormat::Json), "json".parse());
+        assert!("xml".parse::<LogFormat>().is_err());
+    }
+}
@@ -0,0 +1,207 @@
+use std::fmt::Write;
+use std::sync::atomic::{AtomicU64, AtomicUsize, Ordering};
+
+use crate::PoolStats;
+
+// Statuses are counted from 100 up to 599
+const FIRST_STATUS: u16 = 100;
+const STATUSES: usize = 500;
+
+/// Counters for the server as a whole, updated by every worker and read
+/// out in the Prometheus text format with `render`.
+///
+/// ```
+/// use hello::{Metrics, ThreadPool};
+///
+/// let pool = ThreadPool::new(2);
+/// let metrics = Metrics::new(pool.stats());
+/// metrics.record(200);
+///
+/// assert!(metrics.render().contains("http_requests_total 1\n"));
+/// ```
+#[derive(Debug)]
+pub struct Metrics {
+    requests: AtomicU64,
+    statuses: Vec<AtomicU64>,
+    in_flight: AtomicUsize,
+    pool: PoolStats,
+}
+
+impl Metrics {
+    /// Metrics that start from zero, and report the queue and panics of
+    /// the pool `pool` comes from.
+    pub fn new(pool: PoolStats) -> Metrics {
+        Metrics {
+            requests: AtomicU64::new(0),
+            statuses: (0..STATUSES).map(|_| AtomicU64::new(0)).collect(),
+            in_flight: AtomicUsize::new(0),
+            pool,
+        }
+    }
+
+    /// Counts a request answered with `status`. Statuses outside 100 to
+    /// 599 count towards the total only.
+    pub fn record(&self, status: u16) {
+        self.requests.fetch_add(1, Ordering::Relaxed);
+        if let Some(count) = self.status_counter(status) {
+            count.fetch_add(1, Ordering::Relaxed);
+        }
+    }
+
+    /// Counts a connection as in flight until the guard is dropped.
+    pub fn connection(&self) -> InFlight<'_> {
+        self.in_flight.fetch_add(1, Ordering::Relaxed);
+        InFlight { metrics: self }
+    }
+
+    /// How many requests have been answered.
+    pub fn requests_total(&self) -> u64 {
+        self.requests.load(Ordering::Relaxed)
+    }
+
+    /// How many requests have been answered with `status`.
+    pub fn status_total(&self, status: u16) -> u64 {
+        self.status_counter(status)
+            .map_or(0, |count| count.load(Ordering::Relaxed))
+    }
+
+    /// How many connections are being served right now.
+    pub fn in_flight(&self) -> usize {
+        self.in_flight.load(Ordering::Relaxed)
+    }
+
+    /// Everything counted so far, in the Prometheus text format. Statuses
+    /// that haven't been sent yet are left out.
+    pub fn render(&self) -> String {
+        let mut text = String::new();
+
+        metric(
+            &mut text,
+            "http_requests_total",
+            "counter",
+            "Requests answered.",
+        );
+        writeln!(text, "http_requests_total {}", self.requests_total()).unwrap();
+
+        metric(
+            &mut text,
+            "http_responses_total",
+            "counter",
+            "Responses sent, by status.",
+        );
+        for (status, count) in (FIRST_STATUS..).zip(&self.statuses) {
+            let count = count.load(Ordering::Relaxed);
+            if count > 0 {
+                writeln!(
+                    text,
+                    "http_responses_total{{status=\"{}\"}} {}",
+                    status, count
+                )
+                .unwrap();
+            }
+        }
+
+        metric(
+            &mut text,
+            "http_connections_in_flight",
+            "gauge",
+            "Connections being served.",
+        );
+        writeln!(text, "http_connections_in_flight {}", self.in_flight()).unwrap();
+
+        metric(
+            &mut text,
+            "pool_queue_depth",
+            "gauge",
+            "Jobs waiting for a free worker.",
+        );
+        writeln!(text, "pool_queue_depth {}", self.pool.queue_depth()).unwrap();
+
+        metric(
+            &mut text,
+            "pool_panics_total",
+            "counter",
+            "Jobs that panicked.",
+        );
+        writeln!(text, "pool_panics_total {}", self.pool.panics_total()).unwrap();
+
+        text
+    }
+
+    fn status_counter(&self, status: u16) -> Option<&AtomicU64> {
+        status
+            .checked_sub(FIRST_STATUS)
+            .and_then(|i| self.statuses.get(i as usize))
+    }
+}
+
+// The HELP and TYPE lines that come before a metric's values
+fn metric(text: &mut String, name: &str, kind: &str, help: &str) {
+    writeln!(text, "# HELP {} {}", name, help).unwrap();
+    writeln!(text, "# TYPE {} {}", name, kind).unwrap();
+}
+
+/// A connection counted as in flight by `Metrics::connection`, until this
+/// is dropped.
+#[derive(Debug)]
+pub struct InFlight<'a> {
+    metrics: &'a Metrics,
+}
+
+impl Drop for InFlight<'_> {
+    fn drop(&mut self) {
+        self.metrics.in_flight.fetch_sub(1, Ordering::Relaxed);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn counts_requests_by_status() {
+        let metrics = Metrics::new(PoolStats::default());
+        for status in [200, 200, 404, 500, 999] {
+            metrics.record(status);
+        }
+
+        assert_eq!(5, metrics.requests_total());
+        assert_eq!(2, metrics.status_total(200));
+        assert_eq!(1, metrics.status_total(404));
+        assert_eq!(0, metrics.status_total(301));
+        assert_eq!(0, metrics.status_total(999));
+    }
+
+    #[test]
+    fn counts_connections_while_they_last() {
+        let metrics = Metrics::new(PoolStats::default());
+
+        let first = metrics.connection();
+        let second = metrics.connection();
+        assert_eq!(2, metrics.in_flight());
+        drop(first);
+        assert_eq!(1, metrics.in_flight());
+        drop(second);
+        assert_eq!(0, metrics.in_flight());
+    }
+
+    #[test]
+    fn renders_the_text_format() {
+        let metrics = Metrics::new(PoolStats::default());
+        metrics.record(200);
+        metrics.record(404);
+        let _connection = metrics.connection();
+
+        let text = metrics.render();
+        for line in [
+            "# TYPE http_requests_total counter\nhttp_requests_total 2\n",
+            "http_responses_total{status=\"200\"} 1\nhttp_responses_total{status=\"404\"} 1\n",
+            "# TYPE http_connections_in_flight gauge\nhttp_connections_in_flight 1\n",
+            "pool_queue_depth 0\n",
+            "pool_panics_total 0\n",
+        ] {
+            assert!(text.contains(line), "{:?} not in {}", line, text);
+        }
+        assert!(!text.contains("status=\"500\""));
+    }
+}
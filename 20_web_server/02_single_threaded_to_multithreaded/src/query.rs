@@ -0,0 +1,161 @@
+use std::fmt;
+use std::str::FromStr;
+
+use crate::http::{percent_decode, percent_encode};
+
+/// The `name=value` pairs of a query string like `q=rust&limit=5`.
+///
+/// Pairs keep the order they were given in, and a name can appear more
+/// than once (`tag=a&tag=b`), so this is a list rather than a map.
+#[derive(Debug, Clone, Default, PartialEq)]
+pub struct Query {
+    pairs: Vec<(String, String)>,
+}
+
+impl Query {
+    /// Parses a query string, without the leading `?`. Names and values are
+    /// percent-decoded, and `+` stands for a space. A pair without `=` gets
+    /// an empty value.
+    pub fn parse(text: &str) -> Query {
+        let pairs = text
+            .split('&')
+            .filter(|pair| !pair.is_empty())
+            .map(|pair| {
+                let (name, value) = pair.split_once('=').unwrap_or((pair, ""));
+                (decode(name), decode(value))
+            })
+            .collect();
+
+        Query { pairs }
+    }
+
+    /// The first value given for `name`.
+    pub fn get(&self, name: &str) -> Option<&str> {
+        self.pairs
+            .iter()
+            .find(|(pair_name, _)| pair_name == name)
+            .map(|(_, value)| value.as_str())
+    }
+
+    /// Every value given for `name`, in order.
+    pub fn get_all<'a>(&'a self, name: &'a str) -> impl Iterator<Item = &'a str> + 'a {
+        self.pairs
+            .iter()
+            .filter(move |(pair_name, _)| pair_name == name)
+            .map(|(_, value)| value.as_str())
+    }
+
+    /// The first value for `name` parsed as a `T`, e.g. a number. None if
+    /// there's no such value or it doesn't parse.
+    pub fn get_as<T: FromStr>(&self, name: &str) -> Option<T> {
+        self.get(name)?.parse().ok()
+    }
+
+    /// Every pair, in order.
+    pub fn iter(&self) -> impl Iterator<Item = (&str, &str)> {
+        self.pairs
+            .iter()
+            .map(|(name, value)| (name.as_str(), value.as_str()))
+    }
+
+    pub fn len(&self) -> usize {
+        self.pairs.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.pairs.is_empty()
+    }
+}
+
+// Back as a query string, without the leading `?`, that parses to the same
+// pairs
+impl fmt::Display for Query {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        for (i, (name, value)) in self.pairs.iter().enumerate() {
+            if i > 0 {
+                write!(f, "&")?;
+            }
+            write!(
+                f,
+                "{}={}",
+                percent_encode(name, ""),
+                percent_encode(value, "")
+            )?;
+        }
+        Ok(())
+    }
+}
+
+// Query strings are written by hand often enough that a stray `%` is better
+// kept as it is than treated as an error
+fn decode(text: &str) -> String {
+    let text = text.replace('+', " ");
+
+    percent_decode(&text).unwrap_or(text)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn keeps_pairs_in_order_with_repeats() {
+        let query = Query::parse("q=rust&tag=web&limit=5&tag=http");
+
+        assert_eq!(Some("rust"), query.get("q"));
+        assert_eq!(Some("web"), query.get("tag"));
+        assert_eq!(
+            vec!["web", "http"],
+            query.get_all("tag").collect::<Vec<_>>()
+        );
+        assert_eq!(None, query.get("page"));
+        assert_eq!(
+            vec![
+                ("q", "rust"),
+                ("tag", "web"),
+                ("limit", "5"),
+                ("tag", "http")
+            ],
+            query.iter().collect::<Vec<_>>()
+        );
+    }
+
+    #[test]
+    fn decodes_names_and_values() {
+        let query = Query::parse("q=hello+world%21&caf%C3%A9=1&sum=1%2B1&odd=100%");
+
+        assert_eq!(Some("hello world!"), query.get("q"));
+        assert_eq!(Some("1"), query.get("café"));
+        assert_eq!(Some("1+1"), query.get("sum"));
+        assert_eq!(Some("100%"), query.get("odd"));
+    }
+
+    #[test]
+    fn handles_missing_values_and_empty_pairs() {
+        let query = Query::parse("flag&&empty=&=x");
+
+        assert_eq!(3, query.len());
+        assert_eq!(Some(""), query.get("flag"));
+        assert_eq!(Some(""), query.get("empty"));
+        assert_eq!(Some("x"), query.get(""));
+        assert!(Query::parse("").is_empty());
+    }
+
+    #[test]
+    fn parses_typed_values() {
+        let query = Query::parse("limit=5&page=two");
+
+        assert_eq!(Some(5), query.get_as::<usize>("limit"));
+        assert_eq!(None, query.get_as::<usize>("page"));
+        assert_eq!(None, query.get_as::<usize>("missing"));
+    }
+
+    #[test]
+    fn writes_back_what_it_parsed() {
+        let query = Query::parse("q=hello+world%21&tag=a%26b&empty");
+
+        assert_eq!("q=hello%20world%21&tag=a%26b&empty=", query.to_string());
+        assert_eq!(query, Query::parse(&query.to_string()));
+        assert_eq!("", Query::default().to_string());
+    }
+}
@@ -0,0 +1,479 @@
+use std::collections::HashMap;
+use std::panic::{self, AssertUnwindSafe};
+
+use crate::cors::Cors;
+use crate::http::{percent_encode, Request, Response};
+
+// Handlers are shared by every worker thread, so they have to be Send + Sync
+type Handler = Box<dyn Fn(&Request) -> Response + Send + Sync + 'static>;
+
+// One piece of a route's path, between slashes
+enum Segment {
+    Literal(String),
+    // `:name` matches any single segment and makes it available as a param
+    Param(String),
+    // `*name` matches whatever is left of the path, slashes and all, and
+    // can only come last
+    Rest(String),
+}
+
+struct Route {
+    method: String,
+    segments: Vec<Segment>,
+    handler: Handler,
+}
+
+impl Route {
+    // The params captured from `path` if it matches this route
+    fn matches(&self, path: &str) -> Option<HashMap<String, String>> {
+        match_segments(&self.segments, path)
+    }
+}
+
+fn parse_segments(path: &str) -> Vec<Segment> {
+    split_path(path)
+        .map(|part| {
+            if let Some(name) = part.strip_prefix(':') {
+                Segment::Param(name.to_string())
+            } else if let Some(name) = part.strip_prefix('*') {
+                Segment::Rest(name.to_string())
+            } else {
+                Segment::Literal(part.to_string())
+            }
+        })
+        .collect()
+}
+
+// The params captured from `path` if it matches `segments`
+fn match_segments(segments: &[Segment], path: &str) -> Option<HashMap<String, String>> {
+    let parts: Vec<&str> = split_path(path).collect();
+    let mut params = HashMap::new();
+
+    for (i, segment) in segments.iter().enumerate() {
+        match (segment, parts.get(i)) {
+            (Segment::Literal(literal), Some(part)) if literal == part => {}
+            (Segment::Param(name), Some(part)) => {
+                params.insert(name.clone(), part.to_string());
+            }
+            (Segment::Rest(name), Some(_)) => {
+                params.insert(name.clone(), parts[i..].join("/"));
+                return Some(params);
+            }
+            _ => return None,
+        }
+    }
+
+    if parts.len() == segments.len() {
+        Some(params)
+    } else {
+        None
+    }
+}
+
+fn split_path(path: &str) -> impl Iterator<Item = &str> {
+    path.split('/').filter(|part| !part.is_empty())
+}
+
+/// How a Router treats a slash at the end of a path.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum TrailingSlash {
+    /// `/path/` is answered the same as `/path`
+    Ignore,
+    /// `/path/` is redirected to `/path`
+    Strip,
+    /// `/path` is redirected to `/path/`
+    Add,
+}
+
+/// Sends each request to the handler registered for its method and path.
+///
+/// ```no_run
+/// use hello::{Response, Router};
+///
+/// let mut router = Router::new();
+/// router.get("/", |_| Response::ok("Hello!"));
+/// router.get("/users/:id", |req| {
+///     Response::ok(format!("User {}", req.param("id").unwrap()))
+/// });
+/// ```
+pub struct Router {
+    routes: Vec<Route>,
+    // CORS policies and the paths they cover, the first match applying
+    cors: Vec<(Vec<Segment>, Cors)>,
+    trailing_slash: TrailingSlash,
+    not_found: Handler,
+    server_error: Handler,
+}
+
+impl Router {
+    pub fn new() -> Router {
+        Router {
+            routes: Vec::new(),
+            cors: Vec::new(),
+            trailing_slash: TrailingSlash::Ignore,
+            not_found: Box::new(|_| Response::not_found("Not Found")),
+            server_error: Box::new(|_| Response::new(500, "Internal Server Error")),
+        }
+    }
+
+    /// Registers `handler` for GET requests to `path`. Segments of the path
+    /// written as `:name` match anything, and handlers can read what they
+    /// matched with `Request::param`. A last segment written as `*name`
+    /// matches the rest of the path, however many segments that is.
+    pub fn get<F>(&mut self, path: &str, handler: F)
+    where
+        F: Fn(&Request) -> Response + Send + Sync + 'static,
+    {
+        self.route("GET", path, handler);
+    }
+
+    pub fn post<F>(&mut self, path: &str, handler: F)
+    where
+        F: Fn(&Request) -> Response + Send + Sync + 'static,
+    {
+        self.route("POST", path, handler);
+    }
+
+    /// Registers `handler` for requests with any `method` to `path`.
+    pub fn route<F>(&mut self, method: &str, path: &str, handler: F)
+    where
+        F: Fn(&Request) -> Response + Send + Sync + 'static,
+    {
+        self.routes.push(Route {
+            method: method.to_string(),
+            segments: parse_segments(path),
+            handler: Box::new(handler),
+        });
+    }
+
+    /// Lets other sites' scripts call the routes matching `path`, written
+    /// the same way as a route's, as `cors` allows. Preflight OPTIONS
+    /// requests to those paths are answered with the methods the path has
+    /// routes for, unless there's an OPTIONS route of its own.
+    pub fn cors(&mut self, path: &str, cors: Cors) {
+        self.cors.push((parse_segments(path), cors));
+    }
+
+    /// Sets how a slash at the end of a path is treated. Routes match
+    /// either way, so by default both spellings get the same answer; the
+    /// others pick one and redirect the other to it, so each page has one
+    /// address. Paths no route matches aren't redirected, and neither is
+    /// `/`.
+    pub fn trailing_slash(&mut self, trailing_slash: TrailingSlash) {
+        self.trailing_slash = trailing_slash;
+    }
+
+    /// Sets the handler for requests that no route matches.
+    pub fn not_found<F>(&mut self, handler: F)
+    where
+        F: Fn(&Request) -> Response + Send + Sync + 'static,
+    {
+        self.not_found = Box::new(handler);
+    }
+
+    /// Sets the handler that answers in place of a handler that panicked.
+    /// The panic message isn't passed on, so it can't leak to the client.
+    pub fn server_error<F>(&mut self, handler: F)
+    where
+        F: Fn(&Request) -> Response + Send + Sync + 'static,
+    {
+        self.server_error = Box::new(handler);
+    }
+
+    /// Runs the first route registered for the request's method and path.
+    /// HEAD requests without a route of their own go to the GET route, and
+    /// it's up to whoever sends the response to leave out the body. If the
+    /// path has routes but none for this method, the answer is a 405
+    /// listing the methods that are allowed; if it has none at all, it's
+    /// the not found handler's. A handler that panics gets the server error
+    /// handler's response instead.
+    pub fn handle(&self, mut request: Request) -> Response {
+        let cors = self
+            .cors
+            .iter()
+            .find(|(segments, _)| match_segments(segments, &request.path).is_some())
+            .map(|(_, cors)| cors);
+
+        if let Some(cors) = cors.filter(|_| Cors::is_preflight(&request)) {
+            match self.find("OPTIONS", &request.path) {
+                Err(allowed) if !allowed.is_empty() => return cors.preflight(&request, &allowed),
+                _ => {}
+            }
+        }
+
+        if let Some(location) = self.canonical_location(&request) {
+            // 301 lets browsers turn a POST into a GET, which 308 doesn't
+            let status = if request.method == "GET" || request.method == "HEAD" {
+                301
+            } else {
+                308
+            };
+            return Response::redirect(status, &location);
+        }
+
+        let found = match self.find(&request.method, &request.path) {
+            Err(_) if request.method == "HEAD" => self.find("GET", &request.path),
+            found => found,
+        };
+
+        let response = match found {
+            Ok((route, params)) => {
+                request.params = params;
+                self.run(&route.handler, &request)
+            }
+            Err(allowed) if allowed.is_empty() => self.run(&self.not_found, &request),
+            Err(allowed) => {
+                Response::new(405, "Method Not Allowed").header("Allow", &allowed.join(", "))
+            }
+        };
+
+        match cors {
+            Some(cors) => cors.apply(&request, response),
+            None => response,
+        }
+    }
+
+    // The route for `method` and `path` and the params it captured, or if
+    // there isn't one, the methods that do have routes for `path`
+    fn find(
+        &self,
+        method: &str,
+        path: &str,
+    ) -> Result<(&Route, HashMap<String, String>), Vec<&str>> {
+        let mut allowed: Vec<&str> = Vec::new();
+
+        for route in &self.routes {
+            if let Some(params) = route.matches(path) {
+                if route.method == method {
+                    return Ok((route, params));
+                }
+                if !allowed.contains(&route.method.as_str()) {
+                    allowed.push(&route.method);
+                }
+            }
+        }
+
+        // Anything that answers GET answers HEAD too
+        if let Some(i) = allowed.iter().position(|&method| method == "GET") {
+            if !allowed.contains(&"HEAD") {
+                allowed.insert(i + 1, "HEAD");
+            }
+        }
+
+        Err(allowed)
+    }
+
+    // Where to redirect `request` to put its path in the form the trailing
+    // slash setting asks for, or None if it's fine as it is
+    fn canonical_location(&self, request: &Request) -> Option<String> {
+        let path = &request.path;
+        let canonical = match self.trailing_slash {
+            _ if path == "/" => return None,
+            TrailingSlash::Ignore => return None,
+            TrailingSlash::Strip => path.strip_suffix('/')?.to_string(),
+            TrailingSlash::Add if path.ends_with('/') => return None,
+            TrailingSlash::Add => format!("{}/", path),
+        };
+        // Nothing to send them to
+        if self
+            .routes
+            .iter()
+            .all(|route| route.matches(path).is_none())
+        {
+            return None;
+        }
+
+        let mut location = percent_encode(&canonical, "/");
+        if !request.query.is_empty() {
+            location.push('?');
+            location.push_str(&request.query.to_string());
+        }
+        Some(location)
+    }
+
+    fn run(&self, handler: &Handler, request: &Request) -> Response {
+        // A handler can't leave anything half done that later requests
+        // would see, since it only gets the request to look at
+        panic::catch_unwind(AssertUnwindSafe(|| handler(request)))
+            .unwrap_or_else(|_| (self.server_error)(request))
+    }
+}
+
+impl Default for Router {
+    fn default() -> Router {
+        Router::new()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn request(method: &str, path: &str) -> Request {
+        Request::parse(format!("{} {} HTTP/1.1\r\n\r\n", method, path).as_bytes()).unwrap()
+    }
+
+    fn body(response: Response) -> String {
+        String::from_utf8(response.body).unwrap()
+    }
+
+    fn router() -> Router {
+        let mut router = Router::new();
+        router.get("/", |_| Response::ok("home"));
+        router.get("/users/:id", |req| {
+            Response::ok(format!("user {}", req.param("id").unwrap()))
+        });
+        router.get("/users/:id/posts/:post", |req| {
+            Response::ok(format!(
+                "post {} by {}",
+                req.param("post").unwrap(),
+                req.param("id").unwrap()
+            ))
+        });
+        router.post("/users", |_| Response::ok("created"));
+        router
+    }
+
+    #[test]
+    fn dispatches_on_method_and_path() {
+        let router = router();
+
+        assert_eq!("home", body(router.handle(request("GET", "/"))));
+        assert_eq!("created", body(router.handle(request("POST", "/users"))));
+        assert_eq!(404, router.handle(request("GET", "/nope")).status);
+        assert_eq!(404, router.handle(request("POST", "/nope")).status);
+    }
+
+    #[test]
+    fn wrong_methods_get_405_with_the_allowed_ones() {
+        let mut router = router();
+        router.route("PUT", "/users/:id", |_| Response::ok("replaced"));
+
+        let response = router.handle(request("DELETE", "/users/42"));
+        assert_eq!(405, response.status);
+        assert_eq!(
+            vec![(String::from("Allow"), String::from("GET, HEAD, PUT"))],
+            response.headers
+        );
+        assert_eq!(405, router.handle(request("DELETE", "/")).status);
+        assert_eq!(405, router.handle(request("GET", "/users")).status);
+    }
+
+    #[test]
+    fn head_falls_back_to_get() {
+        let mut router = router();
+        router.route("HEAD", "/users/:id", |_| Response::ok("head"));
+
+        let response = router.handle(request("HEAD", "/"));
+        assert_eq!("home", body(response));
+        assert_eq!("head", body(router.handle(request("HEAD", "/users/42"))));
+        // POST /users has no GET route to fall back to
+        assert_eq!(405, router.handle(request("HEAD", "/users")).status);
+    }
+
+    #[test]
+    fn panicking_handlers_get_500() {
+        let mut router = router();
+        router.get("/boom", |_| panic!("secret details"));
+
+        let response = router.handle(request("GET", "/boom"));
+        assert_eq!(500, response.status);
+        assert!(!body(response).contains("secret"));
+
+        router.server_error(|req| Response::new(500, format!("{} failed", req.path)));
+        assert_eq!("/boom failed", body(router.handle(request("GET", "/boom"))));
+        // The router still works afterwards
+        assert_eq!("home", body(router.handle(request("GET", "/"))));
+    }
+
+    #[test]
+    fn captures_path_params() {
+        let router = router();
+
+        assert_eq!("user 42", body(router.handle(request("GET", "/users/42"))));
+        assert_eq!(
+            "post 3 by ann",
+            body(router.handle(request("GET", "/users/ann/posts/3?draft=1")))
+        );
+        assert_eq!(404, router.handle(request("GET", "/users/42/extra")).status);
+    }
+
+    #[test]
+    fn rest_segments_take_the_remaining_path() {
+        let mut router = Router::new();
+        router.get("/static/*file", |req| {
+            Response::ok(req.param("file").unwrap())
+        });
+
+        assert_eq!(
+            "css/site.css",
+            body(router.handle(request("GET", "/static/css/site.css")))
+        );
+        assert_eq!("a", body(router.handle(request("GET", "/static/a"))));
+        assert_eq!(404, router.handle(request("GET", "/static")).status);
+    }
+
+    #[test]
+    fn redirects_to_the_preferred_trailing_slash() {
+        let mut router = router();
+        assert_eq!("user 7", body(router.handle(request("GET", "/users/7/"))));
+
+        router.trailing_slash(TrailingSlash::Strip);
+        let response = router.handle(request("GET", "/users/7/?tab=a%20b"));
+        assert_eq!(301, response.status);
+        assert_eq!(
+            Some("/users/7?tab=a%20b"),
+            response.header_value("Location")
+        );
+        let response = router.handle(request("POST", "/users/"));
+        assert_eq!(308, response.status);
+        assert_eq!("home", body(router.handle(request("GET", "/"))));
+        assert_eq!(404, router.handle(request("GET", "/nowhere/")).status);
+
+        router.trailing_slash(TrailingSlash::Add);
+        let response = router.handle(request("GET", "/users/hello%20there"));
+        assert_eq!(
+            Some("/users/hello%20there/"),
+            response.header_value("Location")
+        );
+        assert_eq!("user 7", body(router.handle(request("GET", "/users/7/"))));
+    }
+
+    #[test]
+    fn cors_covers_the_paths_its_given() {
+        let mut router = router();
+        router.cors("/users/*rest", Cors::new());
+        let with = |method: &str, path: &str, headers: &str| {
+            let head = format!("{} {} HTTP/1.1\r\n{}\r\n", method, path, headers);
+            router.handle(Request::parse(head.as_bytes()).unwrap())
+        };
+        let preflight = "Origin: https://a.com\r\nAccess-Control-Request-Method: GET\r\n";
+
+        let response = with("OPTIONS", "/users/42", preflight);
+        assert_eq!(204, response.status);
+        assert_eq!(
+            Some("GET, HEAD"),
+            response.header_value("Access-Control-Allow-Methods")
+        );
+        let response = with("GET", "/users/42", "Origin: https://a.com\r\n");
+        assert_eq!(
+            Some("*"),
+            response.header_value("Access-Control-Allow-Origin")
+        );
+        assert_eq!("user 42", body(response));
+
+        // Other paths, and paths without routes, are left alone
+        let response = with("GET", "/", "Origin: https://a.com\r\n");
+        assert!(response.headers.is_empty());
+        assert_eq!(404, with("OPTIONS", "/users/42/nope", preflight).status);
+        assert_eq!(405, with("OPTIONS", "/", preflight).status);
+    }
+
+    #[test]
+    fn not_found_handler_can_be_replaced() {
+        let mut router = router();
+        router.not_found(|req| Response::not_found(format!("no {}", req.path)));
+
+        assert_eq!("no /nope", body(router.handle(request("GET", "/nope"))));
+    }
+}
@@ -0,0 +1,235 @@
+use std::collections::HashMap;
+use std::fmt::Write;
+use std::str::FromStr;
+use std::sync::{Arc, Mutex};
+use std::time::{Duration, Instant};
+
+use crate::cookie::{Cookie, SameSite};
+use crate::http::{Request, Response};
+
+// The cookie the session id is kept in
+const COOKIE: &str = "session";
+
+/// What the server remembers about one visitor between requests.
+#[derive(Debug, Clone, PartialEq)]
+pub struct Session {
+    id: String,
+    values: HashMap<String, String>,
+}
+
+impl Session {
+    /// The random id the visitor's browser sends back to pick this session.
+    pub fn id(&self) -> &str {
+        &self.id
+    }
+
+    pub fn get(&self, name: &str) -> Option<&str> {
+        self.values.get(name).map(String::as_str)
+    }
+
+    /// The value for `name` parsed as a `T`, e.g. a number. None if
+    /// there's no such value or it doesn't parse.
+    pub fn get_as<T: FromStr>(&self, name: &str) -> Option<T> {
+        self.get(name)?.parse().ok()
+    }
+
+    pub fn set(&mut self, name: &str, value: impl ToString) {
+        self.values.insert(name.to_string(), value.to_string());
+    }
+
+    pub fn remove(&mut self, name: &str) -> Option<String> {
+        self.values.remove(name)
+    }
+}
+
+// A session as it's kept between requests
+struct Stored {
+    values: HashMap<String, String>,
+    last_used: Instant,
+}
+
+/// Keeps sessions in memory, each picked by a random id kept in a cookie.
+///
+/// Handlers get their visitor's session by being wrapped with `handler`;
+/// what they change in it is there for the next request from the same
+/// browser. Sessions unused for longer than the idle timeout are
+/// forgotten, and so are all of them when the server stops. Cloning is
+/// cheap, and clones share the same sessions.
+///
+/// ```no_run
+/// use std::time::Duration;
+/// use hello::{Response, Router, Sessions};
+///
+/// let sessions = Sessions::new(Duration::from_secs(30 * 60));
+/// let mut router = Router::new();
+/// router.get("/hello", sessions.handler(|_, session| {
+///     let name = session.get("name").unwrap_or("stranger");
+///     Response::ok(format!("Hello, {}!", name))
+/// }));
+/// ```
+#[derive(Clone)]
+pub struct Sessions {
+    store: Arc<Mutex<HashMap<String, Stored>>>,
+    idle_timeout: Duration,
+}
+
+impl Sessions {
+    pub fn new(idle_timeout: Duration) -> Sessions {
+        Sessions {
+            store: Arc::new(Mutex::new(HashMap::new())),
+            idle_timeout,
+        }
+    }
+
+    /// Wraps `handler` into a route handler that's also given the session
+    /// of whoever sent the request. A visitor without a session, or with
+    /// one that's been forgotten, gets a new one along with the cookie
+    /// that picks it.
+    pub fn handler<F>(&self, handler: F) -> impl Fn(&Request) -> Response + Send + Sync + 'static
+    where
+        F: Fn(&Request, &mut Session) -> Response + Send + Sync + 'static,
+    {
+        let sessions = self.clone();
+        move |request| {
+            let (mut session, is_new) = sessions.load(request.cookie(COOKIE));
+            let response = handler(request, &mut session);
+            let id = session.id.clone();
+            sessions.save(session);
+
+            if is_new {
+                let cookie = Cookie::new(COOKIE, &id)
+                    .path("/")
+                    .http_only()
+                    .same_site(SameSite::Lax);
+                response.set_cookie(&cookie)
+            } else {
+                response
+            }
+        }
+    }
+
+    /// How many sessions are being kept.
+    pub fn len(&self) -> usize {
+        self.store.lock().unwrap().len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.len() == 0
+    }
+
+    // The session with the id `id`, or a new one if there's no such
+    // session, and whether it's new
+    fn load(&self, id: Option<&str>) -> (Session, bool) {
+        let mut store = self.store.lock().unwrap();
+        // Forgotten ones are only cleared out now and then, when someone
+        // new turns up, rather than on every request
+        let now = Instant::now();
+        let timeout = self.idle_timeout;
+        let live = |stored: &Stored| now.duration_since(stored.last_used) < timeout;
+
+        // Left in the store, so other requests from the same visitor running
+        // at the same time find it too. Whichever finishes last is the one
+        // whose changes are kept.
+        if let Some((id, stored)) = id.and_then(|id| store.get_key_value(id)) {
+            if live(stored) {
+                let (id, values) = (id.clone(), stored.values.clone());
+                return (Session { id, values }, false);
+            }
+        }
+        store.retain(|_, stored| live(stored));
+
+        let session = Session {
+            id: new_id(),
+            values: HashMap::new(),
+        };
+        (session, true)
+    }
+
+    fn save(&self, session: Session) {
+        let stored = Stored {
+            values: session.values,
+            last_used: Instant::now(),
+        };
+        self.store.lock().unwrap().insert(session.id, stored);
+    }
+}
+
+// 128 random bits, in hex, so ids can't be guessed. The random numbers are
+// the ones TLS uses.
+fn new_id() -> String {
+    let mut bytes = [0; 16];
+    rustls::crypto::ring::default_provider()
+        .secure_random
+        .fill(&mut bytes)
+        .expect("couldn't get random bytes for a session id");
+
+    bytes.iter().fold(String::new(), |mut id, byte| {
+        write!(id, "{:02x}", byte).unwrap();
+        id
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn request(cookie: Option<&str>) -> Request {
+        let head = match cookie {
+            Some(cookie) => format!("GET / HTTP/1.1\r\nCookie: {}\r\n\r\n", cookie),
+            None => String::from("GET / HTTP/1.1\r\n\r\n"),
+        };
+        Request::parse(head.as_bytes()).unwrap()
+    }
+
+    // The id a response's Set-Cookie header hands out
+    fn handed_out(response: &Response) -> Option<String> {
+        let cookie = response.header_value("Set-Cookie")?;
+        let (pair, _) = cookie.split_once(';')?;
+        pair.strip_prefix("session=").map(String::from)
+    }
+
+    fn counter(sessions: &Sessions) -> impl Fn(&Request) -> Response {
+        sessions.handler(|_, session| {
+            let visits = session.get_as::<u32>("visits").unwrap_or(0) + 1;
+            session.set("visits", visits);
+            Response::ok(visits.to_string())
+        })
+    }
+
+    #[test]
+    fn remembers_each_visitor() {
+        let sessions = Sessions::new(Duration::from_secs(60));
+        let handler = counter(&sessions);
+
+        let first = handler(&request(None));
+        let id = handed_out(&first).unwrap();
+        assert_eq!(32, id.len());
+        assert_eq!(b"1".to_vec(), first.body);
+
+        let cookie = format!("session={}", id);
+        let second = handler(&request(Some(&cookie)));
+        assert_eq!(b"2".to_vec(), second.body);
+        assert_eq!(None, second.header_value("Set-Cookie"));
+
+        // Someone else starts from scratch
+        let other = handler(&request(None));
+        assert_eq!(b"1".to_vec(), other.body);
+        assert_ne!(Some(id), handed_out(&other));
+        assert_eq!(2, sessions.len());
+    }
+
+    #[test]
+    fn forgets_unknown_and_idle_sessions() {
+        let sessions = Sessions::new(Duration::ZERO);
+        let handler = counter(&sessions);
+
+        let response = handler(&request(Some("session=made-up")));
+        assert!(handed_out(&response).is_some_and(|id| id != "made-up"));
+
+        let cookie = format!("session={}", handed_out(&response).unwrap());
+        let response = handler(&request(Some(&cookie)));
+        assert_eq!(b"1".to_vec(), response.body);
+        // The idle one was cleared out when the new one was made
+        assert_eq!(1, sessions.len());
+    }
+}
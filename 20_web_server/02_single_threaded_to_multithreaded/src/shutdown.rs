@@ -0,0 +1,130 @@
+use std::io;
+use std::net::{IpAddr, Ipv4Addr, Ipv6Addr, SocketAddr, TcpListener, TcpStream};
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::sync::Arc;
+
+/// Lets another thread, like a Ctrl-C handler, stop a server's accept loop,
+/// or the accept loops of all its listeners at once.
+///
+/// The loop should check `is_requested` after every accepted connection.
+/// Since accepting blocks until someone connects, `trigger` connects to the
+/// listener itself, so the loop wakes up and sees the request right away.
+#[derive(Debug, Clone)]
+pub struct Shutdown {
+    requested: Arc<AtomicBool>,
+    addrs: Vec<SocketAddr>,
+}
+
+impl Shutdown {
+    pub fn new(listener: &TcpListener) -> io::Result<Shutdown> {
+        Shutdown::for_listeners(std::iter::once(listener))
+    }
+
+    /// A shutdown shared by several listeners, which stops all of their
+    /// accept loops together.
+    pub fn for_listeners<'a>(
+        listeners: impl IntoIterator<Item = &'a TcpListener>,
+    ) -> io::Result<Shutdown> {
+        let addrs = listeners
+            .into_iter()
+            .map(|listener| listener.local_addr().map(reachable))
+            .collect::<io::Result<_>>()?;
+
+        Ok(Shutdown {
+            requested: Arc::new(AtomicBool::new(false)),
+            addrs,
+        })
+    }
+
+    /// Asks the accept loops to stop, and wakes up any waiting for a
+    /// connection.
+    pub fn trigger(&self) {
+        self.requested.store(true, Ordering::SeqCst);
+
+        // If this fails the listener is already gone, so there's nothing
+        // left to wake up
+        for addr in &self.addrs {
+            let _ = TcpStream::connect(addr);
+        }
+    }
+
+    pub fn is_requested(&self) -> bool {
+        self.requested.load(Ordering::SeqCst)
+    }
+}
+
+// Where a listener bound to `addr` can be connected to. One bound to every
+// interface can be reached on loopback.
+fn reachable(mut addr: SocketAddr) -> SocketAddr {
+    if addr.ip().is_unspecified() {
+        addr.set_ip(match addr.ip() {
+            IpAddr::V4(_) => IpAddr::V4(Ipv4Addr::LOCALHOST),
+            IpAddr::V6(_) => IpAddr::V6(Ipv6Addr::LOCALHOST),
+        });
+    }
+    addr
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::thread;
+
+    #[test]
+    fn trigger_wakes_up_the_accept_loop() {
+        let listener = TcpListener::bind("127.0.0.1:0").unwrap();
+        let shutdown = Shutdown::new(&listener).unwrap();
+        let handle = shutdown.clone();
+
+        let server = thread::spawn(move || {
+            for _ in listener.incoming() {
+                if shutdown.is_requested() {
+                    break;
+                }
+            }
+        });
+
+        handle.trigger();
+
+        // Without the wake up connection this would wait forever
+        server.join().unwrap();
+        assert!(handle.is_requested());
+    }
+
+    #[test]
+    fn wakes_listeners_bound_to_every_interface_through_loopback() {
+        let listener = TcpListener::bind("0.0.0.0:0").unwrap();
+        let shutdown = Shutdown::new(&listener).unwrap();
+
+        assert_eq!(IpAddr::V4(Ipv4Addr::LOCALHOST), shutdown.addrs[0].ip());
+        assert_eq!(
+            listener.local_addr().unwrap().port(),
+            shutdown.addrs[0].port()
+        );
+    }
+
+    #[test]
+    fn stops_every_listener_at_once() {
+        let listeners = vec![
+            TcpListener::bind("127.0.0.1:0").unwrap(),
+            TcpListener::bind("127.0.0.1:0").unwrap(),
+        ];
+        let shutdown = Shutdown::for_listeners(&listeners).unwrap();
+
+        thread::scope(|scope| {
+            for listener in &listeners {
+                let shutdown = &shutdown;
+                scope.spawn(move || {
+                    for _ in listener.incoming() {
+                        if shutdown.is_requested() {
+                            break;
+                        }
+                    }
+                });
+            }
+
+            shutdown.trigger();
+        });
+        assert!(shutdown.is_requested());
+    }
+}
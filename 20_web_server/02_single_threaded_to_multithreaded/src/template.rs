@@ -0,0 +1,313 @@
+use std::fmt;
+
+use crate::json::Json;
+
+// One piece of a parsed template
+#[derive(Debug, Clone, PartialEq)]
+enum Node {
+    Text(String),
+    // `{{ name }}` or `{{ name.field }}`, the path split at the dots
+    Value(Vec<String>),
+    // `{{ for item in list }} ... {{ end }}`
+    For {
+        item: String,
+        list: Vec<String>,
+        body: Vec<Node>,
+    },
+}
+
+/// A page with gaps to fill in from data, parsed once and rendered as
+/// often as needed.
+///
+/// `{{ name }}` is replaced by the value called `name`, HTML-escaped, and
+/// `{{ name.field }}` reaches into objects. `{{ for item in list }}` repeats
+/// everything up to the matching `{{ end }}` once for each element of the
+/// array `list`, with the element called `item`. The data is a Json
+/// object; names that aren't in it come out empty.
+///
+/// ```
+/// use hello::{Json, Template, ToJson};
+///
+/// let template = Template::parse("{{ for n in names }}<li>{{ n }}</li>{{ end }}").unwrap();
+/// let data = Json::object(vec![("names", vec!["Ann", "Bo"].to_json())]);
+///
+/// assert_eq!("<li>Ann</li><li>Bo</li>", template.render(&data));
+/// ```
+#[derive(Debug, Clone, PartialEq)]
+pub struct Template {
+    nodes: Vec<Node>,
+}
+
+/// Why a template couldn't be parsed.
+#[derive(Debug, Clone, PartialEq)]
+pub struct TemplateError {
+    /// The line the problem is on, counting from 1
+    pub line: usize,
+    pub message: String,
+}
+
+impl fmt::Display for TemplateError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(f, "line {}: {}", self.line, self.message)
+    }
+}
+
+impl std::error::Error for TemplateError {}
+
+// A `for` still waiting for its `end`
+struct OpenLoop {
+    item: String,
+    list: Vec<String>,
+    line: usize,
+    body: Vec<Node>,
+}
+
+impl Template {
+    pub fn parse(text: &str) -> Result<Template, TemplateError> {
+        let mut nodes = Vec::new();
+        // Innermost last
+        let mut open: Vec<OpenLoop> = Vec::new();
+        let mut rest = text;
+
+        while let Some(start) = rest.find("{{") {
+            let line = line_of(text, rest, start);
+            let error = |message: String| TemplateError { line, message };
+
+            let end = rest[start..]
+                .find("}}")
+                .ok_or_else(|| error(String::from("`{{` without a closing `}}`")))?;
+            let tag = rest[start + 2..start + end].trim();
+            if start > 0 {
+                current(&mut nodes, &mut open).push(Node::Text(rest[..start].to_string()));
+            }
+            rest = &rest[start + end + 2..];
+
+            let words: Vec<&str> = tag.split_whitespace().collect();
+            match words[..] {
+                ["for", item, "in", list] => {
+                    let list = path(list).ok_or_else(|| error(format!("bad name `{}`", list)))?;
+                    if path(item).is_none_or(|item| item.len() != 1) {
+                        return Err(error(format!("bad loop variable `{}`", item)));
+                    }
+                    open.push(OpenLoop {
+                        item: item.to_string(),
+                        list,
+                        line,
+                        body: Vec::new(),
+                    });
+                }
+                ["end"] => {
+                    let OpenLoop {
+                        item, list, body, ..
+                    } = open
+                        .pop()
+                        .ok_or_else(|| error(String::from("`end` without a `for`")))?;
+                    current(&mut nodes, &mut open).push(Node::For { item, list, body });
+                }
+                [name] => {
+                    let name = path(name).ok_or_else(|| error(format!("bad name `{}`", name)))?;
+                    current(&mut nodes, &mut open).push(Node::Value(name));
+                }
+                _ => return Err(error(format!("can't make sense of `{{{{ {} }}}}`", tag))),
+            }
+        }
+
+        if let Some(unclosed) = open.last() {
+            return Err(TemplateError {
+                line: unclosed.line,
+                message: String::from("`for` without an `end`"),
+            });
+        }
+        if !rest.is_empty() {
+            nodes.push(Node::Text(rest.to_string()));
+        }
+        Ok(Template { nodes })
+    }
+
+    /// The template filled in from `data`.
+    pub fn render(&self, data: &Json) -> String {
+        let mut out = String::new();
+        render(&self.nodes, data, &mut Vec::new(), &mut out);
+        out
+    }
+}
+
+// Where parsed nodes go: into the innermost open loop, if there is one
+fn current<'a>(nodes: &'a mut Vec<Node>, open: &'a mut [OpenLoop]) -> &'a mut Vec<Node> {
+    match open.last_mut() {
+        Some(open) => &mut open.body,
+        None => nodes,
+    }
+}
+
+// The line of `text` that `rest[offset..]`, a tail of `text`, starts on
+fn line_of(text: &str, rest: &str, offset: usize) -> usize {
+    let consumed = text.len() - rest.len() + offset;
+    text[..consumed].matches('\n').count() + 1
+}
+
+// A dotted name split into its parts, if every part is a name
+fn path(name: &str) -> Option<Vec<String>> {
+    name.split('.')
+        .map(|part| {
+            let valid = !part.is_empty()
+                && part
+                    .chars()
+                    .all(|c| c.is_ascii_alphanumeric() || c == '_' || c == '-');
+            valid.then(|| part.to_string())
+        })
+        .collect()
+}
+
+// `scopes` holds the loop variables in reach, innermost last
+fn render<'a>(
+    nodes: &'a [Node],
+    data: &'a Json,
+    scopes: &mut Vec<(&'a str, &'a Json)>,
+    out: &mut String,
+) {
+    for node in nodes {
+        match node {
+            Node::Text(text) => out.push_str(text),
+            Node::Value(path) => {
+                if let Some(value) = lookup(path, data, scopes) {
+                    out.push_str(&escape_html(&text(value)));
+                }
+            }
+            Node::For { item, list, body } => {
+                if let Some(Json::Array(values)) = lookup(list, data, scopes) {
+                    for value in values {
+                        scopes.push((item, value));
+                        render(body, data, scopes, out);
+                        scopes.pop();
+                    }
+                }
+            }
+        }
+    }
+}
+
+fn lookup<'a>(path: &[String], data: &'a Json, scopes: &[(&str, &'a Json)]) -> Option<&'a Json> {
+    let (first, fields) = path.split_first()?;
+    let start = scopes
+        .iter()
+        .rev()
+        .find(|(name, _)| name == first)
+        .map(|&(_, value)| value)
+        .or_else(|| field(data, first))?;
+
+    fields
+        .iter()
+        .try_fold(start, |value, name| field(value, name))
+}
+
+fn field<'a>(value: &'a Json, name: &str) -> Option<&'a Json> {
+    match value {
+        Json::Object(fields) => fields
+            .iter()
+            .find(|(field, _)| field == name)
+            .map(|(_, value)| value),
+        _ => None,
+    }
+}
+
+// How a value reads on the page: strings as they are, null as nothing, and
+// anything else as JSON
+fn text(value: &Json) -> String {
+    match value {
+        Json::Null => String::new(),
+        Json::String(text) => text.clone(),
+        other => other.to_string(),
+    }
+}
+
+/// Makes text safe to put inside HTML, so what a visitor typed shows up as
+/// text instead of being run as markup.
+pub fn escape_html(text: &str) -> String {
+    text.replace('&', "&amp;")
+        .replace('<', "&lt;")
+        .replace('>', "&gt;")
+        .replace('"', "&quot;")
+        .replace('\'', "&#39;")
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::json::ToJson;
+
+    fn render(text: &str, data: Json) -> String {
+        Template::parse(text).unwrap().render(&data)
+    }
+
+    #[test]
+    fn fills_in_values() {
+        let data = Json::object(vec![
+            ("name", "Ann".to_json()),
+            ("count", 3.to_json()),
+            ("user", Json::object(vec![("id", 7.to_json())])),
+            ("nothing", Json::Null),
+        ]);
+
+        assert_eq!(
+            "Hi Ann, 3 new. #7!",
+            render(
+                "Hi {{name}}, {{ count }} new. #{{ user.id }}{{ nothing }}{{ missing }}!",
+                data
+            )
+        );
+    }
+
+    #[test]
+    fn escapes_values() {
+        let data = Json::object(vec![("name", "<b>\"Bo\" & co</b>".to_json())]);
+
+        assert_eq!(
+            "<p>&lt;b&gt;&quot;Bo&quot; &amp; co&lt;/b&gt;</p>",
+            render("<p>{{ name }}</p>", data)
+        );
+    }
+
+    #[test]
+    fn repeats_loops() {
+        let data = Json::object(vec![
+            ("title", "Links".to_json()),
+            (
+                "links",
+                Json::Array(vec![
+                    Json::object(vec![
+                        ("href", "/a".to_json()),
+                        ("tags", vec!["x", "y"].to_json()),
+                    ]),
+                    Json::object(vec![
+                        ("href", "/b".to_json()),
+                        ("tags", Json::Array(Vec::new())),
+                    ]),
+                ]),
+            ),
+        ]);
+        let text = "{{ for link in links }}[{{ title }} {{ link.href }}:\
+                    {{ for tag in link.tags }}{{ tag }}{{ end }}]{{ end }}";
+
+        assert_eq!("[Links /a:xy][Links /b:]", render(text, data));
+        assert_eq!("", render("{{ for x in missing }}x{{ end }}", Json::Null));
+    }
+
+    #[test]
+    fn reports_mistakes_with_their_line() {
+        let error = |text: &str| Template::parse(text).unwrap_err();
+
+        assert_eq!(
+            TemplateError {
+                line: 2,
+                message: String::from("`for` without an `end`"),
+            },
+            error("<ul>\n{{ for x in xs }}\n<li>")
+        );
+        assert_eq!(3, error("\n\n{{ end }}").line);
+        assert_eq!(1, error("{{ name ").line);
+        assert_eq!(1, error("{{ two words }}").line);
+        assert_eq!(1, error("{{ a..b }}").line);
+        assert_eq!(1, error("{{ for x.y in xs }}{{ end }}").line);
+    }
+}
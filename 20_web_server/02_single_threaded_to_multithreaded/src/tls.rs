@@ -0,0 +1,127 @@
+use std::fs::File;
+use std::io::{self, BufReader};
+use std::net::TcpStream;
+use std::path::Path;
+use std::sync::Arc;
+
+use rustls::pki_types::{CertificateDer, PrivateKeyDer};
+use rustls::{ServerConnection, StreamOwned};
+
+/// A connection with TLS on top, read from and written to like the
+/// TcpStream underneath.
+pub type TlsStream = StreamOwned<ServerConnection, TcpStream>;
+
+/// Turns accepted connections into TLS ones, using one certificate.
+///
+/// Cloning is cheap, since clones share the certificate and settings.
+#[derive(Clone)]
+pub struct TlsAcceptor {
+    config: Arc<rustls::ServerConfig>,
+}
+
+impl TlsAcceptor {
+    /// Loads the certificate chain and private key from PEM files. The
+    /// certificate file can hold the whole chain, the server's own
+    /// certificate first.
+    pub fn from_pem_files(
+        cert: impl AsRef<Path>,
+        key: impl AsRef<Path>,
+    ) -> io::Result<TlsAcceptor> {
+        let certs = rustls_pemfile::certs(&mut BufReader::new(File::open(cert)?))
+            .collect::<Result<Vec<CertificateDer<'static>>, _>>()?;
+        let key: PrivateKeyDer<'static> =
+            rustls_pemfile::private_key(&mut BufReader::new(File::open(key)?))?
+                .ok_or_else(|| invalid("no private key in the key file"))?;
+
+        let provider = Arc::new(rustls::crypto::ring::default_provider());
+        let config = rustls::ServerConfig::builder_with_provider(provider)
+            .with_safe_default_protocol_versions()
+            .map_err(invalid)?
+            .with_no_client_auth()
+            .with_single_cert(certs, key)
+            .map_err(invalid)?;
+
+        Ok(TlsAcceptor {
+            config: Arc::new(config),
+        })
+    }
+
+    /// Wraps `stream` in a TLS session. The handshake happens on the first
+    /// read or write, so its failures show up as errors from those.
+    pub fn accept(&self, stream: TcpStream) -> io::Result<TlsStream> {
+        let connection = ServerConnection::new(Arc::clone(&self.config)).map_err(invalid)?;
+
+        Ok(StreamOwned::new(connection, stream))
+    }
+}
+
+fn invalid(e: impl Into<Box<dyn std::error::Error + Send + Sync>>) -> io::Error {
+    io::Error::new(io::ErrorKind::InvalidData, e)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::convert::TryFrom;
+    use std::io::{Read, Write};
+    use std::net::TcpListener;
+    use std::thread;
+
+    use rustls::pki_types::ServerName;
+    use rustls::{ClientConfig, ClientConnection, RootCertStore};
+
+    // A self-signed certificate for localhost, kept next to the sources
+    const CERT: &str = "tls/localhost.crt";
+    const KEY: &str = "tls/localhost.key";
+
+    // A client that trusts only the test certificate
+    fn client_config() -> Arc<ClientConfig> {
+        let mut roots = RootCertStore::empty();
+        for cert in rustls_pemfile::certs(&mut BufReader::new(File::open(CERT).unwrap())) {
+            roots.add(cert.unwrap()).unwrap();
+        }
+
+        let provider = Arc::new(rustls::crypto::ring::default_provider());
+        let config = ClientConfig::builder_with_provider(provider)
+            .with_safe_default_protocol_versions()
+            .unwrap()
+            .with_root_certificates(roots)
+            .with_no_client_auth();
+        Arc::new(config)
+    }
+
+    #[test]
+    fn talks_tls_with_a_client() {
+        let acceptor = TlsAcceptor::from_pem_files(CERT, KEY).unwrap();
+        let listener = TcpListener::bind("127.0.0.1:0").unwrap();
+        let addr = listener.local_addr().unwrap();
+
+        let server = thread::spawn(move || {
+            let (stream, _) = listener.accept().unwrap();
+            let mut tls = acceptor.accept(stream).unwrap();
+
+            let mut ping = [0; 4];
+            tls.read_exact(&mut ping).unwrap();
+            assert_eq!(b"ping", &ping);
+            tls.write_all(b"pong").unwrap();
+            tls.flush().unwrap();
+        });
+
+        let name = ServerName::try_from("localhost").unwrap();
+        let connection = ClientConnection::new(client_config(), name).unwrap();
+        let mut client = StreamOwned::new(connection, TcpStream::connect(addr).unwrap());
+        client.write_all(b"ping").unwrap();
+        let mut pong = [0; 4];
+        client.read_exact(&mut pong).unwrap();
+
+        assert_eq!(b"pong", &pong);
+        server.join().unwrap();
+    }
+
+    #[test]
+    fn refuses_files_without_a_key() {
+        // The certificate file has no private key in it
+        assert!(TlsAcceptor::from_pem_files(CERT, CERT).is_err());
+        assert!(TlsAcceptor::from_pem_files(CERT, "tls/missing.key").is_err());
+    }
+}
@@ -0,0 +1,139 @@
+use std::collections::HashMap;
+
+use crate::http::{Request, Response};
+use crate::router::Router;
+
+/// Serves several sites from one listener, picking the router for each
+/// request by its Host header.
+///
+/// Host names are matched without regard to case or port, so a site added
+/// as `example.com` also answers `Example.COM:7878`. Requests for a host
+/// that wasn't added, or without a Host header at all, go to the default
+/// router.
+///
+/// ```no_run
+/// use hello::{Response, Router, VirtualHosts};
+///
+/// let mut docs = Router::new();
+/// docs.get("/", |_| Response::ok("The docs"));
+///
+/// let mut hosts = VirtualHosts::new(Router::new());
+/// hosts.add("docs.example.com", docs);
+/// ```
+pub struct VirtualHosts {
+    hosts: HashMap<String, Router>,
+    default: Router,
+}
+
+impl VirtualHosts {
+    /// Virtual hosts that send everything to `default` until others are
+    /// added.
+    pub fn new(default: Router) -> VirtualHosts {
+        VirtualHosts {
+            hosts: HashMap::new(),
+            default,
+        }
+    }
+
+    /// Sends requests for `host` to `router`, in place of any router added
+    /// for it before.
+    pub fn add(&mut self, host: &str, router: Router) {
+        self.hosts.insert(normalize(host), router);
+    }
+
+    /// The router that requests with `host` in their Host header go to.
+    pub fn router(&self, host: Option<&str>) -> &Router {
+        host.and_then(|host| self.hosts.get(&normalize(host)))
+            .unwrap_or(&self.default)
+    }
+
+    /// Hands `request` to the router for its host.
+    pub fn handle(&self, request: Request) -> Response {
+        let router = self.router(request.header("Host"));
+        router.handle(request)
+    }
+}
+
+// A host name as it's looked up: lowercase, and without the port or the
+// trailing dot of a fully qualified name
+fn normalize(host: &str) -> String {
+    let host = host.trim();
+    let host = match host.strip_prefix('[') {
+        // An IPv6 address, whose colons aren't the port's
+        Some(rest) => rest.split(']').next().unwrap(),
+        None => host.split(':').next().unwrap(),
+    };
+
+    host.trim_end_matches('.').to_ascii_lowercase()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn request(host: Option<&str>) -> Request {
+        let host = host.map_or_else(String::new, |host| format!("Host: {}\r\n", host));
+        Request::parse(format!("GET / HTTP/1.1\r\n{}\r\n", host).as_bytes()).unwrap()
+    }
+
+    fn site(name: &'static str) -> Router {
+        let mut router = Router::new();
+        router.get("/", move |_| Response::ok(name));
+        router
+    }
+
+    fn body(response: Response) -> String {
+        String::from_utf8(response.body).unwrap()
+    }
+
+    fn hosts() -> VirtualHosts {
+        let mut hosts = VirtualHosts::new(site("default"));
+        hosts.add("example.com", site("example"));
+        hosts.add("Docs.Example.com", site("docs"));
+        hosts.add("[::1]", site("ipv6"));
+        hosts
+    }
+
+    #[test]
+    fn picks_the_router_by_host() {
+        let hosts = hosts();
+
+        assert_eq!("example", body(hosts.handle(request(Some("example.com")))));
+        assert_eq!(
+            "docs",
+            body(hosts.handle(request(Some("docs.example.com"))))
+        );
+    }
+
+    #[test]
+    fn ignores_case_port_and_trailing_dot() {
+        let hosts = hosts();
+
+        assert_eq!(
+            "example",
+            body(hosts.handle(request(Some("EXAMPLE.com:7878"))))
+        );
+        assert_eq!("example", body(hosts.handle(request(Some("example.com.")))));
+        assert_eq!("ipv6", body(hosts.handle(request(Some("[::1]:7878")))));
+    }
+
+    #[test]
+    fn falls_back_to_the_default() {
+        let hosts = hosts();
+
+        assert_eq!("default", body(hosts.handle(request(Some("other.org")))));
+        assert_eq!("default", body(hosts.handle(request(None))));
+        assert_eq!(
+            "default",
+            body(hosts.handle(request(Some("www.example.com"))))
+        );
+    }
+
+    #[test]
+    fn later_routers_replace_earlier_ones() {
+        let mut hosts = hosts();
+        hosts.add("EXAMPLE.COM", site("replaced"));
+
+        assert_eq!("replaced", body(hosts.handle(request(Some("example.com")))));
+    }
+}